@@ -0,0 +1,84 @@
+mod flat_text;
+
+use criterion::{criterion_group, criterion_main, Criterion, Fun};
+use flat_text::FlatText;
+use memo_core::{time, Buffer, ReplicaId};
+
+const TEXT_LEN: usize = 50 * 1024 * 1024;
+
+// The flat `Text` baseline only applies to opening and reading, since edits never modified an
+// insertion's text in place.
+fn open(c: &mut Criterion) {
+    let rope = Fun::new("rope", |b, len: &usize| {
+        b.iter_with_setup(|| build_text(*len), Buffer::new)
+    });
+    let flat = Fun::new("flat", |b, len: &usize| {
+        b.iter_with_setup(
+            || build_text(*len),
+            |text| FlatText::new(text.encode_utf16().collect()),
+        )
+    });
+    c.bench_functions("open_50mb", vec![rope, flat], TEXT_LEN);
+}
+
+fn edit(c: &mut Criterion) {
+    c.bench_function("edit_50mb", |b| {
+        b.iter_with_setup(
+            || Buffer::new(build_text(TEXT_LEN)),
+            |mut buffer| {
+                let replica_id = ReplicaId::from_u128(1);
+                let mut local_clock = time::Local::new(replica_id);
+                let mut lamport_clock = time::Lamport::new(replica_id);
+                let len = buffer.len();
+                for i in 1..10 {
+                    let offset = len * i / 10;
+                    buffer.edit(
+                        Some(offset..offset + 5),
+                        "abc",
+                        &mut local_clock,
+                        &mut lamport_clock,
+                    );
+                }
+                buffer
+            },
+        )
+    });
+}
+
+fn iter(c: &mut Criterion) {
+    let buffer = Buffer::new(build_text(TEXT_LEN));
+    let rope = Fun::new("rope", move |b, _: &usize| b.iter(|| buffer.iter().count()));
+    let text = FlatText::new(build_text(TEXT_LEN).encode_utf16().collect());
+    let flat = Fun::new("flat", move |b, _: &usize| b.iter(|| text.iter().count()));
+    c.bench_functions("iter_50mb", vec![rope, flat], TEXT_LEN);
+}
+
+fn line(c: &mut Criterion) {
+    let buffer = Buffer::new(build_text(TEXT_LEN));
+    let rope = Fun::new("rope", move |b, _: &usize| {
+        let max_point = buffer.max_point();
+        b.iter(|| buffer.line(max_point.row / 2).unwrap())
+    });
+    let text = FlatText::new(build_text(TEXT_LEN).encode_utf16().collect());
+    let flat = Fun::new("flat", move |b, _: &usize| {
+        let rows = text.rows();
+        b.iter(|| text.line(rows / 2).unwrap())
+    });
+    c.bench_functions("line_50mb", vec![rope, flat], TEXT_LEN);
+}
+
+fn build_text(len: usize) -> String {
+    let line = "The quick brown fox jumps over the lazy dog. Ünïcödé 🦊\n";
+    let mut text = String::with_capacity(len + line.len());
+    while text.len() < len {
+        text.push_str(line);
+    }
+    text
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = open, edit, iter, line
+}
+criterion_main!(benches);
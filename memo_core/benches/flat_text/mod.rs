@@ -0,0 +1,210 @@
+//! The flat representation `Text` used before it became a rope of chunks, kept as a baseline for
+//! the benchmarks: all code units in one vector, plus a balanced tree of line lengths and periodic
+//! length checkpoints.
+
+use std::cmp::{self, Ordering};
+use std::mem;
+use std::ops::{AddAssign, Range};
+
+const TEXT_CHECKPOINT_INTERVAL: usize = 128;
+
+pub struct FlatText {
+    code_units: Vec<u16>,
+    nodes: Vec<LineNode>,
+    // Never read by the benchmarks, but still built so that opening pays for it.
+    #[allow(dead_code)]
+    checkpoints: Vec<TextLen>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct TextLen {
+    utf8: usize,
+    utf16: usize,
+    chars: usize,
+}
+
+#[derive(Clone, Debug)]
+struct LineNode {
+    len: u32,
+    longest_row: u32,
+    longest_row_len: u32,
+    offset: usize,
+    rows: u32,
+}
+
+impl FlatText {
+    pub fn new(code_units: Vec<u16>) -> Self {
+        fn build_tree(index: usize, line_lengths: &[u32], mut tree: &mut [LineNode]) {
+            if line_lengths.is_empty() {
+                return;
+            }
+
+            let mid = if line_lengths.len() == 1 {
+                0
+            } else {
+                let depth = log2_fast(line_lengths.len());
+                let max_elements = (1 << (depth)) - 1;
+                let right_subtree_elements = 1 << (depth - 1);
+                cmp::min(line_lengths.len() - right_subtree_elements, max_elements)
+            };
+            let len = line_lengths[mid];
+            let lower = &line_lengths[0..mid];
+            let upper = &line_lengths[mid + 1..];
+
+            let left_child_index = index * 2 + 1;
+            let right_child_index = index * 2 + 2;
+            build_tree(left_child_index, lower, &mut tree);
+            build_tree(right_child_index, upper, &mut tree);
+            tree[index] = {
+                let left_child = tree.get(left_child_index).cloned();
+                let right_child = tree.get(right_child_index).cloned();
+                let left_child_rows = left_child.as_ref().map_or(0, |node| node.rows);
+
+                let mut longest_row = 0;
+                let mut longest_row_len = 0;
+                if let Some(left_child) = left_child.as_ref() {
+                    longest_row = left_child.longest_row;
+                    longest_row_len = left_child.longest_row_len;
+                }
+                if len > longest_row_len {
+                    longest_row = left_child_rows;
+                    longest_row_len = len;
+                }
+                if let Some(right_child) = right_child.as_ref() {
+                    if right_child.longest_row_len > longest_row_len {
+                        longest_row = left_child_rows + right_child.longest_row + 1;
+                        longest_row_len = right_child.longest_row_len;
+                    }
+                }
+
+                LineNode {
+                    len,
+                    longest_row,
+                    longest_row_len,
+                    offset: left_child.as_ref().map_or(0, |node| node.offset)
+                        + len as usize
+                        + right_child.as_ref().map_or(0, |node| node.offset)
+                        + 1,
+                    rows: left_child_rows + right_child.as_ref().map_or(0, |node| node.rows) + 1,
+                }
+            };
+        }
+
+        let mut line_lengths = Vec::new();
+        let mut prev_offset = 0;
+        for (offset, code_unit) in code_units.iter().enumerate() {
+            if code_unit == &u16::from(b'\n') {
+                line_lengths.push((offset - prev_offset) as u32);
+                prev_offset = offset + 1;
+            }
+        }
+        line_lengths.push((code_units.len() - prev_offset) as u32);
+
+        let mut nodes = Vec::new();
+        nodes.resize(
+            line_lengths.len(),
+            LineNode {
+                len: 0,
+                longest_row_len: 0,
+                longest_row: 0,
+                offset: 0,
+                rows: 0,
+            },
+        );
+        build_tree(0, &line_lengths, &mut nodes);
+
+        let mut checkpoints = vec![TextLen::default()];
+        let mut len = TextLen::default();
+        for chunk in code_units.chunks(TEXT_CHECKPOINT_INTERVAL) {
+            len += &TextLen::of(chunk);
+            checkpoints.push(len);
+        }
+
+        Self {
+            code_units,
+            nodes,
+            checkpoints,
+        }
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.nodes[0].rows
+    }
+
+    pub fn iter<'a>(&'a self) -> impl 'a + Iterator<Item = u16> {
+        self.code_units.iter().cloned()
+    }
+
+    pub fn line(&self, row: u32) -> Option<Vec<u16>> {
+        let (offset_range, _, _) = self.search(|probe| row.cmp(&probe.row))?;
+        Some(self.code_units[offset_range].to_vec())
+    }
+
+    fn search<F>(&self, mut f: F) -> Option<(Range<usize>, u32, &LineNode)>
+    where
+        F: FnMut(LineNodeProbe) -> Ordering,
+    {
+        let mut left_ancestor_end_offset = 0;
+        let mut left_ancestor_row = 0;
+        let mut cur_node_index = 0;
+        while let Some(cur_node) = self.nodes.get(cur_node_index) {
+            let left_child = self.nodes.get(cur_node_index * 2 + 1);
+            let cur_offset_range = {
+                let start = left_ancestor_end_offset + left_child.map_or(0, |node| node.offset);
+                let end = start + cur_node.len as usize;
+                start..end
+            };
+            let cur_row = left_ancestor_row + left_child.map_or(0, |node| node.rows);
+            match f(LineNodeProbe { row: cur_row }) {
+                Ordering::Less => cur_node_index = cur_node_index * 2 + 1,
+                Ordering::Equal => return Some((cur_offset_range, cur_row, cur_node)),
+                Ordering::Greater => {
+                    cur_node_index = cur_node_index * 2 + 2;
+                    left_ancestor_end_offset = cur_offset_range.end + 1;
+                    left_ancestor_row = cur_row + 1;
+                }
+            }
+        }
+        None
+    }
+}
+
+struct LineNodeProbe {
+    row: u32,
+}
+
+impl TextLen {
+    fn of(code_units: &[u16]) -> Self {
+        let mut len = TextLen {
+            utf8: 0,
+            utf16: code_units.len(),
+            chars: 0,
+        };
+        for code_unit in code_units {
+            match *code_unit {
+                0..=0x7f => len.utf8 += 1,
+                0x80..=0x7ff => len.utf8 += 2,
+                0xd800..=0xdbff => {
+                    len.utf8 += 2;
+                    continue;
+                }
+                0xdc00..=0xdfff => len.utf8 += 2,
+                _ => len.utf8 += 3,
+            }
+            len.chars += 1;
+        }
+        len
+    }
+}
+
+impl<'a> AddAssign<&'a Self> for TextLen {
+    fn add_assign(&mut self, other: &Self) {
+        self.utf8 += other.utf8;
+        self.utf16 += other.utf16;
+        self.chars += other.chars;
+    }
+}
+
+fn log2_fast(x: usize) -> usize {
+    8 * mem::size_of::<usize>() - (x.leading_zeros() as usize) - 1
+}
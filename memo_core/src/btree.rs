@@ -749,14 +749,14 @@ impl<T: Item> Cursor<T> {
         D: Dimension<T::Summary>,
     {
         self.reset();
-        self.seek_internal(pos, bias, None)
+        self.seek_internal(pos, bias, None, None)
     }
 
     pub fn seek_forward<D>(&mut self, pos: &D, bias: SeekBias) -> bool
     where
         D: Dimension<T::Summary>,
     {
-        self.seek_internal(pos, bias, None)
+        self.seek_internal(pos, bias, None, None)
     }
 
     pub fn slice<D>(&mut self, end: &D, bias: SeekBias) -> Tree<T>
@@ -764,17 +764,28 @@ impl<T: Item> Cursor<T> {
         D: Dimension<T::Summary>,
     {
         let mut slice = Tree::new();
-        self.seek_internal(end, bias, Some(&mut slice));
+        self.seek_internal(end, bias, Some(&mut slice), None);
         slice
     }
 
+    /// Like `slice`, but only returns the summary of the items we skip over, which avoids
+    /// allocating a new tree.
+    pub fn summary<D>(&mut self, end: &D, bias: SeekBias) -> T::Summary
+    where
+        D: Dimension<T::Summary>,
+    {
+        let mut summary = T::Summary::default();
+        self.seek_internal(end, bias, None, Some(&mut summary));
+        summary
+    }
+
     pub fn suffix<D>(&mut self) -> Tree<T>
     where
         D: Dimension<T::Summary>,
     {
         let extent = self.tree.extent::<D>();
         let mut slice = Tree::new();
-        self.seek_internal(&extent, SeekBias::Right, Some(&mut slice));
+        self.seek_internal(&extent, SeekBias::Right, Some(&mut slice), None);
         slice
     }
 
@@ -783,6 +794,7 @@ impl<T: Item> Cursor<T> {
         target: &D,
         bias: SeekBias,
         mut slice: Option<&mut Tree<T>>,
+        mut summary: Option<&mut T::Summary>,
     ) -> bool
     where
         D: Dimension<T::Summary>,
@@ -817,6 +829,9 @@ impl<T: Item> Cursor<T> {
                                     if let Some(slice) = slice.as_mut() {
                                         slice.push_tree(child_tree.clone());
                                     }
+                                    if let Some(summary) = summary.as_mut() {
+                                        **summary += child_summary;
+                                    }
                                     *index += 1;
                                 } else {
                                     pos = D::from_summary(&self.summary).clone();
@@ -845,6 +860,9 @@ impl<T: Item> Cursor<T> {
                                         slice_items.push(item.clone());
                                         slice_items_summary += &item_summary;
                                     }
+                                    if let Some(summary) = summary.as_mut() {
+                                        **summary += &item_summary;
+                                    }
                                     *index += 1;
                                 } else {
                                     pos = D::from_summary(&self.summary).clone();
@@ -899,6 +917,9 @@ impl<T: Item> Cursor<T> {
                                 if let Some(slice) = slice.as_mut() {
                                     slice.push_tree(child_trees[index].clone());
                                 }
+                                if let Some(summary) = summary.as_mut() {
+                                    **summary += child_summary;
+                                }
                             } else {
                                 pos = D::from_summary(&self.summary).clone();
                                 self.stack
@@ -925,6 +946,9 @@ impl<T: Item> Cursor<T> {
                                     slice_items.push(item.clone());
                                     slice_items_summary += &item_summary;
                                 }
+                                if let Some(summary) = summary.as_mut() {
+                                    **summary += &item_summary;
+                                }
                                 self.summary += &item_summary;
                                 pos = child_end;
                             } else {
@@ -1323,6 +1347,13 @@ mod tests {
         assert_eq!(cursor.slice(&Sum(6), SeekBias::Right).items(), vec![2, 3]);
         assert_eq!(cursor.slice(&Sum(21), SeekBias::Left).items(), vec![4, 5]);
         assert_eq!(cursor.slice(&Sum(21), SeekBias::Right).items(), vec![6]);
+
+        // Summarizing advances the cursor like slicing does.
+        cursor.seek(&Sum(1), SeekBias::Right);
+        let summary = cursor.summary(&Sum(10), SeekBias::Right);
+        assert_eq!(summary.count, Count(3));
+        assert_eq!(summary.sum, Sum(9));
+        assert_eq!(cursor.item(), Some(5));
     }
 
     #[derive(Clone, Default, Debug)]
@@ -5,40 +5,93 @@ use crate::time;
 use crate::{Error, ReplicaId};
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
 use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+use regex_syntax::hir::{self, Hir, HirKind};
 use serde_derive::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use std::cell::RefCell;
+use std::char;
 use std::cmp::{self, Ordering};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::iter;
 use std::mem;
 use std::ops::{Add, AddAssign, Range, Sub};
-use std::sync::Arc;
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use std::vec;
 
+#[cfg(test)]
+const TEXT_CHUNK_LEN: usize = 4;
+#[cfg(not(test))]
+const TEXT_CHUNK_LEN: usize = 128;
+
 pub type SelectionSetId = time::Lamport;
 pub type SelectionsVersion = usize;
 
-#[derive(Clone)]
 pub struct Buffer {
     fragments: btree::Tree<Fragment>,
-    insertion_splits: HashMap<time::Local, btree::Tree<InsertionSplit>>,
-    anchor_cache: RefCell<HashMap<Anchor, (usize, Point)>>,
-    offset_cache: RefCell<HashMap<Point, usize>>,
+    // Shared with snapshots until the buffer is edited again, so taking a snapshot is cheap.
+    insertion_splits: Arc<HashMap<time::Local, btree::Tree<InsertionSplit>>>,
+    anchor_cache: Mutex<HashMap<Anchor, (usize, Point)>>,
+    offset_cache: Mutex<HashMap<Point, usize>>,
     pub version: time::Global,
     last_edit: time::Local,
+    undo_map: UndoMap,
+    history: History,
     selections: HashMap<SelectionSetId, Vec<Selection>>,
     pub selections_last_update: SelectionsVersion,
     deferred_ops: OperationQueue<Operation>,
     deferred_replicas: HashSet<ReplicaId>,
 }
 
+/// An immutable view of a `Buffer` at a given version. Snapshots share their fragments with the
+/// buffer they were taken from, so they are cheap to create and can be sent to other threads.
+#[derive(Clone)]
+pub struct BufferSnapshot {
+    fragments: btree::Tree<Fragment>,
+    insertion_splits: Arc<HashMap<time::Local, btree::Tree<InsertionSplit>>>,
+    pub version: time::Global,
+}
+
 #[derive(Clone, Copy, Deserialize, Eq, PartialEq, Debug, Hash, Serialize)]
 pub struct Point {
     pub row: u32,
     pub column: u32,
 }
 
+/// The units an offset into a buffer can be expressed in. Text is stored as UTF-8 but addressed in
+/// UTF-16 code units, so every other API takes `Utf16` offsets, and `Buffer::convert_offset`
+/// translates from and to the others.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OffsetUnit {
+    Utf8,
+    Utf16,
+    Char,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchOptions {
+    pub regex: bool,
+    pub case_insensitive: bool,
+    pub whole_word: bool,
+}
+
+/// A compiled search pattern. Literal patterns are escaped and compiled to a regex as well, so
+/// invalid patterns are reported when the query is built rather than when it is run.
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+    regex: Regex,
+    // Whether a match can contain a newline. If not, buffers are searched one row at a time
+    // instead of being copied into a single string.
+    spans_rows: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub enum Anchor {
     Start,
@@ -65,13 +118,16 @@ pub struct Selection {
 
 pub struct Iter {
     fragment_cursor: btree::Cursor<Fragment>,
+    fragment: Option<Fragment>,
     fragment_offset: usize,
+    chunk: Option<(time::Local, usize, Vec<u16>)>,
     reversed: bool,
 }
 
 struct ChangesIter<F: Fn(&FragmentSummary) -> bool> {
     cursor: btree::FilterCursor<F, Fragment>,
     since: time::Global,
+    undo_map: UndoMap,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -81,6 +137,22 @@ pub struct Change {
     new_extent: Point,
 }
 
+/// A span of visible text that was inserted by a single edit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuthorshipSpan {
+    pub range: Range<Point>,
+    /// The timestamp of the edit that inserted this text, or `None` if it belongs to the base text.
+    pub inserted_by: Option<time::Lamport>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineDiffHunk {
+    pub old_rows: Range<u32>,
+    pub new_rows: Range<u32>,
+    pub old_text: String,
+    pub new_text: String,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Insertion {
     id: time::Local,
@@ -90,29 +162,33 @@ pub struct Insertion {
     lamport_timestamp: time::Lamport,
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub struct Text {
-    code_units: Vec<u16>,
-    nodes: Vec<LineNode>,
+    chunks: btree::Tree<TextChunk>,
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
-struct LineNode {
-    len: u32,
+// Chunks are encoded as WTF-8, which is UTF-8 extended to also represent unpaired surrogates, so
+// that any sequence of UTF-16 code units round-trips.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TextChunk {
+    bytes: Arc<[u8]>,
+    summary: TextSummary,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TextSummary {
+    len: TextLen,
+    extent_2d: Point,
+    first_row_len: u32,
     longest_row: u32,
     longest_row_len: u32,
-    offset: usize,
-    rows: u32,
 }
 
-struct LineNodeProbe<'a> {
-    offset_range: &'a Range<usize>,
-    row: u32,
-    left_ancestor_end_offset: usize,
-    right_ancestor_start_offset: usize,
-    node: &'a LineNode,
-    left_child: Option<&'a LineNode>,
-    right_child: Option<&'a LineNode>,
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+struct TextLen {
+    utf8: usize,
+    utf16: usize,
+    chars: usize,
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Debug)]
@@ -125,12 +201,16 @@ struct Fragment {
     start_offset: usize,
     end_offset: usize,
     deletions: HashSet<time::Local>,
+    max_undos: time::Global,
+    visible: bool,
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct FragmentSummary {
     extent: usize,
     extent_2d: Point,
+    extent_utf8: usize,
+    extent_chars: usize,
     max_fragment_id: FragmentId,
     first_row_len: u32,
     longest_row: u32,
@@ -138,6 +218,12 @@ pub struct FragmentSummary {
     max_version: time::Global,
 }
 
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+struct Utf8Offset(usize);
+
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+struct CharOffset(usize);
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 struct InsertionSplit {
     extent: usize,
@@ -149,6 +235,33 @@ struct InsertionSplitSummary {
     extent: usize,
 }
 
+#[derive(Clone, Debug, Default)]
+struct UndoMap(HashMap<time::Local, Vec<(time::Local, u32)>>);
+
+#[derive(Clone, Debug, Default)]
+struct History {
+    edits: HashMap<time::Local, EditRange>,
+    undo_stack: Vec<Transaction>,
+    redo_stack: Vec<Transaction>,
+    transaction: Option<Transaction>,
+    transaction_depth: usize,
+}
+
+#[derive(Clone, Debug)]
+struct EditRange {
+    start_id: time::Local,
+    start_offset: usize,
+    end_id: time::Local,
+    end_offset: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Transaction {
+    edit_ids: Vec<time::Local>,
+    start: Option<SystemTime>,
+    end: Option<SystemTime>,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Operation {
     Edit {
@@ -166,6 +279,30 @@ pub enum Operation {
         selections: Option<Vec<Selection>>,
         lamport_timestamp: time::Lamport,
     },
+    Undo {
+        counts: HashMap<time::Local, u32>,
+        local_timestamp: time::Local,
+        lamport_timestamp: time::Lamport,
+    },
+}
+
+impl Clone for Buffer {
+    fn clone(&self) -> Self {
+        Self {
+            fragments: self.fragments.clone(),
+            insertion_splits: self.insertion_splits.clone(),
+            anchor_cache: Mutex::new(self.anchor_cache.lock().unwrap().clone()),
+            offset_cache: Mutex::new(self.offset_cache.lock().unwrap().clone()),
+            version: self.version.clone(),
+            last_edit: self.last_edit,
+            undo_map: self.undo_map.clone(),
+            history: self.history.clone(),
+            selections: self.selections.clone(),
+            selections_last_update: self.selections_last_update,
+            deferred_ops: self.deferred_ops.clone(),
+            deferred_replicas: self.deferred_replicas.clone(),
+        }
+    }
 }
 
 impl Buffer {
@@ -197,6 +334,8 @@ impl Buffer {
             start_offset: 0,
             end_offset: 0,
             deletions: HashSet::new(),
+            max_undos: time::Global::new(),
+            visible: true,
         });
 
         if base_insertion.text.len() > 0 {
@@ -216,16 +355,20 @@ impl Buffer {
                 end_offset: base_insertion.text.len(),
                 insertion: base_insertion,
                 deletions: HashSet::new(),
+                max_undos: time::Global::new(),
+                visible: true,
             });
         }
 
         Self {
             fragments,
-            insertion_splits,
-            anchor_cache: RefCell::new(HashMap::default()),
-            offset_cache: RefCell::new(HashMap::default()),
+            insertion_splits: Arc::new(insertion_splits),
+            anchor_cache: Mutex::new(HashMap::default()),
+            offset_cache: Mutex::new(HashMap::default()),
             version: time::Global::new(),
             last_edit: time::Local::default(),
+            undo_map: UndoMap::default(),
+            history: History::default(),
             selections: HashMap::default(),
             selections_last_update: 0,
             deferred_ops: OperationQueue::new(),
@@ -241,6 +384,49 @@ impl Buffer {
         self.fragments.extent::<usize>()
     }
 
+    pub fn len_in(&self, unit: OffsetUnit) -> usize {
+        match unit {
+            OffsetUnit::Utf8 => self.fragments.extent::<Utf8Offset>().0,
+            OffsetUnit::Utf16 => self.len(),
+            OffsetUnit::Char => self.fragments.extent::<CharOffset>().0,
+        }
+    }
+
+    /// Translates an offset from one unit to another. Offsets that fall inside of a character are
+    /// rejected with `Error::OffsetOutOfRange`.
+    pub fn convert_offset(
+        &self,
+        offset: usize,
+        from: OffsetUnit,
+        to: OffsetUnit,
+    ) -> Result<usize, Error> {
+        if offset > self.len_in(from) {
+            return Err(Error::OffsetOutOfRange);
+        }
+
+        let mut cursor = self.fragments.cursor();
+        match from {
+            OffsetUnit::Utf8 => cursor.seek(&Utf8Offset(offset), SeekBias::Left),
+            OffsetUnit::Utf16 => cursor.seek(&offset, SeekBias::Left),
+            OffsetUnit::Char => cursor.seek(&CharOffset(offset), SeekBias::Left),
+        };
+        let fragment = cursor.item().ok_or(Error::OffsetOutOfRange)?;
+        let fragment_start = TextLen {
+            utf8: cursor.start::<Utf8Offset>().0,
+            utf16: cursor.start::<usize>(),
+            chars: cursor.start::<CharOffset>().0,
+        };
+
+        let text = &fragment.insertion.text;
+        let end_offset = text.offset_for_len(
+            fragment.start_offset,
+            offset - fragment_start.get(from),
+            from,
+        )?;
+        let len_in_fragment = text.len_in_range(fragment.start_offset..end_offset);
+        Ok(fragment_start.get(to) + len_in_fragment.get(to))
+    }
+
     pub fn len_for_row(&self, row: u32) -> Result<u32, Error> {
         let row_start_offset = self.offset_for_point(Point::new(row, 0))?;
         let row_end_offset = if row >= self.max_point().row {
@@ -274,15 +460,131 @@ impl Buffer {
     }
 
     pub fn to_string(&self) -> String {
-        String::from_utf16_lossy(&self.to_u16_chars())
+        fragments_to_string(&self.fragments)
     }
 
     pub fn iter(&self) -> Iter {
-        Iter::new(self)
+        Iter::new(&self.fragments)
     }
 
     pub fn iter_at_point(&self, point: Point) -> Iter {
-        Iter::at_point(self, point)
+        Iter::at_point(&self.fragments, point)
+    }
+
+    /// Returns the ranges of all non-empty matches of `query`, including the ones that overlap.
+    /// The ranges are anchored so that they keep tracking the matched text as the buffer is
+    /// edited.
+    pub fn search(&self, query: &SearchQuery) -> Vec<Range<Anchor>> {
+        self.search_ranges(query)
+            .into_iter()
+            .map(|range| self.anchor_range(range))
+            .collect()
+    }
+
+    /// Returns the first of the matches reported by `search` that starts at or after `offset`,
+    /// or when searching backward, the last one that ends at or before it.
+    pub fn find(
+        &self,
+        query: &SearchQuery,
+        offset: usize,
+        direction: SearchDirection,
+    ) -> Result<Option<Range<Anchor>>, Error> {
+        if offset > self.len() {
+            return Err(Error::OffsetOutOfRange);
+        }
+
+        let range = if query.spans_rows {
+            let mut ranges = self.search_ranges(query).into_iter();
+            match direction {
+                SearchDirection::Forward => ranges.find(|range| range.start >= offset),
+                SearchDirection::Backward => ranges.rev().find(|range| range.end <= offset),
+            }
+        } else {
+            let row = self.point_for_offset(offset)?.row;
+            match direction {
+                SearchDirection::Forward => (row..=self.max_point().row).find_map(|row| {
+                    self.search_row(query, row)
+                        .into_iter()
+                        .find(|range| range.start >= offset)
+                }),
+                SearchDirection::Backward => (0..=row).rev().find_map(|row| {
+                    self.search_row(query, row)
+                        .into_iter()
+                        .rev()
+                        .find(|range| range.end <= offset)
+                }),
+            }
+        };
+        Ok(range.map(|range| self.anchor_range(range)))
+    }
+
+    /// Replaces every match of `query` with `replacement` in a single edit. Of several
+    /// overlapping matches, only the first one is replaced.
+    pub fn replace_all<T>(
+        &mut self,
+        query: &SearchQuery,
+        replacement: T,
+        local_clock: &mut time::Local,
+        lamport_clock: &mut time::Lamport,
+    ) -> Vec<Operation>
+    where
+        T: Into<Text>,
+    {
+        let ranges = self.replacement_ranges(query);
+        if ranges.is_empty() {
+            Vec::new()
+        } else {
+            self.edit(ranges, replacement, local_clock, lamport_clock)
+        }
+    }
+
+    pub(crate) fn search_ranges(&self, query: &SearchQuery) -> Vec<Range<usize>> {
+        if query.spans_rows {
+            query.ranges(&self.to_string(), 0)
+        } else {
+            (0..=self.max_point().row)
+                .flat_map(|row| self.search_row(query, row))
+                .collect()
+        }
+    }
+
+    pub(crate) fn replacement_ranges(&self, query: &SearchQuery) -> Vec<Range<usize>> {
+        let mut ranges = self.search_ranges(query);
+        let mut prev_end = 0;
+        ranges.retain(|range| {
+            if range.start >= prev_end {
+                prev_end = range.end;
+                true
+            } else {
+                false
+            }
+        });
+        ranges
+    }
+
+    fn search_row(&self, query: &SearchQuery, row: u32) -> Vec<Range<usize>> {
+        let row_start = Point::new(row, 0);
+        let text = String::from_utf16_lossy(
+            &self
+                .iter_at_point(row_start)
+                .take_while(|c| *c != u16::from(b'\n'))
+                .collect::<Vec<_>>(),
+        );
+        query.ranges(&text, self.offset_for_point(row_start).unwrap())
+    }
+
+    fn anchor_range(&self, range: Range<usize>) -> Range<Anchor> {
+        let start = self.anchor_after_offset(range.start).unwrap();
+        let end = self.anchor_before_offset(range.end).unwrap();
+        start..end
+    }
+
+    pub fn snapshot(&self) -> BufferSnapshot {
+        BufferSnapshot {
+            fragments: self.fragments.clone(),
+            insertion_splits: self.insertion_splits.clone(),
+            version: self.version.clone(),
+        }
     }
 
     pub fn selections_changed_since(&self, since: SelectionsVersion) -> bool {
@@ -297,7 +599,126 @@ impl Buffer {
         ChangesIter {
             cursor,
             since: since.clone(),
+            undo_map: self.undo_map.clone(),
+        }
+    }
+
+    /// Returns the text of the buffer as it was at `version`.
+    ///
+    /// Versions that precede the last call to `collect_garbage` can't be reconstructed reliably,
+    /// because the tombstones they refer to may have been discarded.
+    pub fn text_at(&self, version: &time::Global) -> Result<Vec<u16>, Error> {
+        if version.changed_since(&self.version) {
+            return Err(Error::VersionUnavailable);
+        }
+
+        let mut text = Vec::new();
+        let mut cursor = self.fragments.cursor();
+        cursor.seek(&FragmentId::min_value(), SeekBias::Left);
+        while let Some(fragment) = cursor.item() {
+            if fragment.was_visible(version, &self.undo_map) {
+                text.extend(fragment.code_units());
+            }
+            cursor.next();
+        }
+        Ok(text)
+    }
+
+    /// Returns the changes that turn the text at `from` into the text at `to`. Like the changes
+    /// returned by `changes_since`, each change's range accounts for the ones preceding it.
+    pub fn changes_between(
+        &self,
+        from: &time::Global,
+        to: &time::Global,
+    ) -> Result<Vec<Change>, Error> {
+        if from.changed_since(&self.version) || to.changed_since(&self.version) {
+            return Err(Error::VersionUnavailable);
+        }
+
+        let mut changes: Vec<Change> = Vec::new();
+        let mut position = Point::zero();
+        let mut cursor = self.fragments.cursor();
+        cursor.seek(&FragmentId::min_value(), SeekBias::Left);
+        while let Some(fragment) = cursor.item() {
+            let was_visible = fragment.was_visible(from, &self.undo_map);
+            let is_visible = fragment.was_visible(to, &self.undo_map);
+            let extent = fragment.extent_2d();
+            let is_adjacent = changes.last().map_or(false, |change| {
+                change.range.start + &change.new_extent == position
+            });
+
+            if !was_visible && is_visible {
+                if is_adjacent {
+                    let change = changes.last_mut().unwrap();
+                    change.code_units.extend(fragment.code_units());
+                    change.new_extent += &extent;
+                } else {
+                    changes.push(Change {
+                        range: position..position,
+                        code_units: fragment.code_units().collect(),
+                        new_extent: extent,
+                    });
+                }
+            } else if was_visible && !is_visible {
+                if is_adjacent {
+                    changes.last_mut().unwrap().range.end += &extent;
+                } else {
+                    changes.push(Change {
+                        range: position..position + &extent,
+                        code_units: Vec::new(),
+                        new_extent: Point::zero(),
+                    });
+                }
+            }
+
+            if is_visible {
+                position += &extent;
+            }
+            cursor.next();
+        }
+        Ok(changes)
+    }
+
+    pub fn authorship(&self) -> Vec<AuthorshipSpan> {
+        self.authorship_in_rows(0..self.max_point().row + 1)
+            .unwrap()
+    }
+
+    /// Returns the authorship of the text in `rows`, coalescing adjacent text that was inserted by
+    /// the same edit.
+    pub fn authorship_in_rows(&self, rows: Range<u32>) -> Result<Vec<AuthorshipSpan>, Error> {
+        let max_point = self.max_point();
+        if rows.start > max_point.row {
+            return Err(Error::OffsetOutOfRange);
+        }
+        let start = Point::new(rows.start, 0);
+        let end = cmp::min(Point::new(rows.end, 0), max_point);
+
+        let mut spans: Vec<AuthorshipSpan> = Vec::new();
+        let mut cursor = self.fragments.cursor();
+        cursor.seek(&start, SeekBias::Right);
+        while let Some(fragment) = cursor.item() {
+            let fragment_start = cursor.start::<Point>();
+            if fragment_start >= end {
+                break;
+            }
+
+            if fragment.is_visible() {
+                let range = cmp::max(fragment_start, start)
+                    ..cmp::min(fragment_start + &fragment.extent_2d(), end);
+                let inserted_by = fragment.inserted_by();
+                match spans.last_mut() {
+                    Some(ref mut span)
+                        if span.inserted_by == inserted_by && span.range.end == range.start =>
+                    {
+                        span.range.end = range.end;
+                    }
+                    _ => spans.push(AuthorshipSpan { range, inserted_by }),
+                }
+            }
+            cursor.next();
         }
+        Ok(spans)
     }
 
     pub fn deferred_ops_len(&self) -> usize {
@@ -322,8 +743,8 @@ impl Buffer {
             None
         };
 
-        self.anchor_cache.borrow_mut().clear();
-        self.offset_cache.borrow_mut().clear();
+        self.anchor_cache.lock().unwrap().clear();
+        self.offset_cache.lock().unwrap().clear();
         let ops = self.splice_fragments(
             old_ranges
                 .into_iter()
@@ -343,6 +764,13 @@ impl Buffer {
                 unreachable!()
             }
         }
+
+        let edit_ids = ops
+            .iter()
+            .filter_map(|op| self.history.record_edit(op))
+            .collect::<Vec<_>>();
+        self.history.push_edits(edit_ids);
+
         ops
     }
 
@@ -368,6 +796,195 @@ impl Buffer {
         self.edit(old_1d_ranges, new_text, local_clock, lamport_clock)
     }
 
+    pub fn start_transaction(&mut self, now: SystemTime) {
+        self.history.start_transaction(now);
+    }
+
+    pub fn end_transaction(
+        &mut self,
+        now: SystemTime,
+        group_interval: Option<Duration>,
+    ) -> Result<(), Error> {
+        self.history.end_transaction(now, group_interval)
+    }
+
+    /// Ends the current transaction however deeply it is nested. Since it has no end time, it
+    /// isn't grouped with later transactions.
+    pub fn end_all_transactions(&mut self) {
+        self.history.end_all_transactions();
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.history.transaction.is_some()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo_stack.is_empty()
+    }
+
+    pub fn undo(
+        &mut self,
+        local_clock: &mut time::Local,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Option<Operation>, Error> {
+        if self.in_transaction() {
+            return Err(Error::InvalidOperation);
+        }
+
+        if let Some(transaction) = self.history.undo_stack.pop() {
+            let op = self.undo_or_redo(&transaction, local_clock, lamport_clock)?;
+            self.history.redo_stack.push(transaction);
+            Ok(Some(op))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn redo(
+        &mut self,
+        local_clock: &mut time::Local,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Option<Operation>, Error> {
+        if self.in_transaction() {
+            return Err(Error::InvalidOperation);
+        }
+
+        if let Some(transaction) = self.history.redo_stack.pop() {
+            let op = self.undo_or_redo(&transaction, local_clock, lamport_clock)?;
+            self.history.undo_stack.push(transaction);
+            Ok(Some(op))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Discards the undo and redo history for the edits observed by `version`, guaranteeing that
+    /// this replica won't generate any further operation that undoes or redoes them.
+    pub fn discard_history(&mut self, version: &time::Global) {
+        self.history
+            .undo_stack
+            .retain(|t| t.edit_ids.iter().all(|id| !version.observed(*id)));
+        self.history
+            .redo_stack
+            .retain(|t| t.edit_ids.iter().all(|id| !version.observed(*id)));
+    }
+
+    /// Compacts the fragments that were deleted before `stable_version`.
+    ///
+    /// Callers must only pass a version whose history has been discarded by every replica via
+    /// `discard_history`, after every operation those replicas generated before doing so has been
+    /// delivered. Changes can't be computed anymore relative to versions that precede it. The
+    /// insertion splits of the discarded fragments are retargeted to the tombstone that replaces
+    /// them, so that anchors referring to them keep resolving to the same position.
+    pub fn collect_garbage(&mut self, stable_version: &time::Global) {
+        if self.deferred_ops.len() > 0 {
+            return;
+        }
+
+        let is_stable = |edit_id: time::Local, undo_map: &UndoMap| {
+            stable_version.observed(edit_id) && undo_map.undos_observed(edit_id, stable_version)
+        };
+        self.discard_history(stable_version);
+        self.history
+            .edits
+            .retain(|id, _| !stable_version.observed(*id));
+
+        let mut fragments = self.fragments.items();
+        let mut is_collectable = Vec::with_capacity(fragments.len());
+        for fragment in &mut fragments {
+            let undo_map = &self.undo_map;
+            fragment
+                .deletions
+                .retain(|d| !is_stable(*d, undo_map) || !undo_map.is_undone(*d));
+            is_collectable.push(
+                !fragment.visible
+                    && is_stable(fragment.insertion.id, undo_map)
+                    && fragment.deletions.iter().all(|d| is_stable(*d, undo_map)),
+            );
+        }
+
+        // An anchor into a deleted fragment resolves to the fragment's position in the visible
+        // text, which is the same for every fragment in a run of tombstones. New edits are never
+        // anchored to a deleted fragment that is followed by another deleted fragment either, so
+        // we can drop all the fragments in a run of stable tombstones except for the last one,
+        // which takes their place in the insertion splits.
+        let mut new_fragments = btree::Tree::new();
+        let mut collected_fragment_ids = Vec::new();
+        let mut replacements = HashMap::new();
+        for (ix, fragment) in fragments.into_iter().enumerate() {
+            if is_collectable[ix] && is_collectable.get(ix + 1).cloned().unwrap_or(false) {
+                collected_fragment_ids.push((fragment.insertion.id, fragment.id));
+            } else {
+                for (insertion_id, fragment_id) in collected_fragment_ids.drain(..) {
+                    replacements
+                        .entry(insertion_id)
+                        .or_insert_with(BTreeMap::new)
+                        .insert(fragment_id, fragment.id.clone());
+                }
+                new_fragments.push(fragment);
+            }
+        }
+        self.fragments = new_fragments;
+
+        for (insertion_id, replacements) in replacements {
+            if let Some(split_tree) =
+                Arc::make_mut(&mut self.insertion_splits).get_mut(&insertion_id)
+            {
+                let mut new_split_tree = btree::Tree::<InsertionSplit>::new();
+                let mut pending_split: Option<InsertionSplit> = None;
+                for mut split in split_tree.items() {
+                    if let Some(fragment_id) = replacements.get(&split.fragment_id) {
+                        split.fragment_id = fragment_id.clone();
+                    }
+                    pending_split = match pending_split {
+                        Some(mut pending_split) => {
+                            if pending_split.fragment_id == split.fragment_id {
+                                pending_split.extent += split.extent;
+                                Some(pending_split)
+                            } else {
+                                new_split_tree.push(pending_split);
+                                Some(split)
+                            }
+                        }
+                        None => Some(split),
+                    };
+                }
+                new_split_tree.extend(pending_split);
+                *split_tree = new_split_tree;
+            }
+        }
+        self.anchor_cache.lock().unwrap().clear();
+        self.offset_cache.lock().unwrap().clear();
+    }
+
+    fn undo_or_redo(
+        &mut self,
+        transaction: &Transaction,
+        local_clock: &mut time::Local,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Operation, Error> {
+        let mut counts = HashMap::new();
+        for edit_id in &transaction.edit_ids {
+            counts.insert(*edit_id, self.undo_map.undo_count(*edit_id) + 1);
+        }
+
+        let local_timestamp = local_clock.tick();
+        let lamport_timestamp = lamport_clock.tick();
+        self.apply_undo(&counts, local_timestamp)?;
+        self.last_edit = local_timestamp;
+        self.version.observe(local_timestamp);
+
+        Ok(Operation::Undo {
+            counts,
+            local_timestamp,
+            lamport_timestamp,
+        })
+    }
+
     pub fn add_selection_set<I>(
         &mut self,
         ranges: I,
@@ -571,8 +1188,17 @@ impl Buffer {
                         local_clock,
                         lamport_clock,
                     )?;
-                    self.anchor_cache.borrow_mut().clear();
-                    self.offset_cache.borrow_mut().clear();
+                    self.history.edits.insert(
+                        local_timestamp,
+                        EditRange {
+                            start_id,
+                            start_offset,
+                            end_id,
+                            end_offset,
+                        },
+                    );
+                    self.anchor_cache.lock().unwrap().clear();
+                    self.offset_cache.lock().unwrap().clear();
                     self.version.observe(local_timestamp);
                 }
             }
@@ -589,6 +1215,18 @@ impl Buffer {
                 lamport_clock.observe(lamport_timestamp);
                 self.selections_last_update += 1;
             }
+            Operation::Undo {
+                counts,
+                local_timestamp,
+                lamport_timestamp,
+            } => {
+                if !self.version.observed(local_timestamp) {
+                    self.apply_undo(&counts, local_timestamp)?;
+                    self.version.observe(local_timestamp);
+                    local_clock.observe(local_timestamp);
+                    lamport_clock.observe(lamport_timestamp);
+                }
+            }
         }
         Ok(())
     }
@@ -665,6 +1303,7 @@ impl Buffer {
                 if let Some(mut fragment) = within_range {
                     if version_in_range.observed(fragment.insertion.id) {
                         fragment.deletions.insert(local_timestamp);
+                        fragment.visible = false;
                     }
                     new_fragments.push(fragment);
                 }
@@ -685,6 +1324,7 @@ impl Buffer {
                 if fragment.id < end_fragment_id && version_in_range.observed(fragment.insertion.id)
                 {
                     fragment.deletions.insert(local_timestamp);
+                    fragment.visible = false;
                 }
                 new_fragments.push(fragment);
             }
@@ -709,6 +1349,70 @@ impl Buffer {
         Ok(())
     }
 
+    fn apply_undo(
+        &mut self,
+        counts: &HashMap<time::Local, u32>,
+        undo_id: time::Local,
+    ) -> Result<(), Error> {
+        // Collect every fragment that was inserted or deleted by one of the undone edits. Text
+        // inserted by an edit can always be found via its insertion splits, whereas deletions
+        // can only occur between the fragments that bound the edit's range.
+        let mut fragment_ids = Vec::new();
+        for edit_id in counts.keys() {
+            let edit = self
+                .history
+                .edits
+                .get(edit_id)
+                .cloned()
+                .ok_or(Error::InvalidOperation)?;
+
+            if let Some(split_tree) = self.insertion_splits.get(edit_id) {
+                fragment_ids.extend(
+                    split_tree
+                        .items()
+                        .into_iter()
+                        .map(|split| split.fragment_id),
+                );
+            }
+
+            let start_fragment_id = self.resolve_fragment_id(edit.start_id, edit.start_offset)?;
+            let end_fragment_id = self.resolve_fragment_id(edit.end_id, edit.end_offset)?;
+            let mut cursor = self.fragments.cursor();
+            cursor.seek(&start_fragment_id, SeekBias::Left);
+            while let Some(fragment) = cursor.item() {
+                if fragment.id > end_fragment_id {
+                    break;
+                }
+                if fragment.deletions.contains(edit_id) {
+                    fragment_ids.push(fragment.id);
+                }
+                cursor.next();
+            }
+        }
+        fragment_ids.sort();
+        fragment_ids.dedup();
+        self.undo_map.insert(undo_id, counts);
+
+        let old_fragments = self.fragments.clone();
+        let mut cursor = old_fragments.cursor();
+        let mut new_fragments = btree::Tree::new();
+        for fragment_id in fragment_ids {
+            new_fragments.push_tree(cursor.slice(&fragment_id, SeekBias::Left));
+            let mut fragment = cursor.item().ok_or(Error::InvalidOperation)?;
+            if fragment.id == fragment_id {
+                fragment.update_visibility(&self.undo_map);
+                fragment.max_undos.observe(undo_id);
+                new_fragments.push(fragment);
+                cursor.next();
+            }
+        }
+        new_fragments.push_tree(cursor.slice(&old_fragments.extent::<usize>(), SeekBias::Right));
+        self.fragments = new_fragments;
+        self.anchor_cache.lock().unwrap().clear();
+        self.offset_cache.lock().unwrap().clear();
+        Ok(())
+    }
+
     fn flush_deferred_ops(
         &mut self,
         local_clock: &mut time::Local,
@@ -743,6 +1447,9 @@ impl Buffer {
                         && self.version.observed(*end_id)
                         && *version_in_range <= self.version
                 }
+                Operation::Undo { counts, .. } => {
+                    counts.keys().all(|edit_id| self.version.observed(*edit_id))
+                }
                 Operation::UpdateSelections { selections, .. } => {
                     if let Some(selections) = selections {
                         selections.iter().all(|selection| {
@@ -822,8 +1529,7 @@ impl Buffer {
             let mut fragment_start = cursor.start::<usize>();
             let mut fragment_end = fragment_start + fragment.len();
 
-            let old_split_tree = self
-                .insertion_splits
+            let old_split_tree = Arc::make_mut(&mut self.insertion_splits)
                 .remove(&fragment.insertion.id)
                 .unwrap();
             let mut splits_cursor = old_split_tree.cursor();
@@ -878,9 +1584,8 @@ impl Buffer {
                         prefix.end_offset = prefix.start_offset + (range.end - fragment_start);
                         prefix.id =
                             FragmentId::between(&new_fragments.last().unwrap().id, &fragment.id);
-                        if fragment.is_visible() {
-                            prefix.deletions.insert(local_timestamp);
-                        }
+                        prefix.deletions.insert(local_timestamp);
+                        prefix.visible = false;
                         fragment.start_offset = prefix.end_offset;
                         new_fragments.push(prefix.clone());
                         new_split_tree.push(InsertionSplit {
@@ -894,9 +1599,8 @@ impl Buffer {
                     }
                 } else {
                     version_in_range.observe(fragment.insertion.id);
-                    if fragment.is_visible() {
-                        fragment.deletions.insert(local_timestamp);
-                    }
+                    fragment.deletions.insert(local_timestamp);
+                    fragment.visible = false;
                 }
 
                 // If the splice ends inside this fragment, we can advance to the next splice and
@@ -935,8 +1639,7 @@ impl Buffer {
             splits_cursor.next();
             new_split_tree
                 .push_tree(splits_cursor.slice(&old_split_tree.extent::<usize>(), SeekBias::Right));
-            self.insertion_splits
-                .insert(fragment.insertion.id, new_split_tree);
+            Arc::make_mut(&mut self.insertion_splits).insert(fragment.insertion.id, new_split_tree);
             new_fragments.push(fragment);
 
             // Scan forward until we find a fragment that is not fully contained by the current splice.
@@ -946,9 +1649,8 @@ impl Buffer {
                     fragment_start = cursor.start::<usize>();
                     fragment_end = fragment_start + fragment.len();
                     if range.start < fragment_start && range.end >= fragment_end {
-                        if fragment.is_visible() {
-                            fragment.deletions.insert(local_timestamp);
-                        }
+                        fragment.deletions.insert(local_timestamp);
+                        fragment.visible = false;
                         version_in_range.observe(fragment.insertion.id);
                         new_fragments.push(fragment.clone());
                         cursor.next();
@@ -1076,8 +1778,7 @@ impl Buffer {
                 None
             };
 
-            let old_split_tree = self
-                .insertion_splits
+            let old_split_tree = Arc::make_mut(&mut self.insertion_splits)
                 .remove(&fragment.insertion.id)
                 .unwrap();
             let mut cursor = old_split_tree.cursor();
@@ -1108,8 +1809,7 @@ impl Buffer {
             new_split_tree
                 .push_tree(cursor.slice(&old_split_tree.extent::<usize>(), SeekBias::Right));
 
-            self.insertion_splits
-                .insert(fragment.insertion.id, new_split_tree);
+            Arc::make_mut(&mut self.insertion_splits).insert(fragment.insertion.id, new_split_tree);
 
             (before_range, within_range, after_range)
         }
@@ -1135,7 +1835,7 @@ impl Buffer {
             extent: text.len(),
             fragment_id: new_fragment_id.clone(),
         });
-        self.insertion_splits.insert(local_timestamp, split_tree);
+        Arc::make_mut(&mut self.insertion_splits).insert(local_timestamp, split_tree);
 
         Fragment::new(
             new_fragment_id,
@@ -1253,15 +1953,12 @@ impl Buffer {
 
     fn position_for_anchor(&self, anchor: &Anchor) -> Result<(usize, Point), Error> {
         match anchor {
-            Anchor::Start => Ok((0, Point { row: 0, column: 0 })),
-            Anchor::End => Ok((self.len(), self.fragments.extent())),
-            Anchor::Middle {
-                ref insertion_id,
-                offset,
-                ref bias,
-            } => {
+            Anchor::Start | Anchor::End => {
+                resolve_anchor(&self.fragments, &self.insertion_splits, anchor)
+            }
+            Anchor::Middle { .. } => {
                 let cached_position = {
-                    let anchor_cache = self.anchor_cache.try_borrow().ok();
+                    let anchor_cache = self.anchor_cache.try_lock().ok();
                     anchor_cache
                         .as_ref()
                         .and_then(|cache| cache.get(anchor).cloned())
@@ -1270,49 +1967,18 @@ impl Buffer {
                 if let Some(cached_position) = cached_position {
                     Ok(cached_position)
                 } else {
-                    let seek_bias = match bias {
-                        AnchorBias::Left => SeekBias::Left,
-                        AnchorBias::Right => SeekBias::Right,
-                    };
-
-                    let splits =
-                        self.insertion_splits
-                            .get(&insertion_id)
-                            .ok_or(Error::InvalidAnchor(
-                                "split does not exist for insertion id".into(),
-                            ))?;
-                    let mut splits_cursor = splits.cursor();
-                    splits_cursor.seek(offset, seek_bias);
-                    splits_cursor
-                        .item()
-                        .ok_or(Error::InvalidAnchor("split offset is out of range".into()))
-                        .and_then(|split| {
-                            let mut fragments_cursor = self.fragments.cursor();
-                            fragments_cursor.seek(&split.fragment_id, SeekBias::Left);
-                            fragments_cursor
-                                .item()
-                                .ok_or(Error::InvalidAnchor("fragment id does not exist".into()))
-                                .and_then(|fragment| {
-                                    let overshoot = if fragment.is_visible() {
-                                        offset - fragment.start_offset
-                                    } else {
-                                        0
-                                    };
-                                    let offset = fragments_cursor.start::<usize>() + overshoot;
-                                    let point = fragments_cursor.start::<Point>()
-                                        + &fragment.point_for_offset(overshoot)?;
-                                    self.cache_position(Some(anchor.clone()), offset, point);
-                                    Ok((offset, point))
-                                })
-                        })
+                    let (offset, point) =
+                        resolve_anchor(&self.fragments, &self.insertion_splits, anchor)?;
+                    self.cache_position(Some(anchor.clone()), offset, point);
+                    Ok((offset, point))
                 }
             }
         }
     }
 
-    fn offset_for_point(&self, point: Point) -> Result<usize, Error> {
+    pub fn offset_for_point(&self, point: Point) -> Result<usize, Error> {
         let cached_offset = {
-            let offset_cache = self.offset_cache.try_borrow().ok();
+            let offset_cache = self.offset_cache.try_lock().ok();
             offset_cache
                 .as_ref()
                 .and_then(|cache| cache.get(&point).cloned())
@@ -1321,22 +1987,86 @@ impl Buffer {
         if let Some(cached_offset) = cached_offset {
             Ok(cached_offset)
         } else {
-            let mut fragments_cursor = self.fragments.cursor();
-            fragments_cursor.seek(&point, SeekBias::Left);
-            fragments_cursor
-                .item()
-                .ok_or(Error::OffsetOutOfRange)
-                .map(|fragment| {
-                    let overshoot = fragment
-                        .offset_for_point(point - &fragments_cursor.start::<Point>())
-                        .unwrap();
-                    let offset = &fragments_cursor.start::<usize>() + &overshoot;
-                    self.cache_position(None, offset, point);
-                    offset
-                })
+            let offset = offset_for_point(&self.fragments, point)?;
+            self.cache_position(None, offset, point);
+            Ok(offset)
+        }
+    }
+
+    pub fn point_for_offset(&self, offset: usize) -> Result<Point, Error> {
+        point_for_offset(&self.fragments, offset)
+    }
+
+    /// Moves `point` to the nearest valid position: past the end of its row or of the buffer it
+    /// is clamped, and inside of a surrogate pair it moves to the start or end of the character
+    /// depending on `bias`.
+    pub fn clip_point(&self, point: Point, bias: AnchorBias) -> Point {
+        let max_point = self.max_point();
+        if point.row > max_point.row {
+            return max_point;
+        }
+
+        let row_len = self.len_for_row(point.row).unwrap();
+        let point = Point::new(point.row, cmp::min(point.column, row_len));
+        let offset = self.offset_for_point(point).unwrap();
+        if self.is_char_boundary(offset) {
+            point
+        } else {
+            match bias {
+                AnchorBias::Left => Point::new(point.row, point.column.saturating_sub(1)),
+                AnchorBias::Right => Point::new(point.row, point.column + 1),
+            }
+        }
+    }
+
+    pub fn clip_offset(&self, offset: usize, bias: AnchorBias) -> usize {
+        let offset = cmp::min(offset, self.len());
+        if self.is_char_boundary(offset) {
+            offset
+        } else {
+            match bias {
+                AnchorBias::Left => offset.saturating_sub(1),
+                AnchorBias::Right => offset + 1,
+            }
         }
     }
 
+    pub fn is_valid_point(&self, point: Point) -> bool {
+        self.clip_point(point, AnchorBias::Left) == point
+    }
+
+    pub fn is_valid_offset(&self, offset: usize) -> bool {
+        self.clip_offset(offset, AnchorBias::Left) == offset
+    }
+
+    fn is_char_boundary(&self, offset: usize) -> bool {
+        if offset == 0 {
+            return true;
+        }
+
+        match (self.code_unit_at(offset - 1), self.code_unit_at(offset)) {
+            (Some(prev), Some(next)) => !(is_high_surrogate(prev) && is_low_surrogate(next)),
+            _ => true,
+        }
+    }
+
+    fn code_unit_at(&self, offset: usize) -> Option<u16> {
+        let mut cursor = self.fragments.cursor();
+        cursor.seek(&offset, SeekBias::Right);
+        while let Some(fragment) = cursor.item() {
+            if fragment.is_visible() {
+                let offset_in_insertion = fragment.start_offset + offset - cursor.start::<usize>();
+                return fragment
+                    .insertion
+                    .text
+                    .code_units(offset_in_insertion..offset_in_insertion + 1)
+                    .next();
+            }
+            cursor.next();
+        }
+        None
+    }
+
     pub fn cmp_anchors(&self, a: &Anchor, b: &Anchor) -> Result<Ordering, Error> {
         let a_offset = self.offset_for_anchor(a)?;
         let b_offset = self.offset_for_anchor(b)?;
@@ -1345,15 +2075,418 @@ impl Buffer {
 
     fn cache_position(&self, anchor: Option<Anchor>, offset: usize, point: Point) {
         anchor.map(|anchor| {
-            if let Ok(mut anchor_cache) = self.anchor_cache.try_borrow_mut() {
+            if let Ok(mut anchor_cache) = self.anchor_cache.try_lock() {
                 anchor_cache.insert(anchor, (offset, point));
             }
         });
 
-        if let Ok(mut offset_cache) = self.offset_cache.try_borrow_mut() {
+        if let Ok(mut offset_cache) = self.offset_cache.try_lock() {
             offset_cache.insert(point, offset);
         }
     }
+
+    pub fn to_flatbuf<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> WIPOffset<serialization::buffer::Snapshot<'fbb>> {
+        let fragments = self.fragments.items();
+
+        let mut insertion_ids = HashSet::new();
+        let insertion_flatbufs = &fragments
+            .iter()
+            .filter(|fragment| insertion_ids.insert(fragment.insertion.id))
+            .map(|fragment| fragment.insertion.to_flatbuf(builder))
+            .collect::<Vec<_>>();
+        let insertions = Some(builder.create_vector(insertion_flatbufs));
+
+        let fragment_flatbufs = &fragments
+            .iter()
+            .map(|fragment| fragment.to_flatbuf(builder))
+            .collect::<Vec<_>>();
+        let fragments = Some(builder.create_vector(fragment_flatbufs));
+
+        let insertion_splits_flatbufs = &self
+            .insertion_splits
+            .iter()
+            .map(|(insertion_id, splits)| {
+                let split_flatbufs = &splits
+                    .items()
+                    .iter()
+                    .map(|split| {
+                        let fragment_id = Some(split.fragment_id.to_flatbuf(builder));
+                        serialization::buffer::InsertionSplit::create(
+                            builder,
+                            &serialization::buffer::InsertionSplitArgs {
+                                extent: split.extent as u64,
+                                fragment_id,
+                            },
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let splits = Some(builder.create_vector(split_flatbufs));
+                serialization::buffer::InsertionSplits::create(
+                    builder,
+                    &serialization::buffer::InsertionSplitsArgs {
+                        insertion_id: Some(&insertion_id.to_flatbuf()),
+                        splits,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let insertion_splits = Some(builder.create_vector(insertion_splits_flatbufs));
+
+        let version = Some(self.version.to_flatbuf(builder));
+
+        let undo_map_flatbufs = &self
+            .undo_map
+            .0
+            .iter()
+            .flat_map(|(edit_id, undos)| undos.iter().map(move |undo| (edit_id, undo)))
+            .map(|(edit_id, (undo_id, count))| {
+                serialization::buffer::UndoMapEntry::create(
+                    builder,
+                    &serialization::buffer::UndoMapEntryArgs {
+                        edit_id: Some(&edit_id.to_flatbuf()),
+                        undo_id: Some(&undo_id.to_flatbuf()),
+                        count: *count,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let undo_map = Some(builder.create_vector(undo_map_flatbufs));
+
+        let selection_set_flatbufs = &self
+            .selections
+            .iter()
+            .map(|(set_id, selections)| {
+                let selection_flatbufs = &selections
+                    .iter()
+                    .map(|selection| selection.to_flatbuf(builder))
+                    .collect::<Vec<_>>();
+                let selections = Some(builder.create_vector(selection_flatbufs));
+                serialization::buffer::SelectionSet::create(
+                    builder,
+                    &serialization::buffer::SelectionSetArgs {
+                        set_id: Some(&set_id.to_flatbuf()),
+                        selections,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let selection_sets = Some(builder.create_vector(selection_set_flatbufs));
+
+        let deferred_op_flatbufs = &self
+            .deferred_ops
+            .items()
+            .iter()
+            .map(|op| op.to_flatbuf(builder))
+            .collect::<Vec<_>>();
+        let deferred_operations = Some(builder.create_vector(deferred_op_flatbufs));
+
+        let edit_flatbufs = &self
+            .history
+            .edits
+            .iter()
+            .map(|(edit_id, edit)| {
+                serialization::buffer::EditRange::create(
+                    builder,
+                    &serialization::buffer::EditRangeArgs {
+                        edit_id: Some(&edit_id.to_flatbuf()),
+                        start_id: Some(&edit.start_id.to_flatbuf()),
+                        start_offset: edit.start_offset as u64,
+                        end_id: Some(&edit.end_id.to_flatbuf()),
+                        end_offset: edit.end_offset as u64,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let edits = Some(builder.create_vector(edit_flatbufs));
+
+        serialization::buffer::Snapshot::create(
+            builder,
+            &serialization::buffer::SnapshotArgs {
+                insertions,
+                fragments,
+                insertion_splits,
+                version,
+                undo_map,
+                selection_sets,
+                deferred_operations,
+                edits,
+            },
+        )
+    }
+
+    pub fn from_flatbuf<'fbb>(
+        message: serialization::buffer::Snapshot<'fbb>,
+    ) -> Result<Self, Error> {
+        let mut insertions = HashMap::new();
+        let insertion_flatbufs = message.insertions().ok_or(Error::DeserializeError)?;
+        for i in 0..insertion_flatbufs.len() {
+            let insertion = Insertion::from_flatbuf(insertion_flatbufs.get(i))?;
+            insertions.insert(insertion.id, insertion);
+        }
+
+        let mut fragments = btree::Tree::new();
+        let fragment_flatbufs = message.fragments().ok_or(Error::DeserializeError)?;
+        for i in 0..fragment_flatbufs.len() {
+            let fragment = fragment_flatbufs.get(i);
+            let insertion_id =
+                time::Local::from_flatbuf(fragment.insertion_id().ok_or(Error::DeserializeError)?);
+            let insertion = insertions
+                .get(&insertion_id)
+                .ok_or(Error::DeserializeError)?
+                .clone();
+            fragments.push(Fragment::from_flatbuf(fragment, insertion)?);
+        }
+
+        let mut insertion_splits = HashMap::new();
+        let insertion_splits_flatbufs =
+            message.insertion_splits().ok_or(Error::DeserializeError)?;
+        for i in 0..insertion_splits_flatbufs.len() {
+            let insertion_splits_flatbuf = insertion_splits_flatbufs.get(i);
+            let split_flatbufs = insertion_splits_flatbuf
+                .splits()
+                .ok_or(Error::DeserializeError)?;
+            let mut splits = btree::Tree::new();
+            for j in 0..split_flatbufs.len() {
+                let split = split_flatbufs.get(j);
+                splits.push(InsertionSplit {
+                    extent: split.extent() as usize,
+                    fragment_id: FragmentId::from_flatbuf(
+                        split.fragment_id().ok_or(Error::DeserializeError)?,
+                    ),
+                });
+            }
+            insertion_splits.insert(
+                time::Local::from_flatbuf(
+                    insertion_splits_flatbuf
+                        .insertion_id()
+                        .ok_or(Error::DeserializeError)?,
+                ),
+                splits,
+            );
+        }
+
+        let mut undo_map = UndoMap::default();
+        let undo_map_flatbufs = message.undo_map().ok_or(Error::DeserializeError)?;
+        for i in 0..undo_map_flatbufs.len() {
+            let entry = undo_map_flatbufs.get(i);
+            undo_map
+                .0
+                .entry(time::Local::from_flatbuf(
+                    entry.edit_id().ok_or(Error::DeserializeError)?,
+                ))
+                .or_insert(Vec::new())
+                .push((
+                    time::Local::from_flatbuf(entry.undo_id().ok_or(Error::DeserializeError)?),
+                    entry.count(),
+                ));
+        }
+
+        let mut selections = HashMap::new();
+        let selection_set_flatbufs = message.selection_sets().ok_or(Error::DeserializeError)?;
+        for i in 0..selection_set_flatbufs.len() {
+            let selection_set = selection_set_flatbufs.get(i);
+            let mut set = Vec::new();
+            if let Some(selection_flatbufs) = selection_set.selections() {
+                for j in 0..selection_flatbufs.len() {
+                    set.push(Selection::from_flatbuf(selection_flatbufs.get(j))?);
+                }
+            }
+            selections.insert(
+                time::Lamport::from_flatbuf(selection_set.set_id().ok_or(Error::DeserializeError)?),
+                set,
+            );
+        }
+
+        let mut deferred_ops = OperationQueue::new();
+        let mut deferred_replicas = HashSet::new();
+        let mut ops = Vec::new();
+        let op_flatbufs = message
+            .deferred_operations()
+            .ok_or(Error::DeserializeError)?;
+        for i in 0..op_flatbufs.len() {
+            if let Some(op) = Operation::from_flatbuf(&op_flatbufs.get(i))? {
+                deferred_replicas.insert(op.replica_id());
+                ops.push(op);
+            }
+        }
+        deferred_ops.insert(ops);
+
+        let mut history = History::default();
+        let edit_flatbufs = message.edits().ok_or(Error::DeserializeError)?;
+        for i in 0..edit_flatbufs.len() {
+            let edit = edit_flatbufs.get(i);
+            history.edits.insert(
+                time::Local::from_flatbuf(edit.edit_id().ok_or(Error::DeserializeError)?),
+                EditRange {
+                    start_id: time::Local::from_flatbuf(
+                        edit.start_id().ok_or(Error::DeserializeError)?,
+                    ),
+                    start_offset: edit.start_offset() as usize,
+                    end_id: time::Local::from_flatbuf(
+                        edit.end_id().ok_or(Error::DeserializeError)?,
+                    ),
+                    end_offset: edit.end_offset() as usize,
+                },
+            );
+        }
+
+        Ok(Self {
+            fragments,
+            insertion_splits: Arc::new(insertion_splits),
+            anchor_cache: Mutex::new(HashMap::default()),
+            offset_cache: Mutex::new(HashMap::default()),
+            version: time::Global::from_flatbuf(message.version().ok_or(Error::DeserializeError)?)?,
+            last_edit: time::Local::default(),
+            undo_map,
+            history,
+            selections,
+            selections_last_update: 0,
+            deferred_ops,
+            deferred_replicas,
+        })
+    }
+}
+
+impl BufferSnapshot {
+    pub fn len(&self) -> usize {
+        self.fragments.extent::<usize>()
+    }
+
+    pub fn max_point(&self) -> Point {
+        self.fragments.extent()
+    }
+
+    pub fn longest_row(&self) -> u32 {
+        self.fragments.summary().longest_row
+    }
+
+    pub fn line(&self, row: u32) -> Result<Vec<u16>, Error> {
+        let mut iterator = self.iter_at_point(Point::new(row, 0)).peekable();
+        if iterator.peek().is_none() {
+            Err(Error::OffsetOutOfRange)
+        } else {
+            Ok(iterator.take_while(|c| *c != u16::from(b'\n')).collect())
+        }
+    }
+
+    pub fn to_u16_chars(&self) -> Vec<u16> {
+        self.iter().collect::<Vec<u16>>()
+    }
+
+    pub fn to_string(&self) -> String {
+        fragments_to_string(&self.fragments)
+    }
+
+    pub fn iter(&self) -> Iter {
+        Iter::new(&self.fragments)
+    }
+
+    pub fn iter_at_point(&self, point: Point) -> Iter {
+        Iter::at_point(&self.fragments, point)
+    }
+
+    pub fn point_for_offset(&self, offset: usize) -> Result<Point, Error> {
+        point_for_offset(&self.fragments, offset)
+    }
+
+    pub fn offset_for_point(&self, point: Point) -> Result<usize, Error> {
+        offset_for_point(&self.fragments, point)
+    }
+
+    pub fn offset_for_anchor(&self, anchor: &Anchor) -> Result<usize, Error> {
+        Ok(resolve_anchor(&self.fragments, &self.insertion_splits, anchor)?.0)
+    }
+
+    pub fn point_for_anchor(&self, anchor: &Anchor) -> Result<Point, Error> {
+        Ok(resolve_anchor(&self.fragments, &self.insertion_splits, anchor)?.1)
+    }
+
+    pub fn cmp_anchors(&self, a: &Anchor, b: &Anchor) -> Result<Ordering, Error> {
+        let a_offset = self.offset_for_anchor(a)?;
+        let b_offset = self.offset_for_anchor(b)?;
+        Ok(a_offset.cmp(&b_offset))
+    }
+}
+
+fn resolve_anchor(
+    fragments: &btree::Tree<Fragment>,
+    insertion_splits: &HashMap<time::Local, btree::Tree<InsertionSplit>>,
+    anchor: &Anchor,
+) -> Result<(usize, Point), Error> {
+    match anchor {
+        Anchor::Start => Ok((0, Point { row: 0, column: 0 })),
+        Anchor::End => Ok((fragments.extent(), fragments.extent())),
+        Anchor::Middle {
+            ref insertion_id,
+            offset,
+            ref bias,
+        } => {
+            let seek_bias = match bias {
+                AnchorBias::Left => SeekBias::Left,
+                AnchorBias::Right => SeekBias::Right,
+            };
+
+            let splits = insertion_splits
+                .get(&insertion_id)
+                .ok_or(Error::InvalidAnchor(
+                    "split does not exist for insertion id".into(),
+                ))?;
+            let mut splits_cursor = splits.cursor();
+            splits_cursor.seek(offset, seek_bias);
+            splits_cursor
+                .item()
+                .ok_or(Error::InvalidAnchor("split offset is out of range".into()))
+                .and_then(|split| {
+                    let mut fragments_cursor = fragments.cursor();
+                    fragments_cursor.seek(&split.fragment_id, SeekBias::Left);
+                    fragments_cursor
+                        .item()
+                        .ok_or(Error::InvalidAnchor("fragment id does not exist".into()))
+                        .and_then(|fragment| {
+                            let overshoot = if fragment.is_visible() {
+                                offset - fragment.start_offset
+                            } else {
+                                0
+                            };
+                            let offset = fragments_cursor.start::<usize>() + overshoot;
+                            let point = fragments_cursor.start::<Point>()
+                                + &fragment.point_for_offset(overshoot)?;
+                            Ok((offset, point))
+                        })
+                })
+        }
+    }
+}
+
+fn point_for_offset(fragments: &btree::Tree<Fragment>, offset: usize) -> Result<Point, Error> {
+    if offset > fragments.extent::<usize>() {
+        return Err(Error::OffsetOutOfRange);
+    }
+
+    let mut cursor = fragments.cursor();
+    cursor.seek(&offset, SeekBias::Left);
+    if let Some(fragment) = cursor.item() {
+        let overshoot = fragment.point_for_offset(offset - cursor.start::<usize>())?;
+        Ok(cursor.start::<Point>() + &overshoot)
+    } else {
+        Ok(cursor.start::<Point>())
+    }
+}
+
+fn offset_for_point(fragments: &btree::Tree<Fragment>, point: Point) -> Result<usize, Error> {
+    let mut cursor = fragments.cursor();
+    cursor.seek(&point, SeekBias::Left);
+    if let Some(fragment) = cursor.item() {
+        let overshoot = fragment.offset_for_point(point - &cursor.start::<Point>())?;
+        Ok(cursor.start::<usize>() + overshoot)
+    } else if point == cursor.start::<Point>() {
+        Ok(cursor.start::<usize>())
+    } else {
+        Err(Error::OffsetOutOfRange)
+    }
 }
 
 impl Point {
@@ -1437,7 +2570,7 @@ impl Ord for Point {
 }
 
 impl Anchor {
-    fn to_flatbuf<'fbb>(
+    pub(crate) fn to_flatbuf<'fbb>(
         &self,
         builder: &mut FlatBufferBuilder<'fbb>,
     ) -> WIPOffset<serialization::buffer::Anchor<'fbb>> {
@@ -1472,7 +2605,7 @@ impl Anchor {
         }
     }
 
-    fn from_flatbuf<'fbb>(
+    pub(crate) fn from_flatbuf<'fbb>(
         message: &serialization::buffer::Anchor<'fbb>,
     ) -> Result<Self, crate::Error> {
         match message.variant() {
@@ -1507,21 +2640,95 @@ impl AnchorBias {
     }
 }
 
+impl SearchQuery {
+    pub fn new(pattern: &str, options: SearchOptions) -> Result<Self, Error> {
+        let mut pattern = if options.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        if options.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(options.case_insensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|error| Error::InvalidSearchQuery(error.to_string().into()))?;
+        let spans_rows = regex_syntax::ParserBuilder::new()
+            .case_insensitive(options.case_insensitive)
+            .multi_line(true)
+            .build()
+            .parse(&pattern)
+            .ok()
+            .map_or(true, |hir| Self::matches_newline(&hir));
+        Ok(Self { regex, spans_rows })
+    }
+
+    fn matches_newline(hir: &Hir) -> bool {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => false,
+            HirKind::Literal(hir::Literal::Unicode(c)) => *c == '\n',
+            HirKind::Literal(hir::Literal::Byte(b)) => *b == b'\n',
+            HirKind::Class(hir::Class::Unicode(class)) => class
+                .iter()
+                .any(|range| range.start() <= '\n' && '\n' <= range.end()),
+            HirKind::Class(hir::Class::Bytes(class)) => class
+                .iter()
+                .any(|range| range.start() <= b'\n' && b'\n' <= range.end()),
+            HirKind::Repetition(repetition) => Self::matches_newline(&repetition.hir),
+            HirKind::Group(group) => Self::matches_newline(&group.hir),
+            HirKind::Concat(hirs) | HirKind::Alternation(hirs) => {
+                hirs.iter().any(Self::matches_newline)
+            }
+        }
+    }
+
+    /// Returns the ranges of the non-empty matches in `text`, in UTF-16 code units relative to
+    /// `start`. Each search resumes right after the start of the previous match rather than at
+    /// its end, so matches that overlap are all reported.
+    fn ranges(&self, text: &str, start: usize) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut prev_start_utf8 = 0;
+        let mut prev_start = start;
+        let mut search_start_utf8 = 0;
+        while let Some(mat) = self.regex.find_at(text, search_start_utf8) {
+            let start = prev_start + text[prev_start_utf8..mat.start()].encode_utf16().count();
+            if mat.start() < mat.end() {
+                ranges.push(start..start + mat.as_str().encode_utf16().count());
+            }
+            prev_start_utf8 = mat.start();
+            prev_start = start;
+
+            match text[mat.start()..].chars().next() {
+                Some(c) => search_start_utf8 = mat.start() + c.len_utf8(),
+                None => break,
+            }
+        }
+        ranges
+    }
+}
+
 impl Iter {
-    fn new(buffer: &Buffer) -> Self {
-        let mut fragment_cursor = buffer.fragments.cursor();
+    fn new(fragments: &btree::Tree<Fragment>) -> Self {
+        let mut fragment_cursor = fragments.cursor();
         fragment_cursor.seek(&0, SeekBias::Right);
+        let fragment = fragment_cursor.item();
         Self {
             fragment_cursor,
+            fragment,
             fragment_offset: 0,
+            chunk: None,
             reversed: false,
         }
     }
 
-    fn at_point(buffer: &Buffer, point: Point) -> Self {
-        let mut fragment_cursor = buffer.fragments.cursor();
+    fn at_point(fragments: &btree::Tree<Fragment>, point: Point) -> Self {
+        let mut fragment_cursor = fragments.cursor();
         fragment_cursor.seek(&point, SeekBias::Right);
-        let fragment_offset = if let Some(fragment) = fragment_cursor.item() {
+        let fragment = fragment_cursor.item();
+        let fragment_offset = if let Some(fragment) = fragment.as_ref() {
             let point_in_fragment = point - &fragment_cursor.start::<Point>();
             fragment.offset_for_point(point_in_fragment).unwrap()
         } else {
@@ -1530,7 +2737,9 @@ impl Iter {
 
         Self {
             fragment_cursor,
+            fragment,
             fragment_offset,
+            chunk: None,
             reversed: false,
         }
     }
@@ -1543,6 +2752,26 @@ impl Iter {
     pub fn into_string(self) -> String {
         String::from_utf16_lossy(&self.collect::<Vec<u16>>())
     }
+
+    fn code_unit(&mut self) -> u16 {
+        let fragment = self.fragment.as_ref().unwrap();
+        let offset = fragment.start_offset + self.fragment_offset;
+        let is_cached =
+            self.chunk
+                .as_ref()
+                .map_or(false, |(insertion_id, chunk_start, code_units)| {
+                    *insertion_id == fragment.insertion.id
+                        && *chunk_start <= offset
+                        && offset < chunk_start + code_units.len()
+                });
+        if !is_cached {
+            let (chunk_start, code_units) = fragment.insertion.text.chunk_at(offset).unwrap();
+            self.chunk = Some((fragment.insertion.id, chunk_start, code_units));
+        }
+
+        let (_, chunk_start, code_units) = self.chunk.as_ref().unwrap();
+        code_units[offset - chunk_start]
+    }
 }
 
 impl Iterator for Iter {
@@ -1550,49 +2779,28 @@ impl Iterator for Iter {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.reversed {
-            if let Some(fragment) = self.fragment_cursor.item() {
-                if self.fragment_offset > 0 {
+            loop {
+                if self.fragment.is_some() && self.fragment_offset > 0 {
                     self.fragment_offset -= 1;
-                    if let Some(c) = fragment.code_unit(self.fragment_offset) {
-                        return Some(c);
-                    }
+                    return Some(self.code_unit());
                 }
-            }
 
-            loop {
                 self.fragment_cursor.prev();
-                if let Some(fragment) = self.fragment_cursor.item() {
-                    if fragment.len() > 0 {
-                        self.fragment_offset = fragment.len() - 1;
-                        return fragment.code_unit(self.fragment_offset);
-                    }
-                } else {
-                    break;
-                }
+                self.fragment = self.fragment_cursor.item();
+                self.fragment_offset = self.fragment.as_ref()?.len();
             }
-
-            None
         } else {
-            if let Some(fragment) = self.fragment_cursor.item() {
-                if let Some(c) = fragment.code_unit(self.fragment_offset) {
+            loop {
+                if self.fragment_offset < self.fragment.as_ref()?.len() {
+                    let code_unit = self.code_unit();
                     self.fragment_offset += 1;
-                    return Some(c);
+                    return Some(code_unit);
                 }
-            }
 
-            loop {
                 self.fragment_cursor.next();
-                if let Some(fragment) = self.fragment_cursor.item() {
-                    if let Some(c) = fragment.code_unit(0) {
-                        self.fragment_offset = 1;
-                        return Some(c);
-                    }
-                } else {
-                    break;
-                }
+                self.fragment = self.fragment_cursor.item();
+                self.fragment_offset = 0;
             }
-
-            None
         }
     }
 }
@@ -1605,7 +2813,7 @@ impl<F: Fn(&FragmentSummary) -> bool> Iterator for ChangesIter<F> {
 
         while let Some(fragment) = self.cursor.item() {
             let position = self.cursor.start();
-            if !fragment.was_visible(&self.since) && fragment.is_visible() {
+            if !fragment.was_visible(&self.since, &self.undo_map) && fragment.is_visible() {
                 if let Some(ref mut change) = change {
                     if change.range.start + &change.new_extent == position {
                         change.code_units.extend(fragment.code_units());
@@ -1616,11 +2824,11 @@ impl<F: Fn(&FragmentSummary) -> bool> Iterator for ChangesIter<F> {
                 } else {
                     change = Some(Change {
                         range: position..position,
-                        code_units: Vec::from(fragment.code_units()),
+                        code_units: fragment.code_units().collect(),
                         new_extent: fragment.extent_2d(),
                     });
                 }
-            } else if fragment.was_visible(&self.since) && !fragment.is_visible() {
+            } else if fragment.was_visible(&self.since, &self.undo_map) && !fragment.is_visible() {
                 if let Some(ref mut change) = change {
                     if change.range.start + &change.new_extent == position {
                         change.range.end += &fragment.extent_2d();
@@ -1708,36 +2916,163 @@ pub fn diff(a: &[u16], b: &[u16]) -> Vec<Change> {
     collector.into_inner().changes
 }
 
-impl Selection {
-    pub fn head(&self) -> &Anchor {
-        if self.reversed {
-            &self.start
-        } else {
-            &self.end
+pub fn line_diff(old_text: &str, new_text: &str) -> Vec<LineDiffHunk> {
+    struct HunkCollector<'a> {
+        old_lines: &'a [&'a str],
+        new_lines: &'a [&'a str],
+        new_row: usize,
+        hunks: Vec<LineDiffHunk>,
+    }
+
+    impl<'a> HunkCollector<'a> {
+        fn push_hunk(&mut self, old: usize, old_len: usize, new: usize, new_len: usize) {
+            self.hunks.push(LineDiffHunk {
+                old_rows: old as u32..(old + old_len) as u32,
+                new_rows: new as u32..(new + new_len) as u32,
+                old_text: self.old_lines[old..old + old_len].concat(),
+                new_text: self.new_lines[new..new + new_len].concat(),
+            });
+            self.new_row = new + new_len;
         }
     }
 
-    pub fn set_head<S>(&mut self, buffer: &Buffer, cursor: Anchor) {
-        if buffer.cmp_anchors(&cursor, self.tail()).unwrap() < Ordering::Equal {
-            if !self.reversed {
-                mem::swap(&mut self.start, &mut self.end);
-                self.reversed = true;
-            }
-            self.start = cursor;
-        } else {
-            if self.reversed {
-                mem::swap(&mut self.start, &mut self.end);
-                self.reversed = false;
-            }
-            self.end = cursor;
-        }
-    }
+    impl<'a> diffs::Diff for HunkCollector<'a> {
+        type Error = ();
 
-    pub fn tail(&self) -> &Anchor {
-        if self.reversed {
-            &self.end
-        } else {
-            &self.start
+        fn equal(&mut self, _: usize, new: usize, len: usize) -> Result<(), ()> {
+            self.new_row = new + len;
+            Ok(())
+        }
+
+        fn delete(&mut self, old: usize, len: usize) -> Result<(), ()> {
+            let new_row = self.new_row;
+            self.push_hunk(old, len, new_row, 0);
+            Ok(())
+        }
+
+        fn insert(&mut self, old: usize, new: usize, new_len: usize) -> Result<(), ()> {
+            self.push_hunk(old, 0, new, new_len);
+            Ok(())
+        }
+
+        fn replace(
+            &mut self,
+            old: usize,
+            old_len: usize,
+            new: usize,
+            new_len: usize,
+        ) -> Result<(), ()> {
+            self.push_hunk(old, old_len, new, new_len);
+            Ok(())
+        }
+    }
+
+    let old_lines = split_lines(old_text);
+    let new_lines = split_lines(new_text);
+    let mut collector = diffs::Replace::new(HunkCollector {
+        old_lines: &old_lines,
+        new_lines: &new_lines,
+        new_row: 0,
+        hunks: Vec::new(),
+    });
+    diffs::myers::diff(
+        &mut collector,
+        &old_lines,
+        0,
+        old_lines.len(),
+        &new_lines,
+        0,
+        new_lines.len(),
+    )
+    .unwrap();
+    collector.into_inner().hunks
+}
+
+/// Applies the changes between `base` and `local` on top of `upstream`. Local hunks that overlap
+/// with changes between `base` and `upstream` are left out of the merged text and returned.
+pub fn line_merge(base: &str, local: &str, upstream: &str) -> (String, Vec<LineDiffHunk>) {
+    let upstream_hunks = line_diff(base, upstream);
+    let upstream_lines = split_lines(upstream);
+    let mut merged = String::new();
+    let mut conflicting_hunks = Vec::new();
+    let mut upstream_row = 0;
+    for hunk in line_diff(base, local) {
+        let mut row_delta = 0;
+        let mut overlaps = false;
+        for upstream_hunk in &upstream_hunks {
+            if upstream_hunk.old_rows.start == hunk.old_rows.start
+                || (upstream_hunk.old_rows.start < hunk.old_rows.end
+                    && hunk.old_rows.start < upstream_hunk.old_rows.end)
+            {
+                overlaps = true;
+                break;
+            } else if upstream_hunk.old_rows.end <= hunk.old_rows.start {
+                row_delta += upstream_hunk.new_rows.len() as isize;
+                row_delta -= upstream_hunk.old_rows.len() as isize;
+            }
+        }
+
+        if overlaps {
+            conflicting_hunks.push(hunk);
+        } else {
+            let start = (hunk.old_rows.start as isize + row_delta) as usize;
+            for line in &upstream_lines[upstream_row..start] {
+                merged.push_str(line);
+            }
+            merged.push_str(&hunk.new_text);
+            upstream_row = start + hunk.old_rows.len();
+        }
+    }
+    for line in &upstream_lines[upstream_row..] {
+        merged.push_str(line);
+    }
+
+    (merged, conflicting_hunks)
+}
+
+fn split_lines(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (ix, _) in text.match_indices('\n') {
+        lines.push(&text[start..ix + 1]);
+        start = ix + 1;
+    }
+    if start < text.len() {
+        lines.push(&text[start..]);
+    }
+    lines
+}
+
+impl Selection {
+    pub fn head(&self) -> &Anchor {
+        if self.reversed {
+            &self.start
+        } else {
+            &self.end
+        }
+    }
+
+    pub fn set_head<S>(&mut self, buffer: &Buffer, cursor: Anchor) {
+        if buffer.cmp_anchors(&cursor, self.tail()).unwrap() < Ordering::Equal {
+            if !self.reversed {
+                mem::swap(&mut self.start, &mut self.end);
+                self.reversed = true;
+            }
+            self.start = cursor;
+        } else {
+            if self.reversed {
+                mem::swap(&mut self.start, &mut self.end);
+                self.reversed = false;
+            }
+            self.end = cursor;
+        }
+    }
+
+    pub fn tail(&self) -> &Anchor {
+        if self.reversed {
+            &self.end
+        } else {
+            &self.start
         }
     }
 
@@ -1779,98 +3114,32 @@ impl Selection {
 
 impl Text {
     pub fn new(code_units: Vec<u16>) -> Self {
-        fn build_tree(index: usize, line_lengths: &[u32], mut tree: &mut [LineNode]) {
-            if line_lengths.is_empty() {
-                return;
-            }
+        Self::from_code_units(code_units)
+    }
 
-            let mid = if line_lengths.len() == 1 {
-                0
-            } else {
-                let depth = log2_fast(line_lengths.len());
-                let max_elements = (1 << (depth)) - 1;
-                let right_subtree_elements = 1 << (depth - 1);
-                cmp::min(line_lengths.len() - right_subtree_elements, max_elements)
-            };
-            let len = line_lengths[mid];
-            let lower = &line_lengths[0..mid];
-            let upper = &line_lengths[mid + 1..];
-
-            let left_child_index = index * 2 + 1;
-            let right_child_index = index * 2 + 2;
-            build_tree(left_child_index, lower, &mut tree);
-            build_tree(right_child_index, upper, &mut tree);
-            tree[index] = {
-                let mut left_child_longest_row = 0;
-                let mut left_child_longest_row_len = 0;
-                let mut left_child_offset = 0;
-                let mut left_child_rows = 0;
-                if let Some(left_child) = tree.get(left_child_index) {
-                    left_child_longest_row = left_child.longest_row;
-                    left_child_longest_row_len = left_child.longest_row_len;
-                    left_child_offset = left_child.offset;
-                    left_child_rows = left_child.rows;
-                }
-                let mut right_child_longest_row = 0;
-                let mut right_child_longest_row_len = 0;
-                let mut right_child_offset = 0;
-                let mut right_child_rows = 0;
-                if let Some(right_child) = tree.get(right_child_index) {
-                    right_child_longest_row = right_child.longest_row;
-                    right_child_longest_row_len = right_child.longest_row_len;
-                    right_child_offset = right_child.offset;
-                    right_child_rows = right_child.rows;
-                }
-
-                let mut longest_row = 0;
-                let mut longest_row_len = 0;
-                if left_child_longest_row_len > longest_row_len {
-                    longest_row = left_child_longest_row;
-                    longest_row_len = left_child_longest_row_len;
-                }
-                if len > longest_row_len {
-                    longest_row = left_child_rows;
-                    longest_row_len = len;
-                }
-                if right_child_longest_row_len > longest_row_len {
-                    longest_row = left_child_rows + right_child_longest_row + 1;
-                    longest_row_len = right_child_longest_row_len;
-                }
-
-                LineNode {
-                    len,
-                    longest_row,
-                    longest_row_len,
-                    offset: left_child_offset + len as usize + right_child_offset + 1,
-                    rows: left_child_rows + right_child_rows + 1,
+    fn from_code_units<I>(code_units: I) -> Self
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        let mut code_units = code_units.into_iter();
+        let mut chunk = Vec::with_capacity(TEXT_CHUNK_LEN + 1);
+        let mut chunks = btree::Tree::new();
+        chunks.extend(
+            iter::repeat_with(|| {
+                chunk.clear();
+                for code_unit in &mut code_units {
+                    chunk.push(code_unit);
+                    // Never split a surrogate pair across chunks, so that chunk boundaries are
+                    // always char boundaries.
+                    if chunk.len() >= TEXT_CHUNK_LEN && !is_high_surrogate(code_unit) {
+                        break;
+                    }
                 }
-            };
-        }
-
-        let mut line_lengths = Vec::new();
-        let mut prev_offset = 0;
-        for (offset, code_unit) in code_units.iter().enumerate() {
-            if code_unit == &u16::from(b'\n') {
-                line_lengths.push((offset - prev_offset) as u32);
-                prev_offset = offset + 1;
-            }
-        }
-        line_lengths.push((code_units.len() - prev_offset) as u32);
-
-        let mut nodes = Vec::new();
-        nodes.resize(
-            line_lengths.len(),
-            LineNode {
-                len: 0,
-                longest_row_len: 0,
-                longest_row: 0,
-                offset: 0,
-                rows: 0,
-            },
+                TextChunk::new(&chunk)
+            })
+            .take_while(|chunk| !chunk.bytes.is_empty()),
         );
-        build_tree(0, &line_lengths, &mut nodes);
-
-        Self { code_units, nodes }
+        Self { chunks }
     }
 
     fn extent(code_units: &[u16]) -> Point {
@@ -1888,158 +3157,220 @@ impl Text {
     }
 
     fn len(&self) -> usize {
-        self.code_units.len()
+        self.chunks.extent::<usize>()
     }
 
-    fn longest_row_in_range(&self, target_range: Range<usize>) -> Result<(u32, u32), Error> {
-        let mut longest_row = 0;
-        let mut longest_row_len = 0;
-
-        self.search(|probe| {
-            if target_range.start <= probe.offset_range.end
-                && probe.right_ancestor_start_offset <= target_range.end
-            {
-                if let Some(right_child) = probe.right_child {
-                    if right_child.longest_row_len >= longest_row_len {
-                        longest_row = probe.row + 1 + right_child.longest_row;
-                        longest_row_len = right_child.longest_row_len;
-                    }
-                }
-            }
+    fn code_units(&self, range: Range<usize>) -> impl Iterator<Item = u16> {
+        let mut cursor = self.chunks.cursor();
+        cursor.seek(&range.start, SeekBias::Right);
+        let start_in_chunk = range.start - cursor.start::<usize>();
+        cursor
+            .flat_map(|chunk| chunk.code_units())
+            .skip(start_in_chunk)
+            .take(range.end - range.start)
+    }
 
-            if target_range.start < probe.offset_range.start {
-                if probe.offset_range.end < target_range.end && probe.node.len >= longest_row_len {
-                    longest_row = probe.row;
-                    longest_row_len = probe.node.len;
-                }
+    /// Returns the code units of the chunk containing `offset` along with the offset at which that
+    /// chunk starts.
+    fn chunk_at(&self, offset: usize) -> Option<(usize, Vec<u16>)> {
+        let mut cursor = self.chunks.cursor();
+        cursor.seek(&offset, SeekBias::Right);
+        cursor
+            .item()
+            .map(|chunk| (cursor.start::<usize>(), chunk.code_units()))
+    }
 
-                Ordering::Less
-            } else if target_range.start > probe.offset_range.end {
-                Ordering::Greater
-            } else {
-                let node_end = cmp::min(probe.offset_range.end, target_range.end);
-                let node_len = (node_end - target_range.start) as u32;
-                if node_len >= longest_row_len {
-                    longest_row = probe.row;
-                    longest_row_len = node_len;
-                }
-                Ordering::Equal
+    /// Appends the WTF-8 encoding of the code units in `range` to `bytes`.
+    fn write_wtf8(&self, range: Range<usize>, bytes: &mut Vec<u8>) {
+        let mut cursor = self.chunks.cursor();
+        cursor.seek(&range.start, SeekBias::Right);
+        while let Some(chunk) = cursor.item() {
+            let chunk_start = cursor.start::<usize>();
+            if chunk_start >= range.end {
+                break;
             }
-        })
-        .ok_or(Error::OffsetOutOfRange)?;
 
-        self.search(|probe| {
-            if target_range.end >= probe.offset_range.start
-                && probe.left_ancestor_end_offset >= target_range.start
-            {
-                if let Some(left_child) = probe.left_child {
-                    if left_child.longest_row_len > longest_row_len {
-                        let left_ancestor_row = probe.row - left_child.rows;
-                        longest_row = left_ancestor_row + left_child.longest_row;
-                        longest_row_len = left_child.longest_row_len;
+            let mut offset = chunk_start;
+            let mut chunk_bytes = &chunk.bytes[..];
+            while !chunk_bytes.is_empty() && offset < range.end {
+                let (code_point, len) = decode_wtf8_code_point(chunk_bytes);
+                if code_point < 0x10000 {
+                    if offset >= range.start {
+                        bytes.extend_from_slice(&chunk_bytes[..len]);
+                    }
+                    offset += 1;
+                } else if offset >= range.start && offset + 2 <= range.end {
+                    bytes.extend_from_slice(&chunk_bytes[..len]);
+                    offset += 2;
+                } else {
+                    // The range splits a surrogate pair, so only encode the half inside of it.
+                    let code_point = code_point - 0x10000;
+                    let high = 0xd800 | (code_point >> 10) as u16;
+                    let low = 0xdc00 | (code_point & 0x3ff) as u16;
+                    for code_unit in &[high, low] {
+                        if offset >= range.start && offset < range.end {
+                            encode_wtf8_code_point(u32::from(*code_unit), bytes);
+                        }
+                        offset += 1;
                     }
                 }
+                chunk_bytes = &chunk_bytes[len..];
             }
+            cursor.next();
+        }
+    }
 
-            if target_range.end < probe.offset_range.start {
-                Ordering::Less
-            } else if target_range.end > probe.offset_range.end {
-                if target_range.start < probe.offset_range.start && probe.node.len > longest_row_len
-                {
-                    longest_row = probe.row;
-                    longest_row_len = probe.node.len;
-                }
-
-                Ordering::Greater
-            } else {
-                let node_start = cmp::max(target_range.start, probe.offset_range.start);
-                let node_len = (target_range.end - node_start) as u32;
-                if node_len > longest_row_len {
-                    longest_row = probe.row;
-                    longest_row_len = node_len;
+    fn summary(&self, range: Range<usize>) -> TextSummary {
+        let mut summary = TextSummary::default();
+        let mut cursor = self.chunks.cursor();
+        cursor.seek(&range.start, SeekBias::Right);
+        if let Some(chunk) = cursor.item() {
+            let chunk_start = cursor.start::<usize>();
+            let chunk_end = cursor.end::<usize>();
+            let end_in_chunk = cmp::min(range.end, chunk_end) - chunk_start;
+            let code_units = chunk.code_units();
+            summary += &TextSummary::of(&code_units[range.start - chunk_start..end_in_chunk]);
+
+            if range.end > chunk_end {
+                cursor.next();
+                summary += &cursor.summary(&range.end, SeekBias::Right);
+                if let Some(chunk) = cursor.item() {
+                    let end_in_chunk = range.end - cursor.start::<usize>();
+                    summary += &TextSummary::of(&chunk.code_units()[..end_in_chunk]);
                 }
-                Ordering::Equal
             }
-        })
-        .ok_or(Error::OffsetOutOfRange)?;
+        }
+        summary
+    }
+
+    fn len_at(&self, offset: usize) -> TextLen {
+        let mut cursor = self.chunks.cursor();
+        cursor.seek(&offset, SeekBias::Left);
+        let mut len = cursor.start::<TextLen>();
+        if let Some(chunk) = cursor.item() {
+            len += &TextLen::of(&chunk.code_units()[..offset - cursor.start::<usize>()]);
+        }
+        len
+    }
 
-        Ok((longest_row, longest_row_len))
+    fn len_in_range(&self, range: Range<usize>) -> TextLen {
+        self.len_at(range.end) - self.len_at(range.start)
     }
 
-    fn point_for_offset(&self, offset: usize) -> Result<Point, Error> {
-        let search_result = self.search(|probe| {
-            if offset < probe.offset_range.start {
-                Ordering::Less
-            } else if offset > probe.offset_range.end {
-                Ordering::Greater
-            } else {
-                Ordering::Equal
-            }
-        });
-        if let Some((offset_range, row, _)) = search_result {
-            Ok(Point::new(row, (offset - offset_range.start) as u32))
+    /// Returns the offset at which the text starting at `start` reaches `len` in the given unit.
+    fn offset_for_len(&self, start: usize, len: usize, unit: OffsetUnit) -> Result<usize, Error> {
+        if len == 0 {
+            return Ok(start);
+        }
+
+        let target = self.len_at(start).get(unit) + len;
+        let mut cursor = self.chunks.cursor();
+        match unit {
+            OffsetUnit::Utf8 => cursor.seek(&Utf8Offset(target), SeekBias::Left),
+            OffsetUnit::Utf16 => cursor.seek(&target, SeekBias::Left),
+            OffsetUnit::Char => cursor.seek(&CharOffset(target), SeekBias::Left),
+        };
+        let chunk = cursor.item().ok_or(Error::OffsetOutOfRange)?;
+        let mut offset = cursor.start::<usize>();
+        let mut cur_len = cursor.start::<TextLen>().get(unit);
+        let chunk_code_units = chunk.code_units();
+        let mut code_units = chunk_code_units.iter();
+        let mut prev_code_unit = None;
+        while cur_len < target {
+            let code_unit = code_units.next().ok_or(Error::OffsetOutOfRange)?;
+            cur_len += TextLen::of(&[*code_unit]).get(unit);
+            offset += 1;
+            prev_code_unit = Some(*code_unit);
+        }
+
+        let is_char_boundary = match (prev_code_unit, code_units.next()) {
+            (Some(prev), Some(next)) => !(is_high_surrogate(prev) && is_low_surrogate(*next)),
+            _ => true,
+        };
+        if cur_len == target && is_char_boundary {
+            Ok(offset)
         } else {
             Err(Error::OffsetOutOfRange)
         }
     }
 
-    fn offset_for_point(&self, point: Point) -> Result<usize, Error> {
-        if let Some((offset_range, _, node)) = self.search(|probe| point.row.cmp(&probe.row)) {
-            if point.column <= node.len {
-                Ok(offset_range.start + point.column as usize)
-            } else {
-                Err(Error::OffsetOutOfRange)
-            }
+    fn point_for_offset(&self, offset: usize) -> Result<Point, Error> {
+        if offset > self.len() {
+            return Err(Error::OffsetOutOfRange);
+        }
+
+        let mut cursor = self.chunks.cursor();
+        cursor.seek(&offset, SeekBias::Left);
+        let chunk_start = cursor.start::<Point>();
+        if let Some(chunk) = cursor.item() {
+            let overshoot = offset - cursor.start::<usize>();
+            Ok(chunk_start + &Self::extent(&chunk.code_units()[..overshoot]))
         } else {
-            Err(Error::OffsetOutOfRange)
+            Ok(chunk_start)
         }
     }
 
-    fn search<F>(&self, mut f: F) -> Option<(Range<usize>, u32, &LineNode)>
-    where
-        F: FnMut(LineNodeProbe) -> Ordering,
-    {
-        let mut left_ancestor_end_offset = 0;
-        let mut left_ancestor_row = 0;
-        let mut right_ancestor_start_offset = self.nodes[0].offset;
-        let mut cur_node_index = 0;
-        while let Some(cur_node) = self.nodes.get(cur_node_index) {
-            let left_child = self.nodes.get(cur_node_index * 2 + 1);
-            let right_child = self.nodes.get(cur_node_index * 2 + 2);
-            let cur_offset_range = {
-                let start = left_ancestor_end_offset + left_child.map_or(0, |node| node.offset);
-                let end = start + cur_node.len as usize;
-                start..end
-            };
-            let cur_row = left_ancestor_row + left_child.map_or(0, |node| node.rows);
-            match f(LineNodeProbe {
-                offset_range: &cur_offset_range,
-                row: cur_row,
-                left_ancestor_end_offset,
-                right_ancestor_start_offset,
-                node: cur_node,
-                left_child,
-                right_child,
-            }) {
-                Ordering::Less => {
-                    cur_node_index = cur_node_index * 2 + 1;
-                    right_ancestor_start_offset = cur_offset_range.start;
-                }
-                Ordering::Equal => return Some((cur_offset_range, cur_row, cur_node)),
-                Ordering::Greater => {
-                    cur_node_index = cur_node_index * 2 + 2;
-                    left_ancestor_end_offset = cur_offset_range.end + 1;
-                    left_ancestor_row = cur_row + 1;
+    fn offset_for_point(&self, point: Point) -> Result<usize, Error> {
+        let mut cursor = self.chunks.cursor();
+        cursor.seek(&point, SeekBias::Left);
+        let mut offset = cursor.start::<usize>();
+        let mut cur_point = cursor.start::<Point>();
+        if let Some(chunk) = cursor.item() {
+            for code_unit in chunk.code_units() {
+                if cur_point == point {
+                    break;
                 }
+
+                if code_unit == b'\n' as u16 {
+                    if cur_point.row == point.row {
+                        break;
+                    }
+                    cur_point.row += 1;
+                    cur_point.column = 0;
+                } else {
+                    cur_point.column += 1;
+                }
+                offset += 1;
             }
         }
-        None
+
+        if cur_point == point {
+            Ok(offset)
+        } else {
+            Err(Error::OffsetOutOfRange)
+        }
+    }
+}
+
+impl PartialEq for Text {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .code_units(0..self.len())
+                .eq(other.code_units(0..other.len()))
     }
 }
 
+impl Eq for Text {}
+
 impl<'a> From<&'a str> for Text {
     fn from(s: &'a str) -> Self {
-        Self::new(s.encode_utf16().collect())
+        // UTF-8 is already valid WTF-8, so chunks copy the string's bytes instead of re-encoding it.
+        let mut chunks = btree::Tree::new();
+        let mut chunk_start = 0;
+        let mut chunk_len = 0;
+        for (offset, ch) in s.char_indices() {
+            if chunk_len >= TEXT_CHUNK_LEN {
+                chunks.push(TextChunk::from_utf8(&s[chunk_start..offset]));
+                chunk_start = offset;
+                chunk_len = 0;
+            }
+            chunk_len += ch.len_utf16();
+        }
+        if chunk_start < s.len() {
+            chunks.push(TextChunk::from_utf8(&s[chunk_start..]));
+        }
+        Self { chunks }
     }
 }
 
@@ -2055,9 +3386,314 @@ impl<'a> From<Vec<u16>> for Text {
     }
 }
 
-#[inline(always)]
-fn log2_fast(x: usize) -> usize {
-    8 * mem::size_of::<usize>() - (x.leading_zeros() as usize) - 1
+impl TextLen {
+    fn of(code_units: &[u16]) -> Self {
+        let mut len = TextLen {
+            utf8: 0,
+            utf16: code_units.len(),
+            chars: 0,
+        };
+        for code_unit in code_units {
+            match *code_unit {
+                0..=0x7f => len.utf8 += 1,
+                0x80..=0x7ff => len.utf8 += 2,
+                // A surrogate pair is a single char encoded as 4 bytes of UTF-8. Splitting the bytes
+                // between the two halves and counting the char at the low surrogate keeps lengths
+                // additive when a pair straddles two fragments.
+                0xd800..=0xdbff => {
+                    len.utf8 += 2;
+                    continue;
+                }
+                0xdc00..=0xdfff => len.utf8 += 2,
+                _ => len.utf8 += 3,
+            }
+            len.chars += 1;
+        }
+        len
+    }
+
+    fn get(&self, unit: OffsetUnit) -> usize {
+        match unit {
+            OffsetUnit::Utf8 => self.utf8,
+            OffsetUnit::Utf16 => self.utf16,
+            OffsetUnit::Char => self.chars,
+        }
+    }
+}
+
+impl<'a> AddAssign<&'a Self> for TextLen {
+    fn add_assign(&mut self, other: &Self) {
+        self.utf8 += other.utf8;
+        self.utf16 += other.utf16;
+        self.chars += other.chars;
+    }
+}
+
+impl<'a> Add<&'a Self> for TextLen {
+    type Output = TextLen;
+
+    fn add(mut self, other: &Self) -> Self::Output {
+        self += other;
+        self
+    }
+}
+
+impl Sub for TextLen {
+    type Output = TextLen;
+
+    fn sub(self, other: Self) -> Self::Output {
+        TextLen {
+            utf8: self.utf8 - other.utf8,
+            utf16: self.utf16 - other.utf16,
+            chars: self.chars - other.chars,
+        }
+    }
+}
+
+impl btree::Dimension<TextSummary> for TextLen {
+    fn from_summary(summary: &TextSummary) -> Self {
+        summary.len
+    }
+}
+
+impl TextChunk {
+    fn new(code_units: &[u16]) -> Self {
+        let mut bytes = Vec::with_capacity(code_units.len());
+        for ch in char::decode_utf16(code_units.iter().cloned()) {
+            match ch {
+                Ok(ch) => encode_wtf8_code_point(ch as u32, &mut bytes),
+                Err(error) => {
+                    encode_wtf8_code_point(u32::from(error.unpaired_surrogate()), &mut bytes)
+                }
+            }
+        }
+
+        Self {
+            bytes: Arc::from(bytes),
+            summary: TextSummary::of(code_units),
+        }
+    }
+
+    fn from_utf8(text: &str) -> Self {
+        Self {
+            bytes: Arc::from(text.as_bytes()),
+            summary: TextSummary::of(&text.encode_utf16().collect::<Vec<_>>()),
+        }
+    }
+
+    fn code_units(&self) -> Vec<u16> {
+        let mut code_units = Vec::with_capacity(self.summary.len.utf16);
+        let mut bytes = &self.bytes[..];
+        while !bytes.is_empty() {
+            let (code_point, len) = decode_wtf8_code_point(bytes);
+            if code_point < 0x10000 {
+                code_units.push(code_point as u16);
+            } else {
+                let code_point = code_point - 0x10000;
+                code_units.push(0xd800 | (code_point >> 10) as u16);
+                code_units.push(0xdc00 | (code_point & 0x3ff) as u16);
+            }
+            bytes = &bytes[len..];
+        }
+        code_units
+    }
+}
+
+impl btree::Item for TextChunk {
+    type Summary = TextSummary;
+
+    fn summarize(&self) -> Self::Summary {
+        self.summary.clone()
+    }
+}
+
+impl TextSummary {
+    fn of(code_units: &[u16]) -> Self {
+        let mut summary = TextSummary {
+            len: TextLen::of(code_units),
+            ..Self::default()
+        };
+        let mut row = 0;
+        let mut row_len = 0;
+        for code_unit in code_units {
+            if *code_unit == b'\n' as u16 {
+                if row == 0 {
+                    summary.first_row_len = row_len;
+                }
+                if row_len > summary.longest_row_len {
+                    summary.longest_row = row;
+                    summary.longest_row_len = row_len;
+                }
+                row += 1;
+                row_len = 0;
+            } else {
+                row_len += 1;
+            }
+        }
+        if row == 0 {
+            summary.first_row_len = row_len;
+        }
+        if row_len > summary.longest_row_len {
+            summary.longest_row = row;
+            summary.longest_row_len = row_len;
+        }
+        summary.extent_2d = Point::new(row, row_len);
+        summary
+    }
+}
+
+impl<'a> AddAssign<&'a TextSummary> for TextSummary {
+    fn add_assign(&mut self, other: &Self) {
+        let last_row_len = self.extent_2d.column + other.first_row_len;
+        if last_row_len > self.longest_row_len {
+            self.longest_row = self.extent_2d.row;
+            self.longest_row_len = last_row_len;
+        }
+        if other.longest_row_len > self.longest_row_len {
+            self.longest_row = self.extent_2d.row + other.longest_row;
+            self.longest_row_len = other.longest_row_len;
+        }
+        if self.extent_2d.row == 0 {
+            self.first_row_len += other.first_row_len;
+        }
+
+        self.len += &other.len;
+        self.extent_2d += &other.extent_2d;
+    }
+}
+
+impl Default for TextSummary {
+    fn default() -> Self {
+        TextSummary {
+            len: TextLen::default(),
+            extent_2d: Point { row: 0, column: 0 },
+            first_row_len: 0,
+            longest_row: 0,
+            longest_row_len: 0,
+        }
+    }
+}
+
+impl btree::Dimension<TextSummary> for usize {
+    fn from_summary(summary: &TextSummary) -> Self {
+        summary.len.utf16
+    }
+}
+
+impl btree::Dimension<TextSummary> for Point {
+    fn from_summary(summary: &TextSummary) -> Self {
+        summary.extent_2d
+    }
+}
+
+impl btree::Dimension<TextSummary> for Utf8Offset {
+    fn from_summary(summary: &TextSummary) -> Self {
+        Utf8Offset(summary.len.utf8)
+    }
+}
+
+impl btree::Dimension<TextSummary> for CharOffset {
+    fn from_summary(summary: &TextSummary) -> Self {
+        CharOffset(summary.len.chars)
+    }
+}
+
+fn is_high_surrogate(code_unit: u16) -> bool {
+    code_unit >= 0xd800 && code_unit <= 0xdbff
+}
+
+fn is_low_surrogate(code_unit: u16) -> bool {
+    code_unit >= 0xdc00 && code_unit <= 0xdfff
+}
+
+fn encode_wtf8_code_point(code_point: u32, bytes: &mut Vec<u8>) {
+    if code_point < 0x80 {
+        bytes.push(code_point as u8);
+    } else if code_point < 0x800 {
+        bytes.push(0xc0 | (code_point >> 6) as u8);
+        bytes.push(0x80 | (code_point & 0x3f) as u8);
+    } else if code_point < 0x10000 {
+        bytes.push(0xe0 | (code_point >> 12) as u8);
+        bytes.push(0x80 | ((code_point >> 6) & 0x3f) as u8);
+        bytes.push(0x80 | (code_point & 0x3f) as u8);
+    } else {
+        bytes.push(0xf0 | (code_point >> 18) as u8);
+        bytes.push(0x80 | ((code_point >> 12) & 0x3f) as u8);
+        bytes.push(0x80 | ((code_point >> 6) & 0x3f) as u8);
+        bytes.push(0x80 | (code_point & 0x3f) as u8);
+    }
+}
+
+/// Decodes the code point at the start of `bytes`, returning it along with its encoded length.
+fn decode_wtf8_code_point(bytes: &[u8]) -> (u32, usize) {
+    let continuation = |i: usize| u32::from(bytes[i] & 0x3f);
+    let lead = u32::from(bytes[0]);
+    if lead < 0x80 {
+        (lead, 1)
+    } else if lead < 0xe0 {
+        ((lead & 0x1f) << 6 | continuation(1), 2)
+    } else if lead < 0xf0 {
+        (
+            (lead & 0x0f) << 12 | continuation(1) << 6 | continuation(2),
+            3,
+        )
+    } else {
+        let code_point =
+            (lead & 0x07) << 18 | continuation(1) << 12 | continuation(2) << 6 | continuation(3);
+        (code_point, 4)
+    }
+}
+
+/// Converts WTF-8 to UTF-8, joining surrogate halves that were encoded separately and replacing
+/// unpaired surrogates with U+FFFD.
+fn wtf8_to_string(bytes: &[u8]) -> String {
+    let mut string = String::with_capacity(bytes.len());
+    let mut start = 0;
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (code_point, len) = decode_wtf8_code_point(&bytes[offset..]);
+        if code_point >= 0xd800 && code_point <= 0xdfff {
+            string.push_str(str::from_utf8(&bytes[start..offset]).unwrap());
+            let low = bytes.get(offset + 3..).and_then(|rest| {
+                if rest.is_empty() {
+                    None
+                } else {
+                    Some(decode_wtf8_code_point(rest).0)
+                }
+            });
+            match low {
+                Some(low) if code_point <= 0xdbff && low >= 0xdc00 && low <= 0xdfff => {
+                    let code_point = 0x10000 + ((code_point & 0x3ff) << 10 | (low & 0x3ff));
+                    string.push(char::from_u32(code_point).unwrap());
+                    offset += 6;
+                }
+                _ => {
+                    string.push('\u{fffd}');
+                    offset += 3;
+                }
+            }
+            start = offset;
+            continue;
+        }
+        offset += len;
+    }
+    string.push_str(str::from_utf8(&bytes[start..]).unwrap());
+    string
+}
+
+fn fragments_to_string(fragments: &btree::Tree<Fragment>) -> String {
+    let mut bytes = Vec::with_capacity(fragments.summary().extent_utf8);
+    let mut cursor = fragments.cursor();
+    cursor.seek(&0, SeekBias::Right);
+    for fragment in cursor {
+        if fragment.is_visible() {
+            fragment
+                .insertion
+                .text
+                .write_wtf8(fragment.start_offset..fragment.end_offset, &mut bytes);
+        }
+    }
+    wtf8_to_string(&bytes)
 }
 
 lazy_static! {
@@ -2095,6 +3731,19 @@ impl FragmentId {
 
         FragmentId(Arc::new(new_entries))
     }
+
+    fn to_flatbuf<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> WIPOffset<flatbuffers::Vector<'fbb, u16>> {
+        builder.create_vector(&self.0[..])
+    }
+
+    fn from_flatbuf<'fbb>(message: flatbuffers::Vector<'fbb, u16>) -> Self {
+        FragmentId(Arc::new(
+            (0..message.len()).map(|i| message.get(i)).collect(),
+        ))
+    }
 }
 
 impl btree::Dimension<FragmentSummary> for FragmentId {
@@ -2128,19 +3777,15 @@ impl Fragment {
             start_offset: 0,
             end_offset,
             deletions: HashSet::new(),
+            max_undos: time::Global::new(),
+            visible: true,
         }
     }
 
-    fn code_unit(&self, offset: usize) -> Option<u16> {
-        if offset < self.len() {
-            Some(self.insertion.text.code_units[self.start_offset + offset].clone())
-        } else {
-            None
-        }
-    }
-
-    fn code_units(&self) -> &[u16] {
-        &self.insertion.text.code_units[self.start_offset..self.end_offset]
+    fn code_units(&self) -> impl Iterator<Item = u16> {
+        self.insertion
+            .text
+            .code_units(self.start_offset..self.end_offset)
     }
 
     fn len(&self) -> usize {
@@ -2160,11 +3805,29 @@ impl Fragment {
     }
 
     fn is_visible(&self) -> bool {
-        self.deletions.is_empty()
+        self.visible
+    }
+
+    fn inserted_by(&self) -> Option<time::Lamport> {
+        if self.insertion.id == time::Local::default() {
+            None
+        } else {
+            Some(self.insertion.lamport_timestamp)
+        }
+    }
+
+    fn was_visible(&self, version: &time::Global, undo_map: &UndoMap) -> bool {
+        version.observed(self.insertion.id)
+            && !undo_map.was_undone(self.insertion.id, version)
+            && self
+                .deletions
+                .iter()
+                .all(|d| !version.observed(*d) || undo_map.was_undone(*d, version))
     }
 
-    fn was_visible(&self, version: &time::Global) -> bool {
-        version.observed(self.insertion.id) && self.deletions.iter().all(|d| !version.observed(*d))
+    fn update_visibility(&mut self, undo_map: &UndoMap) {
+        self.visible = !undo_map.is_undone(self.insertion.id)
+            && self.deletions.iter().all(|d| undo_map.is_undone(*d));
     }
 
     fn point_for_offset(&self, offset: usize) -> Result<Point, Error> {
@@ -2179,6 +3842,57 @@ impl Fragment {
         let point_in_insertion = text.point_for_offset(self.start_offset)? + &point;
         Ok(text.offset_for_point(point_in_insertion)? - self.start_offset)
     }
+
+    fn to_flatbuf<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> WIPOffset<serialization::buffer::Fragment<'fbb>> {
+        let id = Some(self.id.to_flatbuf(builder));
+        let deletions = Some(
+            builder.create_vector(
+                &self
+                    .deletions
+                    .iter()
+                    .map(|deletion| deletion.to_flatbuf())
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        let max_undos = Some(self.max_undos.to_flatbuf(builder));
+        serialization::buffer::Fragment::create(
+            builder,
+            &serialization::buffer::FragmentArgs {
+                id,
+                insertion_id: Some(&self.insertion.id.to_flatbuf()),
+                start_offset: self.start_offset as u64,
+                end_offset: self.end_offset as u64,
+                deletions,
+                max_undos,
+                visible: self.visible,
+            },
+        )
+    }
+
+    fn from_flatbuf<'fbb>(
+        message: serialization::buffer::Fragment<'fbb>,
+        insertion: Insertion,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            id: FragmentId::from_flatbuf(message.id().ok_or(Error::DeserializeError)?),
+            insertion,
+            start_offset: message.start_offset() as usize,
+            end_offset: message.end_offset() as usize,
+            deletions: message
+                .deletions()
+                .ok_or(Error::DeserializeError)?
+                .iter()
+                .map(time::Local::from_flatbuf)
+                .collect(),
+            max_undos: time::Global::from_flatbuf(
+                message.max_undos().ok_or(Error::DeserializeError)?,
+            )?,
+            visible: message.visible(),
+        })
+    }
 }
 
 impl btree::Item for Fragment {
@@ -2190,42 +3904,30 @@ impl btree::Item for Fragment {
         for deletion in &self.deletions {
             max_version.observe(*deletion);
         }
+        max_version.observe_all(&self.max_undos);
 
         if self.is_visible() {
-            let fragment_2d_start = self
-                .insertion
-                .text
-                .point_for_offset(self.start_offset)
-                .unwrap();
-            let fragment_2d_end = self
-                .insertion
-                .text
-                .point_for_offset(self.end_offset)
-                .unwrap();
-
-            let first_row_len = if fragment_2d_start.row == fragment_2d_end.row {
-                self.extent() as u32
-            } else {
-                self.offset_for_point(Point::new(1, 0)).unwrap() as u32 - 1
-            };
-            let (longest_row, longest_row_len) = self
+            let summary = self
                 .insertion
                 .text
-                .longest_row_in_range(self.start_offset as usize..self.end_offset as usize)
-                .unwrap();
+                .summary(self.start_offset..self.end_offset);
             FragmentSummary {
                 extent: self.len(),
-                extent_2d: fragment_2d_end - &fragment_2d_start,
+                extent_2d: summary.extent_2d,
+                extent_utf8: summary.len.utf8,
+                extent_chars: summary.len.chars,
                 max_fragment_id: self.id.clone(),
-                first_row_len,
-                longest_row: longest_row - fragment_2d_start.row,
-                longest_row_len,
+                first_row_len: summary.first_row_len,
+                longest_row: summary.longest_row,
+                longest_row_len: summary.longest_row_len,
                 max_version,
             }
         } else {
             FragmentSummary {
                 extent: 0,
                 extent_2d: Point { row: 0, column: 0 },
+                extent_utf8: 0,
+                extent_chars: 0,
                 max_fragment_id: self.id.clone(),
                 first_row_len: 0,
                 longest_row: 0,
@@ -2253,6 +3955,8 @@ impl<'a> AddAssign<&'a FragmentSummary> for FragmentSummary {
 
         self.extent += other.extent;
         self.extent_2d += &other.extent_2d;
+        self.extent_utf8 += other.extent_utf8;
+        self.extent_chars += other.extent_chars;
         debug_assert!(self.max_fragment_id <= other.max_fragment_id);
         self.max_fragment_id = other.max_fragment_id.clone();
         self.max_version.observe_all(&other.max_version);
@@ -2264,6 +3968,8 @@ impl Default for FragmentSummary {
         FragmentSummary {
             extent: 0,
             extent_2d: Point { row: 0, column: 0 },
+            extent_utf8: 0,
+            extent_chars: 0,
             max_fragment_id: FragmentId::min_value(),
             first_row_len: 0,
             longest_row: 0,
@@ -2279,6 +3985,81 @@ impl btree::Dimension<FragmentSummary> for usize {
     }
 }
 
+impl btree::Dimension<FragmentSummary> for Utf8Offset {
+    fn from_summary(summary: &FragmentSummary) -> Self {
+        Utf8Offset(summary.extent_utf8)
+    }
+}
+
+impl<'a> Add<&'a Self> for Utf8Offset {
+    type Output = Utf8Offset;
+
+    fn add(self, other: &'a Self) -> Self::Output {
+        Utf8Offset(self.0 + other.0)
+    }
+}
+
+impl<'a> AddAssign<&'a Self> for Utf8Offset {
+    fn add_assign(&mut self, other: &'a Self) {
+        self.0 += other.0;
+    }
+}
+
+impl btree::Dimension<FragmentSummary> for CharOffset {
+    fn from_summary(summary: &FragmentSummary) -> Self {
+        CharOffset(summary.extent_chars)
+    }
+}
+
+impl<'a> Add<&'a Self> for CharOffset {
+    type Output = CharOffset;
+
+    fn add(self, other: &'a Self) -> Self::Output {
+        CharOffset(self.0 + other.0)
+    }
+}
+
+impl<'a> AddAssign<&'a Self> for CharOffset {
+    fn add_assign(&mut self, other: &'a Self) {
+        self.0 += other.0;
+    }
+}
+
+impl Insertion {
+    fn to_flatbuf<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> WIPOffset<serialization::buffer::Insertion<'fbb>> {
+        let code_units = self.text.code_units(0..self.text.len()).collect::<Vec<_>>();
+        let text = Some(builder.create_vector(&code_units));
+        serialization::buffer::Insertion::create(
+            builder,
+            &serialization::buffer::InsertionArgs {
+                id: Some(&self.id.to_flatbuf()),
+                parent_id: Some(&self.parent_id.to_flatbuf()),
+                offset_in_parent: self.offset_in_parent as u64,
+                text,
+                lamport_timestamp: Some(&self.lamport_timestamp.to_flatbuf()),
+            },
+        )
+    }
+
+    fn from_flatbuf<'fbb>(message: serialization::buffer::Insertion<'fbb>) -> Result<Self, Error> {
+        let text = message.text().ok_or(Error::DeserializeError)?;
+        Ok(Self {
+            id: time::Local::from_flatbuf(message.id().ok_or(Error::DeserializeError)?),
+            parent_id: time::Local::from_flatbuf(
+                message.parent_id().ok_or(Error::DeserializeError)?,
+            ),
+            offset_in_parent: message.offset_in_parent() as usize,
+            text: Arc::new(Text::from_code_units((0..text.len()).map(|i| text.get(i)))),
+            lamport_timestamp: time::Lamport::from_flatbuf(
+                message.lamport_timestamp().ok_or(Error::DeserializeError)?,
+            ),
+        })
+    }
+}
+
 impl btree::Item for InsertionSplit {
     type Summary = InsertionSplitSummary;
 
@@ -2307,6 +4088,148 @@ impl btree::Dimension<InsertionSplitSummary> for usize {
     }
 }
 
+impl UndoMap {
+    fn insert(&mut self, undo_id: time::Local, counts: &HashMap<time::Local, u32>) {
+        for (edit_id, count) in counts {
+            self.0
+                .entry(*edit_id)
+                .or_insert(Vec::new())
+                .push((undo_id, *count));
+        }
+    }
+
+    fn undo_count(&self, edit_id: time::Local) -> u32 {
+        self.0
+            .get(&edit_id)
+            .and_then(|undos| undos.iter().map(|(_, count)| *count).max())
+            .unwrap_or(0)
+    }
+
+    fn undos_observed(&self, edit_id: time::Local, version: &time::Global) -> bool {
+        self.0.get(&edit_id).map_or(true, |undos| {
+            undos.iter().all(|(undo_id, _)| version.observed(*undo_id))
+        })
+    }
+
+    fn is_undone(&self, edit_id: time::Local) -> bool {
+        self.undo_count(edit_id) % 2 == 1
+    }
+
+    fn was_undone(&self, edit_id: time::Local, version: &time::Global) -> bool {
+        let undo_count = self
+            .0
+            .get(&edit_id)
+            .and_then(|undos| {
+                undos
+                    .iter()
+                    .filter(|(undo_id, _)| version.observed(*undo_id))
+                    .map(|(_, count)| *count)
+                    .max()
+            })
+            .unwrap_or(0);
+        undo_count % 2 == 1
+    }
+}
+
+impl History {
+    fn start_transaction(&mut self, now: SystemTime) {
+        self.transaction_depth += 1;
+        if self.transaction_depth == 1 {
+            self.transaction = Some(Transaction {
+                edit_ids: Vec::new(),
+                start: Some(now),
+                end: None,
+            });
+        }
+    }
+
+    fn end_transaction(
+        &mut self,
+        now: SystemTime,
+        group_interval: Option<Duration>,
+    ) -> Result<(), Error> {
+        if self.transaction_depth == 0 {
+            return Err(Error::InvalidOperation);
+        }
+
+        self.transaction_depth -= 1;
+        if self.transaction_depth == 0 {
+            let mut transaction = self.transaction.take().unwrap();
+            transaction.end = Some(now);
+            if transaction.edit_ids.is_empty() {
+                return Ok(());
+            }
+
+            // Group this transaction with the previous one if it started soon enough after the
+            // previous one ended.
+            if let (Some(group_interval), Some(prev_transaction)) =
+                (group_interval, self.undo_stack.last_mut())
+            {
+                if let (Some(prev_end), Some(start)) = (prev_transaction.end, transaction.start) {
+                    if start <= prev_end + group_interval {
+                        prev_transaction.edit_ids.extend(transaction.edit_ids);
+                        prev_transaction.end = transaction.end;
+                        return Ok(());
+                    }
+                }
+            }
+            self.undo_stack.push(transaction);
+        }
+        Ok(())
+    }
+
+    fn end_all_transactions(&mut self) {
+        self.transaction_depth = 0;
+        if let Some(transaction) = self.transaction.take() {
+            if !transaction.edit_ids.is_empty() {
+                self.undo_stack.push(transaction);
+            }
+        }
+    }
+
+    fn push_edits(&mut self, edit_ids: Vec<time::Local>) {
+        if edit_ids.is_empty() {
+            return;
+        }
+
+        if let Some(transaction) = self.transaction.as_mut() {
+            transaction.edit_ids.extend(edit_ids);
+        } else {
+            self.undo_stack.push(Transaction {
+                edit_ids,
+                start: None,
+                end: None,
+            });
+        }
+        self.redo_stack.clear();
+    }
+
+    fn record_edit(&mut self, op: &Operation) -> Option<time::Local> {
+        if let Operation::Edit {
+            start_id,
+            start_offset,
+            end_id,
+            end_offset,
+            local_timestamp,
+            ..
+        } = op
+        {
+            self.edits.insert(
+                *local_timestamp,
+                EditRange {
+                    start_id: *start_id,
+                    start_offset: *start_offset,
+                    end_id: *end_id,
+                    end_offset: *end_offset,
+                },
+            );
+            Some(*local_timestamp)
+        } else {
+            None
+        }
+    }
+}
+
 impl Operation {
     fn replica_id(&self) -> ReplicaId {
         self.lamport_timestamp().replica_id
@@ -2317,7 +4240,10 @@ impl Operation {
             Operation::Edit {
                 lamport_timestamp, ..
             } => *lamport_timestamp,
-            Operation::UpdateSelections {
+            Operation::UpdateSelections {
+                lamport_timestamp, ..
+            } => *lamport_timestamp,
+            Operation::Undo {
                 lamport_timestamp, ..
             } => *lamport_timestamp,
         }
@@ -2348,7 +4274,8 @@ impl Operation {
                 lamport_timestamp,
             } => {
                 let new_text = new_text.as_ref().map(|new_text| {
-                    builder.create_string(String::from_utf16_lossy(&new_text.code_units).as_str())
+                    let code_units = new_text.code_units(0..new_text.len()).collect::<Vec<_>>();
+                    builder.create_string(String::from_utf16_lossy(&code_units).as_str())
                 });
                 let version_in_range = Some(version_in_range.to_flatbuf(builder));
                 variant_type = serialization::buffer::OperationVariant::Edit;
@@ -2390,6 +4317,35 @@ impl Operation {
                 )
                 .as_union_value();
             }
+            Operation::Undo {
+                counts,
+                local_timestamp,
+                lamport_timestamp,
+            } => {
+                variant_type = serialization::buffer::OperationVariant::Undo;
+                let count_flatbufs = &counts
+                    .iter()
+                    .map(|(edit_id, count)| {
+                        serialization::buffer::UndoCount::create(
+                            builder,
+                            &serialization::buffer::UndoCountArgs {
+                                edit_id: Some(&edit_id.to_flatbuf()),
+                                count: *count,
+                            },
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let counts = Some(builder.create_vector(count_flatbufs));
+                variant = serialization::buffer::Undo::create(
+                    builder,
+                    &serialization::buffer::UndoArgs {
+                        counts,
+                        local_timestamp: Some(&local_timestamp.to_flatbuf()),
+                        lamport_timestamp: Some(&lamport_timestamp.to_flatbuf()),
+                    },
+                )
+                .as_union_value();
+            }
         }
 
         serialization::buffer::Operation::create(
@@ -2463,6 +4419,38 @@ impl Operation {
                     ),
                 }))
             }
+            serialization::buffer::OperationVariant::Undo => {
+                let message = serialization::buffer::Undo::init_from_table(
+                    message.variant().ok_or(crate::Error::DeserializeError)?,
+                );
+
+                let mut counts = HashMap::new();
+                if let Some(flatbufs) = message.counts() {
+                    for i in 0..flatbufs.len() {
+                        let count = flatbufs.get(i);
+                        counts.insert(
+                            time::Local::from_flatbuf(
+                                count.edit_id().ok_or(crate::Error::DeserializeError)?,
+                            ),
+                            count.count(),
+                        );
+                    }
+                }
+
+                Ok(Some(Operation::Undo {
+                    counts,
+                    local_timestamp: time::Local::from_flatbuf(
+                        message
+                            .local_timestamp()
+                            .ok_or(crate::Error::DeserializeError)?,
+                    ),
+                    lamport_timestamp: time::Lamport::from_flatbuf(
+                        message
+                            .lamport_timestamp()
+                            .ok_or(crate::Error::DeserializeError)?,
+                    ),
+                }))
+            }
             serialization::buffer::OperationVariant::NONE => Ok(None),
         }
     }
@@ -2770,9 +4758,13 @@ mod tests {
                     expected_longest_row_len = cur_row_len;
                 }
 
+                // Fragments summarize their range of the insertion's text, so this is how they
+                // find the longest row.
+                let start_row = text.point_for_offset(start).unwrap().row;
+                let summary = text.summary(start..end);
                 assert_eq!(
-                    text.longest_row_in_range(start..end),
-                    Ok((expected_longest_row, expected_longest_row_len))
+                    (start_row + summary.longest_row, summary.longest_row_len),
+                    (expected_longest_row, expected_longest_row_len)
                 );
             }
         }
@@ -2830,156 +4822,940 @@ mod tests {
             buffer.point_for_anchor(&left_anchor).unwrap(),
             Point { row: 1, column: 1 }
         );
-        assert_eq!(
-            buffer.point_for_anchor(&right_anchor).unwrap(),
-            Point { row: 1, column: 1 }
+        assert_eq!(
+            buffer.point_for_anchor(&right_anchor).unwrap(),
+            Point { row: 1, column: 1 }
+        );
+
+        buffer.edit(vec![5..5], "ghi\n", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "adf\nbghi\nc");
+        assert_eq!(buffer.offset_for_anchor(&left_anchor).unwrap(), 5);
+        assert_eq!(buffer.offset_for_anchor(&right_anchor).unwrap(), 9);
+        assert_eq!(
+            buffer.point_for_anchor(&left_anchor).unwrap(),
+            Point { row: 1, column: 1 }
+        );
+        assert_eq!(
+            buffer.point_for_anchor(&right_anchor).unwrap(),
+            Point { row: 2, column: 0 }
+        );
+
+        buffer.edit(vec![7..9], "", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "adf\nbghc");
+        assert_eq!(buffer.offset_for_anchor(&left_anchor).unwrap(), 5);
+        assert_eq!(buffer.offset_for_anchor(&right_anchor).unwrap(), 7);
+        assert_eq!(
+            buffer.point_for_anchor(&left_anchor).unwrap(),
+            Point { row: 1, column: 1 }
+        );
+        assert_eq!(
+            buffer.point_for_anchor(&right_anchor).unwrap(),
+            Point { row: 1, column: 3 }
+        );
+
+        // Ensure anchoring to a point is equivalent to anchoring to an offset.
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 0, column: 0 }),
+            buffer.anchor_before_offset(0)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 0, column: 1 }),
+            buffer.anchor_before_offset(1)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 0, column: 2 }),
+            buffer.anchor_before_offset(2)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 0, column: 3 }),
+            buffer.anchor_before_offset(3)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 1, column: 0 }),
+            buffer.anchor_before_offset(4)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 1, column: 1 }),
+            buffer.anchor_before_offset(5)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 1, column: 2 }),
+            buffer.anchor_before_offset(6)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 1, column: 3 }),
+            buffer.anchor_before_offset(7)
+        );
+        assert_eq!(
+            buffer.anchor_before_point(Point { row: 1, column: 4 }),
+            buffer.anchor_before_offset(8)
+        );
+
+        // Comparison between anchors.
+        let anchor_at_offset_0 = buffer.anchor_before_offset(0).unwrap();
+        let anchor_at_offset_1 = buffer.anchor_before_offset(1).unwrap();
+        let anchor_at_offset_2 = buffer.anchor_before_offset(2).unwrap();
+
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_0, &anchor_at_offset_0),
+            Ok(Ordering::Equal)
+        );
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_1, &anchor_at_offset_1),
+            Ok(Ordering::Equal)
+        );
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_2, &anchor_at_offset_2),
+            Ok(Ordering::Equal)
+        );
+
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_0, &anchor_at_offset_1),
+            Ok(Ordering::Less)
+        );
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_1, &anchor_at_offset_2),
+            Ok(Ordering::Less)
+        );
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_0, &anchor_at_offset_2),
+            Ok(Ordering::Less)
+        );
+
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_1, &anchor_at_offset_0),
+            Ok(Ordering::Greater)
+        );
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_2, &anchor_at_offset_1),
+            Ok(Ordering::Greater)
+        );
+        assert_eq!(
+            buffer.cmp_anchors(&anchor_at_offset_2, &anchor_at_offset_0),
+            Ok(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn test_anchors_at_start_and_end() {
+        let mut buffer = Buffer::new("");
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let before_start_anchor = buffer.anchor_before_offset(0).unwrap();
+        let after_end_anchor = buffer.anchor_after_offset(0).unwrap();
+
+        buffer.edit(vec![0..0], "abc", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "abc");
+        assert_eq!(buffer.offset_for_anchor(&before_start_anchor).unwrap(), 0);
+        assert_eq!(buffer.offset_for_anchor(&after_end_anchor).unwrap(), 3);
+
+        let after_start_anchor = buffer.anchor_after_offset(0).unwrap();
+        let before_end_anchor = buffer.anchor_before_offset(3).unwrap();
+
+        buffer.edit(vec![3..3], "def", &mut local_clock, &mut lamport_clock);
+        buffer.edit(vec![0..0], "ghi", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "ghiabcdef");
+        assert_eq!(buffer.offset_for_anchor(&before_start_anchor).unwrap(), 0);
+        assert_eq!(buffer.offset_for_anchor(&after_start_anchor).unwrap(), 3);
+        assert_eq!(buffer.offset_for_anchor(&before_end_anchor).unwrap(), 6);
+        assert_eq!(buffer.offset_for_anchor(&after_end_anchor).unwrap(), 9);
+    }
+
+    #[test]
+    fn test_is_modified() {
+        let mut buffer = Buffer::new("abc");
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+
+        assert!(!buffer.is_modified());
+        buffer.edit(vec![1..2], "", &mut local_clock, &mut lamport_clock);
+        assert!(buffer.is_modified());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let mut buffer = Buffer::new("1234");
+
+        buffer.edit(vec![1..1], "abx", &mut local_clock, &mut lamport_clock);
+        buffer.edit(vec![3..4], "yzef", &mut local_clock, &mut lamport_clock);
+        buffer.edit(vec![3..5], "cd", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "1abcdef234");
+
+        buffer
+            .undo(&mut local_clock, &mut lamport_clock)
+            .unwrap()
+            .unwrap();
+        assert_eq!(buffer.to_string(), "1abyzef234");
+        buffer
+            .undo(&mut local_clock, &mut lamport_clock)
+            .unwrap()
+            .unwrap();
+        assert_eq!(buffer.to_string(), "1abx234");
+        buffer
+            .redo(&mut local_clock, &mut lamport_clock)
+            .unwrap()
+            .unwrap();
+        assert_eq!(buffer.to_string(), "1abyzef234");
+        buffer
+            .redo(&mut local_clock, &mut lamport_clock)
+            .unwrap()
+            .unwrap();
+        assert_eq!(buffer.to_string(), "1abcdef234");
+        assert_eq!(
+            buffer.redo(&mut local_clock, &mut lamport_clock).unwrap(),
+            None
+        );
+
+        // Undo operations can be replicated and applied more than once.
+        let mut remote_buffer = Buffer::new("1234");
+        let remote_id = Uuid::from_u128(2);
+        let mut remote_local_clock = time::Local::new(remote_id);
+        let mut remote_lamport_clock = time::Lamport::new(remote_id);
+        let mut ops = Vec::new();
+        let mut local_buffer = Buffer::new("1234");
+        ops.extend(local_buffer.edit(vec![1..1], "abx", &mut local_clock, &mut lamport_clock));
+        ops.extend(local_buffer.edit(vec![3..4], "yzef", &mut local_clock, &mut lamport_clock));
+        ops.extend(
+            local_buffer
+                .undo(&mut local_clock, &mut lamport_clock)
+                .unwrap(),
+        );
+        assert_eq!(local_buffer.to_string(), "1abx234");
+        remote_buffer
+            .apply_ops(
+                ops.clone(),
+                &mut remote_local_clock,
+                &mut remote_lamport_clock,
+            )
+            .unwrap();
+        remote_buffer
+            .apply_ops(ops, &mut remote_local_clock, &mut remote_lamport_clock)
+            .unwrap();
+        assert_eq!(remote_buffer.to_string(), "1abx234");
+        assert!(!remote_buffer.can_undo());
+    }
+
+    #[test]
+    fn test_transactions() {
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let mut buffer = Buffer::new("123456");
+        let now = SystemTime::now();
+        let group_interval = Some(Duration::from_millis(300));
+
+        buffer.start_transaction(now);
+        buffer.edit(vec![2..4], "cd", &mut local_clock, &mut lamport_clock);
+        buffer.edit(vec![4..5], "e", &mut local_clock, &mut lamport_clock);
+        assert!(buffer.undo(&mut local_clock, &mut lamport_clock).is_err());
+        buffer.end_transaction(now, group_interval).unwrap();
+        assert_eq!(buffer.to_string(), "12cde6");
+
+        buffer.start_transaction(now + Duration::from_millis(100));
+        buffer.edit(vec![0..1], "a", &mut local_clock, &mut lamport_clock);
+        buffer.edit(vec![1..1], "b", &mut local_clock, &mut lamport_clock);
+        buffer
+            .end_transaction(now + Duration::from_millis(100), group_interval)
+            .unwrap();
+        assert_eq!(buffer.to_string(), "ab2cde6");
+
+        // Transactions that start after the group interval are not grouped.
+        buffer.start_transaction(now + Duration::from_millis(500));
+        buffer.edit(vec![2..3], "", &mut local_clock, &mut lamport_clock);
+        buffer.start_transaction(now + Duration::from_millis(500));
+        buffer.edit(vec![3..4], "", &mut local_clock, &mut lamport_clock);
+        buffer
+            .end_transaction(now + Duration::from_millis(500), group_interval)
+            .unwrap();
+        buffer
+            .end_transaction(now + Duration::from_millis(500), group_interval)
+            .unwrap();
+        assert_eq!(buffer.to_string(), "abce6");
+        assert!(buffer
+            .end_transaction(now + Duration::from_millis(500), group_interval)
+            .is_err());
+
+        buffer.undo(&mut local_clock, &mut lamport_clock).unwrap();
+        assert_eq!(buffer.to_string(), "ab2cde6");
+        buffer.undo(&mut local_clock, &mut lamport_clock).unwrap();
+        assert_eq!(buffer.to_string(), "123456");
+        buffer.redo(&mut local_clock, &mut lamport_clock).unwrap();
+        assert_eq!(buffer.to_string(), "ab2cde6");
+    }
+
+    #[test]
+    fn test_collect_garbage() {
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let mut buffer = Buffer::new("abcdefghi");
+
+        buffer.edit(
+            vec![1..2, 3..4, 5..6],
+            "",
+            &mut local_clock,
+            &mut lamport_clock,
+        );
+        buffer.edit(vec![1..5], "", &mut local_clock, &mut lamport_clock);
+        buffer.edit(vec![1..1], "x", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "axi");
+        let fragment_count = buffer.fragments.items().len();
+
+        let stable_version = buffer.version.clone();
+        let mut remote_buffer = buffer.clone();
+        buffer.collect_garbage(&stable_version);
+        assert_eq!(buffer.to_string(), "axi");
+        assert!(buffer.fragments.items().len() < fragment_count);
+        assert!(!buffer.can_undo());
+
+        // Edits performed after the stable version can still be applied after collecting garbage.
+        let remote_id = Uuid::from_u128(2);
+        let mut remote_local_clock = time::Local::new(remote_id);
+        let mut remote_lamport_clock = time::Lamport::new(remote_id);
+        let remote_ops = remote_buffer.edit(
+            vec![1..2],
+            "yz",
+            &mut remote_local_clock,
+            &mut remote_lamport_clock,
+        );
+        buffer
+            .apply_ops(remote_ops, &mut local_clock, &mut lamport_clock)
+            .unwrap();
+        assert_eq!(buffer.to_string(), "ayzi");
+        assert_eq!(remote_buffer.to_string(), "ayzi");
+    }
+
+    #[test]
+    fn test_collect_garbage_with_operations_in_flight() {
+        let replica_id_1 = Uuid::from_u128(1);
+        let mut local_clock_1 = time::Local::new(replica_id_1);
+        let mut lamport_clock_1 = time::Lamport::new(replica_id_1);
+        let mut buffer_1 = Buffer::new("ab");
+        let replica_id_2 = Uuid::from_u128(2);
+        let mut local_clock_2 = time::Local::new(replica_id_2);
+        let mut lamport_clock_2 = time::Lamport::new(replica_id_2);
+        let mut buffer_2 = Buffer::new("ab");
+
+        let mut ops = buffer_2.edit(vec![1..1], "xyz", &mut local_clock_2, &mut lamport_clock_2);
+        ops.extend(buffer_2.edit(vec![4..4], "123", &mut local_clock_2, &mut lamport_clock_2));
+        ops.extend(buffer_2.edit(vec![8..8], "!", &mut local_clock_2, &mut lamport_clock_2));
+        buffer_1
+            .apply_ops(ops, &mut local_clock_1, &mut lamport_clock_1)
+            .unwrap();
+        let anchor = buffer_2.anchor_before_offset(3).unwrap();
+        let ops = buffer_1.edit(vec![1..7], "", &mut local_clock_1, &mut lamport_clock_1);
+        buffer_2
+            .apply_ops(ops, &mut local_clock_2, &mut lamport_clock_2)
+            .unwrap();
+        assert_eq!(buffer_2.to_string(), "ab!");
+
+        // The second replica undoes an edit before acknowledging the first replica's version, so
+        // the undo is still in flight when the acknowledgment is sent.
+        let undo_op = buffer_2
+            .undo(&mut local_clock_2, &mut lamport_clock_2)
+            .unwrap();
+        let acknowledged_version = buffer_2.version.meet(&buffer_1.version);
+        buffer_2.discard_history(&acknowledged_version);
+        assert!(!buffer_2.can_undo());
+        assert!(!buffer_2.can_redo());
+
+        buffer_1
+            .apply_ops(undo_op, &mut local_clock_1, &mut lamport_clock_1)
+            .unwrap();
+        let fragment_count = buffer_1.fragments.items().len();
+        let stable_version = buffer_1.version.meet(&acknowledged_version);
+        buffer_1.collect_garbage(&stable_version);
+        buffer_2.collect_garbage(&stable_version);
+        assert!(buffer_1.fragments.items().len() < fragment_count);
+        assert_eq!(buffer_1.to_string(), "ab");
+        assert_eq!(buffer_2.to_string(), "ab");
+
+        // Anchors into text whose fragments were discarded can still be resolved.
+        assert_eq!(buffer_1.offset_for_anchor(&anchor).unwrap(), 1);
+        assert_eq!(buffer_2.offset_for_anchor(&anchor).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_text_at() {
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let mut buffer = Buffer::new("abcdef");
+
+        let v0 = buffer.version.clone();
+        buffer.edit(vec![1..3], "", &mut local_clock, &mut lamport_clock);
+        let v1 = buffer.version.clone();
+        buffer.edit(vec![2..2], "XY\nZ", &mut local_clock, &mut lamport_clock);
+        let v2 = buffer.version.clone();
+        buffer.undo(&mut local_clock, &mut lamport_clock).unwrap();
+        let v3 = buffer.version.clone();
+        assert_eq!(buffer.to_string(), "adef");
+
+        let text_at = |version| String::from_utf16(&buffer.text_at(version).unwrap()).unwrap();
+        assert_eq!(text_at(&v0), "abcdef");
+        assert_eq!(text_at(&v1), "adef");
+        assert_eq!(text_at(&v2), "adXY\nZef");
+        assert_eq!(text_at(&v3), "adef");
+
+        for (from, to) in &[(&v0, &v1), (&v1, &v2), (&v0, &v2), (&v2, &v0), (&v2, &v3)] {
+            let mut text = buffer.text_at(from).unwrap();
+            for change in buffer.changes_between(from, to).unwrap() {
+                let start = offset_for_point(&text, change.range.start);
+                let end = offset_for_point(&text, change.range.end);
+                text.splice(start..end, change.code_units);
+            }
+            assert_eq!(text, buffer.text_at(to).unwrap());
+        }
+        assert_eq!(buffer.changes_between(&v1, &v3).unwrap(), vec![]);
+
+        let mut future_version = v3.clone();
+        future_version.observe(time::Local {
+            replica_id: Uuid::from_u128(2),
+            value: 1,
+        });
+        assert_eq!(
+            buffer.text_at(&future_version),
+            Err(Error::VersionUnavailable)
+        );
+        assert_eq!(
+            buffer.changes_between(&v0, &future_version),
+            Err(Error::VersionUnavailable)
+        );
+
+        fn offset_for_point(text: &[u16], point: Point) -> usize {
+            let mut position = Point::zero();
+            let mut offset = 0;
+            while position < point {
+                if text[offset] == u16::from(b'\n') {
+                    position.row += 1;
+                    position.column = 0;
+                } else {
+                    position.column += 1;
+                }
+                offset += 1;
+            }
+            offset
+        }
+    }
+
+    #[test]
+    fn test_authorship() {
+        let mut buffer_1 = Buffer::new("abc\ndef\nghi");
+        let mut buffer_2 = buffer_1.clone();
+        let replica_1 = Uuid::from_u128(1);
+        let mut local_clock_1 = time::Local::new(replica_1);
+        let mut lamport_clock_1 = time::Lamport::new(replica_1);
+        let replica_2 = Uuid::from_u128(2);
+        let mut local_clock_2 = time::Local::new(replica_2);
+        let mut lamport_clock_2 = time::Lamport::new(replica_2);
+
+        let ops_1 = buffer_1.edit(vec![1..2], "XY", &mut local_clock_1, &mut lamport_clock_1);
+        let ops_2 = buffer_2.edit(vec![5..5], "Z\n", &mut local_clock_2, &mut lamport_clock_2);
+        buffer_1
+            .apply_ops(ops_2, &mut local_clock_1, &mut lamport_clock_1)
+            .unwrap();
+        buffer_2
+            .apply_ops(ops_1, &mut local_clock_2, &mut lamport_clock_2)
+            .unwrap();
+        let edit_1 = buffer_1.edit(vec![2..3], "", &mut local_clock_1, &mut lamport_clock_1);
+        buffer_2
+            .apply_ops(edit_1, &mut local_clock_2, &mut lamport_clock_2)
+            .unwrap();
+        assert_eq!(buffer_1.to_string(), "aXc\ndZ\nef\nghi");
+        assert_eq!(buffer_1.authorship(), buffer_2.authorship());
+
+        let insertion_1 = Some(time::Lamport {
+            value: 1,
+            replica_id: replica_1,
+        });
+        let insertion_2 = Some(time::Lamport {
+            value: 1,
+            replica_id: replica_2,
+        });
+        let spans = buffer_1
+            .authorship()
+            .into_iter()
+            .map(|span| (span.range, span.inserted_by))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                (Point::new(0, 0)..Point::new(0, 1), None),
+                (Point::new(0, 1)..Point::new(0, 2), insertion_1),
+                (Point::new(0, 2)..Point::new(1, 1), None),
+                (Point::new(1, 1)..Point::new(2, 0), insertion_2),
+                (Point::new(2, 0)..Point::new(3, 3), None),
+            ]
+        );
+
+        let spans = buffer_1
+            .authorship_in_rows(1..2)
+            .unwrap()
+            .into_iter()
+            .map(|span| (span.range, span.inserted_by))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                (Point::new(1, 0)..Point::new(1, 1), None),
+                (Point::new(1, 1)..Point::new(2, 0), insertion_2),
+            ]
+        );
+        assert_eq!(
+            buffer_1.authorship_in_rows(4..5),
+            Err(Error::OffsetOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_convert_offset() {
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let mut buffer = Buffer::new("aé€😀\n".repeat(40));
+        buffer.edit(
+            vec![3..3, 9..11],
+            "x😀é",
+            &mut local_clock,
+            &mut lamport_clock,
+        );
+        buffer.edit(vec![50..60], "", &mut local_clock, &mut lamport_clock);
+        buffer.edit(
+            vec![200..200],
+            "€€",
+            &mut local_clock,
+            &mut lamport_clock,
         );
 
-        buffer.edit(vec![5..5], "ghi\n", &mut local_clock, &mut lamport_clock);
-        assert_eq!(buffer.to_string(), "adf\nbghi\nc");
-        assert_eq!(buffer.offset_for_anchor(&left_anchor).unwrap(), 5);
-        assert_eq!(buffer.offset_for_anchor(&right_anchor).unwrap(), 9);
+        let text = buffer.to_string();
+        let mut offsets = Vec::new();
+        let mut utf8_offset = 0;
+        let mut utf16_offset = 0;
+        for (char_offset, ch) in text.chars().enumerate() {
+            offsets.push((utf8_offset, utf16_offset, char_offset));
+            utf8_offset += ch.len_utf8();
+            utf16_offset += ch.len_utf16();
+        }
+        offsets.push((utf8_offset, utf16_offset, text.chars().count()));
+        for (utf8_offset, utf16_offset, char_offset) in offsets {
+            assert_eq!(
+                buffer.convert_offset(utf16_offset, OffsetUnit::Utf16, OffsetUnit::Utf8),
+                Ok(utf8_offset)
+            );
+            assert_eq!(
+                buffer.convert_offset(utf8_offset, OffsetUnit::Utf8, OffsetUnit::Char),
+                Ok(char_offset)
+            );
+            assert_eq!(
+                buffer.convert_offset(char_offset, OffsetUnit::Char, OffsetUnit::Utf16),
+                Ok(utf16_offset)
+            );
+        }
+        assert_eq!(buffer.len_in(OffsetUnit::Utf8), text.len());
+        assert_eq!(buffer.len_in(OffsetUnit::Char), text.chars().count());
+
+        let emoji_offset = text.find('😀').unwrap();
         assert_eq!(
-            buffer.point_for_anchor(&left_anchor).unwrap(),
-            Point { row: 1, column: 1 }
+            buffer.convert_offset(emoji_offset + 2, OffsetUnit::Utf8, OffsetUnit::Utf16),
+            Err(Error::OffsetOutOfRange)
         );
         assert_eq!(
-            buffer.point_for_anchor(&right_anchor).unwrap(),
-            Point { row: 2, column: 0 }
+            buffer.convert_offset(text.len() + 1, OffsetUnit::Utf8, OffsetUnit::Utf16),
+            Err(Error::OffsetOutOfRange)
         );
+    }
 
-        buffer.edit(vec![7..9], "", &mut local_clock, &mut lamport_clock);
-        assert_eq!(buffer.to_string(), "adf\nbghc");
-        assert_eq!(buffer.offset_for_anchor(&left_anchor).unwrap(), 5);
-        assert_eq!(buffer.offset_for_anchor(&right_anchor).unwrap(), 7);
+    #[test]
+    fn test_wtf8_storage() {
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+
+        let code_units = vec![0x61, 0xd83d, 0xde00, 0xdc00, 0xe9, 0x20ac, 0xd83d, 0x62];
+        let text = Text::new(code_units.clone());
         assert_eq!(
-            buffer.point_for_anchor(&left_anchor).unwrap(),
-            Point { row: 1, column: 1 }
+            text.code_units(0..text.len()).collect::<Vec<_>>(),
+            code_units
         );
         assert_eq!(
-            buffer.point_for_anchor(&right_anchor).unwrap(),
-            Point { row: 1, column: 3 }
+            text.code_units(2..7).collect::<Vec<_>>(),
+            code_units[2..7].to_vec()
         );
-
-        // Ensure anchoring to a point is equivalent to anchoring to an offset.
+        let text = Text::from("a😀é€\nb");
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 0, column: 0 }),
-            buffer.anchor_before_offset(0)
+            text.code_units(0..text.len()).collect::<Vec<_>>(),
+            "a😀é€\nb".encode_utf16().collect::<Vec<_>>()
         );
+
+        // Splitting a surrogate pair between fragments still produces the original char once the
+        // halves are adjacent again, and lone halves become replacement characters.
+        let mut buffer = Buffer::new(code_units.clone());
+        assert_eq!(buffer.to_string(), String::from_utf16_lossy(&code_units));
+        buffer.edit(vec![2..2], "x", &mut local_clock, &mut lamport_clock);
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 0, column: 1 }),
-            buffer.anchor_before_offset(1)
+            buffer.to_string(),
+            "a\u{fffd}x\u{fffd}\u{fffd}é€\u{fffd}b"
         );
+        buffer.edit(vec![2..3], "", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), String::from_utf16_lossy(&code_units));
+        assert_eq!(buffer.snapshot().to_string(), buffer.to_string());
+    }
+    #[test]
+    fn test_buffer_snapshot() {
+        use std::thread;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BufferSnapshot>();
+
+        let mut buffer = Buffer::new("abc\ndef");
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        buffer.edit(vec![1..2], "xy", &mut local_clock, &mut lamport_clock);
+        let anchor = buffer.anchor_before_offset(6).unwrap();
+        let snapshot = buffer.snapshot();
+        assert_eq!(snapshot.version, buffer.version);
+        assert!(Arc::ptr_eq(
+            &snapshot.insertion_splits,
+            &buffer.insertion_splits
+        ));
+
+        buffer.edit(vec![0..0], "123\n", &mut local_clock, &mut lamport_clock);
+        assert!(!Arc::ptr_eq(
+            &snapshot.insertion_splits,
+            &buffer.insertion_splits
+        ));
+        assert_eq!(buffer.to_string(), "123\naxyc\ndef");
+        assert_eq!(buffer.offset_for_anchor(&anchor).unwrap(), 10);
+
+        thread::spawn(move || {
+            assert_eq!(snapshot.to_string(), "axyc\ndef");
+            assert_eq!(snapshot.len(), 8);
+            assert_eq!(snapshot.max_point(), Point::new(1, 3));
+            assert_eq!(
+                snapshot.line(1).unwrap(),
+                "def".encode_utf16().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                snapshot.iter_at_point(Point::new(0, 2)).into_string(),
+                "yc\ndef"
+            );
+            assert_eq!(snapshot.point_for_offset(6), Ok(Point::new(1, 1)));
+            assert_eq!(snapshot.offset_for_point(Point::new(1, 1)), Ok(6));
+            assert_eq!(snapshot.point_for_offset(9), Err(Error::OffsetOutOfRange));
+            assert_eq!(snapshot.offset_for_anchor(&anchor), Ok(6));
+            assert_eq!(snapshot.point_for_anchor(&anchor), Ok(Point::new(1, 1)));
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_search() {
+        let mut buffer = Buffer::new("Foo foo\nfood 🦊 FOO");
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let offset_ranges = |buffer: &Buffer, ranges: Vec<Range<Anchor>>| {
+            ranges
+                .into_iter()
+                .map(|range| {
+                    buffer.offset_for_anchor(&range.start).unwrap()
+                        ..buffer.offset_for_anchor(&range.end).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let query = SearchQuery::new("foo", SearchOptions::default()).unwrap();
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 0, column: 2 }),
-            buffer.anchor_before_offset(2)
+            offset_ranges(&buffer, buffer.search(&query)),
+            vec![4..7, 8..11]
         );
+
+        let options = SearchOptions {
+            case_insensitive: true,
+            whole_word: true,
+            ..SearchOptions::default()
+        };
+        let query = SearchQuery::new("foo", options).unwrap();
+        let ranges = buffer.search(&query);
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 0, column: 3 }),
-            buffer.anchor_before_offset(3)
+            offset_ranges(&buffer, ranges.clone()),
+            vec![0..3, 4..7, 16..19]
         );
+
+        let find = |offset, direction| {
+            buffer
+                .find(&query, offset, direction)
+                .unwrap()
+                .map(|range| offset_ranges(&buffer, vec![range]).remove(0))
+        };
+        assert_eq!(find(1, SearchDirection::Forward), Some(4..7));
+        assert_eq!(find(17, SearchDirection::Forward), None);
+        assert_eq!(find(6, SearchDirection::Backward), Some(0..3));
+        assert_eq!(find(19, SearchDirection::Backward), Some(16..19));
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 1, column: 0 }),
-            buffer.anchor_before_offset(4)
+            buffer.find(&query, 20, SearchDirection::Forward),
+            Err(Error::OffsetOutOfRange)
         );
+
+        let options = SearchOptions {
+            regex: true,
+            ..SearchOptions::default()
+        };
+        let query = SearchQuery::new(r"^fo+d?\s", options).unwrap();
+        assert_eq!(offset_ranges(&buffer, buffer.search(&query)), vec![8..13]);
+        let query = SearchQuery::new(r"o\sf", options).unwrap();
+        assert!(query.spans_rows);
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 1, column: 1 }),
-            buffer.anchor_before_offset(5)
+            offset_ranges(&buffer, buffer.search(&query)),
+            vec![2..5, 6..9]
         );
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 1, column: 2 }),
-            buffer.anchor_before_offset(6)
+            buffer
+                .find(&query, 19, SearchDirection::Backward)
+                .unwrap()
+                .map(|range| offset_ranges(&buffer, vec![range]).remove(0)),
+            Some(6..9)
         );
+        let query = SearchQuery::new(r"fo+", options).unwrap();
+        assert!(!query.spans_rows);
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 1, column: 3 }),
-            buffer.anchor_before_offset(7)
+            offset_ranges(&buffer, buffer.search(&query)),
+            vec![4..7, 8..11]
         );
+        assert!(SearchQuery::new("(foo", options).is_err());
+        let query = SearchQuery::new("(foo", SearchOptions::default()).unwrap();
+        assert!(buffer.search(&query).is_empty());
+
+        // Match ranges keep tracking the matched text as the buffer is edited around them.
+        buffer.edit(vec![3..4, 7..7], "--", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "Foo--foo--\nfood 🦊 FOO");
+        assert_eq!(offset_ranges(&buffer, ranges), vec![0..3, 5..8, 19..22]);
+
+        let query = SearchQuery::new("fo", SearchOptions::default()).unwrap();
+        let ops = buffer.replace_all(&query, "ba", &mut local_clock, &mut lamport_clock);
+        assert_eq!(ops.len(), 2);
+        assert_eq!(buffer.to_string(), "Foo--bao--\nbaod 🦊 FOO");
+        let ops = buffer.replace_all(&query, "ba", &mut local_clock, &mut lamport_clock);
+        assert!(ops.is_empty());
+
+        // Overlapping matches are all reported, and found from offsets inside other matches.
+        let mut buffer = Buffer::new("aaa\naaa");
+        let query = SearchQuery::new("aa", SearchOptions::default()).unwrap();
         assert_eq!(
-            buffer.anchor_before_point(Point { row: 1, column: 4 }),
-            buffer.anchor_before_offset(8)
+            offset_ranges(&buffer, buffer.search(&query)),
+            vec![0..2, 1..3, 4..6, 5..7]
         );
+        let find = |buffer: &Buffer, offset, direction| {
+            buffer
+                .find(&query, offset, direction)
+                .unwrap()
+                .map(|range| offset_ranges(buffer, vec![range]).remove(0))
+        };
+        assert_eq!(find(&buffer, 1, SearchDirection::Forward), Some(1..3));
+        assert_eq!(find(&buffer, 2, SearchDirection::Forward), Some(4..6));
+        assert_eq!(find(&buffer, 5, SearchDirection::Forward), Some(5..7));
+        assert_eq!(find(&buffer, 3, SearchDirection::Backward), Some(1..3));
+        assert_eq!(find(&buffer, 6, SearchDirection::Backward), Some(4..6));
+        buffer.replace_all(&query, "b", &mut local_clock, &mut lamport_clock);
+        assert_eq!(buffer.to_string(), "ba\nba");
+    }
 
-        // Comparison between anchors.
-        let anchor_at_offset_0 = buffer.anchor_before_offset(0).unwrap();
-        let anchor_at_offset_1 = buffer.anchor_before_offset(1).unwrap();
-        let anchor_at_offset_2 = buffer.anchor_before_offset(2).unwrap();
+    #[test]
+    fn test_clip() {
+        let mut buffer = Buffer::new("ab🦊\nc");
+        let replica_id = Uuid::from_u128(1);
+        let mut local_clock = time::Local::new(replica_id);
+        let mut lamport_clock = time::Lamport::new(replica_id);
 
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_0, &anchor_at_offset_0),
-            Ok(Ordering::Equal)
+            buffer.clip_point(Point::new(0, 3), AnchorBias::Left),
+            Point::new(0, 2)
         );
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_1, &anchor_at_offset_1),
-            Ok(Ordering::Equal)
+            buffer.clip_point(Point::new(0, 3), AnchorBias::Right),
+            Point::new(0, 4)
         );
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_2, &anchor_at_offset_2),
-            Ok(Ordering::Equal)
+            buffer.clip_point(Point::new(0, 10), AnchorBias::Left),
+            Point::new(0, 4)
         );
-
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_0, &anchor_at_offset_1),
-            Ok(Ordering::Less)
+            buffer.clip_point(Point::new(1, 7), AnchorBias::Right),
+            Point::new(1, 1)
         );
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_1, &anchor_at_offset_2),
-            Ok(Ordering::Less)
+            buffer.clip_point(Point::new(5, 0), AnchorBias::Left),
+            Point::new(1, 1)
         );
+        assert!(buffer.is_valid_point(Point::new(0, 4)));
+        assert!(!buffer.is_valid_point(Point::new(0, 3)));
+        assert!(!buffer.is_valid_point(Point::new(1, 2)));
+
+        assert_eq!(buffer.clip_offset(3, AnchorBias::Left), 2);
+        assert_eq!(buffer.clip_offset(3, AnchorBias::Right), 4);
+        assert_eq!(buffer.clip_offset(100, AnchorBias::Left), 6);
+        assert!(buffer.is_valid_offset(6));
+        assert!(!buffer.is_valid_offset(3));
+        assert!(!buffer.is_valid_offset(7));
+
+        assert_eq!(buffer.point_for_offset(5), Ok(Point::new(1, 0)));
+        assert_eq!(buffer.point_for_offset(7), Err(Error::OffsetOutOfRange));
+        assert_eq!(buffer.offset_for_point(Point::new(1, 1)), Ok(6));
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_0, &anchor_at_offset_2),
-            Ok(Ordering::Less)
+            buffer.offset_for_point(Point::new(0, 5)),
+            Err(Error::OffsetOutOfRange)
         );
 
+        buffer.edit(vec![0..1], "", &mut local_clock, &mut lamport_clock);
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_1, &anchor_at_offset_0),
-            Ok(Ordering::Greater)
+            buffer.clip_point(Point::new(0, 2), AnchorBias::Left),
+            Point::new(0, 1)
         );
+        assert_eq!(buffer.clip_offset(2, AnchorBias::Right), 3);
+
+        // Surrogates that aren't part of a pair are characters on their own.
+        let buffer = Buffer::new(vec![0xdc00, 0x61, 0xd83e, 0xdc00]);
+        assert!(buffer.is_valid_offset(0));
+        assert!(buffer.is_valid_offset(1));
+        assert!(!buffer.is_valid_offset(3));
+        assert_eq!(buffer.clip_offset(3, AnchorBias::Left), 2);
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_2, &anchor_at_offset_1),
-            Ok(Ordering::Greater)
+            buffer.clip_point(Point::new(0, 1), AnchorBias::Left),
+            Point::new(0, 1)
         );
+
+        let buffer = Buffer::new("");
         assert_eq!(
-            buffer.cmp_anchors(&anchor_at_offset_2, &anchor_at_offset_0),
-            Ok(Ordering::Greater)
+            buffer.clip_point(Point::new(1, 1), AnchorBias::Left),
+            Point::zero()
         );
+        assert!(buffer.is_valid_point(Point::zero()));
+        assert_eq!(buffer.offset_for_point(Point::zero()), Ok(0));
     }
 
     #[test]
-    fn test_anchors_at_start_and_end() {
-        let mut buffer = Buffer::new("");
-        let replica_id = Uuid::from_u128(1);
-        let mut local_clock = time::Local::new(replica_id);
-        let mut lamport_clock = time::Lamport::new(replica_id);
-        let before_start_anchor = buffer.anchor_before_offset(0).unwrap();
-        let after_end_anchor = buffer.anchor_after_offset(0).unwrap();
+    fn test_snapshot() {
+        for seed in 0..20 {
+            let mut rng = StdRng::from_seed(&[seed]);
+            let replica_id = Uuid::from_u128(1);
+            let mut local_clock = time::Local::new(replica_id);
+            let mut lamport_clock = time::Lamport::new(replica_id);
+            let mut buffer_1 = Buffer::new("abc\ndef");
+            let mut ops_1 = Vec::new();
+            for _ in 0..5 {
+                let (_, _, ops) =
+                    buffer_1.randomly_mutate(&mut rng, &mut local_clock, &mut lamport_clock);
+                ops_1.extend(ops);
+            }
 
-        buffer.edit(vec![0..0], "abc", &mut local_clock, &mut lamport_clock);
-        assert_eq!(buffer.to_string(), "abc");
-        assert_eq!(buffer.offset_for_anchor(&before_start_anchor).unwrap(), 0);
-        assert_eq!(buffer.offset_for_anchor(&after_end_anchor).unwrap(), 3);
+            // Build on replica 1's operations from another replica, so that some of its
+            // operations get deferred on a replica that hasn't seen replica 1's yet.
+            let other_id = Uuid::from_u128(3);
+            let mut other_local_clock = time::Local::new(other_id);
+            let mut other_lamport_clock = time::Lamport::new(other_id);
+            let mut other_buffer = Buffer::new("abc\ndef");
+            other_buffer
+                .apply_ops(
+                    ops_1.iter().cloned(),
+                    &mut other_local_clock,
+                    &mut other_lamport_clock,
+                )
+                .unwrap();
+            let mut ops_2 = Vec::new();
+            for _ in 0..10 {
+                if rng.gen_weighted_bool(4) {
+                    ops_2.extend(
+                        other_buffer
+                            .undo(&mut other_local_clock, &mut other_lamport_clock)
+                            .unwrap(),
+                    );
+                } else {
+                    let (_, _, ops) = other_buffer.randomly_mutate(
+                        &mut rng,
+                        &mut other_local_clock,
+                        &mut other_lamport_clock,
+                    );
+                    ops_2.extend(ops);
+                }
+            }
+            buffer_1
+                .apply_ops(ops_2.iter().cloned(), &mut local_clock, &mut lamport_clock)
+                .unwrap();
 
-        let after_start_anchor = buffer.anchor_after_offset(0).unwrap();
-        let before_end_anchor = buffer.anchor_before_offset(3).unwrap();
+            let remote_id = Uuid::from_u128(2);
+            let mut remote_local_clock = time::Local::new(remote_id);
+            let mut remote_lamport_clock = time::Lamport::new(remote_id);
+            let mut buffer_2 = Buffer::new("abc\ndef");
+            buffer_2
+                .apply_ops(
+                    ops_2.iter().cloned(),
+                    &mut remote_local_clock,
+                    &mut remote_lamport_clock,
+                )
+                .unwrap();
 
-        buffer.edit(vec![3..3], "def", &mut local_clock, &mut lamport_clock);
-        buffer.edit(vec![0..0], "ghi", &mut local_clock, &mut lamport_clock);
-        assert_eq!(buffer.to_string(), "ghiabcdef");
-        assert_eq!(buffer.offset_for_anchor(&before_start_anchor).unwrap(), 0);
-        assert_eq!(buffer.offset_for_anchor(&after_start_anchor).unwrap(), 3);
-        assert_eq!(buffer.offset_for_anchor(&before_end_anchor).unwrap(), 6);
-        assert_eq!(buffer.offset_for_anchor(&after_end_anchor).unwrap(), 9);
+            let mut builder = FlatBufferBuilder::new();
+            let root = buffer_2.to_flatbuf(&mut builder);
+            builder.finish(root, None);
+            let mut buffer_3 = Buffer::from_flatbuf(flatbuffers::get_root::<
+                serialization::buffer::Snapshot,
+            >(builder.finished_data()))
+            .unwrap();
+            assert_eq!(buffer_3.to_string(), buffer_2.to_string());
+            assert_eq!(buffer_3.version, buffer_2.version);
+            assert_eq!(buffer_3.deferred_ops.len(), buffer_2.deferred_ops.len());
+            assert_eq!(
+                buffer_3.all_selections().collect::<HashMap<_, _>>(),
+                buffer_2.all_selections().collect::<HashMap<_, _>>()
+            );
+
+            for buffer in &mut [&mut buffer_2, &mut buffer_3] {
+                buffer
+                    .apply_ops(
+                        ops_1.iter().cloned(),
+                        &mut remote_local_clock,
+                        &mut remote_lamport_clock,
+                    )
+                    .unwrap();
+                assert_eq!(buffer.to_string(), buffer_1.to_string());
+                assert_eq!(
+                    buffer.all_selection_ranges().collect::<HashMap<_, _>>(),
+                    buffer_1.all_selection_ranges().collect::<HashMap<_, _>>()
+                );
+            }
+        }
     }
 
     #[test]
-    fn test_is_modified() {
-        let mut buffer = Buffer::new("abc");
-        let replica_id = Uuid::from_u128(1);
-        let mut local_clock = time::Local::new(replica_id);
-        let mut lamport_clock = time::Lamport::new(replica_id);
+    fn test_line_merge() {
+        let base = "a\nb\nc\nd\ne\n";
+        let local = "a\nB\nc\nd\ne\nf\n";
+        let upstream = "z\na\nb\nc\nD\ne\n";
+        assert_eq!(
+            line_merge(base, local, upstream),
+            ("z\na\nB\nc\nD\ne\nf\n".to_string(), vec![])
+        );
 
-        assert!(!buffer.is_modified());
-        buffer.edit(vec![1..2], "", &mut local_clock, &mut lamport_clock);
-        assert!(buffer.is_modified());
+        let local = "a\nb\nc\nX\ne\n";
+        assert_eq!(
+            line_merge(base, local, upstream),
+            (
+                upstream.to_string(),
+                vec![LineDiffHunk {
+                    old_rows: 3..4,
+                    new_rows: 3..4,
+                    old_text: "d\n".into(),
+                    new_text: "X\n".into(),
+                }]
+            )
+        );
     }
 
     #[test]
@@ -3018,7 +5794,18 @@ mod tests {
                 let local_clock = &mut local_clocks[replica_index];
                 let lamport_clock = &mut lamport_clocks[replica_index];
                 if mutation_count > 0 && rng.gen() {
-                    let (_, _, ops) = buffer.randomly_mutate(&mut rng, local_clock, lamport_clock);
+                    let ops = if rng.gen_weighted_bool(4) {
+                        let op = if rng.gen() {
+                            buffer.undo(local_clock, lamport_clock).unwrap()
+                        } else {
+                            buffer.redo(local_clock, lamport_clock).unwrap()
+                        };
+                        op.into_iter().collect()
+                    } else {
+                        let (_, _, ops) =
+                            buffer.randomly_mutate(&mut rng, local_clock, lamport_clock);
+                        ops
+                    };
                     network.broadcast(replica_id, ops, &mut rng);
                     mutation_count -= 1;
                 } else if network.has_unreceived(replica_id) {
@@ -3050,6 +5837,158 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_random_concurrent_edits_with_garbage_collection() {
+        use crate::tests::Network;
+
+        #[derive(Clone)]
+        enum Message {
+            Operation(Operation),
+            Observed(time::Global),
+            Acknowledged(time::Global),
+        }
+
+        const PEERS: usize = 3;
+
+        for seed in 0..50 {
+            println!("{:?}", seed);
+            let mut rng = StdRng::from_seed(&[seed]);
+
+            let base_text = RandomCharIter(rng)
+                .take(rng.gen_range(0, 10))
+                .collect::<String>();
+            let mut replica_ids = Vec::new();
+            let mut buffers = Vec::new();
+            let mut local_clocks = Vec::new();
+            let mut lamport_clocks = Vec::new();
+            let mut observed_versions = Vec::new();
+            let mut acknowledged_versions = Vec::new();
+            let mut anchors = Vec::new();
+            let mut network = Network::new();
+            for i in 0..PEERS {
+                let buffer = Buffer::new(base_text.as_str());
+                buffers.push(buffer);
+                let replica_id = Uuid::from_u128((i + 1) as u128);
+                replica_ids.push(replica_id);
+                local_clocks.push(time::Local::new(replica_id));
+                lamport_clocks.push(time::Lamport::new(replica_id));
+                observed_versions.push(HashMap::new());
+                acknowledged_versions.push(HashMap::new());
+                anchors.push(Vec::new());
+                network.add_peer(replica_id);
+            }
+
+            let mut mutation_count = 30;
+            loop {
+                let replica_index = rng.gen_range(0, PEERS);
+                let replica_id = replica_ids[replica_index];
+                let buffer = &mut buffers[replica_index];
+                let local_clock = &mut local_clocks[replica_index];
+                let lamport_clock = &mut lamport_clocks[replica_index];
+                let observed_versions = &mut observed_versions[replica_index];
+                let acknowledged_versions = &mut acknowledged_versions[replica_index];
+                let anchors = &mut anchors[replica_index];
+                if mutation_count > 0 && rng.gen() {
+                    let ops = if rng.gen_weighted_bool(4) {
+                        let op = if rng.gen() {
+                            buffer.undo(local_clock, lamport_clock).unwrap()
+                        } else {
+                            buffer.redo(local_clock, lamport_clock).unwrap()
+                        };
+                        op.into_iter().collect()
+                    } else {
+                        let (_, _, ops) =
+                            buffer.randomly_mutate(&mut rng, local_clock, lamport_clock);
+                        ops
+                    };
+                    let messages = ops.into_iter().map(Message::Operation).collect();
+                    network.broadcast(replica_id, messages, &mut rng);
+                    mutation_count -= 1;
+
+                    // Hosts hold on to anchors outside of selection sets, such as search results.
+                    let offset = rng.gen_range(0, buffer.len() + 1);
+                    let anchor = if rng.gen() {
+                        buffer.anchor_before_offset(offset).unwrap()
+                    } else {
+                        buffer.anchor_after_offset(offset).unwrap()
+                    };
+                    anchors.push(anchor);
+                } else if rng.gen_weighted_bool(3) {
+                    // Peers learn about each other's versions and acknowledge them while
+                    // operations are still in flight.
+                    let message = if rng.gen() {
+                        Message::Observed(buffer.version.clone())
+                    } else if observed_versions.len() == PEERS - 1 {
+                        let acknowledged_version = observed_versions
+                            .values()
+                            .fold(buffer.version.clone(), |version, observed_version| {
+                                version.meet(observed_version)
+                            });
+                        buffer.discard_history(&acknowledged_version);
+                        Message::Acknowledged(acknowledged_version)
+                    } else {
+                        continue;
+                    };
+                    network.broadcast(replica_id, vec![message], &mut rng);
+                } else if rng.gen_weighted_bool(3) && acknowledged_versions.len() == PEERS - 1 {
+                    let stable_version = acknowledged_versions
+                        .values()
+                        .fold(buffer.version.clone(), |version, acknowledged_version| {
+                            version.meet(acknowledged_version)
+                        });
+                    let text = buffer.to_string();
+                    let fragment_count = buffer.fragments.items().len();
+                    let anchor_positions = anchors
+                        .iter()
+                        .map(|anchor| buffer.position_for_anchor(anchor).unwrap())
+                        .collect::<Vec<_>>();
+                    buffer.collect_garbage(&stable_version);
+                    assert_eq!(buffer.to_string(), text);
+                    assert!(buffer.fragments.items().len() <= fragment_count);
+                    assert_eq!(
+                        anchors
+                            .iter()
+                            .map(|anchor| buffer.position_for_anchor(anchor).unwrap())
+                            .collect::<Vec<_>>(),
+                        anchor_positions
+                    );
+                } else if network.has_unreceived(replica_id) {
+                    let mut ops = Vec::new();
+                    for (sender, message) in network.receive_with_senders(replica_id, &mut rng) {
+                        match message {
+                            Message::Operation(op) => ops.push(op),
+                            Message::Observed(version) => observed_versions
+                                .entry(sender)
+                                .or_insert_with(time::Global::new)
+                                .observe_all(&version),
+                            Message::Acknowledged(version) => acknowledged_versions
+                                .entry(sender)
+                                .or_insert_with(time::Global::new)
+                                .observe_all(&version),
+                        }
+                    }
+                    buffer.apply_ops(ops, local_clock, lamport_clock).unwrap();
+                }
+
+                if network.is_idle() && mutation_count == 0 {
+                    break;
+                }
+            }
+
+            for buffer in &buffers[1..] {
+                assert_eq!(buffer.to_string(), buffers[0].to_string());
+                assert_eq!(
+                    buffer.all_selections().collect::<HashMap<_, _>>(),
+                    buffers[0].all_selections().collect::<HashMap<_, _>>()
+                );
+                assert_eq!(
+                    buffer.all_selection_ranges().collect::<HashMap<_, _>>(),
+                    buffers[0].all_selection_ranges().collect::<HashMap<_, _>>()
+                );
+            }
+        }
+    }
+
     struct RandomCharIter<T: Rng>(T);
 
     impl<T: Rng> Iterator for RandomCharIter<T> {
@@ -3132,19 +6071,5 @@ mod tests {
 
             (old_ranges, new_text, operations)
         }
-
-        fn point_for_offset(&self, offset: usize) -> Result<Point, Error> {
-            let mut fragments_cursor = self.fragments.cursor();
-            fragments_cursor.seek(&offset, SeekBias::Left);
-            fragments_cursor
-                .item()
-                .ok_or(Error::OffsetOutOfRange)
-                .map(|fragment| {
-                    let overshoot = fragment
-                        .point_for_offset(offset - &fragments_cursor.start::<usize>())
-                        .unwrap();
-                    fragments_cursor.start::<Point>() + &overshoot
-                })
-        }
     }
 }
@@ -0,0 +1,863 @@
+use crate::buffer;
+use crate::epoch::{FileAttributes, FileStatus, FileType};
+use crate::git;
+use crate::work_tree::{BufferId, OperationEnvelope, WorkTree};
+use crate::{Error, Oid};
+use futures::Future;
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+/// Keeps a directory on disk in sync with a `WorkTree`. Changes to the tree are written out with
+/// `write_to_disk`, and external changes to the directory are turned into operations by
+/// `read_from_disk`. The `.git` directory at the root is ignored.
+///
+/// Base text and the contents of binary files are read from the tree's `GitProvider` by waiting on
+/// its futures, so providers are expected to resolve them without relying on the calling thread.
+/// Files on disk that aren't valid UTF-8 text are skipped: they aren't added to the tree, and the
+/// tree entries at their paths are left untouched, except for their executable bit.
+pub struct DirSync {
+    root: PathBuf,
+    _watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>,
+    disk_state: HashMap<PathBuf, DiskEntry>,
+}
+
+/// What we last wrote to or read from disk for a given path. Files whose text comes from the base
+/// commit remember where it came from so that we can skip loading it again.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum DiskEntry {
+    Directory,
+    Text {
+        base: Option<(Option<Oid>, PathBuf)>,
+        hash: u64,
+        executable: bool,
+    },
+    Symlink(PathBuf),
+    Binary {
+        oid: Oid,
+        executable: bool,
+    },
+}
+
+impl DirSync {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Result<Self, Error> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        let root = fs::canonicalize(&root)?;
+
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::watcher(tx, Duration::from_millis(100)).map_err(notify_error)?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(notify_error)?;
+
+        Ok(DirSync {
+            root,
+            _watcher: watcher,
+            events,
+            disk_state: HashMap::new(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Writes every entry of `tree` that differs from what we last saw on disk, and removes the
+    /// entries that no longer exist in the tree.
+    pub fn write_to_disk(&mut self, tree: &WorkTree) -> Result<(), Error> {
+        let head = tree.head();
+        let modified_paths = tree
+            .status()
+            .into_iter()
+            .filter_map(|entry| match entry.status {
+                FileStatus::New | FileStatus::Modified | FileStatus::RenamedAndModified => {
+                    Some(entry.path)
+                }
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        let mut entries = Vec::new();
+        tree.with_cursor(|cursor| loop {
+            let entry = cursor.entry().unwrap();
+            if entry.visible {
+                entries.push((
+                    cursor.path().unwrap().to_path_buf(),
+                    cursor.base_path().unwrap(),
+                    entry.file_type,
+                ));
+            }
+            if !cursor.next(entry.visible) {
+                break;
+            }
+        });
+
+        let mut paths = HashSet::new();
+        for (path, base_path, file_type) in entries {
+            let abs_path = self.root.join(&path);
+            let old_entry = self.disk_state.get(&path).cloned();
+            let attributes = tree.attributes(&path)?;
+            match file_type {
+                FileType::Directory => {
+                    if old_entry != Some(DiskEntry::Directory) {
+                        if old_entry.is_some() {
+                            remove_from_disk(&abs_path)?;
+                        }
+                        fs::create_dir_all(&abs_path)?;
+                        self.disk_state.insert(path.clone(), DiskEntry::Directory);
+                    }
+                }
+                FileType::Text => {
+                    let (base, text) = if modified_paths.contains(&path) {
+                        let text = with_buffer(tree, &path, |buffer_id| {
+                            Ok(tree.text(buffer_id)?.into_string())
+                        })?;
+                        (None, text)
+                    } else {
+                        let base = base_path.map(|base_path| (head, base_path));
+                        if let Some(DiskEntry::Text {
+                            base: old_base,
+                            executable,
+                            ..
+                        }) = &old_entry
+                        {
+                            if old_base.is_some()
+                                && *old_base == base
+                                && *executable == attributes.executable
+                            {
+                                paths.insert(path);
+                                continue;
+                            }
+                        }
+                        (base, tree.read_base_text(&path).wait()?)
+                    };
+
+                    let hash = hash_text(&text);
+                    let (up_to_date, was_executable) = match &old_entry {
+                        Some(DiskEntry::Text {
+                            hash: old_hash,
+                            executable,
+                            ..
+                        }) => (*old_hash == hash, Some(*executable)),
+                        _ => (false, None),
+                    };
+                    if !up_to_date {
+                        if old_entry.is_some() && !is_text(&old_entry) {
+                            remove_from_disk(&abs_path)?;
+                        }
+                        fs::write(&abs_path, text)?;
+                    }
+                    if !up_to_date || was_executable != Some(attributes.executable) {
+                        set_executable(&abs_path, attributes.executable)?;
+                    }
+                    self.disk_state.insert(
+                        path.clone(),
+                        DiskEntry::Text {
+                            base,
+                            hash,
+                            executable: attributes.executable,
+                        },
+                    );
+                }
+                FileType::Symlink => {
+                    let target = attributes.symlink_target.unwrap_or(PathBuf::new());
+                    let new_entry = DiskEntry::Symlink(target.clone());
+                    if old_entry.as_ref() != Some(&new_entry) {
+                        if old_entry.is_some() {
+                            remove_from_disk(&abs_path)?;
+                        }
+                        create_symlink(&target, &abs_path)?;
+                        self.disk_state.insert(path.clone(), new_entry);
+                    }
+                }
+                FileType::Binary => {
+                    let oid = attributes.oid.ok_or(Error::InvalidOperation)?;
+                    let new_entry = DiskEntry::Binary {
+                        oid,
+                        executable: attributes.executable,
+                    };
+                    if old_entry.as_ref() != Some(&new_entry) {
+                        match old_entry {
+                            Some(DiskEntry::Binary { oid: old_oid, .. }) if old_oid == oid => {}
+                            _ => {
+                                if old_entry.is_some() {
+                                    remove_from_disk(&abs_path)?;
+                                }
+                                fs::write(&abs_path, tree.read_blob(oid).wait()?)?;
+                            }
+                        }
+                        set_executable(&abs_path, attributes.executable)?;
+                        self.disk_state.insert(path.clone(), new_entry);
+                    }
+                }
+            }
+            paths.insert(path);
+        }
+
+        let mut stale_paths = self
+            .disk_state
+            .keys()
+            .filter(|path| !paths.contains(*path))
+            .cloned()
+            .collect::<Vec<_>>();
+        stale_paths.sort();
+        for path in stale_paths {
+            self.disk_state.remove(&path);
+            remove_from_disk(&self.root.join(path))?;
+        }
+
+        Ok(())
+    }
+
+    /// Translates the changes that were observed on disk since the last call into operations on
+    /// `tree`.
+    pub fn read_from_disk(&mut self, tree: &WorkTree) -> Result<Vec<OperationEnvelope>, Error> {
+        let mut ops = Vec::new();
+        loop {
+            let event = match self.events.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    return Err(Error::IoError(io::Error::new(
+                        io::ErrorKind::Other,
+                        "file watcher disconnected",
+                    )));
+                }
+            };
+
+            match event {
+                DebouncedEvent::Create(path)
+                | DebouncedEvent::Write(path)
+                | DebouncedEvent::Chmod(path)
+                | DebouncedEvent::Remove(path) => {
+                    if let Some(path) = self.relative_path(&path) {
+                        self.reconcile(tree, &path, false, &mut ops)?;
+                    }
+                }
+                DebouncedEvent::Rename(old_path, new_path) => {
+                    match (self.relative_path(&old_path), self.relative_path(&new_path)) {
+                        (Some(old_path), Some(new_path)) => {
+                            self.rename(tree, &old_path, &new_path, &mut ops)?
+                        }
+                        (Some(path), None) | (None, Some(path)) => {
+                            self.reconcile(tree, &path, false, &mut ops)?
+                        }
+                        (None, None) => {}
+                    }
+                }
+                DebouncedEvent::Rescan => {
+                    self.reconcile(tree, Path::new(""), true, &mut ops)?;
+                }
+                DebouncedEvent::Error(error, _) => return Err(notify_error(error)),
+                DebouncedEvent::NoticeWrite(_) | DebouncedEvent::NoticeRemove(_) => {}
+            }
+        }
+        Ok(ops)
+    }
+
+    /// Compares the entire directory against `tree` and returns the operations needed to bring
+    /// the tree up to date with the disk.
+    pub fn rescan(&mut self, tree: &WorkTree) -> Result<Vec<OperationEnvelope>, Error> {
+        let mut ops = Vec::new();
+        self.reconcile(tree, Path::new(""), true, &mut ops)?;
+        Ok(ops)
+    }
+
+    fn rename(
+        &mut self,
+        tree: &WorkTree,
+        old_path: &Path,
+        new_path: &Path,
+        ops: &mut Vec<OperationEnvelope>,
+    ) -> Result<(), Error> {
+        if tree.exists(old_path)
+            && !tree.exists(new_path)
+            && !self.root.join(old_path).exists()
+            && self.root.join(new_path).exists()
+        {
+            ops.push(tree.rename(old_path, new_path)?);
+            let moved_entries = self
+                .disk_state
+                .keys()
+                .filter(|path| path.starts_with(old_path))
+                .cloned()
+                .collect::<Vec<_>>();
+            for path in moved_entries {
+                let entry = self.disk_state.remove(&path).unwrap();
+                let path = new_path.join(path.strip_prefix(old_path).unwrap());
+                self.disk_state.insert(path, entry);
+            }
+            self.reconcile(tree, new_path, false, ops)
+        } else {
+            self.reconcile(tree, old_path, false, ops)?;
+            self.reconcile(tree, new_path, false, ops)
+        }
+    }
+
+    fn reconcile(
+        &mut self,
+        tree: &WorkTree,
+        path: &Path,
+        recursive: bool,
+        ops: &mut Vec<OperationEnvelope>,
+    ) -> Result<(), Error> {
+        let abs_path = self.root.join(path);
+        let is_root = path == Path::new("");
+        let tree_type = if is_root {
+            Some(FileType::Directory)
+        } else {
+            tree.file_type(path).ok()
+        };
+        let mut contents = None;
+        let mut executable = None;
+        let disk_type = match fs::symlink_metadata(&abs_path) {
+            Ok(metadata) => {
+                executable = is_executable(&metadata);
+                if metadata.is_dir() {
+                    Some(FileType::Directory)
+                } else if metadata.file_type().is_symlink() {
+                    Some(FileType::Symlink)
+                } else if metadata.is_file() {
+                    if tree_type == Some(FileType::Binary) {
+                        Some(FileType::Binary)
+                    } else {
+                        let data = fs::read(&abs_path)?;
+                        if git::is_binary(&data) {
+                            return Ok(());
+                        }
+                        match String::from_utf8(data) {
+                            Ok(text) => {
+                                contents = Some(text);
+                                Some(FileType::Text)
+                            }
+                            Err(_) => return Ok(()),
+                        }
+                    }
+                } else {
+                    None
+                }
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error.into()),
+        };
+
+        if tree_type.is_some() && tree_type != disk_type && !is_root {
+            ops.push(tree.remove(path)?);
+            self.disk_state
+                .retain(|disk_path, _| !disk_path.starts_with(path));
+        }
+
+        match disk_type {
+            Some(FileType::Directory) => {
+                let is_new = tree_type != Some(FileType::Directory);
+                if is_new {
+                    ops.push(tree.create_file(path, FileType::Directory)?);
+                }
+                self.disk_state
+                    .insert(path.to_path_buf(), DiskEntry::Directory);
+
+                if is_new || recursive {
+                    let mut child_names = HashSet::new();
+                    for child in fs::read_dir(&abs_path)? {
+                        let child_name = child?.file_name();
+                        if is_root && child_name == ".git" {
+                            continue;
+                        }
+                        self.reconcile(tree, &path.join(&child_name), recursive, ops)?;
+                        child_names.insert(child_name);
+                    }
+
+                    for child_name in tree_child_names(tree, path) {
+                        if !child_names.contains(&child_name) {
+                            self.reconcile(tree, &path.join(&child_name), recursive, ops)?;
+                        }
+                    }
+                }
+            }
+            Some(FileType::Text) => {
+                if tree_type != Some(FileType::Text) {
+                    ops.push(tree.create_file(path, FileType::Text)?);
+                }
+                let executable = self.reconcile_executable(tree, path, executable, ops)?;
+
+                let text = contents.unwrap();
+                let hash = hash_text(&text);
+                if let Some(DiskEntry::Text {
+                    hash: old_hash,
+                    base,
+                    ..
+                }) = self.disk_state.get(path).cloned()
+                {
+                    if old_hash == hash {
+                        self.disk_state.insert(
+                            path.to_path_buf(),
+                            DiskEntry::Text {
+                                base,
+                                hash,
+                                executable,
+                            },
+                        );
+                        return Ok(());
+                    }
+                }
+
+                with_buffer(tree, path, |buffer_id| {
+                    let changes = buffer::diff(
+                        &tree.text(buffer_id)?.collect::<Vec<_>>(),
+                        &text.encode_utf16().collect::<Vec<_>>(),
+                    );
+                    if !changes.is_empty() {
+                        tree.start_transaction(buffer_id)?;
+                        let mut result = Ok(());
+                        for change in changes {
+                            match tree.edit_2d(buffer_id, Some(change.range), change.code_units) {
+                                Ok(operation) => ops.push(operation),
+                                Err(error) => {
+                                    result = Err(error);
+                                    break;
+                                }
+                            }
+                        }
+                        // End the transaction even if an edit failed, so that it doesn't swallow
+                        // the buffer's subsequent edits.
+                        tree.end_transaction(buffer_id, None)?;
+                        result?;
+                    }
+                    Ok(())
+                })?;
+                self.disk_state.insert(
+                    path.to_path_buf(),
+                    DiskEntry::Text {
+                        base: None,
+                        hash,
+                        executable,
+                    },
+                );
+            }
+            Some(FileType::Symlink) => {
+                let target = fs::read_link(&abs_path)?;
+                if tree_type == Some(FileType::Symlink) {
+                    let attributes = tree.attributes(path)?;
+                    if attributes.symlink_target.as_ref() != Some(&target) {
+                        ops.push(tree.remove(path)?);
+                    }
+                }
+                if !tree.exists(path) {
+                    ops.push(tree.create_file_with_attributes(
+                        path,
+                        FileType::Symlink,
+                        FileAttributes {
+                            symlink_target: Some(target.clone()),
+                            ..FileAttributes::default()
+                        },
+                    )?);
+                }
+                self.disk_state
+                    .insert(path.to_path_buf(), DiskEntry::Symlink(target));
+            }
+            Some(FileType::Binary) => {
+                // Binary contents can't be stored in the tree, so only the executable bit is
+                // synchronized.
+                let executable = self.reconcile_executable(tree, path, executable, ops)?;
+                if let Some(oid) = tree.attributes(path)?.oid {
+                    self.disk_state
+                        .insert(path.to_path_buf(), DiskEntry::Binary { oid, executable });
+                }
+            }
+            None => {
+                self.disk_state.remove(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Updates the executable bit of the file at `path` in the tree to match the disk, and returns
+    /// its new value. Platforms without an executable bit leave the tree untouched.
+    fn reconcile_executable(
+        &self,
+        tree: &WorkTree,
+        path: &Path,
+        executable: Option<bool>,
+        ops: &mut Vec<OperationEnvelope>,
+    ) -> Result<bool, Error> {
+        let attributes = tree.attributes(path)?;
+        match executable {
+            Some(executable) if executable != attributes.executable => {
+                ops.push(tree.update_attributes(
+                    path,
+                    FileAttributes {
+                        executable,
+                        ..attributes
+                    },
+                )?);
+                Ok(executable)
+            }
+            _ => Ok(attributes.executable),
+        }
+    }
+
+    fn relative_path(&self, path: &Path) -> Option<PathBuf> {
+        let path = path.strip_prefix(&self.root).ok()?;
+        match path.components().next() {
+            Some(Component::Normal(name)) if name == ".git" => None,
+            _ => Some(path.to_path_buf()),
+        }
+    }
+}
+
+/// Calls `f` with a buffer for the text file at `path`, reusing the one the host has open if there
+/// is one. Changes `f` makes to the host's buffer are reported to the tree's observer so that the
+/// host's copy of the text stays in sync. Buffers opened here are closed again once `f` returns.
+fn with_buffer<F, T>(tree: &WorkTree, path: &Path, f: F) -> Result<T, Error>
+where
+    F: FnOnce(BufferId) -> Result<T, Error>,
+{
+    if let Some(buffer_id) = tree.buffer_id(path) {
+        let version = tree.buffer_version(buffer_id)?;
+        let result = f(buffer_id);
+        tree.report_changes_since(buffer_id, &version)?;
+        result
+    } else {
+        let buffer_id = tree.open_text_file(path.to_path_buf()).wait()?;
+        let result = f(buffer_id);
+        // Nobody else knows about this buffer, so it has no selection sets whose removal would
+        // need to be broadcast.
+        tree.close_buffer(buffer_id)?;
+        result
+    }
+}
+
+fn tree_child_names(tree: &WorkTree, dir_path: &Path) -> Vec<std::ffi::OsString> {
+    let mut names = Vec::new();
+    tree.with_cursor(|cursor| loop {
+        let entry = cursor.entry().unwrap();
+        let path = cursor.path().unwrap();
+        let descend = entry.visible && dir_path.starts_with(path);
+        if entry.visible && path.parent() == Some(dir_path) {
+            names.push(entry.name.as_ref().clone());
+        }
+        if !cursor.next(descend) {
+            break;
+        }
+    });
+    names
+}
+
+fn remove_from_disk(path: &Path) -> io::Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(ref metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(error) => Err(error),
+    };
+    match result {
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+fn is_text(entry: &Option<DiskEntry>) -> bool {
+    match entry {
+        Some(DiskEntry::Text { .. }) => true,
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, path)
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    permissions.set_mode(if executable {
+        mode | 0o111
+    } else {
+        mode & !0o111
+    });
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn set_executable(_: &Path, _: bool) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> Option<bool> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Some(metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_: &fs::Metadata) -> Option<bool> {
+    None
+}
+
+fn hash_text(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn notify_error(error: notify::Error) -> Error {
+    Error::IoError(io::Error::new(io::ErrorKind::Other, error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{ObjectKind, Repository, TreeBuilder};
+    use crate::tests::temp_dir;
+    use crate::work_tree::tests::TestChangeObserver;
+    use crate::work_tree::ChangeObserver;
+    use futures::Stream;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Instant;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_write_to_disk() {
+        let (tree, _) = new_tree("test_write_to_disk_repo", None);
+        let root = temp_dir("test_write_to_disk");
+        let mut sync = DirSync::new(&root).unwrap();
+
+        sync.write_to_disk(&tree).unwrap();
+        assert_eq!(
+            read_dir(&root),
+            files(&[("a/b", "abc"), ("a/c", "def"), ("bin", "\0bin"), ("d", "")])
+        );
+
+        tree.create_file("e", FileType::Text).unwrap();
+        let e = tree.open_text_file("e").wait().unwrap();
+        tree.edit(e, Some(0..0), "ghi").unwrap();
+        let a_b = tree.open_text_file("a/b").wait().unwrap();
+        tree.edit(a_b, Some(3..3), "!").unwrap();
+        tree.rename("a", "f").unwrap();
+        tree.remove("d").unwrap();
+        tree.update_attributes(
+            "f/c",
+            FileAttributes {
+                executable: true,
+                ..FileAttributes::default()
+            },
+        )
+        .unwrap();
+        sync.write_to_disk(&tree).unwrap();
+        assert_eq!(
+            read_dir(&root),
+            files(&[
+                ("bin", "\0bin"),
+                ("e", "ghi"),
+                ("f/b", "abc!"),
+                ("f/c", "def")
+            ])
+        );
+        assert_ne!(
+            is_executable(&fs::metadata(root.join("f/c")).unwrap()),
+            Some(false)
+        );
+        assert_ne!(
+            is_executable(&fs::metadata(root.join("f/b")).unwrap()),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_rescan() {
+        let (tree, _) = new_tree("test_rescan_repo", None);
+        let root = temp_dir("test_rescan");
+        let mut sync = DirSync::new(&root).unwrap();
+        sync.write_to_disk(&tree).unwrap();
+        assert!(sync.rescan(&tree).unwrap().is_empty());
+
+        fs::write(root.join("a/b"), "xabc").unwrap();
+        fs::remove_file(root.join("a/c")).unwrap();
+        fs::create_dir(root.join("g")).unwrap();
+        fs::write(root.join("g/h"), "jkl").unwrap();
+        fs::create_dir(root.join(".git")).unwrap();
+        assert!(!sync.rescan(&tree).unwrap().is_empty());
+
+        let a_b = tree.open_text_file("a/b").wait().unwrap();
+        assert_eq!(tree.text(a_b).unwrap().into_string(), "xabc");
+        assert!(!tree.exists("a/c"));
+        let g_h = tree.open_text_file("g/h").wait().unwrap();
+        assert_eq!(tree.text(g_h).unwrap().into_string(), "jkl");
+        assert!(!tree.exists(".git"));
+        assert!(sync.rescan(&tree).unwrap().is_empty());
+
+        // Files that aren't valid UTF-8 are skipped, and buffers opened to apply changes from
+        // disk are closed again.
+        tree.close_buffer(a_b).unwrap();
+        tree.close_buffer(g_h).unwrap();
+        fs::write(root.join("a/b"), b"\xff\xfe").unwrap();
+        fs::write(root.join("i"), b"\xff\xfe").unwrap();
+        fs::write(root.join("g/h"), "mno").unwrap();
+        assert!(!sync.rescan(&tree).unwrap().is_empty());
+        assert!(!tree.exists("i"));
+        assert_eq!(tree.buffer_id("a/b"), None);
+        assert_eq!(tree.buffer_id("g/h"), None);
+        let a_b = tree.open_text_file("a/b").wait().unwrap();
+        assert_eq!(tree.text(a_b).unwrap().into_string(), "xabc");
+        let g_h = tree.open_text_file("g/h").wait().unwrap();
+        assert_eq!(tree.text(g_h).unwrap().into_string(), "mno");
+
+        // Executable bits are read from disk, including for binary files.
+        #[cfg(unix)]
+        {
+            set_executable(&root.join("g/h"), true).unwrap();
+            set_executable(&root.join("bin"), true).unwrap();
+            assert_eq!(sync.rescan(&tree).unwrap().len(), 2);
+            assert!(tree.attributes("g/h").unwrap().executable);
+            assert!(tree.attributes("bin").unwrap().executable);
+            assert!(sync.rescan(&tree).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_read_from_disk() {
+        let observer = Arc::new(TestChangeObserver::new());
+        let (tree, repo) = new_tree("test_read_from_disk_repo", Some(observer.clone()));
+        let root = temp_dir("test_read_from_disk");
+        let mut sync = DirSync::new(&root).unwrap();
+        sync.write_to_disk(&tree).unwrap();
+        let a_c = tree.open_text_file("a/c").wait().unwrap();
+        observer.opened_buffer(a_c, &tree);
+
+        fs::write(root.join("a/b"), "xabc").unwrap();
+        fs::write(root.join("a/c"), "defy").unwrap();
+        fs::remove_file(root.join("d")).unwrap();
+        fs::write(root.join("e"), "ghi").unwrap();
+        let ops = read_until(&mut sync, &tree, || {
+            read_text(&tree, "a/b") == "xabc"
+                && read_text(&tree, "a/c") == "defy"
+                && !tree.exists("d")
+                && tree.exists("e")
+                && read_text(&tree, "e") == "ghi"
+        });
+        assert!(!ops.is_empty());
+
+        // Changes to buffers the host has open are reported to the observer.
+        assert_eq!(observer.text(a_c), "defy");
+
+        // The operations bring other replicas up to date with the disk.
+        let (tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
+            tree.head(),
+            vec![],
+            Arc::new(repo),
+            None,
+        )
+        .unwrap();
+        ops_2.collect().wait().unwrap();
+        tree_2
+            .apply_ops(ops.into_iter().map(|envelope| envelope.operation))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(read_text(&tree_2, "a/b"), "xabc");
+        assert_eq!(read_text(&tree_2, "a/c"), "defy");
+        assert!(!tree_2.exists("d"));
+        assert_eq!(read_text(&tree_2, "e"), "ghi");
+    }
+
+    /// Reads changes from disk until `done` returns true, giving the watcher time to report them.
+    fn read_until<F>(sync: &mut DirSync, tree: &WorkTree, done: F) -> Vec<OperationEnvelope>
+    where
+        F: Fn() -> bool,
+    {
+        let start = Instant::now();
+        let mut ops = Vec::new();
+        loop {
+            ops.extend(sync.read_from_disk(tree).unwrap());
+            if done() {
+                return ops;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "timed out waiting for file system events"
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn read_text(tree: &WorkTree, path: &str) -> String {
+        with_buffer(tree, Path::new(path), |buffer_id| {
+            Ok(tree.text(buffer_id)?.into_string())
+        })
+        .unwrap()
+    }
+
+    fn new_tree(name: &str, observer: Option<Arc<ChangeObserver>>) -> (WorkTree, Repository) {
+        let repo = Repository::new(temp_dir(name));
+        let mut base = TreeBuilder::new();
+        base.insert_text(Path::new("a/b"), "abc".into(), false);
+        base.insert_text(Path::new("a/c"), "def".into(), false);
+        base.insert_text(Path::new("d"), "".into(), false);
+        let bin_oid = repo.write_object(ObjectKind::Blob, b"\0bin").unwrap();
+        base.insert_object(Path::new("bin"), bin_oid, false);
+        let base_oid = base.write(&repo).unwrap();
+
+        let (tree, ops) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(base_oid),
+            vec![],
+            Arc::new(repo.clone()),
+            observer,
+        )
+        .unwrap();
+        ops.collect().wait().unwrap();
+        (tree, repo)
+    }
+
+    fn files(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        files
+            .iter()
+            .map(|(path, text)| (path.to_string(), text.to_string()))
+            .collect()
+    }
+
+    fn read_dir(root: &Path) -> Vec<(String, String)> {
+        fn visit(root: &Path, dir: &Path, files: &mut Vec<(String, String)>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    visit(root, &path, files);
+                } else {
+                    files.push((
+                        path.strip_prefix(root)
+                            .unwrap()
+                            .to_string_lossy()
+                            .into_owned(),
+                        fs::read_to_string(&path).unwrap(),
+                    ));
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+        visit(root, root, &mut files);
+        files.sort();
+        files
+    }
+}
@@ -1,24 +1,33 @@
 use crate::btree::{self, SeekBias};
-use crate::buffer::{self, Buffer, Point, Selection, SelectionSetId, Text};
+use crate::buffer::{self, Buffer, OffsetUnit, Point, Selection, SelectionSetId, Text};
+use crate::git;
 use crate::operation_queue::{self, OperationQueue};
 use crate::serialization;
 use crate::time;
 use crate::Error;
 use crate::Oid;
 use crate::ReplicaId;
+use crate::ReplicaIdExt;
 use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_derive::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::ffi::{OsStr, OsString};
+use std::mem;
 use std::ops::{Add, AddAssign, Range};
 use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 pub const ROOT_FILE_ID: FileId = FileId::Base(0);
 
+#[cfg(test)]
+pub(crate) const MAX_HISTORY_LEN: usize = 64;
+#[cfg(not(test))]
+pub(crate) const MAX_HISTORY_LEN: usize = 100_000;
+
 pub type Id = time::Lamport;
 
 #[derive(Clone)]
@@ -35,6 +44,12 @@ pub struct Epoch {
     local_clock: time::Local,
     text_files: HashMap<FileId, TextFile>,
     deferred_ops: OperationQueue<Operation>,
+    history: OperationQueue<Operation>,
+    history_start: time::Global,
+    conflicts: Vec<Conflict>,
+    partial_replication: bool,
+    subscribed_files: HashSet<FileId>,
+    edited_unopened_files: HashSet<FileId>,
 }
 
 pub struct Cursor<'a> {
@@ -61,6 +76,65 @@ pub struct CursorEntry {
     pub visible: bool,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusEntry {
+    pub file_id: FileId,
+    pub file_type: FileType,
+    pub path: PathBuf,
+    pub base_path: Option<PathBuf>,
+    pub status: FileStatus,
+}
+
+/// A concurrent tree operation that this replica had to adjust to keep the tree valid.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Conflict {
+    /// `file_id` was moved to `name` in `parent_id`, but `winner_id` already had that name, so
+    /// `file_id` was given a unique name instead.
+    Name {
+        file_id: FileId,
+        winner_id: FileId,
+        parent_id: FileId,
+        name: Arc<OsString>,
+    },
+    /// Moving `file_id` to `name` in `parent_id` was reverted because a concurrent move had put
+    /// `parent_id` inside of it. `winner_id` is the child of `file_id` that contains `parent_id`.
+    Cycle {
+        file_id: FileId,
+        winner_id: FileId,
+        parent_id: FileId,
+        name: Arc<OsString>,
+    },
+}
+
+/// A change to the visible tree caused by integrating operations. Moving or removing a directory
+/// is reported once for the directory itself, not for each of its descendants. Likewise, switching
+/// to a new epoch is reported as the removal of every entry at the root of the old epoch followed
+/// by the creation of every entry at the root of the new one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TreeChange {
+    Created {
+        file_id: FileId,
+        path: PathBuf,
+    },
+    Renamed {
+        file_id: FileId,
+        old_path: PathBuf,
+        new_path: PathBuf,
+    },
+    Removed {
+        file_id: FileId,
+        path: PathBuf,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConflictEntry {
+    pub conflict: Conflict,
+    pub path: PathBuf,
+    pub winner_path: PathBuf,
+    pub requested_path: PathBuf,
+}
+
 #[derive(Clone, Debug, Eq, Deserialize, PartialEq, Serialize)]
 pub struct DirEntry {
     pub depth: usize,
@@ -71,6 +145,24 @@ pub struct DirEntry {
     pub name: OsString,
     #[serde(rename = "type")]
     pub file_type: FileType,
+    #[serde(default)]
+    pub attributes: FileAttributes,
+}
+
+/// Metadata that git tracks for files in addition to their type. Symlinks store their target
+/// and binary files refer to the blob holding their contents, since neither can be edited as text.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FileAttributes {
+    #[serde(default)]
+    pub executable: bool,
+    #[serde(default, rename = "symlinkTarget")]
+    pub symlink_target: Option<PathBuf>,
+    #[serde(
+        default,
+        serialize_with = "serialize_oid",
+        deserialize_with = "deserialize_oid"
+    )]
+    pub oid: Option<Oid>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -78,6 +170,7 @@ pub enum Operation {
     InsertMetadata {
         file_id: FileId,
         file_type: FileType,
+        attributes: FileAttributes,
         parent: Option<(FileId, Arc<OsString>)>,
         local_timestamp: time::Local,
         lamport_timestamp: time::Lamport,
@@ -88,6 +181,12 @@ pub enum Operation {
         local_timestamp: time::Local,
         lamport_timestamp: time::Lamport,
     },
+    UpdateAttributes {
+        file_id: FileId,
+        attributes: FileAttributes,
+        local_timestamp: time::Local,
+        lamport_timestamp: time::Lamport,
+    },
     BufferOperation {
         file_id: FileId,
         operations: Vec<buffer::Operation>,
@@ -96,10 +195,29 @@ pub enum Operation {
     },
     UpdateActiveLocation {
         file_id: Option<FileId>,
+        scroll_anchor: Option<buffer::Anchor>,
+        user: UserInfo,
         lamport_timestamp: time::Lamport,
     },
 }
 
+/// How a replica's user is shown to collaborators.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UserInfo {
+    pub name: String,
+    pub color: String,
+}
+
+/// A replica's user, the file it's looking at, its primary cursor and its scroll position. The
+/// cursor and scroll position are only available once the file has been opened locally.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Presence {
+    pub user: UserInfo,
+    pub file_id: Option<FileId>,
+    pub cursor: Option<Point>,
+    pub scroll_position: Option<Point>,
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum FileId {
     Base(u64),
@@ -120,12 +238,16 @@ pub enum FileStatus {
 pub enum FileType {
     Directory,
     Text,
+    Symlink,
+    Binary,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Metadata {
     file_id: FileId,
     file_type: FileType,
+    attributes: FileAttributes,
+    attributes_timestamp: time::Lamport,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -176,6 +298,8 @@ pub struct ChildRefKey {
 #[derive(Clone)]
 struct ReplicaLocation {
     file_id: Option<FileId>,
+    scroll_anchor: Option<buffer::Anchor>,
+    user: UserInfo,
     lamport_timestamp: time::Lamport,
 }
 
@@ -200,7 +324,51 @@ impl Epoch {
             local_clock: time::Local::new(replica_id),
             text_files: HashMap::new(),
             deferred_ops: OperationQueue::new(),
+            history: OperationQueue::new(),
+            history_start: time::Global::new(),
+            conflicts: Vec::new(),
+            partial_replication: false,
+            subscribed_files: HashSet::new(),
+            edited_unopened_files: HashSet::new(),
+        }
+    }
+
+    /// When partial replication is enabled, operations for text files that haven't been opened
+    /// or subscribed to are only kept in the history, and are applied once the file is opened.
+    pub fn set_partial_replication(&mut self, enabled: bool) {
+        self.partial_replication = enabled;
+    }
+
+    pub fn partial_replication(&self) -> bool {
+        self.partial_replication
+    }
+
+    /// Integrates the operations for the given text file as they are received, even under partial
+    /// replication and before the file is opened.
+    pub fn subscribe(&mut self, file_id: FileId) -> Result<(), Error> {
+        self.check_file_id(file_id, Some(FileType::Text))?;
+        self.subscribed_files.insert(file_id);
+        if !self.text_files.contains_key(&file_id) {
+            let operations = self.unopened_buffer_operations(file_id, None);
+            self.text_files
+                .insert(file_id, TextFile::Deferred(operations));
         }
+        Ok(())
+    }
+
+    pub fn unsubscribe(&mut self, file_id: FileId) {
+        self.subscribed_files.remove(&file_id);
+    }
+
+    pub fn subscribed_paths(&self) -> Vec<PathBuf> {
+        self.subscribed_files
+            .iter()
+            .filter_map(|file_id| self.path(*file_id))
+            .collect()
+    }
+
+    fn holds_back_buffer_operations(&self, file_id: FileId) -> bool {
+        self.partial_replication && !self.subscribed_files.contains(&file_id)
     }
 
     pub fn buffer_version(&self, file_id: FileId) -> Result<time::Global, Error> {
@@ -245,11 +413,199 @@ impl Epoch {
         }
     }
 
+    pub fn status(&self) -> Vec<StatusEntry> {
+        let mut entries = Vec::new();
+        let mut removed_file_ids = HashSet::new();
+        if let Some(mut cursor) = self.cursor() {
+            loop {
+                let entry = cursor.entry().unwrap();
+                if entry.status == FileStatus::Removed {
+                    // A removed file may still be referenced from several of its former
+                    // locations, but we only want to report it once under its base path.
+                    if removed_file_ids.insert(entry.file_id) {
+                        if let Ok(Some(base_path)) = cursor.base_path() {
+                            entries.push(StatusEntry {
+                                file_id: entry.file_id,
+                                file_type: entry.file_type,
+                                path: base_path.clone(),
+                                base_path: Some(base_path),
+                                status: entry.status,
+                            });
+                        }
+                    }
+                } else if entry.visible && entry.status != FileStatus::Unchanged {
+                    entries.push(StatusEntry {
+                        file_id: entry.file_id,
+                        file_type: entry.file_type,
+                        path: cursor.path().unwrap().to_path_buf(),
+                        base_path: cursor.base_path().unwrap_or(None),
+                        status: entry.status,
+                    });
+                }
+
+                if !cursor.next(entry.visible) {
+                    break;
+                }
+            }
+        }
+        entries
+    }
+
+    /// Returns the visible files at the root of the tree along with their paths.
+    pub fn root_entries(&self) -> Vec<(FileId, PathBuf)> {
+        let mut entries = Vec::new();
+        if let Some(mut cursor) = self.cursor() {
+            loop {
+                let entry = cursor.entry().unwrap();
+                if entry.visible {
+                    entries.push((entry.file_id, cursor.path().unwrap().to_path_buf()));
+                }
+
+                if !cursor.next(false) {
+                    break;
+                }
+            }
+        }
+        entries
+    }
+
+    /// Returns the conflicts this replica resolved automatically that still apply to the
+    /// current state of the tree.
+    pub fn conflicts(&self) -> Vec<ConflictEntry> {
+        self.conflicts
+            .iter()
+            .filter(|conflict| self.is_unresolved(conflict))
+            .filter_map(|conflict| {
+                let (file_id, winner_id, parent_id, name) = conflict.ids();
+                Some(ConflictEntry {
+                    conflict: conflict.clone(),
+                    path: self.path(file_id)?,
+                    winner_path: self.path(winner_id)?,
+                    requested_path: self.path(parent_id)?.join(name.as_os_str()),
+                })
+            })
+            .collect()
+    }
+
+    /// Records conflicts reported by another epoch in this one, locating their files by path.
+    /// Conflicts whose files can't be found or that no longer apply here are dropped.
+    pub fn restore_conflicts<I>(&mut self, conflicts: I)
+    where
+        I: IntoIterator<Item = ConflictEntry>,
+    {
+        for entry in conflicts {
+            let parent_path = entry
+                .requested_path
+                .parent()
+                .unwrap_or_else(|| Path::new(""));
+            let name = match entry.requested_path.file_name() {
+                Some(name) => Arc::new(OsString::from(name)),
+                None => continue,
+            };
+            let ids = (
+                self.file_id(&entry.path),
+                self.file_id(&entry.winner_path),
+                self.file_id(parent_path),
+            );
+            if let (Ok(file_id), Ok(winner_id), Ok(parent_id)) = ids {
+                let conflict = match entry.conflict {
+                    Conflict::Name { .. } => Conflict::Name {
+                        file_id,
+                        winner_id,
+                        parent_id,
+                        name,
+                    },
+                    Conflict::Cycle { .. } => Conflict::Cycle {
+                        file_id,
+                        winner_id,
+                        parent_id,
+                        name,
+                    },
+                };
+                if self.is_unresolved(&conflict) {
+                    self.record_conflict(conflict);
+                }
+            }
+        }
+    }
+
+    /// Resolves the given conflict in favor of `winner_id`, which must be one of the files
+    /// involved. Choosing the file that won originally just dismisses the conflict. Otherwise,
+    /// the winner is moved out of the way and the file it displaced is moved to the location it
+    /// originally requested.
+    pub fn resolve_conflict(
+        &mut self,
+        conflict: &Conflict,
+        winner_id: FileId,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Vec<Operation>, Error> {
+        if !self.is_unresolved(conflict) {
+            return Err(Error::InvalidOperation);
+        }
+
+        let (file_id, prev_winner_id, parent_id, name) = conflict.ids();
+        let moves = if winner_id == prev_winner_id {
+            Vec::new()
+        } else if winner_id == file_id {
+            let (file_parent_id, file_name) = self.parent(file_id).unwrap();
+            let (_, prev_winner_name) = self.parent(prev_winner_id).unwrap();
+            match conflict {
+                Conflict::Name { .. } => vec![
+                    (prev_winner_id, (parent_id, file_name)),
+                    (file_id, (parent_id, name.clone())),
+                ],
+                Conflict::Cycle { .. } => vec![
+                    (prev_winner_id, (file_parent_id, prev_winner_name)),
+                    (file_id, (parent_id, name.clone())),
+                ],
+            }
+        } else {
+            return Err(Error::InvalidFileId(
+                "winner must be one of the conflicting files".into(),
+            ));
+        };
+
+        let mut operations = moves
+            .into_iter()
+            .map(|(child_id, new_parent)| Operation::UpdateParent {
+                child_id,
+                new_parent: Some(new_parent),
+                local_timestamp: self.local_clock.tick(),
+                lamport_timestamp: lamport_clock.tick(),
+            })
+            .collect::<Vec<_>>();
+        let fixup_ops =
+            self.apply_ops_internal(operations.clone(), &mut Vec::new(), lamport_clock)?;
+        operations.extend(fixup_ops);
+        self.conflicts.retain(|c| c != conflict);
+        Ok(operations)
+    }
+
+    /// Returns how many base entries have been appended so far.
+    pub fn base_entries_len(&self) -> u64 {
+        self.base_entries_next_id - 1
+    }
+
     pub fn append_base_entries<I>(
         &mut self,
         entries: I,
         lamport_clock: &mut time::Lamport,
     ) -> Result<Vec<Operation>, Error>
+    where
+        I: IntoIterator<Item = DirEntry>,
+    {
+        Ok(self
+            .append_base_entries_with_changes(entries, lamport_clock)?
+            .0)
+    }
+
+    /// Like `append_base_entries`, but also reports how the deferred operations that could be
+    /// applied once the entries were loaded changed the visible tree.
+    pub fn append_base_entries_with_changes<I>(
+        &mut self,
+        entries: I,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<(Vec<Operation>, Vec<TreeChange>), Error>
     where
         I: IntoIterator<Item = DirEntry>,
     {
@@ -277,6 +633,8 @@ impl Epoch {
             metadata_edits.push(btree::Edit::Insert(Metadata {
                 file_id,
                 file_type: entry.file_type,
+                attributes: entry.attributes,
+                attributes_timestamp: time::Lamport::default(),
             }));
             parent_ref_edits.push(btree::Edit::Insert(ParentRefValue {
                 child_id: file_id,
@@ -311,10 +669,15 @@ impl Epoch {
         for file_id in name_conflicts {
             fixup_ops.extend(self.fix_name_conflicts(file_id, lamport_clock));
         }
+        let mut tree_changes = Vec::new();
         let deferred_ops = self.deferred_ops.drain();
-        fixup_ops.extend(self.apply_ops_internal(deferred_ops, lamport_clock)?);
+        fixup_ops.extend(self.apply_ops_internal(
+            deferred_ops,
+            &mut tree_changes,
+            lamport_clock,
+        )?);
 
-        Ok(fixup_ops)
+        Ok((fixup_ops, tree_changes))
     }
 
     pub fn apply_ops<I>(
@@ -322,19 +685,37 @@ impl Epoch {
         ops: I,
         lamport_clock: &mut time::Lamport,
     ) -> Result<Vec<Operation>, Error>
+    where
+        I: IntoIterator<Item = Operation>,
+    {
+        Ok(self.apply_ops_with_changes(ops, lamport_clock)?.0)
+    }
+
+    /// Like `apply_ops`, but also reports how the visible tree changed.
+    pub fn apply_ops_with_changes<I>(
+        &mut self,
+        ops: I,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<(Vec<Operation>, Vec<TreeChange>), Error>
     where
         I: IntoIterator<Item = Operation>,
     {
         let mut fixup_ops = Vec::new();
-        fixup_ops.extend(self.apply_ops_internal(ops, lamport_clock)?);
+        let mut tree_changes = Vec::new();
+        fixup_ops.extend(self.apply_ops_internal(ops, &mut tree_changes, lamport_clock)?);
         let deferred_ops = self.deferred_ops.drain();
-        fixup_ops.extend(self.apply_ops_internal(deferred_ops, lamport_clock)?);
-        Ok(fixup_ops)
+        fixup_ops.extend(self.apply_ops_internal(
+            deferred_ops,
+            &mut tree_changes,
+            lamport_clock,
+        )?);
+        Ok((fixup_ops, tree_changes))
     }
 
     fn apply_ops_internal<I>(
         &mut self,
         ops: I,
+        tree_changes: &mut Vec<TreeChange>,
         lamport_clock: &mut time::Lamport,
     ) -> Result<Vec<Operation>, Error>
     where
@@ -347,7 +728,7 @@ impl Epoch {
 
         let mut new_epoch = self.clone();
         let mut deferred_ops = Vec::new();
-        let mut potential_conflicts = HashSet::new();
+        let mut potential_conflicts = BTreeSet::new();
 
         for op in ops {
             if new_epoch.can_apply_op(&op) {
@@ -376,6 +757,30 @@ impl Epoch {
             fixup_ops.extend(new_epoch.fix_conflicts(*file_id, lamport_clock));
         }
 
+        let mut moved_files = potential_conflicts;
+        for op in &fixup_ops {
+            if let Operation::UpdateParent { child_id, .. } = op {
+                moved_files.insert(*child_id);
+            }
+        }
+        for file_id in moved_files {
+            match (self.path(file_id), new_epoch.path(file_id)) {
+                (None, Some(path)) => tree_changes.push(TreeChange::Created { file_id, path }),
+                (Some(path), None) => tree_changes.push(TreeChange::Removed { file_id, path }),
+                (Some(old_path), Some(new_path)) => {
+                    if old_path != new_path {
+                        tree_changes.push(TreeChange::Renamed {
+                            file_id,
+                            old_path,
+                            new_path,
+                        });
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        new_epoch.prune_conflicts();
         *self = new_epoch;
         Ok(fixup_ops)
     }
@@ -386,6 +791,10 @@ impl Epoch {
         lamport_clock: &mut time::Lamport,
     ) -> Result<(), Error> {
         if let Some(local_timestamp) = op.local_timestamp() {
+            if !self.version.observed(local_timestamp) {
+                self.history.insert(vec![op.clone()]);
+                self.trim_history();
+            }
             self.version.observe(local_timestamp);
             self.local_clock.observe(local_timestamp);
         }
@@ -395,12 +804,18 @@ impl Epoch {
             Operation::InsertMetadata {
                 file_id,
                 file_type,
+                attributes,
                 parent,
                 lamport_timestamp,
                 ..
             } => {
                 if !self.metadata.cursor().seek(&file_id, SeekBias::Left) {
-                    self.metadata.insert(Metadata { file_id, file_type });
+                    self.metadata.insert(Metadata {
+                        file_id,
+                        file_type,
+                        attributes,
+                        attributes_timestamp: lamport_timestamp,
+                    });
                     if let Some((parent_id, name)) = parent {
                         self.parent_refs.insert(ParentRefValue {
                             child_id: file_id,
@@ -508,41 +923,61 @@ impl Epoch {
                     })]);
                 self.child_refs.edit(&mut child_ref_edits);
             }
+            Operation::UpdateAttributes {
+                file_id,
+                attributes,
+                lamport_timestamp,
+                ..
+            } => {
+                let mut metadata = self.metadata(file_id)?;
+                // Concurrent updates are resolved in favor of the latest one.
+                if lamport_timestamp > metadata.attributes_timestamp {
+                    metadata.attributes = attributes;
+                    metadata.attributes_timestamp = lamport_timestamp;
+                    self.metadata.insert(metadata);
+                }
+            }
             Operation::BufferOperation {
                 file_id,
                 operations,
                 ..
-            } => match self
-                .text_files
-                .entry(file_id)
-                .or_insert_with(|| TextFile::Deferred(Vec::new()))
-            {
-                TextFile::Deferred(deferred_operations) => {
-                    deferred_operations.extend(operations);
-                }
-                TextFile::Buffered(buffer) => {
+            } => {
+                if let Some(TextFile::Buffered(buffer)) = self.text_files.get_mut(&file_id) {
                     buffer
                         .apply_ops(operations, &mut self.local_clock, lamport_clock)
                         .map_err(|_| Error::InvalidOperation)?;
+                } else if self.holds_back_buffer_operations(file_id) {
+                    if operations.iter().any(|op| op.is_edit()) {
+                        self.edited_unopened_files.insert(file_id);
+                    }
+                } else if let TextFile::Deferred(deferred_operations) = self
+                    .text_files
+                    .entry(file_id)
+                    .or_insert_with(|| TextFile::Deferred(Vec::new()))
+                {
+                    deferred_operations.extend(operations);
                 }
-            },
+            }
             Operation::UpdateActiveLocation {
                 file_id,
+                scroll_anchor,
+                user,
                 lamport_timestamp,
-                ..
             } => {
+                let location = ReplicaLocation {
+                    file_id,
+                    scroll_anchor,
+                    user,
+                    lamport_timestamp,
+                };
                 self.replica_locations
                     .entry(lamport_timestamp.replica_id)
-                    .and_modify(|location| {
-                        if lamport_timestamp > location.lamport_timestamp {
-                            location.file_id = file_id;
-                            location.lamport_timestamp = lamport_timestamp;
+                    .and_modify(|existing_location| {
+                        if lamport_timestamp > existing_location.lamport_timestamp {
+                            *existing_location = location.clone();
                         }
                     })
-                    .or_insert(ReplicaLocation {
-                        file_id,
-                        lamport_timestamp,
-                    });
+                    .or_insert(location);
             }
         }
 
@@ -553,6 +988,7 @@ impl Epoch {
         match op {
             Operation::InsertMetadata { .. } => true,
             Operation::UpdateParent { child_id, .. } => self.metadata(*child_id).is_ok(),
+            Operation::UpdateAttributes { file_id, .. } => self.metadata(*file_id).is_ok(),
             Operation::BufferOperation { file_id, .. } => self.metadata(*file_id).is_ok(),
             Operation::UpdateActiveLocation { file_id, .. } => {
                 file_id.map_or(true, |file_id| self.metadata(file_id).is_ok())
@@ -567,10 +1003,33 @@ impl Epoch {
         file_type: FileType,
         lamport_clock: &mut time::Lamport,
     ) -> Result<Operation, Error>
+    where
+        N: AsRef<OsStr>,
+    {
+        self.create_file_with_attributes(
+            parent_id,
+            name,
+            file_type,
+            FileAttributes::default(),
+            lamport_clock,
+        )
+    }
+
+    pub fn create_file_with_attributes<N>(
+        &mut self,
+        parent_id: FileId,
+        name: N,
+        file_type: FileType,
+        attributes: FileAttributes,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Operation, Error>
     where
         N: AsRef<OsStr>,
     {
         self.check_file_id(parent_id, Some(FileType::Directory))?;
+        if !attributes.is_valid_for(file_type) {
+            return Err(Error::InvalidOperation);
+        }
 
         let mut new_lamport_clock = *lamport_clock;
         let mut new_epoch = self.clone();
@@ -578,12 +1037,17 @@ impl Epoch {
         let operation = Operation::InsertMetadata {
             file_id,
             file_type,
+            attributes,
             parent: Some((parent_id, Arc::new(name.as_ref().into()))),
             local_timestamp: new_epoch.local_clock.tick(),
             lamport_timestamp: new_lamport_clock.tick(),
         };
         let fixup_ops = new_epoch
-            .apply_ops_internal(Some(operation.clone()), &mut new_lamport_clock)
+            .apply_ops_internal(
+                Some(operation.clone()),
+                &mut Vec::new(),
+                &mut new_lamport_clock,
+            )
             .unwrap();
         if fixup_ops.is_empty() {
             *lamport_clock = new_lamport_clock;
@@ -599,6 +1063,7 @@ impl Epoch {
         let operation = Operation::InsertMetadata {
             file_id,
             file_type: FileType::Text,
+            attributes: FileAttributes::default(),
             parent: None,
             local_timestamp: self.local_clock.tick(),
             lamport_timestamp: lamport_clock.tick(),
@@ -619,25 +1084,96 @@ impl Epoch {
         self.check_file_id(file_id, Some(FileType::Text))?;
 
         match self.text_files.remove(&file_id) {
-            Some(TextFile::Deferred(operations)) => {
+            Some(TextFile::Buffered(buffer)) => {
+                self.text_files.insert(file_id, TextFile::Buffered(buffer));
+            }
+            text_file => {
+                let operations = self.unopened_buffer_operations(file_id, text_file.as_ref());
                 let mut buffer = Buffer::new(base_text);
                 buffer
                     .apply_ops(operations, &mut self.local_clock, lamport_clock)
                     .map_err(|_| Error::InvalidOperation)?;
                 self.text_files.insert(file_id, TextFile::Buffered(buffer));
-            }
-            Some(text_file) => {
-                self.text_files.insert(file_id, text_file);
-            }
-            None => {
-                self.text_files
-                    .insert(file_id, TextFile::Buffered(Buffer::new(base_text)));
+                self.edited_unopened_files.remove(&file_id);
             }
         }
 
         Ok(())
     }
 
+    /// Returns the text of the given file, reconstructing it from the base text and the operations
+    /// received so far if the file hasn't been opened.
+    pub fn text_with_base<T>(&self, file_id: FileId, base_text: T) -> Result<String, Error>
+    where
+        T: Into<Text>,
+    {
+        self.check_file_id(file_id, Some(FileType::Text))?;
+
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            Ok(buffer.to_string())
+        } else {
+            let operations =
+                self.unopened_buffer_operations(file_id, self.text_files.get(&file_id));
+            let mut buffer = Buffer::new(base_text);
+            buffer
+                .apply_ops(
+                    operations,
+                    &mut self.local_clock.clone(),
+                    &mut time::Lamport::new(self.local_clock.replica_id),
+                )
+                .map_err(|_| Error::InvalidOperation)?;
+            Ok(buffer.to_string())
+        }
+    }
+
+    fn unopened_buffer_operations(
+        &self,
+        file_id: FileId,
+        text_file: Option<&TextFile>,
+    ) -> Vec<buffer::Operation> {
+        let mut operations = match text_file {
+            Some(TextFile::Deferred(operations)) => operations.clone(),
+            _ => Vec::new(),
+        };
+        // Operations may have been held back while partial replication was enabled, even if it
+        // isn't anymore. Buffer operations are idempotent, so applying them twice is harmless.
+        operations.extend(
+            self.buffer_operations_in_history(file_id)
+                .into_iter()
+                .flat_map(|op| match op {
+                    Operation::BufferOperation { operations, .. } => operations,
+                    _ => Vec::new(),
+                }),
+        );
+        operations
+    }
+
+    /// Returns every operation for the given text file, so that replicas that didn't receive them
+    /// because of partial replication can catch up once they open it.
+    pub fn buffer_operations(&self, file_id: FileId) -> Result<Vec<Operation>, Error> {
+        self.check_file_id(file_id, Some(FileType::Text))?;
+        // Operations for files that are tracked outside the history may have been collected.
+        if self.text_files.contains_key(&file_id) && self.history_start != time::Global::new() {
+            Err(Error::OperationsUnavailable)
+        } else {
+            Ok(self.buffer_operations_in_history(file_id))
+        }
+    }
+
+    fn buffer_operations_in_history(&self, file_id: FileId) -> Vec<Operation> {
+        self.history
+            .items()
+            .into_iter()
+            .filter(|op| match op {
+                Operation::BufferOperation {
+                    file_id: op_file_id,
+                    ..
+                } => *op_file_id == file_id,
+                _ => false,
+            })
+            .collect()
+    }
+
     pub fn rename<N>(
         &mut self,
         file_id: FileId,
@@ -660,7 +1196,11 @@ impl Epoch {
             lamport_timestamp: new_lamport_clock.tick(),
         };
         let fixup_ops = new_epoch
-            .apply_ops_internal(Some(operation.clone()), &mut new_lamport_clock)
+            .apply_ops_internal(
+                Some(operation.clone()),
+                &mut Vec::new(),
+                &mut new_lamport_clock,
+            )
             .unwrap();
         if fixup_ops.is_empty() {
             *lamport_clock = new_lamport_clock;
@@ -685,6 +1225,28 @@ impl Epoch {
             lamport_timestamp: lamport_clock.tick(),
         };
         self.apply_op(operation.clone(), lamport_clock).unwrap();
+        self.prune_conflicts();
+        Ok(operation)
+    }
+
+    pub fn update_attributes(
+        &mut self,
+        file_id: FileId,
+        attributes: FileAttributes,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Operation, Error> {
+        let file_type = self.file_type(file_id)?;
+        if file_id == ROOT_FILE_ID || !attributes.is_valid_for(file_type) {
+            return Err(Error::InvalidOperation);
+        }
+
+        let operation = Operation::UpdateAttributes {
+            file_id,
+            attributes,
+            local_timestamp: self.local_clock.tick(),
+            lamport_timestamp: lamport_clock.tick(),
+        };
+        self.apply_op(operation.clone(), lamport_clock).unwrap();
         Ok(operation)
     }
 
@@ -692,21 +1254,49 @@ impl Epoch {
         &mut self,
         file_id: Option<FileId>,
         lamport_clock: &mut time::Lamport,
+    ) -> Result<Operation, Error> {
+        let user = self
+            .replica_locations
+            .get(&lamport_clock.replica_id)
+            .map_or(UserInfo::default(), |location| location.user.clone());
+        self.set_presence(file_id, None, user, lamport_clock)
+    }
+
+    pub fn set_presence(
+        &mut self,
+        file_id: Option<FileId>,
+        scroll_position: Option<Point>,
+        user: UserInfo,
+        lamport_clock: &mut time::Lamport,
     ) -> Result<Operation, Error> {
         if let Some(file_id) = file_id {
             self.check_file_id(file_id, Some(FileType::Text))?;
         }
 
+        let scroll_anchor = match (file_id, scroll_position) {
+            (Some(file_id), Some(scroll_position)) => match self.text_files.get(&file_id) {
+                Some(TextFile::Buffered(buffer)) => {
+                    Some(buffer.anchor_before_point(scroll_position)?)
+                }
+                _ => return Err(Error::InvalidFileId("file has not been opened".into())),
+            },
+            _ => None,
+        };
+
         let lamport_timestamp = lamport_clock.tick();
         self.replica_locations.insert(
             lamport_timestamp.replica_id,
             ReplicaLocation {
                 lamport_timestamp,
+                scroll_anchor: scroll_anchor.clone(),
+                user: user.clone(),
                 file_id,
             },
         );
         Ok(Operation::UpdateActiveLocation {
             file_id,
+            scroll_anchor,
+            user,
             lamport_timestamp,
         })
     }
@@ -717,6 +1307,42 @@ impl Epoch {
             .and_then(|location| location.file_id)
     }
 
+    pub fn presence(&self, replica_id: ReplicaId) -> Option<Presence> {
+        let location = self.replica_locations.get(&replica_id)?;
+        let mut presence = Presence {
+            user: location.user.clone(),
+            file_id: location.file_id,
+            cursor: None,
+            scroll_position: None,
+        };
+
+        let buffer = location
+            .file_id
+            .and_then(|file_id| match self.text_files.get(&file_id) {
+                Some(TextFile::Buffered(buffer)) => Some(buffer),
+                _ => None,
+            });
+        if let Some(buffer) = buffer {
+            presence.cursor = buffer
+                .all_selections()
+                .filter(|(set_id, selections)| {
+                    set_id.replica_id == replica_id && !selections.is_empty()
+                })
+                .min_by_key(|(set_id, _)| *set_id)
+                .and_then(|(_, selections)| buffer.point_for_anchor(selections[0].head()).ok());
+            presence.scroll_position = location
+                .scroll_anchor
+                .as_ref()
+                .and_then(|anchor| buffer.point_for_anchor(anchor).ok());
+        }
+
+        Some(presence)
+    }
+
+    pub fn replica_ids<'a>(&'a self) -> impl Iterator<Item = ReplicaId> + 'a {
+        self.replica_locations.keys().cloned()
+    }
+
     pub fn replica_locations<'a>(&'a self) -> impl Iterator<Item = (ReplicaId, FileId)> + 'a {
         self.replica_locations
             .iter()
@@ -765,6 +1391,71 @@ impl Epoch {
         )
     }
 
+    pub fn start_transaction(&mut self, file_id: FileId, now: SystemTime) -> Result<(), Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get_mut(&file_id) {
+            buffer.start_transaction(now);
+            Ok(())
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn end_transaction(
+        &mut self,
+        file_id: FileId,
+        now: SystemTime,
+        group_interval: Option<Duration>,
+    ) -> Result<(), Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get_mut(&file_id) {
+            buffer.end_transaction(now, group_interval)
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn end_all_transactions(&mut self, file_id: FileId) -> Result<(), Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get_mut(&file_id) {
+            buffer.end_all_transactions();
+            Ok(())
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn undo(
+        &mut self,
+        file_id: FileId,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Operation, Error> {
+        self.mutate_buffer(
+            file_id,
+            lamport_clock,
+            |buffer, local_clock, lamport_clock| {
+                Ok(buffer
+                    .undo(local_clock, lamport_clock)?
+                    .into_iter()
+                    .collect())
+            },
+        )
+    }
+
+    pub fn redo(
+        &mut self,
+        file_id: FileId,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Operation, Error> {
+        self.mutate_buffer(
+            file_id,
+            lamport_clock,
+            |buffer, local_clock, lamport_clock| {
+                Ok(buffer
+                    .redo(local_clock, lamport_clock)?
+                    .into_iter()
+                    .collect())
+            },
+        )
+    }
+
     pub fn add_selection_set<I>(
         &mut self,
         file_id: FileId,
@@ -860,6 +1551,30 @@ impl Epoch {
         }
     }
 
+    pub fn replace_all<T>(
+        &mut self,
+        file_id: FileId,
+        query: &buffer::SearchQuery,
+        replacement: T,
+        lamport_clock: &mut time::Lamport,
+    ) -> Result<Option<Operation>, Error>
+    where
+        T: Into<Text>,
+    {
+        let ranges = if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            buffer.replacement_ranges(query)
+        } else {
+            return Err(Error::InvalidFileId("file has not been opened".into()));
+        };
+
+        if ranges.is_empty() {
+            Ok(None)
+        } else {
+            self.edit(file_id, ranges, replacement, lamport_clock)
+                .map(Some)
+        }
+    }
+
     fn mutate_buffer<F>(
         &mut self,
         file_id: FileId,
@@ -877,17 +1592,110 @@ impl Epoch {
             let operations = mutate(buffer, &mut self.local_clock, lamport_clock)?;
             let local_timestamp = self.local_clock.tick();
             self.version.observe(local_timestamp);
-            Ok(Operation::BufferOperation {
+            let operation = Operation::BufferOperation {
                 file_id,
                 operations,
                 local_timestamp,
                 lamport_timestamp: lamport_clock.tick(),
-            })
+            };
+            self.history.insert(vec![operation.clone()]);
+            self.trim_history();
+            Ok(operation)
         } else {
             Err(Error::InvalidFileId("file has not been opened".into()))
         }
     }
 
+    pub fn discard_history(&mut self, version: &time::Global) {
+        for text_file in self.text_files.values_mut() {
+            if let TextFile::Buffered(buffer) = text_file {
+                buffer.discard_history(version);
+            }
+        }
+    }
+
+    pub fn collect_garbage(&mut self, stable_version: &time::Global) {
+        for text_file in self.text_files.values_mut() {
+            if let TextFile::Buffered(buffer) = text_file {
+                buffer.collect_garbage(stable_version);
+            }
+        }
+
+        // Operations that were held back for files that haven't been opened are only kept in the
+        // history.
+        let text_files = &self.text_files;
+        let history = self
+            .history
+            .drain()
+            .filter(|op| {
+                is_unopened_file_op(text_files, op)
+                    || op
+                        .local_timestamp()
+                        .map_or(true, |timestamp| !stable_version.observed(timestamp))
+            })
+            .collect();
+        self.history.insert(history);
+        self.history_start.observe_all(stable_version);
+    }
+
+    /// Keeps the history bounded even if garbage is never collected by discarding its oldest
+    /// operations once it grows past `MAX_HISTORY_LEN`. Replicas that haven't observed them can't
+    /// catch up with `operations_since` anymore.
+    fn trim_history(&mut self) {
+        if self.history.len() <= MAX_HISTORY_LEN {
+            return;
+        }
+
+        // Trim to half the limit so that the cost of trimming is amortized across insertions.
+        let mut excess_len = self.history.len() - MAX_HISTORY_LEN / 2;
+        let text_files = &self.text_files;
+        let history_start = &mut self.history_start;
+        let history = self
+            .history
+            .drain()
+            .filter(|op| {
+                if excess_len == 0 || is_unopened_file_op(text_files, op) {
+                    return true;
+                }
+                excess_len -= 1;
+                if let Some(local_timestamp) = op.local_timestamp() {
+                    history_start.observe(local_timestamp);
+                }
+                false
+            })
+            .collect();
+        self.history.insert(history);
+    }
+
+    /// Returns the operations that a replica at `version` needs to catch up with this epoch,
+    /// including the operations that were received but couldn't be applied yet.
+    pub fn operations_since(&self, version: &time::Global) -> Result<Vec<Operation>, Error> {
+        match version.partial_cmp(&self.history_start) {
+            Some(Ordering::Less) | None => return Err(Error::OperationsUnavailable),
+            _ => {}
+        }
+
+        let mut operations = self
+            .history
+            .items()
+            .into_iter()
+            .chain(self.deferred_ops.items())
+            .filter(|op| {
+                op.local_timestamp()
+                    .map_or(true, |timestamp| !version.observed(timestamp))
+            })
+            .collect::<Vec<_>>();
+        operations.extend(self.replica_locations.values().map(|location| {
+            Operation::UpdateActiveLocation {
+                file_id: location.file_id,
+                scroll_anchor: location.scroll_anchor.clone(),
+                user: location.user.clone(),
+                lamport_timestamp: location.lamport_timestamp,
+            }
+        }));
+        Ok(operations)
+    }
+
     pub fn file_id<P>(&self, path: P) -> Result<FileId, Error>
     where
         P: AsRef<Path>,
@@ -968,9 +1776,21 @@ impl Epoch {
         }
     }
 
+    /// Returns the text of an opened file. Use `text_with_base` for files that haven't been
+    /// opened, so that the operations received for them are taken into account.
     pub fn text(&self, file_id: FileId) -> Result<buffer::Iter, Error> {
         if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
             Ok(buffer.iter())
+        } else {
+            Err(Error::InvalidFileId(
+                "file has not been opened, use text_with_base instead".into(),
+            ))
+        }
+    }
+
+    pub fn buffer_snapshot(&self, file_id: FileId) -> Result<buffer::BufferSnapshot, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            Ok(buffer.snapshot())
         } else {
             Err(Error::InvalidFileId("file has not been opened".into()))
         }
@@ -1000,6 +1820,153 @@ impl Epoch {
         }
     }
 
+    pub fn text_at(&self, file_id: FileId, version: &time::Global) -> Result<Vec<u16>, Error> {
+        self.check_history_available(version)?;
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            // Buffers only observe the timestamps of their own operations.
+            buffer.text_at(&version.meet(&buffer.version))
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn changes_between(
+        &self,
+        file_id: FileId,
+        from: &time::Global,
+        to: &time::Global,
+    ) -> Result<Vec<buffer::Change>, Error> {
+        self.check_history_available(from)?;
+        self.check_history_available(to)?;
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            buffer.changes_between(&from.meet(&buffer.version), &to.meet(&buffer.version))
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn authorship_in_rows(
+        &self,
+        file_id: FileId,
+        rows: Range<u32>,
+    ) -> Result<Vec<buffer::AuthorshipSpan>, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            buffer.authorship_in_rows(rows)
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn search(
+        &self,
+        file_id: FileId,
+        query: &buffer::SearchQuery,
+    ) -> Result<Vec<Range<buffer::Anchor>>, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            Ok(buffer.search(query))
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn find(
+        &self,
+        file_id: FileId,
+        query: &buffer::SearchQuery,
+        offset: usize,
+        direction: buffer::SearchDirection,
+    ) -> Result<Option<Range<buffer::Anchor>>, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            buffer.find(query, offset, direction)
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn clip_point(
+        &self,
+        file_id: FileId,
+        point: Point,
+        bias: buffer::AnchorBias,
+    ) -> Result<Point, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            Ok(buffer.clip_point(point, bias))
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn clip_offset(
+        &self,
+        file_id: FileId,
+        offset: usize,
+        bias: buffer::AnchorBias,
+    ) -> Result<usize, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            Ok(buffer.clip_offset(offset, bias))
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn is_valid_point(&self, file_id: FileId, point: Point) -> Result<bool, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            Ok(buffer.is_valid_point(point))
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn is_valid_offset(&self, file_id: FileId, offset: usize) -> Result<bool, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            Ok(buffer.is_valid_offset(offset))
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn point_for_offset(&self, file_id: FileId, offset: usize) -> Result<Point, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            buffer.point_for_offset(offset)
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn offset_for_point(&self, file_id: FileId, point: Point) -> Result<usize, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            buffer.offset_for_point(point)
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    pub fn convert_offset(
+        &self,
+        file_id: FileId,
+        offset: usize,
+        from: OffsetUnit,
+        to: OffsetUnit,
+    ) -> Result<usize, Error> {
+        if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
+            buffer.convert_offset(offset, from, to)
+        } else {
+            Err(Error::InvalidFileId("file has not been opened".into()))
+        }
+    }
+
+    /// Versions preceding the last garbage collection may refer to discarded tombstones, and
+    /// versions this replica hasn't reached yet can't be reconstructed at all.
+    fn check_history_available(&self, version: &time::Global) -> Result<(), Error> {
+        if version.changed_since(&self.version) {
+            return Err(Error::VersionUnavailable);
+        }
+        match version.partial_cmp(&self.history_start) {
+            Some(Ordering::Less) | None => Err(Error::VersionUnavailable),
+            _ => Ok(()),
+        }
+    }
+
     pub fn buffer_deferred_ops_len(&self, file_id: FileId) -> Result<usize, Error> {
         if let Some(TextFile::Buffered(buffer)) = self.text_files.get(&file_id) {
             Ok(buffer.deferred_ops_len())
@@ -1012,11 +1979,17 @@ impl Epoch {
         Ok(self.metadata(file_id)?.file_type)
     }
 
+    pub fn attributes(&self, file_id: FileId) -> Result<FileAttributes, Error> {
+        Ok(self.metadata(file_id)?.attributes)
+    }
+
     fn metadata(&self, file_id: FileId) -> Result<Metadata, Error> {
         if file_id == ROOT_FILE_ID {
             Ok(Metadata {
                 file_id: ROOT_FILE_ID,
                 file_type: FileType::Directory,
+                attributes: FileAttributes::default(),
+                attributes_timestamp: time::Lamport::default(),
             })
         } else {
             let mut cursor = self.metadata.cursor();
@@ -1083,6 +2056,7 @@ impl Epoch {
 
         let mut fixup_ops = Vec::new();
         let mut reverted_moves: HashMap<FileId, time::Lamport> = HashMap::new();
+        let mut rejected_parents = Vec::new();
 
         // TODO: Only check for cycles if the child was moved and is a directory.
         let mut visited = HashSet::new();
@@ -1094,7 +2068,11 @@ impl Epoch {
             let mut parent_ref = cursor.item().unwrap();
             if visited.contains(&parent_ref.child_id) {
                 // Cycle detected. Revert the most recent move contributing to the cycle.
-                cursor.seek(&latest_move.as_ref().unwrap().key(), SeekBias::Right);
+                let reverted_move = latest_move.as_ref().unwrap();
+                if let Some(parent) = reverted_move.parent.clone() {
+                    rejected_parents.push((reverted_move.child_id, parent));
+                }
+                cursor.seek(&reverted_move.key(), SeekBias::Right);
 
                 // Find the previous value for this parent ref that isn't a deletion and store
                 // its timestamp in our reverted_moves map.
@@ -1175,6 +2153,16 @@ impl Epoch {
         for op in &fixup_ops {
             self.apply_op(op.clone(), lamport_clock).unwrap();
         }
+        for (file_id, (parent_id, name)) in rejected_parents {
+            if let Some(winner_id) = self.child_containing(file_id, parent_id) {
+                self.record_conflict(Conflict::Cycle {
+                    file_id,
+                    winner_id,
+                    parent_id,
+                    name,
+                });
+            }
+        }
         for file_id in moved_file_ids {
             fixup_ops.extend(self.fix_name_conflicts(file_id, lamport_clock));
         }
@@ -1196,14 +2184,12 @@ impl Epoch {
         let mut parent_ref_cursor = self.parent_refs.cursor();
         parent_ref_cursor.seek(&file_id, SeekBias::Left);
         if let Some((parent_id, name)) = parent_ref_cursor.item().unwrap().parent {
+            let child_ref_key = ChildRefKey {
+                parent_id,
+                name: name.clone(),
+            };
             let mut cursor_1 = self.child_refs.cursor();
-            cursor_1.seek(
-                &ChildRefKey {
-                    parent_id,
-                    name: name.clone(),
-                },
-                SeekBias::Left,
-            );
+            cursor_1.seek(&child_ref_key, SeekBias::Left);
             cursor_1.next();
 
             let mut cursor_2 = cursor_1.clone();
@@ -1241,15 +2227,557 @@ impl Epoch {
                     self.apply_op(fixup_op.clone(), lamport_clock).unwrap();
                     fixup_ops.push(fixup_op);
 
-                    let visible_index = cursor_1.end::<usize>();
-                    cursor_1.seek_forward(&visible_index, SeekBias::Right);
-                } else {
-                    break;
+                    let visible_index = cursor_1.end::<usize>();
+                    cursor_1.seek_forward(&visible_index, SeekBias::Right);
+                } else {
+                    break;
+                }
+            }
+
+            if !fixup_ops.is_empty() {
+                let mut cursor = self.child_refs.cursor();
+                cursor.seek(&child_ref_key, SeekBias::Left);
+                if let Some(winner) = cursor.item() {
+                    if winner.visible && winner.parent_id == parent_id && winner.name == name {
+                        self.record_conflict(Conflict::Name {
+                            file_id,
+                            winner_id: winner.child_id,
+                            parent_id,
+                            name,
+                        });
+                    }
+                }
+            }
+        }
+
+        fixup_ops
+    }
+
+    fn record_conflict(&mut self, conflict: Conflict) {
+        if !self.conflicts.contains(&conflict) {
+            self.conflicts.push(conflict);
+        }
+    }
+
+    fn prune_conflicts(&mut self) {
+        let conflicts = mem::replace(&mut self.conflicts, Vec::new());
+        self.conflicts = conflicts
+            .into_iter()
+            .filter(|conflict| self.is_unresolved(conflict))
+            .collect();
+    }
+
+    fn is_unresolved(&self, conflict: &Conflict) -> bool {
+        let (file_id, winner_id, parent_id, name) = conflict.ids();
+        if self.path(file_id).is_none() || self.path(winner_id).is_none() {
+            return false;
+        }
+
+        match conflict {
+            Conflict::Name { .. } => {
+                self.parent(winner_id) == Some((parent_id, name.clone()))
+                    && self
+                        .parent(file_id)
+                        .map_or(false, |(file_parent_id, file_name)| {
+                            file_parent_id == parent_id && file_name != *name
+                        })
+            }
+            Conflict::Cycle { .. } => self.child_containing(file_id, parent_id) == Some(winner_id),
+        }
+    }
+
+    fn parent(&self, file_id: FileId) -> Option<(FileId, Arc<OsString>)> {
+        let mut cursor = self.parent_refs.cursor();
+        cursor.seek(&file_id, SeekBias::Left);
+        cursor
+            .item()
+            .filter(|parent_ref| parent_ref.child_id == file_id)
+            .and_then(|parent_ref| parent_ref.parent)
+    }
+
+    /// Returns the child of `ancestor_id` that contains `file_id`, if any.
+    fn child_containing(&self, ancestor_id: FileId, mut file_id: FileId) -> Option<FileId> {
+        let mut visited = HashSet::new();
+        while visited.insert(file_id) {
+            match self.parent(file_id) {
+                Some((parent_id, _)) if parent_id == ancestor_id => return Some(file_id),
+                Some((parent_id, _)) => file_id = parent_id,
+                None => return None,
+            }
+        }
+        None
+    }
+
+    pub fn to_flatbuf<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> WIPOffset<serialization::epoch::Snapshot<'fbb>> {
+        use crate::serialization::epoch::{
+            ChildRef, ChildRefArgs, Conflict as ConflictMessage, ConflictArgs, DeferredOperation,
+            DeferredOperationArgs, FileId as FileIdType, Metadata as MetadataMessage, MetadataArgs,
+            ParentRef, ParentRefArgs, ReplicaLocation as ReplicaLocationMessage,
+            ReplicaLocationArgs, Snapshot, SnapshotArgs, TextFile as TextFileMessage, TextFileArgs,
+        };
+
+        let head = self.head.as_ref().map(|head| builder.create_vector(head));
+        let base_entries_stack = Some(
+            builder.create_vector(
+                &self
+                    .base_entries_stack
+                    .iter()
+                    .map(|file_id| match file_id {
+                        FileId::Base(index) => *index,
+                        FileId::New(_) => unreachable!(),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+        );
+
+        let metadata_flatbufs = &self
+            .metadata
+            .items()
+            .iter()
+            .map(|metadata| {
+                let (file_id_type, file_id) = metadata.file_id.to_flatbuf(builder);
+                let (executable, symlink_target, oid) = metadata.attributes.to_flatbuf(builder);
+                MetadataMessage::create(
+                    builder,
+                    &MetadataArgs {
+                        file_id_type,
+                        file_id: Some(file_id),
+                        file_type: metadata.file_type.to_flatbuf(),
+                        executable,
+                        symlink_target,
+                        oid,
+                        attributes_timestamp: Some(&metadata.attributes_timestamp.to_flatbuf()),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let metadata = Some(builder.create_vector(metadata_flatbufs));
+
+        let parent_ref_flatbufs = &self
+            .parent_refs
+            .items()
+            .iter()
+            .map(|parent_ref| {
+                let (child_id_type, child_id) = parent_ref.child_id.to_flatbuf(builder);
+                let (parent_id_type, parent_id, name_in_parent) =
+                    if let Some((parent_id, name)) = parent_ref.parent.as_ref() {
+                        let (parent_id_type, parent_id) = parent_id.to_flatbuf(builder);
+                        (
+                            parent_id_type,
+                            Some(parent_id),
+                            Some(builder.create_string(name.to_string_lossy().as_ref())),
+                        )
+                    } else {
+                        (FileIdType::NONE, None, None)
+                    };
+                ParentRef::create(
+                    builder,
+                    &ParentRefArgs {
+                        child_id_type,
+                        child_id: Some(child_id),
+                        timestamp: Some(&parent_ref.timestamp.to_flatbuf()),
+                        parent_id_type,
+                        parent_id,
+                        name_in_parent,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let parent_refs = Some(builder.create_vector(parent_ref_flatbufs));
+
+        let child_ref_flatbufs = &self
+            .child_refs
+            .items()
+            .iter()
+            .map(|child_ref| {
+                let (parent_id_type, parent_id) = child_ref.parent_id.to_flatbuf(builder);
+                let name = builder.create_string(child_ref.name.to_string_lossy().as_ref());
+                let (child_id_type, child_id) = child_ref.child_id.to_flatbuf(builder);
+                ChildRef::create(
+                    builder,
+                    &ChildRefArgs {
+                        parent_id_type,
+                        parent_id: Some(parent_id),
+                        name: Some(name),
+                        timestamp: Some(&child_ref.timestamp.to_flatbuf()),
+                        child_id_type,
+                        child_id: Some(child_id),
+                        visible: child_ref.visible,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let child_refs = Some(builder.create_vector(child_ref_flatbufs));
+
+        let replica_location_flatbufs = &self
+            .replica_locations
+            .iter()
+            .map(|(replica_id, location)| {
+                let (file_id_type, file_id) = if let Some(file_id) = location.file_id {
+                    let (file_id_type, file_id) = file_id.to_flatbuf(builder);
+                    (file_id_type, Some(file_id))
+                } else {
+                    (FileIdType::NONE, None)
+                };
+                let scroll_anchor = location
+                    .scroll_anchor
+                    .as_ref()
+                    .map(|anchor| anchor.to_flatbuf(builder));
+                let user_name = Some(builder.create_string(&location.user.name));
+                let user_color = Some(builder.create_string(&location.user.color));
+                ReplicaLocationMessage::create(
+                    builder,
+                    &ReplicaLocationArgs {
+                        replica_id: Some(&replica_id.to_flatbuf()),
+                        file_id_type,
+                        file_id,
+                        lamport_timestamp: Some(&location.lamport_timestamp.to_flatbuf()),
+                        scroll_anchor,
+                        user_name,
+                        user_color,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let replica_locations = Some(builder.create_vector(replica_location_flatbufs));
+
+        let version = Some(self.version.to_flatbuf(builder));
+
+        // Operations that were held back for files that haven't been opened are only in the
+        // history, so they're merged into the deferred operations.
+        let mut unopened_text_files = HashMap::new();
+        for op in self.history.items() {
+            if let Operation::BufferOperation { file_id, .. } = op {
+                match self.text_files.get(&file_id) {
+                    Some(TextFile::Buffered(_)) => {}
+                    text_file => {
+                        if !unopened_text_files.contains_key(&file_id) {
+                            let operations = self.unopened_buffer_operations(file_id, text_file);
+                            unopened_text_files.insert(file_id, TextFile::Deferred(operations));
+                        }
+                    }
+                }
+            }
+        }
+
+        let text_file_flatbufs = &self
+            .text_files
+            .iter()
+            .filter(|(file_id, _)| !unopened_text_files.contains_key(file_id))
+            .chain(unopened_text_files.iter())
+            .map(|(file_id, text_file)| {
+                let (file_id_type, file_id) = file_id.to_flatbuf(builder);
+                let (buffer, deferred_operations) = match text_file {
+                    TextFile::Deferred(operations) => {
+                        let op_flatbufs = &operations
+                            .iter()
+                            .map(|op| op.to_flatbuf(builder))
+                            .collect::<Vec<_>>();
+                        (None, Some(builder.create_vector(op_flatbufs)))
+                    }
+                    TextFile::Buffered(buffer) => (Some(buffer.to_flatbuf(builder)), None),
+                };
+                TextFileMessage::create(
+                    builder,
+                    &TextFileArgs {
+                        file_id_type,
+                        file_id: Some(file_id),
+                        buffer,
+                        deferred_operations,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let text_files = Some(builder.create_vector(text_file_flatbufs));
+
+        let deferred_op_flatbufs = &self
+            .deferred_ops
+            .items()
+            .iter()
+            .map(|op| {
+                let (operation_type, operation) = op.to_flatbuf(builder);
+                DeferredOperation::create(
+                    builder,
+                    &DeferredOperationArgs {
+                        operation_type,
+                        operation: Some(operation),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let deferred_operations = Some(builder.create_vector(deferred_op_flatbufs));
+
+        let conflict_flatbufs = &self
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                let (file_id, winner_id, parent_id, name) = conflict.ids();
+                let (file_id_type, file_id) = file_id.to_flatbuf(builder);
+                let (winner_id_type, winner_id) = winner_id.to_flatbuf(builder);
+                let (parent_id_type, parent_id) = parent_id.to_flatbuf(builder);
+                let name = builder.create_string(name.to_string_lossy().as_ref());
+                ConflictMessage::create(
+                    builder,
+                    &ConflictArgs {
+                        file_id_type,
+                        file_id: Some(file_id),
+                        winner_id_type,
+                        winner_id: Some(winner_id),
+                        parent_id_type,
+                        parent_id: Some(parent_id),
+                        name: Some(name),
+                        cycle: match conflict {
+                            Conflict::Name { .. } => false,
+                            Conflict::Cycle { .. } => true,
+                        },
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let conflicts = Some(builder.create_vector(conflict_flatbufs));
+
+        Snapshot::create(
+            builder,
+            &SnapshotArgs {
+                id: Some(&self.id.to_flatbuf()),
+                head,
+                base_entries_next_id: self.base_entries_next_id,
+                base_entries_stack,
+                metadata,
+                parent_refs,
+                child_refs,
+                replica_locations,
+                version,
+                text_files,
+                deferred_operations,
+                conflicts,
+            },
+        )
+    }
+
+    pub fn from_flatbuf<'a>(
+        replica_id: ReplicaId,
+        message: serialization::epoch::Snapshot<'a>,
+    ) -> Result<Self, Error> {
+        let id = Id::from_flatbuf(message.id().ok_or(Error::DeserializeError)?);
+        let head = if let Some(head) = message.head() {
+            if head.len() != 20 {
+                return Err(Error::DeserializeError);
+            }
+            let mut oid = [0; 20];
+            oid.copy_from_slice(head);
+            Some(oid)
+        } else {
+            None
+        };
+        let mut epoch = Self::new(replica_id, id, head);
+
+        epoch.base_entries_next_id = message.base_entries_next_id();
+        let base_entries_stack = message
+            .base_entries_stack()
+            .ok_or(Error::DeserializeError)?;
+        for i in 0..base_entries_stack.len() {
+            epoch
+                .base_entries_stack
+                .push(FileId::Base(base_entries_stack.get(i)));
+        }
+
+        let metadata_messages = message.metadata().ok_or(Error::DeserializeError)?;
+        for i in 0..metadata_messages.len() {
+            let metadata = metadata_messages.get(i);
+            epoch.metadata.push(Metadata {
+                file_id: FileId::from_flatbuf(
+                    metadata.file_id_type(),
+                    metadata.file_id().ok_or(Error::DeserializeError)?,
+                ),
+                file_type: FileType::from_flatbuf(&metadata.file_type()),
+                attributes: FileAttributes::from_flatbuf(
+                    metadata.executable(),
+                    metadata.symlink_target(),
+                    metadata.oid(),
+                )?,
+                attributes_timestamp: time::Lamport::from_flatbuf(
+                    metadata
+                        .attributes_timestamp()
+                        .ok_or(Error::DeserializeError)?,
+                ),
+            });
+        }
+
+        let parent_ref_messages = message.parent_refs().ok_or(Error::DeserializeError)?;
+        for i in 0..parent_ref_messages.len() {
+            let parent_ref = parent_ref_messages.get(i);
+            let parent = if let Some(parent_id) = parent_ref.parent_id() {
+                Some((
+                    FileId::from_flatbuf(parent_ref.parent_id_type(), parent_id),
+                    Arc::new(OsString::from(
+                        parent_ref.name_in_parent().ok_or(Error::DeserializeError)?,
+                    )),
+                ))
+            } else {
+                None
+            };
+            epoch.parent_refs.push(ParentRefValue {
+                child_id: FileId::from_flatbuf(
+                    parent_ref.child_id_type(),
+                    parent_ref.child_id().ok_or(Error::DeserializeError)?,
+                ),
+                timestamp: time::Lamport::from_flatbuf(
+                    parent_ref.timestamp().ok_or(Error::DeserializeError)?,
+                ),
+                parent,
+            });
+        }
+
+        let child_ref_messages = message.child_refs().ok_or(Error::DeserializeError)?;
+        for i in 0..child_ref_messages.len() {
+            let child_ref = child_ref_messages.get(i);
+            epoch.child_refs.push(ChildRefValue {
+                parent_id: FileId::from_flatbuf(
+                    child_ref.parent_id_type(),
+                    child_ref.parent_id().ok_or(Error::DeserializeError)?,
+                ),
+                name: Arc::new(OsString::from(
+                    child_ref.name().ok_or(Error::DeserializeError)?,
+                )),
+                timestamp: time::Lamport::from_flatbuf(
+                    child_ref.timestamp().ok_or(Error::DeserializeError)?,
+                ),
+                child_id: FileId::from_flatbuf(
+                    child_ref.child_id_type(),
+                    child_ref.child_id().ok_or(Error::DeserializeError)?,
+                ),
+                visible: child_ref.visible(),
+            });
+        }
+
+        let replica_location_messages =
+            message.replica_locations().ok_or(Error::DeserializeError)?;
+        for i in 0..replica_location_messages.len() {
+            let location = replica_location_messages.get(i);
+            epoch.replica_locations.insert(
+                ReplicaId::from_flatbuf(location.replica_id().ok_or(Error::DeserializeError)?),
+                ReplicaLocation {
+                    file_id: location
+                        .file_id()
+                        .map(|file_id| FileId::from_flatbuf(location.file_id_type(), file_id)),
+                    scroll_anchor: match location.scroll_anchor() {
+                        Some(anchor) => Some(buffer::Anchor::from_flatbuf(&anchor)?),
+                        None => None,
+                    },
+                    user: UserInfo {
+                        name: location.user_name().unwrap_or("").into(),
+                        color: location.user_color().unwrap_or("").into(),
+                    },
+                    lamport_timestamp: time::Lamport::from_flatbuf(
+                        location
+                            .lamport_timestamp()
+                            .ok_or(Error::DeserializeError)?,
+                    ),
+                },
+            );
+        }
+
+        epoch.version =
+            time::Global::from_flatbuf(message.version().ok_or(Error::DeserializeError)?)?;
+        epoch.history_start = epoch.version.clone();
+        epoch.local_clock.observe(time::Local {
+            replica_id,
+            value: epoch.version.get(replica_id),
+        });
+
+        let text_file_messages = message.text_files().ok_or(Error::DeserializeError)?;
+        for i in 0..text_file_messages.len() {
+            let text_file = text_file_messages.get(i);
+            let file_id = FileId::from_flatbuf(
+                text_file.file_id_type(),
+                text_file.file_id().ok_or(Error::DeserializeError)?,
+            );
+            if let Some(buffer) = text_file.buffer() {
+                epoch
+                    .text_files
+                    .insert(file_id, TextFile::Buffered(Buffer::from_flatbuf(buffer)?));
+            } else {
+                let op_messages = text_file
+                    .deferred_operations()
+                    .ok_or(Error::DeserializeError)?;
+                let mut operations = Vec::with_capacity(op_messages.len());
+                for j in 0..op_messages.len() {
+                    if let Some(op) = buffer::Operation::from_flatbuf(&op_messages.get(j))? {
+                        operations.push(op);
+                    }
                 }
+                epoch
+                    .text_files
+                    .insert(file_id, TextFile::Deferred(operations));
             }
         }
 
-        fixup_ops
+        let deferred_op_messages = message
+            .deferred_operations()
+            .ok_or(Error::DeserializeError)?;
+        let mut deferred_ops = Vec::with_capacity(deferred_op_messages.len());
+        for i in 0..deferred_op_messages.len() {
+            let deferred_op = deferred_op_messages.get(i);
+            if let Some(op) = Operation::from_flatbuf(
+                deferred_op.operation_type(),
+                deferred_op.operation().ok_or(Error::DeserializeError)?,
+            )? {
+                deferred_ops.push(op);
+            }
+        }
+        epoch.deferred_ops.insert(deferred_ops);
+
+        let conflict_messages = message.conflicts().ok_or(Error::DeserializeError)?;
+        for i in 0..conflict_messages.len() {
+            let conflict = conflict_messages.get(i);
+            let file_id = FileId::from_flatbuf(
+                conflict.file_id_type(),
+                conflict.file_id().ok_or(Error::DeserializeError)?,
+            );
+            let winner_id = FileId::from_flatbuf(
+                conflict.winner_id_type(),
+                conflict.winner_id().ok_or(Error::DeserializeError)?,
+            );
+            let parent_id = FileId::from_flatbuf(
+                conflict.parent_id_type(),
+                conflict.parent_id().ok_or(Error::DeserializeError)?,
+            );
+            let name = Arc::new(OsString::from(
+                conflict.name().ok_or(Error::DeserializeError)?,
+            ));
+            epoch.conflicts.push(if conflict.cycle() {
+                Conflict::Cycle {
+                    file_id,
+                    winner_id,
+                    parent_id,
+                    name,
+                }
+            } else {
+                Conflict::Name {
+                    file_id,
+                    winner_id,
+                    parent_id,
+                    name,
+                }
+            });
+        }
+
+        Ok(epoch)
+    }
+}
+
+/// Operations for files that haven't been opened are only kept in the history until they are.
+fn is_unopened_file_op(text_files: &HashMap<FileId, TextFile>, op: &Operation) -> bool {
+    match op {
+        Operation::BufferOperation { file_id, .. } => match text_files.get(file_id) {
+            Some(TextFile::Buffered(_)) => false,
+            _ => true,
+        },
+        _ => false,
     }
 }
 
@@ -1368,10 +2896,12 @@ impl<'a> Cursor<'a> {
     }
 
     fn is_modified_file(&self, file_id: FileId) -> bool {
-        self.epoch
-            .text_files
-            .get(&file_id)
-            .map_or(false, |f| f.is_modified())
+        self.epoch.edited_unopened_files.contains(&file_id)
+            || self
+                .epoch
+                .text_files
+                .get(&file_id)
+                .map_or(false, |f| f.is_modified())
     }
 }
 
@@ -1384,6 +2914,9 @@ impl Operation {
             Operation::UpdateParent {
                 local_timestamp, ..
             } => Some(*local_timestamp),
+            Operation::UpdateAttributes {
+                local_timestamp, ..
+            } => Some(*local_timestamp),
             Operation::BufferOperation {
                 local_timestamp, ..
             } => Some(*local_timestamp),
@@ -1399,6 +2932,9 @@ impl Operation {
             Operation::UpdateParent {
                 lamport_timestamp, ..
             } => *lamport_timestamp,
+            Operation::UpdateAttributes {
+                lamport_timestamp, ..
+            } => *lamport_timestamp,
             Operation::BufferOperation {
                 lamport_timestamp, ..
             } => *lamport_timestamp,
@@ -1415,7 +2951,8 @@ impl Operation {
         use crate::serialization::epoch::{
             BufferOperation, BufferOperationArgs, FileId as FileIdType, InsertMetadata,
             InsertMetadataArgs, Operation as OperationType, UpdateActiveLocation,
-            UpdateActiveLocationArgs, UpdateParent, UpdateParentArgs,
+            UpdateActiveLocationArgs, UpdateAttributes, UpdateAttributesArgs, UpdateParent,
+            UpdateParentArgs,
         };
 
         fn parent_to_flatbuf<'a, 'fbb>(
@@ -1442,6 +2979,7 @@ impl Operation {
             Operation::InsertMetadata {
                 file_id,
                 file_type,
+                attributes,
                 parent,
                 local_timestamp,
                 lamport_timestamp,
@@ -1449,6 +2987,7 @@ impl Operation {
                 let (file_id_type, file_id) = file_id.to_flatbuf(builder);
                 let (parent_id_type, parent_id, name_in_parent) =
                     parent_to_flatbuf(parent, builder);
+                let (executable, symlink_target, oid) = attributes.to_flatbuf(builder);
 
                 (
                     OperationType::InsertMetadata,
@@ -1463,6 +3002,9 @@ impl Operation {
                             name_in_parent,
                             local_timestamp: Some(&local_timestamp.to_flatbuf()),
                             lamport_timestamp: Some(&lamport_timestamp.to_flatbuf()),
+                            executable,
+                            symlink_target,
+                            oid,
                         },
                     )
                     .as_union_value(),
@@ -1494,6 +3036,31 @@ impl Operation {
                     .as_union_value(),
                 )
             }
+            Operation::UpdateAttributes {
+                file_id,
+                attributes,
+                local_timestamp,
+                lamport_timestamp,
+            } => {
+                let (file_id_type, file_id) = file_id.to_flatbuf(builder);
+                let (executable, symlink_target, oid) = attributes.to_flatbuf(builder);
+                (
+                    OperationType::UpdateAttributes,
+                    UpdateAttributes::create(
+                        builder,
+                        &UpdateAttributesArgs {
+                            file_id_type,
+                            file_id: Some(file_id),
+                            local_timestamp: Some(&local_timestamp.to_flatbuf()),
+                            lamport_timestamp: Some(&lamport_timestamp.to_flatbuf()),
+                            executable,
+                            symlink_target,
+                            oid,
+                        },
+                    )
+                    .as_union_value(),
+                )
+            }
             Operation::BufferOperation {
                 file_id,
                 operations,
@@ -1524,6 +3091,8 @@ impl Operation {
             }
             Operation::UpdateActiveLocation {
                 file_id,
+                scroll_anchor,
+                user,
                 lamport_timestamp,
             } => {
                 let file_id_type;
@@ -1536,6 +3105,11 @@ impl Operation {
                     file_id_type = FileIdType::NONE;
                     file_id_buf = None;
                 }
+                let scroll_anchor = scroll_anchor
+                    .as_ref()
+                    .map(|anchor| anchor.to_flatbuf(builder));
+                let user_name = Some(builder.create_string(&user.name));
+                let user_color = Some(builder.create_string(&user.color));
 
                 (
                     OperationType::UpdateActiveLocation,
@@ -1545,6 +3119,9 @@ impl Operation {
                             file_id_type,
                             file_id: file_id_buf,
                             lamport_timestamp: Some(&lamport_timestamp.to_flatbuf()),
+                            scroll_anchor,
+                            user_name,
+                            user_color,
                         },
                     )
                     .as_union_value(),
@@ -1578,6 +3155,11 @@ impl Operation {
                         message.file_id().ok_or(Error::DeserializeError)?,
                     ),
                     file_type: FileType::from_flatbuf(&message.file_type()),
+                    attributes: FileAttributes::from_flatbuf(
+                        message.executable(),
+                        message.symlink_target(),
+                        message.oid(),
+                    )?,
                     parent: parent_from_flatbuf(
                         message.parent_id_type(),
                         message.parent_id(),
@@ -1609,6 +3191,26 @@ impl Operation {
                     ),
                 }))
             }
+            serialization::epoch::Operation::UpdateAttributes => {
+                let message = serialization::epoch::UpdateAttributes::init_from_table(message);
+                Ok(Some(Operation::UpdateAttributes {
+                    file_id: FileId::from_flatbuf(
+                        message.file_id_type(),
+                        message.file_id().ok_or(Error::DeserializeError)?,
+                    ),
+                    attributes: FileAttributes::from_flatbuf(
+                        message.executable(),
+                        message.symlink_target(),
+                        message.oid(),
+                    )?,
+                    local_timestamp: time::Local::from_flatbuf(
+                        message.local_timestamp().ok_or(Error::DeserializeError)?,
+                    ),
+                    lamport_timestamp: time::Lamport::from_flatbuf(
+                        message.lamport_timestamp().ok_or(Error::DeserializeError)?,
+                    ),
+                }))
+            }
             serialization::epoch::Operation::BufferOperation => {
                 let message = serialization::epoch::BufferOperation::init_from_table(message);
                 let op_messages = message.operations().ok_or(Error::DeserializeError)?;
@@ -1643,6 +3245,14 @@ impl Operation {
 
                 Ok(Some(Operation::UpdateActiveLocation {
                     file_id,
+                    scroll_anchor: match message.scroll_anchor() {
+                        Some(anchor) => Some(buffer::Anchor::from_flatbuf(&anchor)?),
+                        None => None,
+                    },
+                    user: UserInfo {
+                        name: message.user_name().unwrap_or("").into(),
+                        color: message.user_color().unwrap_or("").into(),
+                    },
                     lamport_timestamp: time::Lamport::from_flatbuf(
                         message.lamport_timestamp().ok_or(Error::DeserializeError)?,
                     ),
@@ -1717,6 +3327,8 @@ impl FileType {
         match self {
             FileType::Directory => serialization::epoch::FileType::Directory,
             FileType::Text => serialization::epoch::FileType::Text,
+            FileType::Symlink => serialization::epoch::FileType::Symlink,
+            FileType::Binary => serialization::epoch::FileType::Binary,
         }
     }
 
@@ -1724,8 +3336,62 @@ impl FileType {
         match message {
             serialization::epoch::FileType::Directory => FileType::Directory,
             serialization::epoch::FileType::Text => FileType::Text,
+            serialization::epoch::FileType::Symlink => FileType::Symlink,
+            serialization::epoch::FileType::Binary => FileType::Binary,
+        }
+    }
+}
+
+impl FileAttributes {
+    fn is_valid_for(&self, file_type: FileType) -> bool {
+        match file_type {
+            FileType::Directory => *self == FileAttributes::default(),
+            FileType::Text => self.symlink_target.is_none() && self.oid.is_none(),
+            FileType::Symlink => {
+                !self.executable && self.symlink_target.is_some() && self.oid.is_none()
+            }
+            FileType::Binary => self.symlink_target.is_none() && self.oid.is_some(),
         }
     }
+
+    fn to_flatbuf<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> (
+        bool,
+        Option<WIPOffset<&'fbb str>>,
+        Option<WIPOffset<flatbuffers::Vector<'fbb, u8>>>,
+    ) {
+        let symlink_target = self
+            .symlink_target
+            .as_ref()
+            .map(|target| builder.create_string(target.to_string_lossy().as_ref()));
+        let oid = self.oid.as_ref().map(|oid| builder.create_vector(oid));
+        (self.executable, symlink_target, oid)
+    }
+
+    fn from_flatbuf(
+        executable: bool,
+        symlink_target: Option<&str>,
+        oid: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        let oid = if let Some(oid) = oid {
+            if oid.len() != 20 {
+                return Err(Error::DeserializeError);
+            }
+            let mut bytes = [0; 20];
+            bytes.copy_from_slice(oid);
+            Some(bytes)
+        } else {
+            None
+        };
+
+        Ok(FileAttributes {
+            executable,
+            symlink_target: symlink_target.map(PathBuf::from),
+            oid,
+        })
+    }
 }
 
 impl btree::Dimension<FileId> for FileId {
@@ -1970,6 +3636,25 @@ impl btree::Dimension<ChildRefValueSummary> for usize {
     }
 }
 
+impl Conflict {
+    fn ids(&self) -> (FileId, FileId, FileId, &Arc<OsString>) {
+        match self {
+            Conflict::Name {
+                file_id,
+                winner_id,
+                parent_id,
+                name,
+            }
+            | Conflict::Cycle {
+                file_id,
+                winner_id,
+                parent_id,
+                name,
+            } => (*file_id, *winner_id, *parent_id, name),
+        }
+    }
+}
+
 impl TextFile {
     fn is_modified(&self) -> bool {
         match self {
@@ -2001,6 +3686,25 @@ where
     Ok(OsString::from(String::deserialize(deserializer)?))
 }
 
+fn serialize_oid<S>(oid: &Option<Oid>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    oid.map(git::oid_to_hex).serialize(serializer)
+}
+
+fn deserialize_oid<'de, D>(deserializer: D) -> Result<Option<Oid>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(hex) => git::hex_to_oid(&hex)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid object id {:?}", hex))),
+        None => Ok(None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2022,21 +3726,25 @@ mod tests {
                         depth: 1,
                         name: OsString::from("a"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 2,
                         name: OsString::from("b"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 3,
                         name: OsString::from("c"),
                         file_type: FileType::Text,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 2,
                         name: OsString::from("d"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                 ],
                 &mut lamport_clock,
@@ -2059,11 +3767,13 @@ mod tests {
                         depth: 2,
                         name: OsString::from("e"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 1,
                         name: OsString::from("f"),
                         file_type: FileType::Text,
+                        attributes: FileAttributes::default(),
                     },
                 ],
                 &mut lamport_clock,
@@ -2076,6 +3786,269 @@ mod tests {
         assert_eq!(fixup_ops.len(), 1);
     }
 
+    #[test]
+    fn test_trim_history() {
+        let mut epoch = Epoch::with_replica_id(Uuid::from_u128(1));
+        let mut lamport_clock = time::Lamport::new(Uuid::from_u128(1));
+        let (file_id, _) = epoch.new_text_file(&mut lamport_clock);
+        epoch
+            .open_text_file(file_id, "", &mut lamport_clock)
+            .unwrap();
+        let start_version = epoch.version();
+
+        let mut recent_version = None;
+        for i in 0..MAX_HISTORY_LEN {
+            epoch
+                .edit(file_id, Some(i..i), "a", &mut lamport_clock)
+                .unwrap();
+            if i == MAX_HISTORY_LEN - 10 {
+                recent_version = Some(epoch.version());
+            }
+        }
+        assert!(epoch.history.len() <= MAX_HISTORY_LEN);
+        assert_eq!(
+            epoch.operations_since(&start_version).err(),
+            Some(Error::OperationsUnavailable)
+        );
+        assert_eq!(
+            epoch
+                .operations_since(&recent_version.unwrap())
+                .unwrap()
+                .len(),
+            9
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut epoch_1 = Epoch::with_replica_id(Uuid::from_u128(1));
+        let mut lamport_clock_1 = time::Lamport::new(Uuid::from_u128(1));
+        let mut epoch_2 = Epoch::with_replica_id(Uuid::from_u128(2));
+        let mut lamport_clock_2 = time::Lamport::new(Uuid::from_u128(2));
+
+        let base_entries = vec![
+            DirEntry {
+                depth: 1,
+                name: OsString::from("a"),
+                file_type: FileType::Directory,
+                attributes: FileAttributes::default(),
+            },
+            DirEntry {
+                depth: 1,
+                name: OsString::from("b"),
+                file_type: FileType::Directory,
+                attributes: FileAttributes::default(),
+            },
+        ];
+        epoch_1
+            .append_base_entries(base_entries.clone(), &mut lamport_clock_1)
+            .unwrap();
+        epoch_2
+            .append_base_entries(base_entries, &mut lamport_clock_2)
+            .unwrap();
+        let a = epoch_1.file_id("a").unwrap();
+        let b = epoch_1.file_id("b").unwrap();
+
+        let ops = vec![
+            epoch_1
+                .create_file(ROOT_FILE_ID, "c", FileType::Text, &mut lamport_clock_1)
+                .unwrap(),
+            epoch_1.rename(a, b, "a", &mut lamport_clock_1).unwrap(),
+        ];
+        epoch_2
+            .create_file(ROOT_FILE_ID, "c", FileType::Text, &mut lamport_clock_2)
+            .unwrap();
+        epoch_2.rename(b, a, "b", &mut lamport_clock_2).unwrap();
+        let c_1 = epoch_1.file_id("c").unwrap();
+        let c_2 = epoch_2.file_id("c").unwrap();
+
+        epoch_2.apply_ops(ops, &mut lamport_clock_2).unwrap();
+        assert_eq!(epoch_2.paths(), vec!["b", "b/a", "c", "c~"]);
+
+        let mut conflicts = epoch_2.conflicts();
+        conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            conflicts,
+            vec![
+                ConflictEntry {
+                    conflict: Conflict::Cycle {
+                        file_id: b,
+                        winner_id: a,
+                        parent_id: a,
+                        name: Arc::new("b".into()),
+                    },
+                    path: PathBuf::from("b"),
+                    winner_path: PathBuf::from("b/a"),
+                    requested_path: PathBuf::from("b/a/b"),
+                },
+                ConflictEntry {
+                    conflict: Conflict::Name {
+                        file_id: c_1,
+                        winner_id: c_2,
+                        parent_id: ROOT_FILE_ID,
+                        name: Arc::new("c".into()),
+                    },
+                    path: PathBuf::from("c~"),
+                    winner_path: PathBuf::from("c"),
+                    requested_path: PathBuf::from("c"),
+                },
+            ]
+        );
+
+        let mut builder = FlatBufferBuilder::new();
+        let root = epoch_2.to_flatbuf(&mut builder);
+        builder.finish(root, None);
+        let message =
+            flatbuffers::get_root::<serialization::epoch::Snapshot>(builder.finished_data());
+        let mut epoch_3 = Epoch::from_flatbuf(Uuid::from_u128(3), message).unwrap();
+        let mut lamport_clock_3 = time::Lamport::new(Uuid::from_u128(3));
+        assert_eq!(epoch_3.conflicts(), epoch_2.conflicts());
+        epoch_3
+            .rename(c_2, ROOT_FILE_ID, "d", &mut lamport_clock_3)
+            .unwrap();
+        assert_eq!(epoch_3.conflicts.len(), 1);
+
+        let mut epoch_4 = Epoch::with_replica_id(Uuid::from_u128(4));
+        let mut lamport_clock_4 = time::Lamport::new(Uuid::from_u128(4));
+        epoch_4
+            .append_base_entries(
+                vec![
+                    DirEntry {
+                        depth: 1,
+                        name: OsString::from("b"),
+                        file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
+                    },
+                    DirEntry {
+                        depth: 2,
+                        name: OsString::from("a"),
+                        file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
+                    },
+                    DirEntry {
+                        depth: 1,
+                        name: OsString::from("c"),
+                        file_type: FileType::Text,
+                        attributes: FileAttributes::default(),
+                    },
+                    DirEntry {
+                        depth: 1,
+                        name: OsString::from("c~"),
+                        file_type: FileType::Text,
+                        attributes: FileAttributes::default(),
+                    },
+                ],
+                &mut lamport_clock_4,
+            )
+            .unwrap();
+        epoch_4.restore_conflicts(epoch_2.conflicts());
+        let mut restored_conflicts = epoch_4.conflicts();
+        restored_conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(
+            restored_conflicts
+                .iter()
+                .map(|entry| (&entry.path, &entry.winner_path, &entry.requested_path))
+                .collect::<Vec<_>>(),
+            conflicts
+                .iter()
+                .map(|entry| (&entry.path, &entry.winner_path, &entry.requested_path))
+                .collect::<Vec<_>>()
+        );
+        let c_4 = epoch_4.file_id("c~").unwrap();
+        epoch_4.remove(c_4, &mut lamport_clock_4).unwrap();
+        assert_eq!(epoch_4.conflicts.len(), 1);
+
+        assert!(epoch_2
+            .resolve_conflict(&conflicts[1].conflict, a, &mut lamport_clock_2)
+            .is_err());
+        epoch_2
+            .resolve_conflict(&conflicts[1].conflict, c_1, &mut lamport_clock_2)
+            .unwrap();
+        assert_eq!(epoch_2.path(c_1), Some(PathBuf::from("c")));
+        assert_eq!(epoch_2.path(c_2), Some(PathBuf::from("c~")));
+
+        epoch_2
+            .resolve_conflict(&conflicts[0].conflict, b, &mut lamport_clock_2)
+            .unwrap();
+        assert_eq!(epoch_2.paths(), vec!["a", "a/b", "c", "c~"]);
+        assert_eq!(epoch_2.conflicts(), vec![]);
+    }
+
+    #[test]
+    fn test_tree_changes() {
+        let mut epoch_1 = Epoch::with_replica_id(Uuid::from_u128(1));
+        let mut lamport_clock_1 = time::Lamport::new(Uuid::from_u128(1));
+        let mut epoch_2 = Epoch::with_replica_id(Uuid::from_u128(2));
+        let mut lamport_clock_2 = time::Lamport::new(Uuid::from_u128(2));
+
+        let base_entries = vec![
+            DirEntry {
+                depth: 1,
+                name: OsString::from("a"),
+                file_type: FileType::Directory,
+                attributes: FileAttributes::default(),
+            },
+            DirEntry {
+                depth: 1,
+                name: OsString::from("b"),
+                file_type: FileType::Text,
+                attributes: FileAttributes::default(),
+            },
+            DirEntry {
+                depth: 1,
+                name: OsString::from("d"),
+                file_type: FileType::Text,
+                attributes: FileAttributes::default(),
+            },
+        ];
+        epoch_1
+            .append_base_entries(base_entries.clone(), &mut lamport_clock_1)
+            .unwrap();
+        epoch_2
+            .append_base_entries(base_entries, &mut lamport_clock_2)
+            .unwrap();
+        let a = epoch_1.file_id("a").unwrap();
+        let b = epoch_1.file_id("b").unwrap();
+        let d = epoch_1.file_id("d").unwrap();
+
+        let mut ops = vec![
+            epoch_1
+                .create_file(ROOT_FILE_ID, "c", FileType::Text, &mut lamport_clock_1)
+                .unwrap(),
+            epoch_1
+                .create_file(ROOT_FILE_ID, "e", FileType::Text, &mut lamport_clock_1)
+                .unwrap(),
+            epoch_1.rename(b, a, "b", &mut lamport_clock_1).unwrap(),
+            epoch_1.remove(d, &mut lamport_clock_1).unwrap(),
+        ];
+        let c = epoch_1.file_id("c").unwrap();
+        let e = epoch_1.file_id("e").unwrap();
+        ops.push(epoch_1.remove(e, &mut lamport_clock_1).unwrap());
+
+        let (fixup_ops, tree_changes) = epoch_2
+            .apply_ops_with_changes(ops, &mut lamport_clock_2)
+            .unwrap();
+        assert!(fixup_ops.is_empty());
+        assert_eq!(
+            tree_changes,
+            vec![
+                TreeChange::Renamed {
+                    file_id: b,
+                    old_path: PathBuf::from("b"),
+                    new_path: PathBuf::from("a/b"),
+                },
+                TreeChange::Removed {
+                    file_id: d,
+                    path: PathBuf::from("d"),
+                },
+                TreeChange::Created {
+                    file_id: c,
+                    path: PathBuf::from("c"),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_cursor() {
         let replica_id = Uuid::nil();
@@ -2089,36 +4062,43 @@ mod tests {
                         depth: 1,
                         name: OsString::from("a"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 2,
                         name: OsString::from("b"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 3,
                         name: OsString::from("c"),
                         file_type: FileType::Text,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 2,
                         name: OsString::from("d"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 2,
                         name: OsString::from("e"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 1,
                         name: OsString::from("f"),
                         file_type: FileType::Directory,
+                        attributes: FileAttributes::default(),
                     },
                     DirEntry {
                         depth: 2,
                         name: OsString::from("g"),
                         file_type: FileType::Text,
+                        attributes: FileAttributes::default(),
                     },
                 ],
                 &mut lamport_clock,
@@ -2285,11 +4265,13 @@ mod tests {
                 depth: 1,
                 name: OsString::from("dir"),
                 file_type: FileType::Directory,
+                attributes: FileAttributes::default(),
             },
             DirEntry {
                 depth: 1,
                 name: OsString::from("file"),
                 file_type: FileType::Text,
+                attributes: FileAttributes::default(),
             },
         ];
         let base_text = Text::from("abc");
@@ -2346,6 +4328,65 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    fn test_update_attributes() {
+        let base_entries = vec![DirEntry {
+            depth: 1,
+            name: OsString::from("file"),
+            file_type: FileType::Text,
+            attributes: FileAttributes::default(),
+        }];
+
+        let replica_id_1 = Uuid::from_u128(1);
+        let mut epoch_1 = Epoch::with_replica_id(replica_id_1);
+        let mut lamport_clock_1 = time::Lamport::new(replica_id_1);
+        epoch_1
+            .append_base_entries(base_entries.clone(), &mut lamport_clock_1)
+            .unwrap();
+        let replica_id_2 = Uuid::from_u128(2);
+        let mut epoch_2 = Epoch::with_replica_id(replica_id_2);
+        let mut lamport_clock_2 = time::Lamport::new(replica_id_2);
+        epoch_2
+            .append_base_entries(base_entries, &mut lamport_clock_2)
+            .unwrap();
+
+        let file_id = epoch_1.file_id("file").unwrap();
+        let executable = FileAttributes {
+            executable: true,
+            ..FileAttributes::default()
+        };
+        let op_1 = epoch_1
+            .update_attributes(file_id, executable.clone(), &mut lamport_clock_1)
+            .unwrap();
+        let op_2 = epoch_2
+            .update_attributes(file_id, executable.clone(), &mut lamport_clock_2)
+            .unwrap();
+        let op_3 = epoch_2
+            .update_attributes(file_id, FileAttributes::default(), &mut lamport_clock_2)
+            .unwrap();
+        epoch_1
+            .apply_ops(vec![op_2, op_3], &mut lamport_clock_1)
+            .unwrap();
+        epoch_2.apply_ops(Some(op_1), &mut lamport_clock_2).unwrap();
+        assert_eq!(epoch_1.attributes(file_id), Ok(FileAttributes::default()));
+        assert_eq!(epoch_2.attributes(file_id), Ok(FileAttributes::default()));
+
+        let invalid_attributes = FileAttributes {
+            symlink_target: Some(PathBuf::from("a")),
+            ..FileAttributes::default()
+        };
+        assert!(epoch_1
+            .update_attributes(file_id, invalid_attributes, &mut lamport_clock_1)
+            .is_err());
+        assert!(epoch_1
+            .update_attributes(
+                ROOT_FILE_ID,
+                FileAttributes::default(),
+                &mut lamport_clock_1
+            )
+            .is_err());
+    }
+
     #[test]
     fn test_buffer_deferred_ops_len() -> Result<(), Error> {
         let replica_1_id = Uuid::from_u128(1);
@@ -2406,6 +4447,7 @@ mod tests {
                     depth: entry.depth,
                     name: entry.name.as_ref().clone(),
                     file_type: entry.file_type,
+                    attributes: FileAttributes::default(),
                 })
                 .collect::<Vec<_>>();
 
@@ -2540,7 +4582,11 @@ mod tests {
                 loop {
                     let entry = cursor.entry().unwrap();
                     let advanced = if entry.visible {
-                        entries.push(entry.into());
+                        let attributes = self.attributes(entry.file_id).unwrap();
+                        entries.push(DirEntry {
+                            attributes,
+                            ..entry.into()
+                        });
                         cursor.next(true)
                     } else {
                         cursor.next(false)
@@ -2683,6 +4729,7 @@ mod tests {
                 depth: entry.depth,
                 name: entry.name.as_ref().clone(),
                 file_type: entry.file_type,
+                attributes: FileAttributes::default(),
             }
         }
     }
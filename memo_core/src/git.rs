@@ -0,0 +1,1165 @@
+use crate::epoch::{DirEntry, FileAttributes, FileType};
+use crate::work_tree::GitProvider;
+use crate::Oid;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use futures::{future, stream, Future, Stream};
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::vec;
+
+const PACK_INDEX_MAGIC: [u8; 4] = [0xff, 0x74, 0x4f, 0x63];
+const DIR_MODE: u32 = 0o040_000;
+const FILE_MODE: u32 = 0o100_644;
+const EXECUTABLE_MODE: u32 = 0o100_755;
+const SYMLINK_MODE: u32 = 0o120_000;
+// Git considers blobs containing a null byte in this many leading bytes to be binary.
+const BINARY_CHECK_LEN: usize = 8000;
+
+#[derive(Clone, Debug)]
+pub struct Repository {
+    git_dir: PathBuf,
+    packs: Arc<Mutex<Option<Vec<Arc<PackIndex>>>>>,
+    binary_blobs: Arc<Mutex<HashMap<Oid, bool>>>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ObjectKind {
+    Blob,
+    Tree,
+    Commit,
+    Tag,
+}
+
+#[derive(Debug)]
+struct PackIndex {
+    pack_path: PathBuf,
+    data: Vec<u8>,
+    len: usize,
+}
+
+#[derive(Clone, Debug)]
+struct TreeEntry {
+    mode: u32,
+    name: OsString,
+    oid: Oid,
+}
+
+struct BaseEntries {
+    repo: Repository,
+    stack: Vec<vec::IntoIter<TreeEntry>>,
+}
+
+#[derive(Debug, Default)]
+pub struct TreeBuilder {
+    entries: BTreeMap<OsString, TreeBuilderEntry>,
+}
+
+#[derive(Debug)]
+enum TreeBuilderEntry {
+    Blob { mode: u32, contents: BlobContents },
+    Tree(TreeBuilder),
+}
+
+#[derive(Debug)]
+enum BlobContents {
+    Data(Vec<u8>),
+    Object(Oid),
+}
+
+impl Repository {
+    pub fn new<P: Into<PathBuf>>(git_dir: P) -> Self {
+        Repository {
+            git_dir: git_dir.into(),
+            packs: Arc::new(Mutex::new(None)),
+            binary_blobs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    pub fn write_object(&self, kind: ObjectKind, data: &[u8]) -> io::Result<Oid> {
+        let mut object = format!("{} {}\0", kind.name(), data.len()).into_bytes();
+        object.extend_from_slice(data);
+        let oid = sha1::Sha1::from(&object).digest().bytes();
+
+        let path = self.loose_object_path(oid);
+        if !path.exists() {
+            let dir = path.parent().unwrap();
+            fs::create_dir_all(dir)?;
+
+            // Write to a temporary file first so that concurrent readers never observe a
+            // partially-written object.
+            let temp_path = dir.join(format!("tmp_obj_{}", oid_to_hex(oid)));
+            let mut encoder =
+                ZlibEncoder::new(fs::File::create(&temp_path)?, Compression::default());
+            encoder.write_all(&object)?;
+            encoder.finish()?;
+            fs::rename(&temp_path, &path)?;
+        }
+
+        Ok(oid)
+    }
+
+    pub fn read_object(&self, oid: Oid) -> io::Result<(ObjectKind, Vec<u8>)> {
+        self.read_object_prefix(oid, u64::max_value())
+    }
+
+    /// Reads at most `max_len` bytes of the given object, so that large blobs can be classified
+    /// without inflating them entirely. Deltified objects still need to be reconstructed in full.
+    fn read_object_prefix(&self, oid: Oid, max_len: u64) -> io::Result<(ObjectKind, Vec<u8>)> {
+        match fs::File::open(self.loose_object_path(oid)) {
+            Ok(file) => {
+                // The header is the object kind followed by its decimal size and a null byte.
+                let mut object = Vec::new();
+                ZlibDecoder::new(file)
+                    .take(max_len.saturating_add(32))
+                    .read_to_end(&mut object)?;
+                let header_len = object
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or_else(|| invalid_data("missing object header"))?;
+                let kind = {
+                    let header = String::from_utf8_lossy(&object[0..header_len]);
+                    let name = header.split(' ').next().unwrap();
+                    ObjectKind::from_name(name)
+                        .ok_or_else(|| invalid_data(format!("unknown object type {}", name)))?
+                };
+                let mut data = object.split_off(header_len + 1);
+                data.truncate(cmp::min(data.len() as u64, max_len) as usize);
+                Ok((kind, data))
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => {
+                self.read_packed_object(oid, max_len)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn read_packed_object(&self, oid: Oid, max_len: u64) -> io::Result<(ObjectKind, Vec<u8>)> {
+        // Packs are indexed lazily and indexed again on a miss, in case new packs were written
+        // since we last looked.
+        for reload in &[false, true] {
+            for pack in self.packs(*reload)? {
+                if let Some(offset) = pack.offset(oid) {
+                    let mut file = fs::File::open(&pack.pack_path)?;
+                    return self.read_pack_entry(&mut file, offset, max_len);
+                }
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("object {} not found", oid_to_hex(oid)),
+        ))
+    }
+
+    fn read_pack_entry(
+        &self,
+        file: &mut fs::File,
+        offset: u64,
+        max_len: u64,
+    ) -> io::Result<(ObjectKind, Vec<u8>)> {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::new(&mut *file);
+        let mut byte = read_byte(&mut reader)?;
+        let type_id = (byte >> 4) & 0x7;
+        let mut size = (byte & 0xf) as u64;
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = read_byte(&mut reader)?;
+            size |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+        }
+
+        let prefix_len = cmp::min(size, max_len);
+        let (kind, mut data) = match type_id {
+            1 => (ObjectKind::Commit, inflate(&mut reader, prefix_len)?),
+            2 => (ObjectKind::Tree, inflate(&mut reader, prefix_len)?),
+            3 => (ObjectKind::Blob, inflate(&mut reader, prefix_len)?),
+            4 => (ObjectKind::Tag, inflate(&mut reader, prefix_len)?),
+            6 => {
+                let mut byte = read_byte(&mut reader)?;
+                let mut base_distance = (byte & 0x7f) as u64;
+                while byte & 0x80 != 0 {
+                    byte = read_byte(&mut reader)?;
+                    base_distance = ((base_distance + 1) << 7) | (byte & 0x7f) as u64;
+                }
+                let delta = inflate(&mut reader, size)?;
+                drop(reader);
+
+                let base_offset = offset
+                    .checked_sub(base_distance)
+                    .ok_or_else(|| invalid_data("invalid delta base offset"))?;
+                let (kind, base) = self.read_pack_entry(file, base_offset, u64::max_value())?;
+                (kind, apply_delta(&base, &delta)?)
+            }
+            7 => {
+                let mut base_oid = [0; 20];
+                reader.read_exact(&mut base_oid)?;
+                let delta = inflate(&mut reader, size)?;
+                let (kind, base) = self.read_object(base_oid)?;
+                (kind, apply_delta(&base, &delta)?)
+            }
+            _ => return Err(invalid_data(format!("unknown pack entry type {}", type_id))),
+        };
+        data.truncate(cmp::min(data.len() as u64, max_len) as usize);
+        Ok((kind, data))
+    }
+
+    /// Deltified blobs have to be reconstructed in full to be classified, so the result is
+    /// remembered for every blob that has been looked at.
+    fn is_binary_blob(&self, oid: Oid) -> io::Result<bool> {
+        if let Some(binary) = self.binary_blobs.lock().unwrap().get(&oid) {
+            return Ok(*binary);
+        }
+
+        let (_, data) = self.read_object_prefix(oid, BINARY_CHECK_LEN as u64)?;
+        let binary = is_binary(&data);
+        self.binary_blobs.lock().unwrap().insert(oid, binary);
+        Ok(binary)
+    }
+
+    fn packs(&self, reload: bool) -> io::Result<Vec<Arc<PackIndex>>> {
+        let mut packs = self.packs.lock().unwrap();
+        if reload || packs.is_none() {
+            let mut new_packs = Vec::new();
+            let pack_dir = self.git_dir.join("objects").join("pack");
+            if pack_dir.exists() {
+                for entry in fs::read_dir(&pack_dir)? {
+                    let path = entry?.path();
+                    if path.extension().map_or(false, |ext| ext == "idx") {
+                        new_packs.push(Arc::new(PackIndex::load(path)?));
+                    }
+                }
+            }
+            *packs = Some(new_packs);
+        }
+        Ok(packs.as_ref().unwrap().clone())
+    }
+
+    fn read_tree(&self, oid: Oid) -> io::Result<Vec<TreeEntry>> {
+        let (kind, data) = self.read_object(oid)?;
+        if kind != ObjectKind::Tree {
+            return Err(invalid_data(format!("{} is not a tree", oid_to_hex(oid))));
+        }
+
+        let mut entries = Vec::new();
+        let mut ix = 0;
+        while ix < data.len() {
+            let space_ix = ix
+                + data[ix..]
+                    .iter()
+                    .position(|b| *b == b' ')
+                    .ok_or_else(|| invalid_data("invalid tree entry"))?;
+            let nul_ix = space_ix
+                + data[space_ix..]
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or_else(|| invalid_data("invalid tree entry"))?;
+            if nul_ix + 21 > data.len() {
+                return Err(invalid_data("invalid tree entry"));
+            }
+
+            let mode = u32::from_str_radix(&String::from_utf8_lossy(&data[ix..space_ix]), 8)
+                .map_err(|_| invalid_data("invalid tree entry mode"))?;
+            let name = os_string_from_bytes(&data[space_ix + 1..nul_ix]);
+            let mut oid = [0; 20];
+            oid.copy_from_slice(&data[nul_ix + 1..nul_ix + 21]);
+            entries.push(TreeEntry { mode, name, oid });
+            ix = nul_ix + 21;
+        }
+        Ok(entries)
+    }
+
+    /// Returns the root tree of the given commit. Tree oids are returned as is and annotated tags
+    /// are peeled to the object they point at.
+    fn root_tree(&self, oid: Oid) -> io::Result<Oid> {
+        let (kind, data) = self.read_object(oid)?;
+        match kind {
+            ObjectKind::Tree => Ok(oid),
+            ObjectKind::Commit => {
+                first_header(&data, "tree ").ok_or_else(|| invalid_data("commit has no tree"))
+            }
+            ObjectKind::Tag => {
+                let target = first_header(&data, "object ")
+                    .ok_or_else(|| invalid_data("tag has no object"))?;
+                self.root_tree(target)
+            }
+            _ => Err(invalid_data(format!("{} is not a commit", oid_to_hex(oid)))),
+        }
+    }
+
+    fn read_text(&self, oid: Oid, path: &Path) -> io::Result<String> {
+        let mut oid = self.root_tree(oid)?;
+        for component in path.components() {
+            if let Component::Normal(name) = component {
+                oid = self
+                    .read_tree(oid)?
+                    .into_iter()
+                    .find(|entry| entry.name == name)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("path {:?} does not exist", path),
+                        )
+                    })?
+                    .oid;
+            }
+        }
+
+        match self.read_object(oid)? {
+            (ObjectKind::Blob, data) => {
+                String::from_utf8(data).map_err(|_| invalid_data("file is not valid UTF-8"))
+            }
+            _ => Err(invalid_data(format!("path {:?} is not a file", path))),
+        }
+    }
+
+    fn loose_object_path(&self, oid: Oid) -> PathBuf {
+        let hex = oid_to_hex(oid);
+        self.git_dir
+            .join("objects")
+            .join(&hex[0..2])
+            .join(&hex[2..])
+    }
+}
+
+impl GitProvider for Repository {
+    fn base_entries(&self, oid: Oid) -> Box<Stream<Item = DirEntry, Error = io::Error> + Send> {
+        match self
+            .root_tree(oid)
+            .and_then(|tree_oid| self.read_tree(tree_oid))
+        {
+            Ok(entries) => Box::new(stream::iter_result(BaseEntries {
+                repo: self.clone(),
+                stack: vec![entries.into_iter()],
+            })),
+            Err(error) => Box::new(stream::once(Err(error))),
+        }
+    }
+
+    fn base_text(
+        &self,
+        oid: Oid,
+        path: &Path,
+    ) -> Box<Future<Item = String, Error = io::Error> + Send> {
+        Box::new(future::result(self.read_text(oid, path)))
+    }
+
+    fn blob(&self, oid: Oid) -> Box<Future<Item = Vec<u8>, Error = io::Error> + Send> {
+        Box::new(future::result(self.read_object(oid).and_then(
+            |(kind, data)| match kind {
+                ObjectKind::Blob => Ok(data),
+                _ => Err(invalid_data(format!("{} is not a blob", oid_to_hex(oid)))),
+            },
+        )))
+    }
+}
+
+impl Iterator for BaseEntries {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let depth = self.stack.len();
+            let next_entry = self.stack.last_mut()?.next();
+            let entry = match next_entry {
+                Some(entry) => entry,
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            match entry.file_type(&self.repo) {
+                Ok(Some((file_type, attributes))) => {
+                    if file_type == FileType::Directory {
+                        match self.repo.read_tree(entry.oid) {
+                            Ok(children) => self.stack.push(children.into_iter()),
+                            Err(error) => return Some(Err(error)),
+                        }
+                    }
+
+                    return Some(Ok(DirEntry {
+                        depth,
+                        name: entry.name,
+                        file_type,
+                        attributes,
+                    }));
+                }
+                Ok(None) => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+impl PackIndex {
+    fn load(index_path: PathBuf) -> io::Result<Self> {
+        let data = fs::read(&index_path)?;
+        if data.len() < 8 + 256 * 4 || data[0..4] != PACK_INDEX_MAGIC || read_u32(&data, 4) != 2 {
+            return Err(invalid_data(format!(
+                "unsupported pack index {:?}",
+                index_path
+            )));
+        }
+
+        let len = read_u32(&data, 8 + 255 * 4) as usize;
+        if data.len() < 8 + 256 * 4 + len * 28 {
+            return Err(invalid_data(format!(
+                "truncated pack index {:?}",
+                index_path
+            )));
+        }
+
+        Ok(PackIndex {
+            pack_path: index_path.with_extension("pack"),
+            data,
+            len,
+        })
+    }
+
+    fn offset(&self, oid: Oid) -> Option<u64> {
+        let fanout_start = 8;
+        let oids_start = fanout_start + 256 * 4;
+        let offsets_start = oids_start + self.len * 24;
+        let large_offsets_start = offsets_start + self.len * 4;
+
+        let mut start = if oid[0] == 0 {
+            0
+        } else {
+            read_u32(&self.data, fanout_start + (oid[0] as usize - 1) * 4) as usize
+        };
+        let mut end = read_u32(&self.data, fanout_start + oid[0] as usize * 4) as usize;
+        while start < end {
+            let mid = (start + end) / 2;
+            let mid_oid = &self.data[oids_start + mid * 20..oids_start + (mid + 1) * 20];
+            if mid_oid < &oid[..] {
+                start = mid + 1;
+            } else if mid_oid > &oid[..] {
+                end = mid;
+            } else {
+                let offset = read_u32(&self.data, offsets_start + mid * 4);
+                if offset & 0x8000_0000 == 0 {
+                    return Some(offset as u64);
+                } else {
+                    let ix = large_offsets_start + (offset & 0x7fff_ffff) as usize * 8;
+                    if ix + 8 > self.data.len() {
+                        return None;
+                    }
+                    return Some(
+                        (read_u32(&self.data, ix) as u64) << 32
+                            | read_u32(&self.data, ix + 4) as u64,
+                    );
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl TreeEntry {
+    /// Only the start of files is read to tell text from binary, while symlinks are read in full
+    /// to find their targets.
+    fn file_type(&self, repo: &Repository) -> io::Result<Option<(FileType, FileAttributes)>> {
+        let mut attributes = FileAttributes::default();
+        let file_type = match self.mode {
+            DIR_MODE => FileType::Directory,
+            SYMLINK_MODE => {
+                let (_, data) = repo.read_object(self.oid)?;
+                attributes.symlink_target =
+                    Some(PathBuf::from(String::from_utf8_lossy(&data).into_owned()));
+                FileType::Symlink
+            }
+            FILE_MODE | EXECUTABLE_MODE => {
+                attributes.executable = self.mode == EXECUTABLE_MODE;
+                if repo.is_binary_blob(self.oid)? {
+                    attributes.oid = Some(self.oid);
+                    FileType::Binary
+                } else {
+                    FileType::Text
+                }
+            }
+            // Submodules aren't supported yet.
+            _ => return Ok(None),
+        };
+        Ok(Some((file_type, attributes)))
+    }
+}
+
+impl ObjectKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "blob" => Some(ObjectKind::Blob),
+            "tree" => Some(ObjectKind::Tree),
+            "commit" => Some(ObjectKind::Commit),
+            "tag" => Some(ObjectKind::Tag),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ObjectKind::Blob => "blob",
+            ObjectKind::Tree => "tree",
+            ObjectKind::Commit => "commit",
+            ObjectKind::Tag => "tag",
+        }
+    }
+}
+
+impl TreeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert_dir(&mut self, path: &Path) {
+        self.dir_mut(path);
+    }
+
+    pub fn insert_text(&mut self, path: &Path, text: String, executable: bool) {
+        let mode = if executable {
+            EXECUTABLE_MODE
+        } else {
+            FILE_MODE
+        };
+        self.insert_blob(path, mode, BlobContents::Data(text.into_bytes()));
+    }
+
+    pub fn insert_symlink(&mut self, path: &Path, target: &Path) {
+        let target = target.to_string_lossy().into_owned();
+        self.insert_blob(path, SYMLINK_MODE, BlobContents::Data(target.into_bytes()));
+    }
+
+    /// Inserts a file whose contents are already stored in the repository.
+    pub fn insert_object(&mut self, path: &Path, oid: Oid, executable: bool) {
+        let mode = if executable {
+            EXECUTABLE_MODE
+        } else {
+            FILE_MODE
+        };
+        self.insert_blob(path, mode, BlobContents::Object(oid));
+    }
+
+    pub fn write(&self, repo: &Repository) -> io::Result<Oid> {
+        let mut entries = Vec::new();
+        for (name, entry) in &self.entries {
+            let name = os_str_bytes(name);
+            match entry {
+                TreeBuilderEntry::Blob { mode, contents } => {
+                    let oid = match contents {
+                        BlobContents::Data(data) => repo.write_object(ObjectKind::Blob, data)?,
+                        BlobContents::Object(oid) => *oid,
+                    };
+                    entries.push((name, *mode, oid));
+                }
+                TreeBuilderEntry::Tree(tree) => {
+                    let oid = tree.write(repo)?;
+                    entries.push((name, DIR_MODE, oid));
+                }
+            }
+        }
+
+        // Git sorts tree entries as if directory names had a trailing slash.
+        entries.sort_by(|(name_1, mode_1, _), (name_2, mode_2, _)| {
+            let key_1 = name_1.iter().cloned().chain(if *mode_1 == DIR_MODE {
+                Some(b'/')
+            } else {
+                None
+            });
+            let key_2 = name_2.iter().cloned().chain(if *mode_2 == DIR_MODE {
+                Some(b'/')
+            } else {
+                None
+            });
+            key_1.cmp(key_2)
+        });
+
+        let mut data = Vec::new();
+        for (name, mode, oid) in entries {
+            data.extend_from_slice(format!("{:o} ", mode).as_bytes());
+            data.extend_from_slice(&name);
+            data.push(0);
+            data.extend_from_slice(&oid);
+        }
+        repo.write_object(ObjectKind::Tree, &data)
+    }
+
+    fn insert_blob(&mut self, path: &Path, mode: u32, contents: BlobContents) {
+        if let Some(name) = path.file_name() {
+            let parent = self.dir_mut(path.parent().unwrap_or(Path::new("")));
+            parent.entries.insert(
+                name.to_os_string(),
+                TreeBuilderEntry::Blob { mode, contents },
+            );
+        }
+    }
+
+    fn dir_mut(&mut self, path: &Path) -> &mut TreeBuilder {
+        let mut dir = self;
+        for component in path.components() {
+            if let Component::Normal(name) = component {
+                let entry = dir
+                    .entries
+                    .entry(name.to_os_string())
+                    .or_insert_with(|| TreeBuilderEntry::Tree(TreeBuilder::new()));
+                if let TreeBuilderEntry::Blob { .. } = entry {
+                    *entry = TreeBuilderEntry::Tree(TreeBuilder::new());
+                }
+                dir = match entry {
+                    TreeBuilderEntry::Tree(tree) => tree,
+                    TreeBuilderEntry::Blob { .. } => unreachable!(),
+                };
+            }
+        }
+        dir
+    }
+}
+
+pub fn oid_to_hex(oid: Oid) -> String {
+    let mut hex = String::with_capacity(40);
+    for byte in oid.iter() {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
+
+pub fn hex_to_oid(hex: &str) -> Option<Oid> {
+    if hex.len() != 40 {
+        return None;
+    }
+
+    let mut oid = [0; 20];
+    for i in 0..20 {
+        oid[i] = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(oid)
+}
+
+/// Parses the object id on the first line of a commit or tag if it starts with `prefix`.
+fn first_header(data: &[u8], prefix: &str) -> Option<Oid> {
+    let data = String::from_utf8_lossy(data);
+    data.lines().next().and_then(|line| {
+        if line.starts_with(prefix) {
+            hex_to_oid(&line[prefix.len()..])
+        } else {
+            None
+        }
+    })
+}
+
+// Git stores names as raw bytes, which only map losslessly onto `OsStr` on unix.
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStrExt;
+    OsStr::from_bytes(bytes).to_os_string()
+}
+
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
+
+#[cfg(unix)]
+fn os_str_bytes(name: &OsStr) -> Cow<[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(name.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn os_str_bytes(name: &OsStr) -> Cow<[u8]> {
+    match name.to_string_lossy() {
+        Cow::Borrowed(name) => Cow::Borrowed(name.as_bytes()),
+        Cow::Owned(name) => Cow::Owned(name.into_bytes()),
+    }
+}
+
+fn apply_delta(base: &[u8], delta: &[u8]) -> io::Result<Vec<u8>> {
+    fn next_byte(delta: &[u8], ix: &mut usize) -> io::Result<u8> {
+        let byte = *delta
+            .get(*ix)
+            .ok_or_else(|| invalid_data("truncated delta"))?;
+        *ix += 1;
+        Ok(byte)
+    }
+
+    fn read_size(delta: &[u8], ix: &mut usize) -> io::Result<usize> {
+        let mut size = 0;
+        let mut shift = 0;
+        loop {
+            let byte = next_byte(delta, ix)?;
+            size |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(size);
+            }
+        }
+    }
+
+    let mut ix = 0;
+    if read_size(delta, &mut ix)? != base.len() {
+        return Err(invalid_data("delta base size mismatch"));
+    }
+    let result_len = read_size(delta, &mut ix)?;
+    let mut result = Vec::with_capacity(result_len);
+    while ix < delta.len() {
+        let instruction = next_byte(delta, &mut ix)?;
+        if instruction & 0x80 != 0 {
+            let mut offset = 0;
+            let mut size = 0;
+            for i in 0..4 {
+                if instruction & (1 << i) != 0 {
+                    offset |= (next_byte(delta, &mut ix)? as usize) << (i * 8);
+                }
+            }
+            for i in 0..3 {
+                if instruction & (0x10 << i) != 0 {
+                    size |= (next_byte(delta, &mut ix)? as usize) << (i * 8);
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+            result.extend_from_slice(
+                base.get(offset..offset + size)
+                    .ok_or_else(|| invalid_data("delta copy out of range"))?,
+            );
+        } else if instruction != 0 {
+            let len = instruction as usize;
+            result.extend_from_slice(
+                delta
+                    .get(ix..ix + len)
+                    .ok_or_else(|| invalid_data("truncated delta"))?,
+            );
+            ix += len;
+        } else {
+            return Err(invalid_data("invalid delta instruction"));
+        }
+    }
+
+    if result.len() == result_len {
+        Ok(result)
+    } else {
+        Err(invalid_data("delta result size mismatch"))
+    }
+}
+
+fn inflate<R: Read>(reader: R, size: u64) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(size as usize);
+    ZlibDecoder::new(reader).take(size).read_to_end(&mut data)?;
+    if data.len() as u64 == size {
+        Ok(data)
+    } else {
+        Err(invalid_data("truncated pack entry"))
+    }
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_u32(data: &[u8], ix: usize) -> u32 {
+    (data[ix] as u32) << 24
+        | (data[ix + 1] as u32) << 16
+        | (data[ix + 2] as u32) << 8
+        | data[ix + 3] as u32
+}
+
+/// Only looks at the first `BINARY_CHECK_LEN` bytes, so text files may still turn out to contain
+/// invalid UTF-8 further on.
+pub(crate) fn is_binary(data: &[u8]) -> bool {
+    let prefix = &data[..cmp::min(data.len(), BINARY_CHECK_LEN)];
+    prefix.contains(&0)
+        || match str::from_utf8(prefix) {
+            Ok(_) => false,
+            // Characters cut off at the end of the prefix are fine.
+            Err(error) => error.error_len().is_some(),
+        }
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::temp_dir;
+    use crate::work_tree::{Operation, WorkTree};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_write_object() {
+        let repo = Repository::new(temp_dir("test_write_object"));
+        let oid = repo.write_object(ObjectKind::Blob, b"abc").unwrap();
+        assert_eq!(oid_to_hex(oid), "f2ba8f84ab5c1bce84a7b441cb1959cfc7093b7f");
+
+        let mut object = Vec::new();
+        ZlibDecoder::new(fs::File::open(repo.loose_object_path(oid)).unwrap())
+            .read_to_end(&mut object)
+            .unwrap();
+        assert_eq!(object, b"blob 3\0abc");
+
+        // Writing the same object twice is a no-op.
+        assert_eq!(repo.write_object(ObjectKind::Blob, b"abc").unwrap(), oid);
+    }
+
+    #[test]
+    fn test_tree_builder() {
+        let repo = Repository::new(temp_dir("test_tree_builder"));
+        let mut tree = TreeBuilder::new();
+        tree.insert_text(Path::new("a/b"), "abc".into(), false);
+        tree.insert_text(Path::new("a.txt"), "abc".into(), false);
+        tree.insert_text(Path::new("c"), "".into(), false);
+        tree.insert_dir(Path::new("d"));
+        assert_eq!(
+            oid_to_hex(tree.write(&repo).unwrap()),
+            "59218bccaf562000914d9b264fbf3a606f3426a7"
+        );
+    }
+
+    #[test]
+    fn test_git_provider() {
+        let repo = Repository::new(temp_dir("test_git_provider"));
+        let mut tree = TreeBuilder::new();
+        tree.insert_text(Path::new("a/b"), "abc".into(), false);
+        tree.insert_text(Path::new("a/c"), "def".into(), false);
+        tree.insert_dir(Path::new("d/e"));
+        tree.insert_text(Path::new("f"), "ghi".into(), false);
+        let tree_oid = tree.write(&repo).unwrap();
+        let commit_oid = write_commit(&repo, tree_oid);
+
+        let entries = repo.base_entries(commit_oid).collect().wait().unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.depth, entry.name.to_str().unwrap(), entry.file_type))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "a", FileType::Directory),
+                (2, "b", FileType::Text),
+                (2, "c", FileType::Text),
+                (1, "d", FileType::Directory),
+                (2, "e", FileType::Directory),
+                (1, "f", FileType::Text),
+            ]
+        );
+        assert_eq!(
+            repo.base_text(commit_oid, Path::new("a/c")).wait().unwrap(),
+            "def"
+        );
+        assert_eq!(
+            repo.base_text(commit_oid, Path::new("a/x"))
+                .wait()
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+
+        // A work tree can be started from the commit and written back out unchanged.
+        let (work_tree, ops) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(commit_oid),
+            vec![],
+            Arc::new(repo.clone()),
+            None,
+        )
+        .unwrap();
+        ops.collect().wait().unwrap();
+        let buffer_id = work_tree.open_text_file("f").wait().unwrap();
+        assert_eq!(work_tree.text(buffer_id).unwrap().into_string(), "ghi");
+        assert_eq!(work_tree.write_tree(&repo).wait().unwrap(), tree_oid);
+    }
+
+    #[test]
+    fn test_annotated_tag() {
+        let repo = Repository::new(temp_dir("test_annotated_tag"));
+        let mut tree = TreeBuilder::new();
+        tree.insert_text(Path::new("a"), "abc".into(), false);
+        let commit_oid = write_commit(&repo, tree.write(&repo).unwrap());
+        let tag = format!(
+            "object {}\ntype commit\ntag v1\ntagger A <a@example.com> 0 +0000\n\nTag\n",
+            oid_to_hex(commit_oid)
+        );
+        let tag_oid = repo.write_object(ObjectKind::Tag, tag.as_bytes()).unwrap();
+
+        let entries = repo.base_entries(tag_oid).collect().wait().unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.name.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["a"]
+        );
+        assert_eq!(
+            repo.base_text(tag_oid, Path::new("a")).wait().unwrap(),
+            "abc"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let repo = Repository::new(temp_dir("test_non_utf8_names"));
+        let name = OsStr::from_bytes(b"a\xffb");
+        let mut tree = TreeBuilder::new();
+        tree.insert_text(&Path::new("d").join(name), "abc".into(), false);
+        let tree_oid = tree.write(&repo).unwrap();
+
+        let entries = repo.base_entries(tree_oid).collect().wait().unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.name.as_os_str())
+                .collect::<Vec<_>>(),
+            vec![OsStr::new("d"), name]
+        );
+        assert_eq!(
+            repo.base_text(tree_oid, &Path::new("d").join(name))
+                .wait()
+                .unwrap(),
+            "abc"
+        );
+    }
+
+    #[test]
+    fn test_file_attributes() {
+        let repo = Repository::new(temp_dir("test_file_attributes"));
+        let binary_oid = repo.write_object(ObjectKind::Blob, b"\0\x01\x02").unwrap();
+        let mut tree = TreeBuilder::new();
+        tree.insert_text(Path::new("a"), "#!/bin/sh\n".into(), true);
+        tree.insert_symlink(Path::new("b"), Path::new("a"));
+        tree.insert_object(Path::new("c"), binary_oid, false);
+        // Only the start of files is checked for null bytes, like git does.
+        let long_text = "x".repeat(BINARY_CHECK_LEN) + "\0";
+        tree.insert_text(Path::new("d"), long_text.clone(), false);
+        let tree_oid = tree.write(&repo).unwrap();
+        let commit_oid = write_commit(&repo, tree_oid);
+
+        let entries = repo.base_entries(commit_oid).collect().wait().unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (
+                    entry.name.to_str().unwrap(),
+                    entry.file_type,
+                    entry.attributes.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "a",
+                    FileType::Text,
+                    FileAttributes {
+                        executable: true,
+                        ..FileAttributes::default()
+                    }
+                ),
+                (
+                    "b",
+                    FileType::Symlink,
+                    FileAttributes {
+                        symlink_target: Some(PathBuf::from("a")),
+                        ..FileAttributes::default()
+                    }
+                ),
+                (
+                    "c",
+                    FileType::Binary,
+                    FileAttributes {
+                        oid: Some(binary_oid),
+                        ..FileAttributes::default()
+                    }
+                ),
+                ("d", FileType::Text, FileAttributes::default()),
+            ]
+        );
+        assert_eq!(
+            repo.blob(binary_oid).wait().unwrap(),
+            b"\0\x01\x02".to_vec()
+        );
+
+        let (work_tree, ops) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(commit_oid),
+            vec![],
+            Arc::new(repo.clone()),
+            None,
+        )
+        .unwrap();
+        let ops = ops.collect().wait().unwrap();
+        assert_eq!(work_tree.file_type("b").unwrap(), FileType::Symlink);
+        assert_eq!(work_tree.attributes("c").unwrap().oid, Some(binary_oid));
+        assert_eq!(work_tree.write_tree(&repo).wait().unwrap(), tree_oid);
+
+        // Attribute changes are replicated and reflected in written trees.
+        let op = work_tree
+            .update_attributes("a", FileAttributes::default())
+            .unwrap();
+        assert!(work_tree
+            .update_attributes("b", FileAttributes::default())
+            .is_err());
+        let (work_tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
+            Some(commit_oid),
+            ops.into_iter().map(|envelope| envelope.operation),
+            Arc::new(repo.clone()),
+            None,
+        )
+        .unwrap();
+        ops_2.collect().wait().unwrap();
+        let op = Operation::deserialize(&op.operation.serialize())
+            .unwrap()
+            .unwrap();
+        work_tree_2
+            .apply_ops(Some(op))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert!(!work_tree_2.attributes("a").unwrap().executable);
+
+        let mut tree = TreeBuilder::new();
+        tree.insert_text(Path::new("a"), "#!/bin/sh\n".into(), false);
+        tree.insert_symlink(Path::new("b"), Path::new("a"));
+        tree.insert_object(Path::new("c"), binary_oid, false);
+        tree.insert_text(Path::new("d"), long_text, false);
+        let tree_oid = tree.write(&repo).unwrap();
+        assert_eq!(work_tree_2.write_tree(&repo).wait().unwrap(), tree_oid);
+    }
+
+    #[test]
+    fn test_binary_blob_cache() {
+        let repo = Repository::new(temp_dir("test_binary_blob_cache"));
+        let binary_oid = repo.write_object(ObjectKind::Blob, b"\0\x01\x02").unwrap();
+        let mut tree = TreeBuilder::new();
+        tree.insert_object(Path::new("a"), binary_oid, false);
+        tree.insert_text(Path::new("b"), "abc".into(), false);
+        let tree_oid = tree.write(&repo).unwrap();
+        let file_types = |repo: &Repository| {
+            repo.base_entries(tree_oid)
+                .collect()
+                .wait()
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.file_type)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(file_types(&repo), vec![FileType::Binary, FileType::Text]);
+
+        // Blobs are only read the first time they are classified, including by clones.
+        fs::remove_file(repo.loose_object_path(binary_oid)).unwrap();
+        fs::remove_file(repo.loose_object_path(object_oid(ObjectKind::Blob, b"abc"))).unwrap();
+        assert_eq!(
+            file_types(&repo.clone()),
+            vec![FileType::Binary, FileType::Text]
+        );
+    }
+
+    #[test]
+    fn test_packed_objects() {
+        let git_dir = temp_dir("test_packed_objects");
+        let repo = Repository::new(&git_dir);
+
+        let base = b"hello world\n";
+        let target = b"hello there world\n";
+        let base_oid = object_oid(ObjectKind::Blob, base);
+        let target_oid = object_oid(ObjectKind::Blob, target);
+        let mut delta = vec![base.len() as u8, target.len() as u8];
+        delta.extend_from_slice(&[0x90, 6]);
+        delta.push(6);
+        delta.extend_from_slice(b"there ");
+        delta.extend_from_slice(&[0x91, 6, 6]);
+
+        let mut pack = b"PACK".to_vec();
+        pack.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 2]);
+        let base_offset = pack.len();
+        pack.push(0x30 | base.len() as u8);
+        pack.extend(deflate(base));
+        let target_offset = pack.len();
+        pack.push(0x60 | delta.len() as u8);
+        pack.push((target_offset - base_offset) as u8);
+        pack.extend(deflate(&delta));
+        pack.extend_from_slice(&[0; 20]);
+
+        let mut entries = vec![(base_oid, base_offset), (target_oid, target_offset)];
+        entries.sort();
+        let mut index = PACK_INDEX_MAGIC.to_vec();
+        index.extend_from_slice(&[0, 0, 0, 2]);
+        for i in 0..256 {
+            let count = entries
+                .iter()
+                .filter(|(oid, _)| oid[0] as usize <= i)
+                .count();
+            index.extend_from_slice(&(count as u32).to_be_bytes());
+        }
+        for (oid, _) in &entries {
+            index.extend_from_slice(oid);
+        }
+        for _ in &entries {
+            index.extend_from_slice(&[0; 4]);
+        }
+        for (_, offset) in &entries {
+            index.extend_from_slice(&(*offset as u32).to_be_bytes());
+        }
+        index.extend_from_slice(&[0; 40]);
+
+        let pack_dir = git_dir.join("objects").join("pack");
+        fs::create_dir_all(&pack_dir).unwrap();
+        fs::write(pack_dir.join("pack-test.pack"), pack).unwrap();
+        fs::write(pack_dir.join("pack-test.idx"), index).unwrap();
+
+        assert_eq!(
+            repo.read_object(base_oid).unwrap(),
+            (ObjectKind::Blob, base.to_vec())
+        );
+        assert_eq!(
+            repo.read_object(target_oid).unwrap(),
+            (ObjectKind::Blob, target.to_vec())
+        );
+        assert_eq!(
+            repo.read_object([0; 20]).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    fn write_commit(repo: &Repository, tree_oid: Oid) -> Oid {
+        let commit = format!(
+            "tree {}\nauthor A <a@example.com> 0 +0000\ncommitter A <a@example.com> 0 +0000\n\nCommit\n",
+            oid_to_hex(tree_oid)
+        );
+        repo.write_object(ObjectKind::Commit, commit.as_bytes())
+            .unwrap()
+    }
+
+    fn object_oid(kind: ObjectKind, data: &[u8]) -> Oid {
+        let mut object = format!("{} {}\0", kind.name(), data.len()).into_bytes();
+        object.extend_from_slice(data);
+        sha1::Sha1::from(&object).digest().bytes()
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+}
@@ -1,17 +1,29 @@
 mod btree;
 mod buffer;
+#[cfg(not(target_arch = "wasm32"))]
+mod dir_sync;
 mod epoch;
+mod git;
 #[allow(non_snake_case, unused_imports)]
 mod operation_queue;
 mod serialization;
 pub mod time;
 mod work_tree;
 
-pub use crate::buffer::{Buffer, Change, Point};
-pub use crate::epoch::{Cursor, DirEntry, Epoch, FileStatus, FileType, ROOT_FILE_ID};
+pub use crate::buffer::{
+    Anchor, AnchorBias, AuthorshipSpan, Buffer, BufferSnapshot, Change, LineDiffHunk, OffsetUnit,
+    Point, SearchDirection, SearchOptions, SearchQuery,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::dir_sync::DirSync;
+pub use crate::epoch::{
+    Conflict, ConflictEntry, Cursor, DirEntry, Epoch, FileAttributes, FileId, FileStatus, FileType,
+    Presence, StatusEntry, TreeChange, UserInfo, ROOT_FILE_ID,
+};
+pub use crate::git::{ObjectKind, Repository};
 pub use crate::work_tree::{
     BufferId, BufferSelectionRanges, ChangeObserver, GitProvider, LocalSelectionSetId, Operation,
-    OperationEnvelope, WorkTree,
+    OperationEnvelope, RebaseConflict, ReplicaPresence, Version, WorkTree,
 };
 use std::borrow::Cow;
 use std::fmt;
@@ -34,8 +46,11 @@ pub enum Error {
     InvalidSelectionSet(buffer::SelectionSetId),
     InvalidLocalSelectionSet(LocalSelectionSetId),
     InvalidAnchor(Cow<'static, str>),
+    InvalidSearchQuery(Cow<'static, str>),
     OffsetOutOfRange,
     CursorExhausted,
+    OperationsUnavailable,
+    VersionUnavailable,
 }
 
 trait ReplicaIdExt {
@@ -110,8 +125,11 @@ impl PartialEq for Error {
                 id_1 == id_2
             }
             (Error::InvalidAnchor(err_1), Error::InvalidAnchor(err_2)) => err_1 == err_2,
+            (Error::InvalidSearchQuery(err_1), Error::InvalidSearchQuery(err_2)) => err_1 == err_2,
             (Error::OffsetOutOfRange, Error::OffsetOutOfRange) => true,
             (Error::CursorExhausted, Error::CursorExhausted) => true,
+            (Error::OperationsUnavailable, Error::OperationsUnavailable) => true,
+            (Error::VersionUnavailable, Error::VersionUnavailable) => true,
             _ => false,
         }
     }
@@ -122,6 +140,8 @@ mod tests {
     use crate::ReplicaId;
     use rand::Rng;
     use std::collections::BTreeMap;
+    use std::path::PathBuf;
+    use std::{env, fs, process};
 
     #[derive(Clone)]
     struct Envelope<T: Clone> {
@@ -197,6 +217,20 @@ mod tests {
         }
 
         pub fn receive<R>(&mut self, receiver: ReplicaId, rng: &mut R) -> Vec<T>
+        where
+            R: Rng,
+        {
+            self.receive_with_senders(receiver, rng)
+                .into_iter()
+                .map(|(_, message)| message)
+                .collect()
+        }
+
+        pub fn receive_with_senders<R>(
+            &mut self,
+            receiver: ReplicaId,
+            rng: &mut R,
+        ) -> Vec<(ReplicaId, T)>
         where
             R: Rng,
         {
@@ -204,7 +238,7 @@ mod tests {
             let count = rng.gen_range(0, inbox.len() + 1);
             inbox
                 .drain(0..count)
-                .map(|envelope| envelope.message)
+                .map(|envelope| (envelope.sender, envelope.message))
                 .collect()
         }
 
@@ -212,4 +246,11 @@ mod tests {
             self.inboxes.get_mut(&receiver).unwrap().clear();
         }
     }
+
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("memo_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
 }
@@ -33,6 +33,10 @@ impl<T: Operation> OperationQueue<T> {
         self.0.summary().len
     }
 
+    pub fn items(&self) -> Vec<T> {
+        self.0.items()
+    }
+
     pub fn insert(&mut self, mut ops: Vec<T>) {
         ops.sort_by_key(|op| op.timestamp());
         ops.dedup_by_key(|op| op.timestamp());
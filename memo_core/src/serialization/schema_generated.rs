@@ -343,11 +343,12 @@ pub enum OperationVariant {
   NONE = 0,
   Edit = 1,
   UpdateSelections = 2,
+  Undo = 3,
 
 }
 
 const ENUM_MIN_OPERATION_VARIANT: u8 = 0;
-const ENUM_MAX_OPERATION_VARIANT: u8 = 2;
+const ENUM_MAX_OPERATION_VARIANT: u8 = 3;
 
 impl<'a> flatbuffers::Follow<'a> for OperationVariant {
   type Inner = Self;
@@ -381,17 +382,19 @@ impl flatbuffers::Push for OperationVariant {
 }
 
 #[allow(non_camel_case_types)]
-const ENUM_VALUES_OPERATION_VARIANT:[OperationVariant; 3] = [
+const ENUM_VALUES_OPERATION_VARIANT:[OperationVariant; 4] = [
   OperationVariant::NONE,
   OperationVariant::Edit,
-  OperationVariant::UpdateSelections
+  OperationVariant::UpdateSelections,
+  OperationVariant::Undo
 ];
 
 #[allow(non_camel_case_types)]
-const ENUM_NAMES_OPERATION_VARIANT:[&'static str; 3] = [
+const ENUM_NAMES_OPERATION_VARIANT:[&'static str; 4] = [
     "NONE",
     "Edit",
-    "UpdateSelections"
+    "UpdateSelections",
+    "Undo"
 ];
 
 pub fn enum_name_operation_variant(e: OperationVariant) -> &'static str {
@@ -872,6 +875,194 @@ impl<'a: 'b, 'b> UpdateSelectionsBuilder<'a, 'b> {
   }
 }
 
+pub enum UndoCountOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct UndoCount<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for UndoCount<'a> {
+    type Inner = UndoCount<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> UndoCount<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        UndoCount {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UndoCountArgs<'args>) -> flatbuffers::WIPOffset<UndoCount<'bldr>> {
+      let mut builder = UndoCountBuilder::new(_fbb);
+      builder.add_count(args.count);
+      if let Some(x) = args.edit_id { builder.add_edit_id(x); }
+      builder.finish()
+    }
+
+    pub const VT_EDIT_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_COUNT: flatbuffers::VOffsetT = 6;
+
+  #[inline]
+  pub fn edit_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UndoCount::VT_EDIT_ID, None)
+  }
+  #[inline]
+  pub fn count(&self) -> u32 {
+    self._tab.get::<u32>(UndoCount::VT_COUNT, Some(0)).unwrap()
+  }
+}
+
+pub struct UndoCountArgs<'a> {
+    pub edit_id: Option<&'a  super::Timestamp>,
+    pub count: u32,
+}
+impl<'a> Default for UndoCountArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UndoCountArgs {
+            edit_id: None,
+            count: 0,
+        }
+    }
+}
+pub struct UndoCountBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UndoCountBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_edit_id(&mut self, edit_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UndoCount::VT_EDIT_ID, edit_id);
+  }
+  #[inline]
+  pub fn add_count(&mut self, count: u32) {
+    self.fbb_.push_slot::<u32>(UndoCount::VT_COUNT, count, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UndoCountBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    UndoCountBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<UndoCount<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum UndoOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct Undo<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for Undo<'a> {
+    type Inner = Undo<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> Undo<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        Undo {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UndoArgs<'args>) -> flatbuffers::WIPOffset<Undo<'bldr>> {
+      let mut builder = UndoBuilder::new(_fbb);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
+      if let Some(x) = args.counts { builder.add_counts(x); }
+      builder.finish()
+    }
+
+    pub const VT_COUNTS: flatbuffers::VOffsetT = 4;
+    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 6;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 8;
+
+  #[inline]
+  pub fn counts(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<UndoCount<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<UndoCount<'a>>>>>(Undo::VT_COUNTS, None)
+  }
+  #[inline]
+  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Undo::VT_LOCAL_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Undo::VT_LAMPORT_TIMESTAMP, None)
+  }
+}
+
+pub struct UndoArgs<'a> {
+    pub counts: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<UndoCount<'a >>>>>,
+    pub local_timestamp: Option<&'a  super::Timestamp>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+}
+impl<'a> Default for UndoArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UndoArgs {
+            counts: None,
+            local_timestamp: None,
+            lamport_timestamp: None,
+        }
+    }
+}
+pub struct UndoBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UndoBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_counts(&mut self, counts: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<UndoCount<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Undo::VT_COUNTS, counts);
+  }
+  #[inline]
+  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Undo::VT_LOCAL_TIMESTAMP, local_timestamp);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Undo::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UndoBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    UndoBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Undo<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
 pub enum OperationOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
@@ -937,6 +1128,16 @@ impl<'a> Operation<'a> {
     }
   }
 
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn variant_as_undo(&'a self) -> Option<Undo> {
+    if self.variant_type() == OperationVariant::Undo {
+      self.variant().map(|u| Undo::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
 }
 
 pub struct OperationArgs {
@@ -980,221 +1181,287 @@ impl<'a: 'b, 'b> OperationBuilder<'a, 'b> {
   }
 }
 
-}  // pub mod buffer
-
-pub mod epoch {
-  #![allow(dead_code)]
-  #![allow(unused_imports)]
-
-  use std::mem;
-  use std::cmp::Ordering;
-
-  extern crate flatbuffers;
-  use self::flatbuffers::EndianScalar;
+pub enum InsertionOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
 
-#[allow(non_camel_case_types)]
-#[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum FileId {
-  NONE = 0,
-  BaseFileId = 1,
-  NewFileId = 2,
+pub struct Insertion<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
 
+impl<'a> flatbuffers::Follow<'a> for Insertion<'a> {
+    type Inner = Insertion<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
 }
 
-const ENUM_MIN_FILE_ID: u8 = 0;
-const ENUM_MAX_FILE_ID: u8 = 2;
+impl<'a> Insertion<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        Insertion {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args InsertionArgs<'args>) -> flatbuffers::WIPOffset<Insertion<'bldr>> {
+      let mut builder = InsertionBuilder::new(_fbb);
+      builder.add_offset_in_parent(args.offset_in_parent);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.text { builder.add_text(x); }
+      if let Some(x) = args.parent_id { builder.add_parent_id(x); }
+      if let Some(x) = args.id { builder.add_id(x); }
+      builder.finish()
+    }
+
+    pub const VT_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_PARENT_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_OFFSET_IN_PARENT: flatbuffers::VOffsetT = 8;
+    pub const VT_TEXT: flatbuffers::VOffsetT = 10;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 12;
 
-impl<'a> flatbuffers::Follow<'a> for FileId {
-  type Inner = Self;
   #[inline]
-  fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    flatbuffers::read_scalar_at::<Self>(buf, loc)
+  pub fn id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Insertion::VT_ID, None)
   }
-}
-
-impl flatbuffers::EndianScalar for FileId {
   #[inline]
-  fn to_little_endian(self) -> Self {
-    let n = u8::to_le(self as u8);
-    let p = &n as *const u8 as *const FileId;
-    unsafe { *p }
+  pub fn parent_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Insertion::VT_PARENT_ID, None)
   }
   #[inline]
-  fn from_little_endian(self) -> Self {
-    let n = u8::from_le(self as u8);
-    let p = &n as *const u8 as *const FileId;
-    unsafe { *p }
+  pub fn offset_in_parent(&self) -> u64 {
+    self._tab.get::<u64>(Insertion::VT_OFFSET_IN_PARENT, Some(0)).unwrap()
+  }
+  #[inline]
+  pub fn text(&self) -> Option<flatbuffers::Vector<'a, u16>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u16>>>(Insertion::VT_TEXT, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Insertion::VT_LAMPORT_TIMESTAMP, None)
   }
 }
 
-impl flatbuffers::Push for FileId {
-    type Output = FileId;
+pub struct InsertionArgs<'a> {
+    pub id: Option<&'a  super::Timestamp>,
+    pub parent_id: Option<&'a  super::Timestamp>,
+    pub offset_in_parent: u64,
+    pub text: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u16>>>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+}
+impl<'a> Default for InsertionArgs<'a> {
     #[inline]
-    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
-        flatbuffers::emplace_scalar::<FileId>(dst, *self);
+    fn default() -> Self {
+        InsertionArgs {
+            id: None,
+            parent_id: None,
+            offset_in_parent: 0,
+            text: None,
+            lamport_timestamp: None,
+        }
     }
 }
-
-#[allow(non_camel_case_types)]
-const ENUM_VALUES_FILE_ID:[FileId; 3] = [
-  FileId::NONE,
-  FileId::BaseFileId,
-  FileId::NewFileId
-];
-
-#[allow(non_camel_case_types)]
-const ENUM_NAMES_FILE_ID:[&'static str; 3] = [
-    "NONE",
-    "BaseFileId",
-    "NewFileId"
-];
-
-pub fn enum_name_file_id(e: FileId) -> &'static str {
-  let index: usize = e as usize;
-  ENUM_NAMES_FILE_ID[index]
-}
-
-pub struct FileIdUnionTableOffset {}
-#[allow(non_camel_case_types)]
-#[repr(i8)]
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum FileType {
-  Directory = 0,
-  Text = 1,
-
+pub struct InsertionBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-
-const ENUM_MIN_FILE_TYPE: i8 = 0;
-const ENUM_MAX_FILE_TYPE: i8 = 1;
-
-impl<'a> flatbuffers::Follow<'a> for FileType {
-  type Inner = Self;
+impl<'a: 'b, 'b> InsertionBuilder<'a, 'b> {
   #[inline]
-  fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    flatbuffers::read_scalar_at::<Self>(buf, loc)
+  pub fn add_id(&mut self, id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Insertion::VT_ID, id);
   }
-}
-
-impl flatbuffers::EndianScalar for FileType {
   #[inline]
-  fn to_little_endian(self) -> Self {
-    let n = i8::to_le(self as i8);
-    let p = &n as *const i8 as *const FileType;
-    unsafe { *p }
+  pub fn add_parent_id(&mut self, parent_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Insertion::VT_PARENT_ID, parent_id);
   }
   #[inline]
-  fn from_little_endian(self) -> Self {
-    let n = i8::from_le(self as i8);
-    let p = &n as *const i8 as *const FileType;
-    unsafe { *p }
+  pub fn add_offset_in_parent(&mut self, offset_in_parent: u64) {
+    self.fbb_.push_slot::<u64>(Insertion::VT_OFFSET_IN_PARENT, offset_in_parent, 0);
   }
-}
-
-impl flatbuffers::Push for FileType {
-    type Output = FileType;
-    #[inline]
-    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
-        flatbuffers::emplace_scalar::<FileType>(dst, *self);
+  #[inline]
+  pub fn add_text(&mut self, text: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u16>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Insertion::VT_TEXT, text);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Insertion::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> InsertionBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    InsertionBuilder {
+      fbb_: _fbb,
+      start_: start,
     }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Insertion<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
 }
 
-#[allow(non_camel_case_types)]
-const ENUM_VALUES_FILE_TYPE:[FileType; 2] = [
-  FileType::Directory,
-  FileType::Text
-];
-
-#[allow(non_camel_case_types)]
-const ENUM_NAMES_FILE_TYPE:[&'static str; 2] = [
-    "Directory",
-    "Text"
-];
+pub enum FragmentOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
 
-pub fn enum_name_file_type(e: FileType) -> &'static str {
-  let index: usize = e as usize;
-  ENUM_NAMES_FILE_TYPE[index]
+pub struct Fragment<'a> {
+  pub _tab: flatbuffers::Table<'a>,
 }
 
-#[allow(non_camel_case_types)]
-#[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum Operation {
-  NONE = 0,
-  InsertMetadata = 1,
-  UpdateParent = 2,
-  BufferOperation = 3,
-  UpdateActiveLocation = 4,
-
+impl<'a> flatbuffers::Follow<'a> for Fragment<'a> {
+    type Inner = Fragment<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
 }
 
-const ENUM_MIN_OPERATION: u8 = 0;
-const ENUM_MAX_OPERATION: u8 = 4;
+impl<'a> Fragment<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        Fragment {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args FragmentArgs<'args>) -> flatbuffers::WIPOffset<Fragment<'bldr>> {
+      let mut builder = FragmentBuilder::new(_fbb);
+      builder.add_end_offset(args.end_offset);
+      builder.add_start_offset(args.start_offset);
+      if let Some(x) = args.max_undos { builder.add_max_undos(x); }
+      if let Some(x) = args.deletions { builder.add_deletions(x); }
+      if let Some(x) = args.insertion_id { builder.add_insertion_id(x); }
+      if let Some(x) = args.id { builder.add_id(x); }
+      builder.add_visible(args.visible);
+      builder.finish()
+    }
+
+    pub const VT_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_INSERTION_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_START_OFFSET: flatbuffers::VOffsetT = 8;
+    pub const VT_END_OFFSET: flatbuffers::VOffsetT = 10;
+    pub const VT_DELETIONS: flatbuffers::VOffsetT = 12;
+    pub const VT_MAX_UNDOS: flatbuffers::VOffsetT = 14;
+    pub const VT_VISIBLE: flatbuffers::VOffsetT = 16;
 
-impl<'a> flatbuffers::Follow<'a> for Operation {
-  type Inner = Self;
   #[inline]
-  fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
-    flatbuffers::read_scalar_at::<Self>(buf, loc)
+  pub fn id(&self) -> Option<flatbuffers::Vector<'a, u16>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u16>>>(Fragment::VT_ID, None)
   }
-}
-
-impl flatbuffers::EndianScalar for Operation {
   #[inline]
-  fn to_little_endian(self) -> Self {
-    let n = u8::to_le(self as u8);
-    let p = &n as *const u8 as *const Operation;
-    unsafe { *p }
+  pub fn insertion_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Fragment::VT_INSERTION_ID, None)
   }
   #[inline]
-  fn from_little_endian(self) -> Self {
-    let n = u8::from_le(self as u8);
-    let p = &n as *const u8 as *const Operation;
-    unsafe { *p }
+  pub fn start_offset(&self) -> u64 {
+    self._tab.get::<u64>(Fragment::VT_START_OFFSET, Some(0)).unwrap()
+  }
+  #[inline]
+  pub fn end_offset(&self) -> u64 {
+    self._tab.get::<u64>(Fragment::VT_END_OFFSET, Some(0)).unwrap()
+  }
+  #[inline]
+  pub fn deletions(&self) -> Option<&'a [super::Timestamp]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<super::Timestamp>>>(Fragment::VT_DELETIONS, None).map(|v| v.safe_slice() )
+  }
+  #[inline]
+  pub fn max_undos(&self) -> Option<super::GlobalTimestamp<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<super::GlobalTimestamp<'a>>>(Fragment::VT_MAX_UNDOS, None)
+  }
+  #[inline]
+  pub fn visible(&self) -> bool {
+    self._tab.get::<bool>(Fragment::VT_VISIBLE, Some(false)).unwrap()
   }
 }
 
-impl flatbuffers::Push for Operation {
-    type Output = Operation;
+pub struct FragmentArgs<'a> {
+    pub id: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u16>>>,
+    pub insertion_id: Option<&'a  super::Timestamp>,
+    pub start_offset: u64,
+    pub end_offset: u64,
+    pub deletions: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , super::Timestamp>>>,
+    pub max_undos: Option<flatbuffers::WIPOffset<super::GlobalTimestamp<'a >>>,
+    pub visible: bool,
+}
+impl<'a> Default for FragmentArgs<'a> {
     #[inline]
-    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
-        flatbuffers::emplace_scalar::<Operation>(dst, *self);
+    fn default() -> Self {
+        FragmentArgs {
+            id: None,
+            insertion_id: None,
+            start_offset: 0,
+            end_offset: 0,
+            deletions: None,
+            max_undos: None,
+            visible: false,
+        }
     }
 }
-
-#[allow(non_camel_case_types)]
-const ENUM_VALUES_OPERATION:[Operation; 5] = [
-  Operation::NONE,
-  Operation::InsertMetadata,
-  Operation::UpdateParent,
-  Operation::BufferOperation,
-  Operation::UpdateActiveLocation
-];
-
-#[allow(non_camel_case_types)]
-const ENUM_NAMES_OPERATION:[&'static str; 5] = [
-    "NONE",
-    "InsertMetadata",
-    "UpdateParent",
-    "BufferOperation",
-    "UpdateActiveLocation"
-];
-
-pub fn enum_name_operation(e: Operation) -> &'static str {
-  let index: usize = e as usize;
-  ENUM_NAMES_OPERATION[index]
+pub struct FragmentBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> FragmentBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_id(&mut self, id: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u16>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Fragment::VT_ID, id);
+  }
+  #[inline]
+  pub fn add_insertion_id(&mut self, insertion_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Fragment::VT_INSERTION_ID, insertion_id);
+  }
+  #[inline]
+  pub fn add_start_offset(&mut self, start_offset: u64) {
+    self.fbb_.push_slot::<u64>(Fragment::VT_START_OFFSET, start_offset, 0);
+  }
+  #[inline]
+  pub fn add_end_offset(&mut self, end_offset: u64) {
+    self.fbb_.push_slot::<u64>(Fragment::VT_END_OFFSET, end_offset, 0);
+  }
+  #[inline]
+  pub fn add_deletions(&mut self, deletions: flatbuffers::WIPOffset<flatbuffers::Vector<'b , super::Timestamp>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Fragment::VT_DELETIONS, deletions);
+  }
+  #[inline]
+  pub fn add_max_undos(&mut self, max_undos: flatbuffers::WIPOffset<super::GlobalTimestamp<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<super::GlobalTimestamp>>(Fragment::VT_MAX_UNDOS, max_undos);
+  }
+  #[inline]
+  pub fn add_visible(&mut self, visible: bool) {
+    self.fbb_.push_slot::<bool>(Fragment::VT_VISIBLE, visible, false);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> FragmentBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    FragmentBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Fragment<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
 }
 
-pub struct OperationUnionTableOffset {}
-pub enum BaseFileIdOffset {}
+pub enum InsertionSplitOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
-pub struct BaseFileId<'a> {
+pub struct InsertionSplit<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for BaseFileId<'a> {
-    type Inner = BaseFileId<'a>;
+impl<'a> flatbuffers::Follow<'a> for InsertionSplit<'a> {
+    type Inner = InsertionSplit<'a>;
     #[inline]
     fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
         Self {
@@ -1203,74 +1470,86 @@ impl<'a> flatbuffers::Follow<'a> for BaseFileId<'a> {
     }
 }
 
-impl<'a> BaseFileId<'a> {
+impl<'a> InsertionSplit<'a> {
     #[inline]
     pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-        BaseFileId {
+        InsertionSplit {
             _tab: table,
         }
     }
     #[allow(unused_mut)]
     pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
         _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
-        args: &'args BaseFileIdArgs) -> flatbuffers::WIPOffset<BaseFileId<'bldr>> {
-      let mut builder = BaseFileIdBuilder::new(_fbb);
-      builder.add_index(args.index);
+        args: &'args InsertionSplitArgs<'args>) -> flatbuffers::WIPOffset<InsertionSplit<'bldr>> {
+      let mut builder = InsertionSplitBuilder::new(_fbb);
+      builder.add_extent(args.extent);
+      if let Some(x) = args.fragment_id { builder.add_fragment_id(x); }
       builder.finish()
     }
 
-    pub const VT_INDEX: flatbuffers::VOffsetT = 4;
+    pub const VT_EXTENT: flatbuffers::VOffsetT = 4;
+    pub const VT_FRAGMENT_ID: flatbuffers::VOffsetT = 6;
 
   #[inline]
-  pub fn index(&self) -> u64 {
-    self._tab.get::<u64>(BaseFileId::VT_INDEX, Some(0)).unwrap()
+  pub fn extent(&self) -> u64 {
+    self._tab.get::<u64>(InsertionSplit::VT_EXTENT, Some(0)).unwrap()
+  }
+  #[inline]
+  pub fn fragment_id(&self) -> Option<flatbuffers::Vector<'a, u16>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u16>>>(InsertionSplit::VT_FRAGMENT_ID, None)
   }
 }
 
-pub struct BaseFileIdArgs {
-    pub index: u64,
+pub struct InsertionSplitArgs<'a> {
+    pub extent: u64,
+    pub fragment_id: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u16>>>,
 }
-impl<'a> Default for BaseFileIdArgs {
+impl<'a> Default for InsertionSplitArgs<'a> {
     #[inline]
     fn default() -> Self {
-        BaseFileIdArgs {
-            index: 0,
+        InsertionSplitArgs {
+            extent: 0,
+            fragment_id: None,
         }
     }
 }
-pub struct BaseFileIdBuilder<'a: 'b, 'b> {
+pub struct InsertionSplitBuilder<'a: 'b, 'b> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b> BaseFileIdBuilder<'a, 'b> {
+impl<'a: 'b, 'b> InsertionSplitBuilder<'a, 'b> {
   #[inline]
-  pub fn add_index(&mut self, index: u64) {
-    self.fbb_.push_slot::<u64>(BaseFileId::VT_INDEX, index, 0);
+  pub fn add_extent(&mut self, extent: u64) {
+    self.fbb_.push_slot::<u64>(InsertionSplit::VT_EXTENT, extent, 0);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> BaseFileIdBuilder<'a, 'b> {
+  pub fn add_fragment_id(&mut self, fragment_id: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u16>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertionSplit::VT_FRAGMENT_ID, fragment_id);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> InsertionSplitBuilder<'a, 'b> {
     let start = _fbb.start_table();
-    BaseFileIdBuilder {
+    InsertionSplitBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<BaseFileId<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<InsertionSplit<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-pub enum NewFileIdOffset {}
+pub enum InsertionSplitsOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
-pub struct NewFileId<'a> {
+pub struct InsertionSplits<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for NewFileId<'a> {
-    type Inner = NewFileId<'a>;
+impl<'a> flatbuffers::Follow<'a> for InsertionSplits<'a> {
+    type Inner = InsertionSplits<'a>;
     #[inline]
     fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
         Self {
@@ -1279,74 +1558,2516 @@ impl<'a> flatbuffers::Follow<'a> for NewFileId<'a> {
     }
 }
 
-impl<'a> NewFileId<'a> {
+impl<'a> InsertionSplits<'a> {
     #[inline]
     pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-        NewFileId {
+        InsertionSplits {
             _tab: table,
         }
     }
     #[allow(unused_mut)]
     pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
         _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
-        args: &'args NewFileIdArgs<'args>) -> flatbuffers::WIPOffset<NewFileId<'bldr>> {
-      let mut builder = NewFileIdBuilder::new(_fbb);
-      if let Some(x) = args.id { builder.add_id(x); }
+        args: &'args InsertionSplitsArgs<'args>) -> flatbuffers::WIPOffset<InsertionSplits<'bldr>> {
+      let mut builder = InsertionSplitsBuilder::new(_fbb);
+      if let Some(x) = args.splits { builder.add_splits(x); }
+      if let Some(x) = args.insertion_id { builder.add_insertion_id(x); }
       builder.finish()
     }
 
-    pub const VT_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_INSERTION_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_SPLITS: flatbuffers::VOffsetT = 6;
 
   #[inline]
-  pub fn id(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(NewFileId::VT_ID, None)
+  pub fn insertion_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(InsertionSplits::VT_INSERTION_ID, None)
+  }
+  #[inline]
+  pub fn splits(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<InsertionSplit<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<InsertionSplit<'a>>>>>(InsertionSplits::VT_SPLITS, None)
   }
 }
 
-pub struct NewFileIdArgs<'a> {
-    pub id: Option<&'a  super::Timestamp>,
+pub struct InsertionSplitsArgs<'a> {
+    pub insertion_id: Option<&'a  super::Timestamp>,
+    pub splits: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<InsertionSplit<'a >>>>>,
 }
-impl<'a> Default for NewFileIdArgs<'a> {
+impl<'a> Default for InsertionSplitsArgs<'a> {
     #[inline]
     fn default() -> Self {
-        NewFileIdArgs {
-            id: None,
+        InsertionSplitsArgs {
+            insertion_id: None,
+            splits: None,
         }
     }
 }
-pub struct NewFileIdBuilder<'a: 'b, 'b> {
+pub struct InsertionSplitsBuilder<'a: 'b, 'b> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b> NewFileIdBuilder<'a, 'b> {
+impl<'a: 'b, 'b> InsertionSplitsBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_insertion_id(&mut self, insertion_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(InsertionSplits::VT_INSERTION_ID, insertion_id);
+  }
+  #[inline]
+  pub fn add_splits(&mut self, splits: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<InsertionSplit<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertionSplits::VT_SPLITS, splits);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> InsertionSplitsBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    InsertionSplitsBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<InsertionSplits<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum UndoMapEntryOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct UndoMapEntry<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for UndoMapEntry<'a> {
+    type Inner = UndoMapEntry<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> UndoMapEntry<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        UndoMapEntry {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UndoMapEntryArgs<'args>) -> flatbuffers::WIPOffset<UndoMapEntry<'bldr>> {
+      let mut builder = UndoMapEntryBuilder::new(_fbb);
+      builder.add_count(args.count);
+      if let Some(x) = args.undo_id { builder.add_undo_id(x); }
+      if let Some(x) = args.edit_id { builder.add_edit_id(x); }
+      builder.finish()
+    }
+
+    pub const VT_EDIT_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_UNDO_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_COUNT: flatbuffers::VOffsetT = 8;
+
+  #[inline]
+  pub fn edit_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UndoMapEntry::VT_EDIT_ID, None)
+  }
+  #[inline]
+  pub fn undo_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UndoMapEntry::VT_UNDO_ID, None)
+  }
+  #[inline]
+  pub fn count(&self) -> u32 {
+    self._tab.get::<u32>(UndoMapEntry::VT_COUNT, Some(0)).unwrap()
+  }
+}
+
+pub struct UndoMapEntryArgs<'a> {
+    pub edit_id: Option<&'a  super::Timestamp>,
+    pub undo_id: Option<&'a  super::Timestamp>,
+    pub count: u32,
+}
+impl<'a> Default for UndoMapEntryArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UndoMapEntryArgs {
+            edit_id: None,
+            undo_id: None,
+            count: 0,
+        }
+    }
+}
+pub struct UndoMapEntryBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UndoMapEntryBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_edit_id(&mut self, edit_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UndoMapEntry::VT_EDIT_ID, edit_id);
+  }
+  #[inline]
+  pub fn add_undo_id(&mut self, undo_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UndoMapEntry::VT_UNDO_ID, undo_id);
+  }
+  #[inline]
+  pub fn add_count(&mut self, count: u32) {
+    self.fbb_.push_slot::<u32>(UndoMapEntry::VT_COUNT, count, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UndoMapEntryBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    UndoMapEntryBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<UndoMapEntry<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum EditRangeOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct EditRange<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for EditRange<'a> {
+    type Inner = EditRange<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> EditRange<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        EditRange {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args EditRangeArgs<'args>) -> flatbuffers::WIPOffset<EditRange<'bldr>> {
+      let mut builder = EditRangeBuilder::new(_fbb);
+      builder.add_end_offset(args.end_offset);
+      builder.add_start_offset(args.start_offset);
+      if let Some(x) = args.end_id { builder.add_end_id(x); }
+      if let Some(x) = args.start_id { builder.add_start_id(x); }
+      if let Some(x) = args.edit_id { builder.add_edit_id(x); }
+      builder.finish()
+    }
+
+    pub const VT_EDIT_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_START_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_START_OFFSET: flatbuffers::VOffsetT = 8;
+    pub const VT_END_ID: flatbuffers::VOffsetT = 10;
+    pub const VT_END_OFFSET: flatbuffers::VOffsetT = 12;
+
+  #[inline]
+  pub fn edit_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(EditRange::VT_EDIT_ID, None)
+  }
+  #[inline]
+  pub fn start_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(EditRange::VT_START_ID, None)
+  }
+  #[inline]
+  pub fn start_offset(&self) -> u64 {
+    self._tab.get::<u64>(EditRange::VT_START_OFFSET, Some(0)).unwrap()
+  }
+  #[inline]
+  pub fn end_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(EditRange::VT_END_ID, None)
+  }
+  #[inline]
+  pub fn end_offset(&self) -> u64 {
+    self._tab.get::<u64>(EditRange::VT_END_OFFSET, Some(0)).unwrap()
+  }
+}
+
+pub struct EditRangeArgs<'a> {
+    pub edit_id: Option<&'a  super::Timestamp>,
+    pub start_id: Option<&'a  super::Timestamp>,
+    pub start_offset: u64,
+    pub end_id: Option<&'a  super::Timestamp>,
+    pub end_offset: u64,
+}
+impl<'a> Default for EditRangeArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        EditRangeArgs {
+            edit_id: None,
+            start_id: None,
+            start_offset: 0,
+            end_id: None,
+            end_offset: 0,
+        }
+    }
+}
+pub struct EditRangeBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> EditRangeBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_edit_id(&mut self, edit_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(EditRange::VT_EDIT_ID, edit_id);
+  }
+  #[inline]
+  pub fn add_start_id(&mut self, start_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(EditRange::VT_START_ID, start_id);
+  }
+  #[inline]
+  pub fn add_start_offset(&mut self, start_offset: u64) {
+    self.fbb_.push_slot::<u64>(EditRange::VT_START_OFFSET, start_offset, 0);
+  }
+  #[inline]
+  pub fn add_end_id(&mut self, end_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(EditRange::VT_END_ID, end_id);
+  }
+  #[inline]
+  pub fn add_end_offset(&mut self, end_offset: u64) {
+    self.fbb_.push_slot::<u64>(EditRange::VT_END_OFFSET, end_offset, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> EditRangeBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    EditRangeBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<EditRange<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum SelectionSetOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct SelectionSet<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for SelectionSet<'a> {
+    type Inner = SelectionSet<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> SelectionSet<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        SelectionSet {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args SelectionSetArgs<'args>) -> flatbuffers::WIPOffset<SelectionSet<'bldr>> {
+      let mut builder = SelectionSetBuilder::new(_fbb);
+      if let Some(x) = args.selections { builder.add_selections(x); }
+      if let Some(x) = args.set_id { builder.add_set_id(x); }
+      builder.finish()
+    }
+
+    pub const VT_SET_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_SELECTIONS: flatbuffers::VOffsetT = 6;
+
+  #[inline]
+  pub fn set_id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(SelectionSet::VT_SET_ID, None)
+  }
+  #[inline]
+  pub fn selections(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Selection<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Selection<'a>>>>>(SelectionSet::VT_SELECTIONS, None)
+  }
+}
+
+pub struct SelectionSetArgs<'a> {
+    pub set_id: Option<&'a  super::Timestamp>,
+    pub selections: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<Selection<'a >>>>>,
+}
+impl<'a> Default for SelectionSetArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        SelectionSetArgs {
+            set_id: None,
+            selections: None,
+        }
+    }
+}
+pub struct SelectionSetBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> SelectionSetBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_set_id(&mut self, set_id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(SelectionSet::VT_SET_ID, set_id);
+  }
+  #[inline]
+  pub fn add_selections(&mut self, selections: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<Selection<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(SelectionSet::VT_SELECTIONS, selections);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> SelectionSetBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    SelectionSetBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<SelectionSet<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum SnapshotOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct Snapshot<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for Snapshot<'a> {
+    type Inner = Snapshot<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> Snapshot<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        Snapshot {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args SnapshotArgs<'args>) -> flatbuffers::WIPOffset<Snapshot<'bldr>> {
+      let mut builder = SnapshotBuilder::new(_fbb);
+      if let Some(x) = args.edits { builder.add_edits(x); }
+      if let Some(x) = args.deferred_operations { builder.add_deferred_operations(x); }
+      if let Some(x) = args.selection_sets { builder.add_selection_sets(x); }
+      if let Some(x) = args.undo_map { builder.add_undo_map(x); }
+      if let Some(x) = args.version { builder.add_version(x); }
+      if let Some(x) = args.insertion_splits { builder.add_insertion_splits(x); }
+      if let Some(x) = args.fragments { builder.add_fragments(x); }
+      if let Some(x) = args.insertions { builder.add_insertions(x); }
+      builder.finish()
+    }
+
+    pub const VT_INSERTIONS: flatbuffers::VOffsetT = 4;
+    pub const VT_FRAGMENTS: flatbuffers::VOffsetT = 6;
+    pub const VT_INSERTION_SPLITS: flatbuffers::VOffsetT = 8;
+    pub const VT_VERSION: flatbuffers::VOffsetT = 10;
+    pub const VT_UNDO_MAP: flatbuffers::VOffsetT = 12;
+    pub const VT_SELECTION_SETS: flatbuffers::VOffsetT = 14;
+    pub const VT_DEFERRED_OPERATIONS: flatbuffers::VOffsetT = 16;
+    pub const VT_EDITS: flatbuffers::VOffsetT = 18;
+
+  #[inline]
+  pub fn insertions(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Insertion<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Insertion<'a>>>>>(Snapshot::VT_INSERTIONS, None)
+  }
+  #[inline]
+  pub fn fragments(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Fragment<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Fragment<'a>>>>>(Snapshot::VT_FRAGMENTS, None)
+  }
+  #[inline]
+  pub fn insertion_splits(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<InsertionSplits<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<InsertionSplits<'a>>>>>(Snapshot::VT_INSERTION_SPLITS, None)
+  }
+  #[inline]
+  pub fn version(&self) -> Option<super::GlobalTimestamp<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<super::GlobalTimestamp<'a>>>(Snapshot::VT_VERSION, None)
+  }
+  #[inline]
+  pub fn undo_map(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<UndoMapEntry<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<UndoMapEntry<'a>>>>>(Snapshot::VT_UNDO_MAP, None)
+  }
+  #[inline]
+  pub fn selection_sets(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<SelectionSet<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<SelectionSet<'a>>>>>(Snapshot::VT_SELECTION_SETS, None)
+  }
+  #[inline]
+  pub fn deferred_operations(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Operation<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Operation<'a>>>>>(Snapshot::VT_DEFERRED_OPERATIONS, None)
+  }
+  #[inline]
+  pub fn edits(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<EditRange<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<EditRange<'a>>>>>(Snapshot::VT_EDITS, None)
+  }
+}
+
+pub struct SnapshotArgs<'a> {
+    pub insertions: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<Insertion<'a >>>>>,
+    pub fragments: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<Fragment<'a >>>>>,
+    pub insertion_splits: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<InsertionSplits<'a >>>>>,
+    pub version: Option<flatbuffers::WIPOffset<super::GlobalTimestamp<'a >>>,
+    pub undo_map: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<UndoMapEntry<'a >>>>>,
+    pub selection_sets: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<SelectionSet<'a >>>>>,
+    pub deferred_operations: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<Operation<'a >>>>>,
+    pub edits: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<EditRange<'a >>>>>,
+}
+impl<'a> Default for SnapshotArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        SnapshotArgs {
+            insertions: None,
+            fragments: None,
+            insertion_splits: None,
+            version: None,
+            undo_map: None,
+            selection_sets: None,
+            deferred_operations: None,
+            edits: None,
+        }
+    }
+}
+pub struct SnapshotBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> SnapshotBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_insertions(&mut self, insertions: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<Insertion<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_INSERTIONS, insertions);
+  }
+  #[inline]
+  pub fn add_fragments(&mut self, fragments: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<Fragment<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_FRAGMENTS, fragments);
+  }
+  #[inline]
+  pub fn add_insertion_splits(&mut self, insertion_splits: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<InsertionSplits<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_INSERTION_SPLITS, insertion_splits);
+  }
+  #[inline]
+  pub fn add_version(&mut self, version: flatbuffers::WIPOffset<super::GlobalTimestamp<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<super::GlobalTimestamp>>(Snapshot::VT_VERSION, version);
+  }
+  #[inline]
+  pub fn add_undo_map(&mut self, undo_map: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<UndoMapEntry<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_UNDO_MAP, undo_map);
+  }
+  #[inline]
+  pub fn add_selection_sets(&mut self, selection_sets: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<SelectionSet<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_SELECTION_SETS, selection_sets);
+  }
+  #[inline]
+  pub fn add_deferred_operations(&mut self, deferred_operations: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<Operation<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_DEFERRED_OPERATIONS, deferred_operations);
+  }
+  #[inline]
+  pub fn add_edits(&mut self, edits: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<EditRange<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_EDITS, edits);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> SnapshotBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    SnapshotBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Snapshot<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+}  // pub mod buffer
+
+pub mod epoch {
+  #![allow(dead_code)]
+  #![allow(unused_imports)]
+
+  use std::mem;
+  use std::cmp::Ordering;
+
+  extern crate flatbuffers;
+  use self::flatbuffers::EndianScalar;
+
+#[allow(non_camel_case_types)]
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FileId {
+  NONE = 0,
+  BaseFileId = 1,
+  NewFileId = 2,
+
+}
+
+const ENUM_MIN_FILE_ID: u8 = 0;
+const ENUM_MAX_FILE_ID: u8 = 2;
+
+impl<'a> flatbuffers::Follow<'a> for FileId {
+  type Inner = Self;
+  #[inline]
+  fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    flatbuffers::read_scalar_at::<Self>(buf, loc)
+  }
+}
+
+impl flatbuffers::EndianScalar for FileId {
+  #[inline]
+  fn to_little_endian(self) -> Self {
+    let n = u8::to_le(self as u8);
+    let p = &n as *const u8 as *const FileId;
+    unsafe { *p }
+  }
+  #[inline]
+  fn from_little_endian(self) -> Self {
+    let n = u8::from_le(self as u8);
+    let p = &n as *const u8 as *const FileId;
+    unsafe { *p }
+  }
+}
+
+impl flatbuffers::Push for FileId {
+    type Output = FileId;
+    #[inline]
+    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
+        flatbuffers::emplace_scalar::<FileId>(dst, *self);
+    }
+}
+
+#[allow(non_camel_case_types)]
+const ENUM_VALUES_FILE_ID:[FileId; 3] = [
+  FileId::NONE,
+  FileId::BaseFileId,
+  FileId::NewFileId
+];
+
+#[allow(non_camel_case_types)]
+const ENUM_NAMES_FILE_ID:[&'static str; 3] = [
+    "NONE",
+    "BaseFileId",
+    "NewFileId"
+];
+
+pub fn enum_name_file_id(e: FileId) -> &'static str {
+  let index: usize = e as usize;
+  ENUM_NAMES_FILE_ID[index]
+}
+
+pub struct FileIdUnionTableOffset {}
+#[allow(non_camel_case_types)]
+#[repr(i8)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FileType {
+  Directory = 0,
+  Text = 1,
+  Symlink = 2,
+  Binary = 3,
+
+}
+
+const ENUM_MIN_FILE_TYPE: i8 = 0;
+const ENUM_MAX_FILE_TYPE: i8 = 3;
+
+impl<'a> flatbuffers::Follow<'a> for FileType {
+  type Inner = Self;
+  #[inline]
+  fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    flatbuffers::read_scalar_at::<Self>(buf, loc)
+  }
+}
+
+impl flatbuffers::EndianScalar for FileType {
+  #[inline]
+  fn to_little_endian(self) -> Self {
+    let n = i8::to_le(self as i8);
+    let p = &n as *const i8 as *const FileType;
+    unsafe { *p }
+  }
+  #[inline]
+  fn from_little_endian(self) -> Self {
+    let n = i8::from_le(self as i8);
+    let p = &n as *const i8 as *const FileType;
+    unsafe { *p }
+  }
+}
+
+impl flatbuffers::Push for FileType {
+    type Output = FileType;
+    #[inline]
+    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
+        flatbuffers::emplace_scalar::<FileType>(dst, *self);
+    }
+}
+
+#[allow(non_camel_case_types)]
+const ENUM_VALUES_FILE_TYPE:[FileType; 4] = [
+  FileType::Directory,
+  FileType::Text,
+  FileType::Symlink,
+  FileType::Binary
+];
+
+#[allow(non_camel_case_types)]
+const ENUM_NAMES_FILE_TYPE:[&'static str; 4] = [
+    "Directory",
+    "Text",
+    "Symlink",
+    "Binary"
+];
+
+pub fn enum_name_file_type(e: FileType) -> &'static str {
+  let index: usize = e as usize;
+  ENUM_NAMES_FILE_TYPE[index]
+}
+
+#[allow(non_camel_case_types)]
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operation {
+  NONE = 0,
+  InsertMetadata = 1,
+  UpdateParent = 2,
+  BufferOperation = 3,
+  UpdateActiveLocation = 4,
+  UpdateAttributes = 5,
+
+}
+
+const ENUM_MIN_OPERATION: u8 = 0;
+const ENUM_MAX_OPERATION: u8 = 5;
+
+impl<'a> flatbuffers::Follow<'a> for Operation {
+  type Inner = Self;
+  #[inline]
+  fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+    flatbuffers::read_scalar_at::<Self>(buf, loc)
+  }
+}
+
+impl flatbuffers::EndianScalar for Operation {
+  #[inline]
+  fn to_little_endian(self) -> Self {
+    let n = u8::to_le(self as u8);
+    let p = &n as *const u8 as *const Operation;
+    unsafe { *p }
+  }
+  #[inline]
+  fn from_little_endian(self) -> Self {
+    let n = u8::from_le(self as u8);
+    let p = &n as *const u8 as *const Operation;
+    unsafe { *p }
+  }
+}
+
+impl flatbuffers::Push for Operation {
+    type Output = Operation;
+    #[inline]
+    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
+        flatbuffers::emplace_scalar::<Operation>(dst, *self);
+    }
+}
+
+#[allow(non_camel_case_types)]
+const ENUM_VALUES_OPERATION:[Operation; 6] = [
+  Operation::NONE,
+  Operation::InsertMetadata,
+  Operation::UpdateParent,
+  Operation::BufferOperation,
+  Operation::UpdateActiveLocation,
+  Operation::UpdateAttributes
+];
+
+#[allow(non_camel_case_types)]
+const ENUM_NAMES_OPERATION:[&'static str; 6] = [
+    "NONE",
+    "InsertMetadata",
+    "UpdateParent",
+    "BufferOperation",
+    "UpdateActiveLocation",
+    "UpdateAttributes"
+];
+
+pub fn enum_name_operation(e: Operation) -> &'static str {
+  let index: usize = e as usize;
+  ENUM_NAMES_OPERATION[index]
+}
+
+pub struct OperationUnionTableOffset {}
+pub enum BaseFileIdOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct BaseFileId<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for BaseFileId<'a> {
+    type Inner = BaseFileId<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> BaseFileId<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        BaseFileId {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args BaseFileIdArgs) -> flatbuffers::WIPOffset<BaseFileId<'bldr>> {
+      let mut builder = BaseFileIdBuilder::new(_fbb);
+      builder.add_index(args.index);
+      builder.finish()
+    }
+
+    pub const VT_INDEX: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn index(&self) -> u64 {
+    self._tab.get::<u64>(BaseFileId::VT_INDEX, Some(0)).unwrap()
+  }
+}
+
+pub struct BaseFileIdArgs {
+    pub index: u64,
+}
+impl<'a> Default for BaseFileIdArgs {
+    #[inline]
+    fn default() -> Self {
+        BaseFileIdArgs {
+            index: 0,
+        }
+    }
+}
+pub struct BaseFileIdBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> BaseFileIdBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_index(&mut self, index: u64) {
+    self.fbb_.push_slot::<u64>(BaseFileId::VT_INDEX, index, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> BaseFileIdBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    BaseFileIdBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<BaseFileId<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum NewFileIdOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct NewFileId<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for NewFileId<'a> {
+    type Inner = NewFileId<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> NewFileId<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        NewFileId {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args NewFileIdArgs<'args>) -> flatbuffers::WIPOffset<NewFileId<'bldr>> {
+      let mut builder = NewFileIdBuilder::new(_fbb);
+      if let Some(x) = args.id { builder.add_id(x); }
+      builder.finish()
+    }
+
+    pub const VT_ID: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(NewFileId::VT_ID, None)
+  }
+}
+
+pub struct NewFileIdArgs<'a> {
+    pub id: Option<&'a  super::Timestamp>,
+}
+impl<'a> Default for NewFileIdArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        NewFileIdArgs {
+            id: None,
+        }
+    }
+}
+pub struct NewFileIdBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> NewFileIdBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_id(&mut self, id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(NewFileId::VT_ID, id);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> NewFileIdBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    NewFileIdBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<NewFileId<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum InsertMetadataOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct InsertMetadata<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for InsertMetadata<'a> {
+    type Inner = InsertMetadata<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> InsertMetadata<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        InsertMetadata {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args InsertMetadataArgs<'args>) -> flatbuffers::WIPOffset<InsertMetadata<'bldr>> {
+      let mut builder = InsertMetadataBuilder::new(_fbb);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
+      if let Some(x) = args.oid { builder.add_oid(x); }
+      if let Some(x) = args.symlink_target { builder.add_symlink_target(x); }
+      if let Some(x) = args.name_in_parent { builder.add_name_in_parent(x); }
+      if let Some(x) = args.parent_id { builder.add_parent_id(x); }
+      if let Some(x) = args.file_id { builder.add_file_id(x); }
+      builder.add_executable(args.executable);
+      builder.add_parent_id_type(args.parent_id_type);
+      builder.add_file_type(args.file_type);
+      builder.add_file_id_type(args.file_id_type);
+      builder.finish()
+    }
+
+    pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_FILE_TYPE: flatbuffers::VOffsetT = 8;
+    pub const VT_PARENT_ID_TYPE: flatbuffers::VOffsetT = 10;
+    pub const VT_PARENT_ID: flatbuffers::VOffsetT = 12;
+    pub const VT_NAME_IN_PARENT: flatbuffers::VOffsetT = 14;
+    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 16;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 18;
+    pub const VT_EXECUTABLE: flatbuffers::VOffsetT = 20;
+    pub const VT_SYMLINK_TARGET: flatbuffers::VOffsetT = 22;
+    pub const VT_OID: flatbuffers::VOffsetT = 24;
+
+  #[inline]
+  pub fn file_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(InsertMetadata::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(InsertMetadata::VT_FILE_ID, None)
+  }
+  #[inline]
+  pub fn file_type(&self) -> FileType {
+    self._tab.get::<FileType>(InsertMetadata::VT_FILE_TYPE, Some(FileType::Directory)).unwrap()
+  }
+  #[inline]
+  pub fn parent_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(InsertMetadata::VT_PARENT_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn parent_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(InsertMetadata::VT_PARENT_ID, None)
+  }
+  #[inline]
+  pub fn name_in_parent(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(InsertMetadata::VT_NAME_IN_PARENT, None)
+  }
+  #[inline]
+  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(InsertMetadata::VT_LOCAL_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(InsertMetadata::VT_LAMPORT_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn executable(&self) -> bool {
+    self._tab.get::<bool>(InsertMetadata::VT_EXECUTABLE, Some(false)).unwrap()
+  }
+  #[inline]
+  pub fn symlink_target(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(InsertMetadata::VT_SYMLINK_TARGET, None)
+  }
+  #[inline]
+  pub fn oid(&self) -> Option<&'a [u8]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(InsertMetadata::VT_OID, None).map(|v| v.safe_slice())
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.file_id_type() == FileId::BaseFileId {
+      self.file_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.file_id_type() == FileId::NewFileId {
+      self.file_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.parent_id_type() == FileId::BaseFileId {
+      self.parent_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.parent_id_type() == FileId::NewFileId {
+      self.parent_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct InsertMetadataArgs<'a> {
+    pub file_id_type: FileId,
+    pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub file_type: FileType,
+    pub parent_id_type: FileId,
+    pub parent_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub name_in_parent: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub local_timestamp: Option<&'a  super::Timestamp>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+    pub executable: bool,
+    pub symlink_target: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub oid: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u8>>>,
+}
+impl<'a> Default for InsertMetadataArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        InsertMetadataArgs {
+            file_id_type: FileId::NONE,
+            file_id: None,
+            file_type: FileType::Directory,
+            parent_id_type: FileId::NONE,
+            parent_id: None,
+            name_in_parent: None,
+            local_timestamp: None,
+            lamport_timestamp: None,
+            executable: false,
+            symlink_target: None,
+            oid: None,
+        }
+    }
+}
+pub struct InsertMetadataBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> InsertMetadataBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_file_id_type(&mut self, file_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(InsertMetadata::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_FILE_ID, file_id);
+  }
+  #[inline]
+  pub fn add_file_type(&mut self, file_type: FileType) {
+    self.fbb_.push_slot::<FileType>(InsertMetadata::VT_FILE_TYPE, file_type, FileType::Directory);
+  }
+  #[inline]
+  pub fn add_parent_id_type(&mut self, parent_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(InsertMetadata::VT_PARENT_ID_TYPE, parent_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_parent_id(&mut self, parent_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_PARENT_ID, parent_id);
+  }
+  #[inline]
+  pub fn add_name_in_parent(&mut self, name_in_parent: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_NAME_IN_PARENT, name_in_parent);
+  }
+  #[inline]
+  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(InsertMetadata::VT_LOCAL_TIMESTAMP, local_timestamp);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(InsertMetadata::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn add_executable(&mut self, executable: bool) {
+    self.fbb_.push_slot::<bool>(InsertMetadata::VT_EXECUTABLE, executable, false);
+  }
+  #[inline]
+  pub fn add_symlink_target(&mut self, symlink_target: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_SYMLINK_TARGET, symlink_target);
+  }
+  #[inline]
+  pub fn add_oid(&mut self, oid: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u8>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_OID, oid);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> InsertMetadataBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    InsertMetadataBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<InsertMetadata<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum UpdateParentOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct UpdateParent<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for UpdateParent<'a> {
+    type Inner = UpdateParent<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> UpdateParent<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        UpdateParent {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UpdateParentArgs<'args>) -> flatbuffers::WIPOffset<UpdateParent<'bldr>> {
+      let mut builder = UpdateParentBuilder::new(_fbb);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
+      if let Some(x) = args.new_name_in_parent { builder.add_new_name_in_parent(x); }
+      if let Some(x) = args.new_parent_id { builder.add_new_parent_id(x); }
+      if let Some(x) = args.child_id { builder.add_child_id(x); }
+      builder.add_new_parent_id_type(args.new_parent_id_type);
+      builder.add_child_id_type(args.child_id_type);
+      builder.finish()
+    }
+
+    pub const VT_CHILD_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_CHILD_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_NEW_PARENT_ID_TYPE: flatbuffers::VOffsetT = 8;
+    pub const VT_NEW_PARENT_ID: flatbuffers::VOffsetT = 10;
+    pub const VT_NEW_NAME_IN_PARENT: flatbuffers::VOffsetT = 12;
+    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 14;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 16;
+
+  #[inline]
+  pub fn child_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(UpdateParent::VT_CHILD_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn child_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(UpdateParent::VT_CHILD_ID, None)
+  }
+  #[inline]
+  pub fn new_parent_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(UpdateParent::VT_NEW_PARENT_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn new_parent_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(UpdateParent::VT_NEW_PARENT_ID, None)
+  }
+  #[inline]
+  pub fn new_name_in_parent(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(UpdateParent::VT_NEW_NAME_IN_PARENT, None)
+  }
+  #[inline]
+  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UpdateParent::VT_LOCAL_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UpdateParent::VT_LAMPORT_TIMESTAMP, None)
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn child_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.child_id_type() == FileId::BaseFileId {
+      self.child_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn child_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.child_id_type() == FileId::NewFileId {
+      self.child_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn new_parent_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.new_parent_id_type() == FileId::BaseFileId {
+      self.new_parent_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn new_parent_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.new_parent_id_type() == FileId::NewFileId {
+      self.new_parent_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct UpdateParentArgs<'a> {
+    pub child_id_type: FileId,
+    pub child_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub new_parent_id_type: FileId,
+    pub new_parent_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub new_name_in_parent: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub local_timestamp: Option<&'a  super::Timestamp>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+}
+impl<'a> Default for UpdateParentArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UpdateParentArgs {
+            child_id_type: FileId::NONE,
+            child_id: None,
+            new_parent_id_type: FileId::NONE,
+            new_parent_id: None,
+            new_name_in_parent: None,
+            local_timestamp: None,
+            lamport_timestamp: None,
+        }
+    }
+}
+pub struct UpdateParentBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UpdateParentBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_child_id_type(&mut self, child_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(UpdateParent::VT_CHILD_ID_TYPE, child_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_child_id(&mut self, child_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateParent::VT_CHILD_ID, child_id);
+  }
+  #[inline]
+  pub fn add_new_parent_id_type(&mut self, new_parent_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(UpdateParent::VT_NEW_PARENT_ID_TYPE, new_parent_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_new_parent_id(&mut self, new_parent_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateParent::VT_NEW_PARENT_ID, new_parent_id);
+  }
+  #[inline]
+  pub fn add_new_name_in_parent(&mut self, new_name_in_parent: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateParent::VT_NEW_NAME_IN_PARENT, new_name_in_parent);
+  }
+  #[inline]
+  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateParent::VT_LOCAL_TIMESTAMP, local_timestamp);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateParent::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UpdateParentBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    UpdateParentBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<UpdateParent<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum UpdateAttributesOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct UpdateAttributes<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for UpdateAttributes<'a> {
+    type Inner = UpdateAttributes<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> UpdateAttributes<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        UpdateAttributes {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UpdateAttributesArgs<'args>) -> flatbuffers::WIPOffset<UpdateAttributes<'bldr>> {
+      let mut builder = UpdateAttributesBuilder::new(_fbb);
+      if let Some(x) = args.oid { builder.add_oid(x); }
+      if let Some(x) = args.symlink_target { builder.add_symlink_target(x); }
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
+      if let Some(x) = args.file_id { builder.add_file_id(x); }
+      builder.add_executable(args.executable);
+      builder.add_file_id_type(args.file_id_type);
+      builder.finish()
+    }
+
+    pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 8;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 10;
+    pub const VT_EXECUTABLE: flatbuffers::VOffsetT = 12;
+    pub const VT_SYMLINK_TARGET: flatbuffers::VOffsetT = 14;
+    pub const VT_OID: flatbuffers::VOffsetT = 16;
+
+  #[inline]
+  pub fn file_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(UpdateAttributes::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(UpdateAttributes::VT_FILE_ID, None)
+  }
+  #[inline]
+  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UpdateAttributes::VT_LOCAL_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UpdateAttributes::VT_LAMPORT_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn executable(&self) -> bool {
+    self._tab.get::<bool>(UpdateAttributes::VT_EXECUTABLE, Some(false)).unwrap()
+  }
+  #[inline]
+  pub fn symlink_target(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(UpdateAttributes::VT_SYMLINK_TARGET, None)
+  }
+  #[inline]
+  pub fn oid(&self) -> Option<&'a [u8]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(UpdateAttributes::VT_OID, None).map(|v| v.safe_slice())
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.file_id_type() == FileId::BaseFileId {
+      self.file_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.file_id_type() == FileId::NewFileId {
+      self.file_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct UpdateAttributesArgs<'a> {
+    pub file_id_type: FileId,
+    pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub local_timestamp: Option<&'a  super::Timestamp>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+    pub executable: bool,
+    pub symlink_target: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub oid: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u8>>>,
+}
+impl<'a> Default for UpdateAttributesArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UpdateAttributesArgs {
+            file_id_type: FileId::NONE,
+            file_id: None,
+            local_timestamp: None,
+            lamport_timestamp: None,
+            executable: false,
+            symlink_target: None,
+            oid: None,
+        }
+    }
+}
+pub struct UpdateAttributesBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UpdateAttributesBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_file_id_type(&mut self, file_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(UpdateAttributes::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateAttributes::VT_FILE_ID, file_id);
+  }
+  #[inline]
+  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateAttributes::VT_LOCAL_TIMESTAMP, local_timestamp);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateAttributes::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn add_executable(&mut self, executable: bool) {
+    self.fbb_.push_slot::<bool>(UpdateAttributes::VT_EXECUTABLE, executable, false);
+  }
+  #[inline]
+  pub fn add_symlink_target(&mut self, symlink_target: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateAttributes::VT_SYMLINK_TARGET, symlink_target);
+  }
+  #[inline]
+  pub fn add_oid(&mut self, oid: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u8>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateAttributes::VT_OID, oid);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UpdateAttributesBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    UpdateAttributesBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<UpdateAttributes<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum BufferOperationOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct BufferOperation<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for BufferOperation<'a> {
+    type Inner = BufferOperation<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> BufferOperation<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        BufferOperation {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args BufferOperationArgs<'args>) -> flatbuffers::WIPOffset<BufferOperation<'bldr>> {
+      let mut builder = BufferOperationBuilder::new(_fbb);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
+      if let Some(x) = args.operations { builder.add_operations(x); }
+      if let Some(x) = args.file_id { builder.add_file_id(x); }
+      builder.add_file_id_type(args.file_id_type);
+      builder.finish()
+    }
+
+    pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_OPERATIONS: flatbuffers::VOffsetT = 8;
+    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 10;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 12;
+
+  #[inline]
+  pub fn file_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(BufferOperation::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(BufferOperation::VT_FILE_ID, None)
+  }
+  #[inline]
+  pub fn operations(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<super::buffer::Operation<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<super::buffer::Operation<'a>>>>>(BufferOperation::VT_OPERATIONS, None)
+  }
+  #[inline]
+  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(BufferOperation::VT_LOCAL_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(BufferOperation::VT_LAMPORT_TIMESTAMP, None)
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.file_id_type() == FileId::BaseFileId {
+      self.file_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.file_id_type() == FileId::NewFileId {
+      self.file_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct BufferOperationArgs<'a> {
+    pub file_id_type: FileId,
+    pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub operations: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<super::buffer::Operation<'a >>>>>,
+    pub local_timestamp: Option<&'a  super::Timestamp>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+}
+impl<'a> Default for BufferOperationArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        BufferOperationArgs {
+            file_id_type: FileId::NONE,
+            file_id: None,
+            operations: None,
+            local_timestamp: None,
+            lamport_timestamp: None,
+        }
+    }
+}
+pub struct BufferOperationBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> BufferOperationBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_file_id_type(&mut self, file_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(BufferOperation::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(BufferOperation::VT_FILE_ID, file_id);
+  }
+  #[inline]
+  pub fn add_operations(&mut self, operations: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<super::buffer::Operation<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(BufferOperation::VT_OPERATIONS, operations);
+  }
+  #[inline]
+  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(BufferOperation::VT_LOCAL_TIMESTAMP, local_timestamp);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(BufferOperation::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> BufferOperationBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    BufferOperationBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<BufferOperation<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum UpdateActiveLocationOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct UpdateActiveLocation<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for UpdateActiveLocation<'a> {
+    type Inner = UpdateActiveLocation<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> UpdateActiveLocation<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        UpdateActiveLocation {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UpdateActiveLocationArgs<'args>) -> flatbuffers::WIPOffset<UpdateActiveLocation<'bldr>> {
+      let mut builder = UpdateActiveLocationBuilder::new(_fbb);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.user_color { builder.add_user_color(x); }
+      if let Some(x) = args.user_name { builder.add_user_name(x); }
+      if let Some(x) = args.scroll_anchor { builder.add_scroll_anchor(x); }
+      if let Some(x) = args.file_id { builder.add_file_id(x); }
+      builder.add_file_id_type(args.file_id_type);
+      builder.finish()
+    }
+
+    pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 8;
+    pub const VT_SCROLL_ANCHOR: flatbuffers::VOffsetT = 10;
+    pub const VT_USER_NAME: flatbuffers::VOffsetT = 12;
+    pub const VT_USER_COLOR: flatbuffers::VOffsetT = 14;
+
+  #[inline]
+  pub fn file_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(UpdateActiveLocation::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(UpdateActiveLocation::VT_FILE_ID, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(UpdateActiveLocation::VT_LAMPORT_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn scroll_anchor(&self) -> Option<super::buffer::Anchor<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<super::buffer::Anchor<'a>>>(UpdateActiveLocation::VT_SCROLL_ANCHOR, None)
+  }
+  #[inline]
+  pub fn user_name(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(UpdateActiveLocation::VT_USER_NAME, None)
+  }
+  #[inline]
+  pub fn user_color(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(UpdateActiveLocation::VT_USER_COLOR, None)
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.file_id_type() == FileId::BaseFileId {
+      self.file_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.file_id_type() == FileId::NewFileId {
+      self.file_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct UpdateActiveLocationArgs<'a> {
+    pub file_id_type: FileId,
+    pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+    pub scroll_anchor: Option<flatbuffers::WIPOffset<super::buffer::Anchor<'a >>>,
+    pub user_name: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub user_color: Option<flatbuffers::WIPOffset<&'a  str>>,
+}
+impl<'a> Default for UpdateActiveLocationArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UpdateActiveLocationArgs {
+            file_id_type: FileId::NONE,
+            file_id: None,
+            lamport_timestamp: None,
+            scroll_anchor: None,
+            user_name: None,
+            user_color: None,
+        }
+    }
+}
+pub struct UpdateActiveLocationBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UpdateActiveLocationBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_file_id_type(&mut self, file_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(UpdateActiveLocation::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateActiveLocation::VT_FILE_ID, file_id);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateActiveLocation::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn add_scroll_anchor(&mut self, scroll_anchor: flatbuffers::WIPOffset<super::buffer::Anchor<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<super::buffer::Anchor>>(UpdateActiveLocation::VT_SCROLL_ANCHOR, scroll_anchor);
+  }
+  #[inline]
+  pub fn add_user_name(&mut self, user_name: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateActiveLocation::VT_USER_NAME, user_name);
+  }
+  #[inline]
+  pub fn add_user_color(&mut self, user_color: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateActiveLocation::VT_USER_COLOR, user_color);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UpdateActiveLocationBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    UpdateActiveLocationBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<UpdateActiveLocation<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum MetadataOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct Metadata<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for Metadata<'a> {
+    type Inner = Metadata<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> Metadata<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        Metadata {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args MetadataArgs<'args>) -> flatbuffers::WIPOffset<Metadata<'bldr>> {
+      let mut builder = MetadataBuilder::new(_fbb);
+      if let Some(x) = args.attributes_timestamp { builder.add_attributes_timestamp(x); }
+      if let Some(x) = args.oid { builder.add_oid(x); }
+      if let Some(x) = args.symlink_target { builder.add_symlink_target(x); }
+      if let Some(x) = args.file_id { builder.add_file_id(x); }
+      builder.add_executable(args.executable);
+      builder.add_file_type(args.file_type);
+      builder.add_file_id_type(args.file_id_type);
+      builder.finish()
+    }
+
+    pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_FILE_TYPE: flatbuffers::VOffsetT = 8;
+    pub const VT_EXECUTABLE: flatbuffers::VOffsetT = 10;
+    pub const VT_SYMLINK_TARGET: flatbuffers::VOffsetT = 12;
+    pub const VT_OID: flatbuffers::VOffsetT = 14;
+    pub const VT_ATTRIBUTES_TIMESTAMP: flatbuffers::VOffsetT = 16;
+
+  #[inline]
+  pub fn file_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(Metadata::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(Metadata::VT_FILE_ID, None)
+  }
+  #[inline]
+  pub fn file_type(&self) -> FileType {
+    self._tab.get::<FileType>(Metadata::VT_FILE_TYPE, Some(FileType::Directory)).unwrap()
+  }
+  #[inline]
+  pub fn executable(&self) -> bool {
+    self._tab.get::<bool>(Metadata::VT_EXECUTABLE, Some(false)).unwrap()
+  }
+  #[inline]
+  pub fn symlink_target(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Metadata::VT_SYMLINK_TARGET, None)
+  }
+  #[inline]
+  pub fn oid(&self) -> Option<&'a [u8]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(Metadata::VT_OID, None).map(|v| v.safe_slice())
+  }
+  #[inline]
+  pub fn attributes_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Metadata::VT_ATTRIBUTES_TIMESTAMP, None)
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.file_id_type() == FileId::BaseFileId {
+      self.file_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.file_id_type() == FileId::NewFileId {
+      self.file_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct MetadataArgs<'a> {
+    pub file_id_type: FileId,
+    pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub file_type: FileType,
+    pub executable: bool,
+    pub symlink_target: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub oid: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u8>>>,
+    pub attributes_timestamp: Option<&'a  super::Timestamp>,
+}
+impl<'a> Default for MetadataArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        MetadataArgs {
+            file_id_type: FileId::NONE,
+            file_id: None,
+            file_type: FileType::Directory,
+            executable: false,
+            symlink_target: None,
+            oid: None,
+            attributes_timestamp: None,
+        }
+    }
+}
+pub struct MetadataBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> MetadataBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_file_id_type(&mut self, file_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(Metadata::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Metadata::VT_FILE_ID, file_id);
+  }
+  #[inline]
+  pub fn add_file_type(&mut self, file_type: FileType) {
+    self.fbb_.push_slot::<FileType>(Metadata::VT_FILE_TYPE, file_type, FileType::Directory);
+  }
+  #[inline]
+  pub fn add_executable(&mut self, executable: bool) {
+    self.fbb_.push_slot::<bool>(Metadata::VT_EXECUTABLE, executable, false);
+  }
+  #[inline]
+  pub fn add_symlink_target(&mut self, symlink_target: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Metadata::VT_SYMLINK_TARGET, symlink_target);
+  }
+  #[inline]
+  pub fn add_oid(&mut self, oid: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u8>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Metadata::VT_OID, oid);
+  }
+  #[inline]
+  pub fn add_attributes_timestamp(&mut self, attributes_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Metadata::VT_ATTRIBUTES_TIMESTAMP, attributes_timestamp);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> MetadataBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    MetadataBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Metadata<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum ParentRefOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct ParentRef<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for ParentRef<'a> {
+    type Inner = ParentRef<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> ParentRef<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        ParentRef {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args ParentRefArgs<'args>) -> flatbuffers::WIPOffset<ParentRef<'bldr>> {
+      let mut builder = ParentRefBuilder::new(_fbb);
+      if let Some(x) = args.name_in_parent { builder.add_name_in_parent(x); }
+      if let Some(x) = args.parent_id { builder.add_parent_id(x); }
+      if let Some(x) = args.timestamp { builder.add_timestamp(x); }
+      if let Some(x) = args.child_id { builder.add_child_id(x); }
+      builder.add_parent_id_type(args.parent_id_type);
+      builder.add_child_id_type(args.child_id_type);
+      builder.finish()
+    }
+
+    pub const VT_CHILD_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_CHILD_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_TIMESTAMP: flatbuffers::VOffsetT = 8;
+    pub const VT_PARENT_ID_TYPE: flatbuffers::VOffsetT = 10;
+    pub const VT_PARENT_ID: flatbuffers::VOffsetT = 12;
+    pub const VT_NAME_IN_PARENT: flatbuffers::VOffsetT = 14;
+
+  #[inline]
+  pub fn child_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(ParentRef::VT_CHILD_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn child_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(ParentRef::VT_CHILD_ID, None)
+  }
+  #[inline]
+  pub fn timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(ParentRef::VT_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn parent_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(ParentRef::VT_PARENT_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn parent_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(ParentRef::VT_PARENT_ID, None)
+  }
+  #[inline]
+  pub fn name_in_parent(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(ParentRef::VT_NAME_IN_PARENT, None)
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn child_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.child_id_type() == FileId::BaseFileId {
+      self.child_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn child_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.child_id_type() == FileId::NewFileId {
+      self.child_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.parent_id_type() == FileId::BaseFileId {
+      self.parent_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.parent_id_type() == FileId::NewFileId {
+      self.parent_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct ParentRefArgs<'a> {
+    pub child_id_type: FileId,
+    pub child_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub timestamp: Option<&'a  super::Timestamp>,
+    pub parent_id_type: FileId,
+    pub parent_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub name_in_parent: Option<flatbuffers::WIPOffset<&'a  str>>,
+}
+impl<'a> Default for ParentRefArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        ParentRefArgs {
+            child_id_type: FileId::NONE,
+            child_id: None,
+            timestamp: None,
+            parent_id_type: FileId::NONE,
+            parent_id: None,
+            name_in_parent: None,
+        }
+    }
+}
+pub struct ParentRefBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> ParentRefBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_child_id_type(&mut self, child_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(ParentRef::VT_CHILD_ID_TYPE, child_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_child_id(&mut self, child_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ParentRef::VT_CHILD_ID, child_id);
+  }
+  #[inline]
+  pub fn add_timestamp(&mut self, timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(ParentRef::VT_TIMESTAMP, timestamp);
+  }
+  #[inline]
+  pub fn add_parent_id_type(&mut self, parent_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(ParentRef::VT_PARENT_ID_TYPE, parent_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_parent_id(&mut self, parent_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ParentRef::VT_PARENT_ID, parent_id);
+  }
+  #[inline]
+  pub fn add_name_in_parent(&mut self, name_in_parent: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ParentRef::VT_NAME_IN_PARENT, name_in_parent);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> ParentRefBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    ParentRefBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<ParentRef<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum ChildRefOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct ChildRef<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for ChildRef<'a> {
+    type Inner = ChildRef<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> ChildRef<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        ChildRef {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args ChildRefArgs<'args>) -> flatbuffers::WIPOffset<ChildRef<'bldr>> {
+      let mut builder = ChildRefBuilder::new(_fbb);
+      if let Some(x) = args.child_id { builder.add_child_id(x); }
+      if let Some(x) = args.timestamp { builder.add_timestamp(x); }
+      if let Some(x) = args.name { builder.add_name(x); }
+      if let Some(x) = args.parent_id { builder.add_parent_id(x); }
+      builder.add_visible(args.visible);
+      builder.add_child_id_type(args.child_id_type);
+      builder.add_parent_id_type(args.parent_id_type);
+      builder.finish()
+    }
+
+    pub const VT_PARENT_ID_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_PARENT_ID: flatbuffers::VOffsetT = 6;
+    pub const VT_NAME: flatbuffers::VOffsetT = 8;
+    pub const VT_TIMESTAMP: flatbuffers::VOffsetT = 10;
+    pub const VT_CHILD_ID_TYPE: flatbuffers::VOffsetT = 12;
+    pub const VT_CHILD_ID: flatbuffers::VOffsetT = 14;
+    pub const VT_VISIBLE: flatbuffers::VOffsetT = 16;
+
+  #[inline]
+  pub fn parent_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(ChildRef::VT_PARENT_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn parent_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(ChildRef::VT_PARENT_ID, None)
+  }
+  #[inline]
+  pub fn name(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(ChildRef::VT_NAME, None)
+  }
+  #[inline]
+  pub fn timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(ChildRef::VT_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn child_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(ChildRef::VT_CHILD_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn child_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(ChildRef::VT_CHILD_ID, None)
+  }
+  #[inline]
+  pub fn visible(&self) -> bool {
+    self._tab.get::<bool>(ChildRef::VT_VISIBLE, Some(false)).unwrap()
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.parent_id_type() == FileId::BaseFileId {
+      self.parent_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.parent_id_type() == FileId::NewFileId {
+      self.parent_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn child_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.child_id_type() == FileId::BaseFileId {
+      self.child_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn child_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.child_id_type() == FileId::NewFileId {
+      self.child_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct ChildRefArgs<'a> {
+    pub parent_id_type: FileId,
+    pub parent_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub name: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub timestamp: Option<&'a  super::Timestamp>,
+    pub child_id_type: FileId,
+    pub child_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub visible: bool,
+}
+impl<'a> Default for ChildRefArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        ChildRefArgs {
+            parent_id_type: FileId::NONE,
+            parent_id: None,
+            name: None,
+            timestamp: None,
+            child_id_type: FileId::NONE,
+            child_id: None,
+            visible: false,
+        }
+    }
+}
+pub struct ChildRefBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> ChildRefBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_parent_id_type(&mut self, parent_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(ChildRef::VT_PARENT_ID_TYPE, parent_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_parent_id(&mut self, parent_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ChildRef::VT_PARENT_ID, parent_id);
+  }
+  #[inline]
+  pub fn add_name(&mut self, name: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ChildRef::VT_NAME, name);
+  }
+  #[inline]
+  pub fn add_timestamp(&mut self, timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(ChildRef::VT_TIMESTAMP, timestamp);
+  }
+  #[inline]
+  pub fn add_child_id_type(&mut self, child_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(ChildRef::VT_CHILD_ID_TYPE, child_id_type, FileId::NONE);
+  }
+  #[inline]
+  pub fn add_child_id(&mut self, child_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ChildRef::VT_CHILD_ID, child_id);
+  }
+  #[inline]
+  pub fn add_visible(&mut self, visible: bool) {
+    self.fbb_.push_slot::<bool>(ChildRef::VT_VISIBLE, visible, false);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> ChildRefBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    ChildRefBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<ChildRef<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum ReplicaLocationOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct ReplicaLocation<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for ReplicaLocation<'a> {
+    type Inner = ReplicaLocation<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> ReplicaLocation<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        ReplicaLocation {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args ReplicaLocationArgs<'args>) -> flatbuffers::WIPOffset<ReplicaLocation<'bldr>> {
+      let mut builder = ReplicaLocationBuilder::new(_fbb);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.user_color { builder.add_user_color(x); }
+      if let Some(x) = args.user_name { builder.add_user_name(x); }
+      if let Some(x) = args.scroll_anchor { builder.add_scroll_anchor(x); }
+      if let Some(x) = args.file_id { builder.add_file_id(x); }
+      if let Some(x) = args.replica_id { builder.add_replica_id(x); }
+      builder.add_file_id_type(args.file_id_type);
+      builder.finish()
+    }
+
+    pub const VT_REPLICA_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 6;
+    pub const VT_FILE_ID: flatbuffers::VOffsetT = 8;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 10;
+    pub const VT_SCROLL_ANCHOR: flatbuffers::VOffsetT = 12;
+    pub const VT_USER_NAME: flatbuffers::VOffsetT = 14;
+    pub const VT_USER_COLOR: flatbuffers::VOffsetT = 16;
+
+  #[inline]
+  pub fn replica_id(&self) -> Option<&'a super::ReplicaId> {
+    self._tab.get::<super::ReplicaId>(ReplicaLocation::VT_REPLICA_ID, None)
+  }
+  #[inline]
+  pub fn file_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(ReplicaLocation::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(ReplicaLocation::VT_FILE_ID, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(ReplicaLocation::VT_LAMPORT_TIMESTAMP, None)
+  }
+  #[inline]
+  pub fn scroll_anchor(&self) -> Option<super::buffer::Anchor<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<super::buffer::Anchor<'a>>>(ReplicaLocation::VT_SCROLL_ANCHOR, None)
+  }
+  #[inline]
+  pub fn user_name(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(ReplicaLocation::VT_USER_NAME, None)
+  }
+  #[inline]
+  pub fn user_color(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(ReplicaLocation::VT_USER_COLOR, None)
+  }
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.file_id_type() == FileId::BaseFileId {
+      self.file_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn file_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.file_id_type() == FileId::NewFileId {
+      self.file_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+}
+
+pub struct ReplicaLocationArgs<'a> {
+    pub replica_id: Option<&'a  super::ReplicaId>,
+    pub file_id_type: FileId,
+    pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+    pub scroll_anchor: Option<flatbuffers::WIPOffset<super::buffer::Anchor<'a >>>,
+    pub user_name: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub user_color: Option<flatbuffers::WIPOffset<&'a  str>>,
+}
+impl<'a> Default for ReplicaLocationArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        ReplicaLocationArgs {
+            replica_id: None,
+            file_id_type: FileId::NONE,
+            file_id: None,
+            lamport_timestamp: None,
+            scroll_anchor: None,
+            user_name: None,
+            user_color: None,
+        }
+    }
+}
+pub struct ReplicaLocationBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> ReplicaLocationBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_replica_id(&mut self, replica_id: &'b  super::ReplicaId) {
+    self.fbb_.push_slot_always::<&super::ReplicaId>(ReplicaLocation::VT_REPLICA_ID, replica_id);
+  }
+  #[inline]
+  pub fn add_file_id_type(&mut self, file_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(ReplicaLocation::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+  }
   #[inline]
-  pub fn add_id(&mut self, id: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(NewFileId::VT_ID, id);
+  pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ReplicaLocation::VT_FILE_ID, file_id);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> NewFileIdBuilder<'a, 'b> {
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(ReplicaLocation::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn add_scroll_anchor(&mut self, scroll_anchor: flatbuffers::WIPOffset<super::buffer::Anchor<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<super::buffer::Anchor>>(ReplicaLocation::VT_SCROLL_ANCHOR, scroll_anchor);
+  }
+  #[inline]
+  pub fn add_user_name(&mut self, user_name: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ReplicaLocation::VT_USER_NAME, user_name);
+  }
+  #[inline]
+  pub fn add_user_color(&mut self, user_color: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(ReplicaLocation::VT_USER_COLOR, user_color);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> ReplicaLocationBuilder<'a, 'b> {
     let start = _fbb.start_table();
-    NewFileIdBuilder {
+    ReplicaLocationBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<NewFileId<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<ReplicaLocation<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-pub enum InsertMetadataOffset {}
+pub enum TextFileOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
-pub struct InsertMetadata<'a> {
+pub struct TextFile<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for InsertMetadata<'a> {
-    type Inner = InsertMetadata<'a>;
+impl<'a> flatbuffers::Follow<'a> for TextFile<'a> {
+    type Inner = TextFile<'a>;
     #[inline]
     fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
         Self {
@@ -1355,69 +4076,45 @@ impl<'a> flatbuffers::Follow<'a> for InsertMetadata<'a> {
     }
 }
 
-impl<'a> InsertMetadata<'a> {
+impl<'a> TextFile<'a> {
     #[inline]
     pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-        InsertMetadata {
+        TextFile {
             _tab: table,
         }
     }
     #[allow(unused_mut)]
     pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
         _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
-        args: &'args InsertMetadataArgs<'args>) -> flatbuffers::WIPOffset<InsertMetadata<'bldr>> {
-      let mut builder = InsertMetadataBuilder::new(_fbb);
-      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
-      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
-      if let Some(x) = args.name_in_parent { builder.add_name_in_parent(x); }
-      if let Some(x) = args.parent_id { builder.add_parent_id(x); }
+        args: &'args TextFileArgs<'args>) -> flatbuffers::WIPOffset<TextFile<'bldr>> {
+      let mut builder = TextFileBuilder::new(_fbb);
+      if let Some(x) = args.deferred_operations { builder.add_deferred_operations(x); }
+      if let Some(x) = args.buffer { builder.add_buffer(x); }
       if let Some(x) = args.file_id { builder.add_file_id(x); }
-      builder.add_parent_id_type(args.parent_id_type);
-      builder.add_file_type(args.file_type);
       builder.add_file_id_type(args.file_id_type);
       builder.finish()
     }
 
     pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
     pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
-    pub const VT_FILE_TYPE: flatbuffers::VOffsetT = 8;
-    pub const VT_PARENT_ID_TYPE: flatbuffers::VOffsetT = 10;
-    pub const VT_PARENT_ID: flatbuffers::VOffsetT = 12;
-    pub const VT_NAME_IN_PARENT: flatbuffers::VOffsetT = 14;
-    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 16;
-    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 18;
+    pub const VT_BUFFER: flatbuffers::VOffsetT = 8;
+    pub const VT_DEFERRED_OPERATIONS: flatbuffers::VOffsetT = 10;
 
   #[inline]
   pub fn file_id_type(&self) -> FileId {
-    self._tab.get::<FileId>(InsertMetadata::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+    self._tab.get::<FileId>(TextFile::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
   }
   #[inline]
   pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(InsertMetadata::VT_FILE_ID, None)
-  }
-  #[inline]
-  pub fn file_type(&self) -> FileType {
-    self._tab.get::<FileType>(InsertMetadata::VT_FILE_TYPE, Some(FileType::Directory)).unwrap()
-  }
-  #[inline]
-  pub fn parent_id_type(&self) -> FileId {
-    self._tab.get::<FileId>(InsertMetadata::VT_PARENT_ID_TYPE, Some(FileId::NONE)).unwrap()
-  }
-  #[inline]
-  pub fn parent_id(&self) -> Option<flatbuffers::Table<'a>> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(InsertMetadata::VT_PARENT_ID, None)
-  }
-  #[inline]
-  pub fn name_in_parent(&self) -> Option<&'a str> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(InsertMetadata::VT_NAME_IN_PARENT, None)
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(TextFile::VT_FILE_ID, None)
   }
   #[inline]
-  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(InsertMetadata::VT_LOCAL_TIMESTAMP, None)
+  pub fn buffer(&self) -> Option<super::buffer::Snapshot<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<super::buffer::Snapshot<'a>>>(TextFile::VT_BUFFER, None)
   }
   #[inline]
-  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(InsertMetadata::VT_LAMPORT_TIMESTAMP, None)
+  pub fn deferred_operations(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<super::buffer::Operation<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<super::buffer::Operation<'a>>>>>(TextFile::VT_DEFERRED_OPERATIONS, None)
   }
   #[inline]
   #[allow(non_snake_case)]
@@ -1439,114 +4136,70 @@ impl<'a> InsertMetadata<'a> {
     }
   }
 
-  #[inline]
-  #[allow(non_snake_case)]
-  pub fn parent_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
-    if self.parent_id_type() == FileId::BaseFileId {
-      self.parent_id().map(|u| BaseFileId::init_from_table(u))
-    } else {
-      None
-    }
-  }
-
-  #[inline]
-  #[allow(non_snake_case)]
-  pub fn parent_id_as_new_file_id(&'a self) -> Option<NewFileId> {
-    if self.parent_id_type() == FileId::NewFileId {
-      self.parent_id().map(|u| NewFileId::init_from_table(u))
-    } else {
-      None
-    }
-  }
-
 }
 
-pub struct InsertMetadataArgs<'a> {
+pub struct TextFileArgs<'a> {
     pub file_id_type: FileId,
     pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
-    pub file_type: FileType,
-    pub parent_id_type: FileId,
-    pub parent_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
-    pub name_in_parent: Option<flatbuffers::WIPOffset<&'a  str>>,
-    pub local_timestamp: Option<&'a  super::Timestamp>,
-    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+    pub buffer: Option<flatbuffers::WIPOffset<super::buffer::Snapshot<'a >>>,
+    pub deferred_operations: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<super::buffer::Operation<'a >>>>>,
 }
-impl<'a> Default for InsertMetadataArgs<'a> {
+impl<'a> Default for TextFileArgs<'a> {
     #[inline]
     fn default() -> Self {
-        InsertMetadataArgs {
+        TextFileArgs {
             file_id_type: FileId::NONE,
             file_id: None,
-            file_type: FileType::Directory,
-            parent_id_type: FileId::NONE,
-            parent_id: None,
-            name_in_parent: None,
-            local_timestamp: None,
-            lamport_timestamp: None,
+            buffer: None,
+            deferred_operations: None,
         }
     }
 }
-pub struct InsertMetadataBuilder<'a: 'b, 'b> {
+pub struct TextFileBuilder<'a: 'b, 'b> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b> InsertMetadataBuilder<'a, 'b> {
+impl<'a: 'b, 'b> TextFileBuilder<'a, 'b> {
   #[inline]
   pub fn add_file_id_type(&mut self, file_id_type: FileId) {
-    self.fbb_.push_slot::<FileId>(InsertMetadata::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+    self.fbb_.push_slot::<FileId>(TextFile::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
   }
   #[inline]
   pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_FILE_ID, file_id);
-  }
-  #[inline]
-  pub fn add_file_type(&mut self, file_type: FileType) {
-    self.fbb_.push_slot::<FileType>(InsertMetadata::VT_FILE_TYPE, file_type, FileType::Directory);
-  }
-  #[inline]
-  pub fn add_parent_id_type(&mut self, parent_id_type: FileId) {
-    self.fbb_.push_slot::<FileId>(InsertMetadata::VT_PARENT_ID_TYPE, parent_id_type, FileId::NONE);
-  }
-  #[inline]
-  pub fn add_parent_id(&mut self, parent_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_PARENT_ID, parent_id);
-  }
-  #[inline]
-  pub fn add_name_in_parent(&mut self, name_in_parent: flatbuffers::WIPOffset<&'b  str>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(InsertMetadata::VT_NAME_IN_PARENT, name_in_parent);
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(TextFile::VT_FILE_ID, file_id);
   }
   #[inline]
-  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(InsertMetadata::VT_LOCAL_TIMESTAMP, local_timestamp);
+  pub fn add_buffer(&mut self, buffer: flatbuffers::WIPOffset<super::buffer::Snapshot<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<super::buffer::Snapshot>>(TextFile::VT_BUFFER, buffer);
   }
   #[inline]
-  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(InsertMetadata::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  pub fn add_deferred_operations(&mut self, deferred_operations: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<super::buffer::Operation<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(TextFile::VT_DEFERRED_OPERATIONS, deferred_operations);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> InsertMetadataBuilder<'a, 'b> {
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> TextFileBuilder<'a, 'b> {
     let start = _fbb.start_table();
-    InsertMetadataBuilder {
+    TextFileBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<InsertMetadata<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<TextFile<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-pub enum UpdateParentOffset {}
+pub enum DeferredOperationOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
-pub struct UpdateParent<'a> {
+pub struct DeferredOperation<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for UpdateParent<'a> {
-    type Inner = UpdateParent<'a>;
+impl<'a> flatbuffers::Follow<'a> for DeferredOperation<'a> {
+    type Inner = DeferredOperation<'a>;
     #[inline]
     fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
         Self {
@@ -1555,69 +4208,49 @@ impl<'a> flatbuffers::Follow<'a> for UpdateParent<'a> {
     }
 }
 
-impl<'a> UpdateParent<'a> {
+impl<'a> DeferredOperation<'a> {
     #[inline]
     pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-        UpdateParent {
+        DeferredOperation {
             _tab: table,
         }
     }
     #[allow(unused_mut)]
     pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
         _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
-        args: &'args UpdateParentArgs<'args>) -> flatbuffers::WIPOffset<UpdateParent<'bldr>> {
-      let mut builder = UpdateParentBuilder::new(_fbb);
-      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
-      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
-      if let Some(x) = args.new_name_in_parent { builder.add_new_name_in_parent(x); }
-      if let Some(x) = args.new_parent_id { builder.add_new_parent_id(x); }
-      if let Some(x) = args.child_id { builder.add_child_id(x); }
-      builder.add_new_parent_id_type(args.new_parent_id_type);
-      builder.add_child_id_type(args.child_id_type);
+        args: &'args DeferredOperationArgs) -> flatbuffers::WIPOffset<DeferredOperation<'bldr>> {
+      let mut builder = DeferredOperationBuilder::new(_fbb);
+      if let Some(x) = args.operation { builder.add_operation(x); }
+      builder.add_operation_type(args.operation_type);
       builder.finish()
     }
 
-    pub const VT_CHILD_ID_TYPE: flatbuffers::VOffsetT = 4;
-    pub const VT_CHILD_ID: flatbuffers::VOffsetT = 6;
-    pub const VT_NEW_PARENT_ID_TYPE: flatbuffers::VOffsetT = 8;
-    pub const VT_NEW_PARENT_ID: flatbuffers::VOffsetT = 10;
-    pub const VT_NEW_NAME_IN_PARENT: flatbuffers::VOffsetT = 12;
-    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 14;
-    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 16;
+    pub const VT_OPERATION_TYPE: flatbuffers::VOffsetT = 4;
+    pub const VT_OPERATION: flatbuffers::VOffsetT = 6;
 
   #[inline]
-  pub fn child_id_type(&self) -> FileId {
-    self._tab.get::<FileId>(UpdateParent::VT_CHILD_ID_TYPE, Some(FileId::NONE)).unwrap()
-  }
-  #[inline]
-  pub fn child_id(&self) -> Option<flatbuffers::Table<'a>> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(UpdateParent::VT_CHILD_ID, None)
-  }
-  #[inline]
-  pub fn new_parent_id_type(&self) -> FileId {
-    self._tab.get::<FileId>(UpdateParent::VT_NEW_PARENT_ID_TYPE, Some(FileId::NONE)).unwrap()
-  }
-  #[inline]
-  pub fn new_parent_id(&self) -> Option<flatbuffers::Table<'a>> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(UpdateParent::VT_NEW_PARENT_ID, None)
-  }
-  #[inline]
-  pub fn new_name_in_parent(&self) -> Option<&'a str> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(UpdateParent::VT_NEW_NAME_IN_PARENT, None)
+  pub fn operation_type(&self) -> Operation {
+    self._tab.get::<Operation>(DeferredOperation::VT_OPERATION_TYPE, Some(Operation::NONE)).unwrap()
   }
   #[inline]
-  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(UpdateParent::VT_LOCAL_TIMESTAMP, None)
+  pub fn operation(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(DeferredOperation::VT_OPERATION, None)
   }
   #[inline]
-  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(UpdateParent::VT_LAMPORT_TIMESTAMP, None)
+  #[allow(non_snake_case)]
+  pub fn operation_as_insert_metadata(&'a self) -> Option<InsertMetadata> {
+    if self.operation_type() == Operation::InsertMetadata {
+      self.operation().map(|u| InsertMetadata::init_from_table(u))
+    } else {
+      None
+    }
   }
+
   #[inline]
   #[allow(non_snake_case)]
-  pub fn child_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
-    if self.child_id_type() == FileId::BaseFileId {
-      self.child_id().map(|u| BaseFileId::init_from_table(u))
+  pub fn operation_as_update_parent(&'a self) -> Option<UpdateParent> {
+    if self.operation_type() == Operation::UpdateParent {
+      self.operation().map(|u| UpdateParent::init_from_table(u))
     } else {
       None
     }
@@ -1625,9 +4258,9 @@ impl<'a> UpdateParent<'a> {
 
   #[inline]
   #[allow(non_snake_case)]
-  pub fn child_id_as_new_file_id(&'a self) -> Option<NewFileId> {
-    if self.child_id_type() == FileId::NewFileId {
-      self.child_id().map(|u| NewFileId::init_from_table(u))
+  pub fn operation_as_buffer_operation(&'a self) -> Option<BufferOperation> {
+    if self.operation_type() == Operation::BufferOperation {
+      self.operation().map(|u| BufferOperation::init_from_table(u))
     } else {
       None
     }
@@ -1635,9 +4268,9 @@ impl<'a> UpdateParent<'a> {
 
   #[inline]
   #[allow(non_snake_case)]
-  pub fn new_parent_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
-    if self.new_parent_id_type() == FileId::BaseFileId {
-      self.new_parent_id().map(|u| BaseFileId::init_from_table(u))
+  pub fn operation_as_update_active_location(&'a self) -> Option<UpdateActiveLocation> {
+    if self.operation_type() == Operation::UpdateActiveLocation {
+      self.operation().map(|u| UpdateActiveLocation::init_from_table(u))
     } else {
       None
     }
@@ -1645,9 +4278,9 @@ impl<'a> UpdateParent<'a> {
 
   #[inline]
   #[allow(non_snake_case)]
-  pub fn new_parent_id_as_new_file_id(&'a self) -> Option<NewFileId> {
-    if self.new_parent_id_type() == FileId::NewFileId {
-      self.new_parent_id().map(|u| NewFileId::init_from_table(u))
+  pub fn operation_as_update_attributes(&'a self) -> Option<UpdateAttributes> {
+    if self.operation_type() == Operation::UpdateAttributes {
+      self.operation().map(|u| UpdateAttributes::init_from_table(u))
     } else {
       None
     }
@@ -1655,86 +4288,56 @@ impl<'a> UpdateParent<'a> {
 
 }
 
-pub struct UpdateParentArgs<'a> {
-    pub child_id_type: FileId,
-    pub child_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
-    pub new_parent_id_type: FileId,
-    pub new_parent_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
-    pub new_name_in_parent: Option<flatbuffers::WIPOffset<&'a  str>>,
-    pub local_timestamp: Option<&'a  super::Timestamp>,
-    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+pub struct DeferredOperationArgs {
+    pub operation_type: Operation,
+    pub operation: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
 }
-impl<'a> Default for UpdateParentArgs<'a> {
+impl<'a> Default for DeferredOperationArgs {
     #[inline]
     fn default() -> Self {
-        UpdateParentArgs {
-            child_id_type: FileId::NONE,
-            child_id: None,
-            new_parent_id_type: FileId::NONE,
-            new_parent_id: None,
-            new_name_in_parent: None,
-            local_timestamp: None,
-            lamport_timestamp: None,
+        DeferredOperationArgs {
+            operation_type: Operation::NONE,
+            operation: None,
         }
     }
 }
-pub struct UpdateParentBuilder<'a: 'b, 'b> {
+pub struct DeferredOperationBuilder<'a: 'b, 'b> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b> UpdateParentBuilder<'a, 'b> {
-  #[inline]
-  pub fn add_child_id_type(&mut self, child_id_type: FileId) {
-    self.fbb_.push_slot::<FileId>(UpdateParent::VT_CHILD_ID_TYPE, child_id_type, FileId::NONE);
-  }
-  #[inline]
-  pub fn add_child_id(&mut self, child_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateParent::VT_CHILD_ID, child_id);
-  }
-  #[inline]
-  pub fn add_new_parent_id_type(&mut self, new_parent_id_type: FileId) {
-    self.fbb_.push_slot::<FileId>(UpdateParent::VT_NEW_PARENT_ID_TYPE, new_parent_id_type, FileId::NONE);
-  }
-  #[inline]
-  pub fn add_new_parent_id(&mut self, new_parent_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateParent::VT_NEW_PARENT_ID, new_parent_id);
-  }
-  #[inline]
-  pub fn add_new_name_in_parent(&mut self, new_name_in_parent: flatbuffers::WIPOffset<&'b  str>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateParent::VT_NEW_NAME_IN_PARENT, new_name_in_parent);
-  }
+impl<'a: 'b, 'b> DeferredOperationBuilder<'a, 'b> {
   #[inline]
-  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateParent::VT_LOCAL_TIMESTAMP, local_timestamp);
+  pub fn add_operation_type(&mut self, operation_type: Operation) {
+    self.fbb_.push_slot::<Operation>(DeferredOperation::VT_OPERATION_TYPE, operation_type, Operation::NONE);
   }
   #[inline]
-  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateParent::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  pub fn add_operation(&mut self, operation: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(DeferredOperation::VT_OPERATION, operation);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UpdateParentBuilder<'a, 'b> {
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> DeferredOperationBuilder<'a, 'b> {
     let start = _fbb.start_table();
-    UpdateParentBuilder {
+    DeferredOperationBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<UpdateParent<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<DeferredOperation<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-pub enum BufferOperationOffset {}
+pub enum ConflictOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
-pub struct BufferOperation<'a> {
+pub struct Conflict<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for BufferOperation<'a> {
-    type Inner = BufferOperation<'a>;
+impl<'a> flatbuffers::Follow<'a> for Conflict<'a> {
+    type Inner = Conflict<'a>;
     #[inline]
     fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
         Self {
@@ -1743,51 +4346,69 @@ impl<'a> flatbuffers::Follow<'a> for BufferOperation<'a> {
     }
 }
 
-impl<'a> BufferOperation<'a> {
+impl<'a> Conflict<'a> {
     #[inline]
     pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-        BufferOperation {
+        Conflict {
             _tab: table,
         }
     }
     #[allow(unused_mut)]
     pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
         _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
-        args: &'args BufferOperationArgs<'args>) -> flatbuffers::WIPOffset<BufferOperation<'bldr>> {
-      let mut builder = BufferOperationBuilder::new(_fbb);
-      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
-      if let Some(x) = args.local_timestamp { builder.add_local_timestamp(x); }
-      if let Some(x) = args.operations { builder.add_operations(x); }
+        args: &'args ConflictArgs<'args>) -> flatbuffers::WIPOffset<Conflict<'bldr>> {
+      let mut builder = ConflictBuilder::new(_fbb);
+      if let Some(x) = args.name { builder.add_name(x); }
+      if let Some(x) = args.parent_id { builder.add_parent_id(x); }
+      if let Some(x) = args.winner_id { builder.add_winner_id(x); }
       if let Some(x) = args.file_id { builder.add_file_id(x); }
+      builder.add_cycle(args.cycle);
+      builder.add_parent_id_type(args.parent_id_type);
+      builder.add_winner_id_type(args.winner_id_type);
       builder.add_file_id_type(args.file_id_type);
       builder.finish()
     }
 
     pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
     pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
-    pub const VT_OPERATIONS: flatbuffers::VOffsetT = 8;
-    pub const VT_LOCAL_TIMESTAMP: flatbuffers::VOffsetT = 10;
-    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 12;
+    pub const VT_WINNER_ID_TYPE: flatbuffers::VOffsetT = 8;
+    pub const VT_WINNER_ID: flatbuffers::VOffsetT = 10;
+    pub const VT_PARENT_ID_TYPE: flatbuffers::VOffsetT = 12;
+    pub const VT_PARENT_ID: flatbuffers::VOffsetT = 14;
+    pub const VT_NAME: flatbuffers::VOffsetT = 16;
+    pub const VT_CYCLE: flatbuffers::VOffsetT = 18;
 
   #[inline]
-  pub fn file_id_type(&self) -> FileId {
-    self._tab.get::<FileId>(BufferOperation::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  pub fn file_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(Conflict::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(Conflict::VT_FILE_ID, None)
+  }
+  #[inline]
+  pub fn winner_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(Conflict::VT_WINNER_ID_TYPE, Some(FileId::NONE)).unwrap()
+  }
+  #[inline]
+  pub fn winner_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(Conflict::VT_WINNER_ID, None)
   }
   #[inline]
-  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(BufferOperation::VT_FILE_ID, None)
+  pub fn parent_id_type(&self) -> FileId {
+    self._tab.get::<FileId>(Conflict::VT_PARENT_ID_TYPE, Some(FileId::NONE)).unwrap()
   }
   #[inline]
-  pub fn operations(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<super::buffer::Operation<'a>>>> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<super::buffer::Operation<'a>>>>>(BufferOperation::VT_OPERATIONS, None)
+  pub fn parent_id(&self) -> Option<flatbuffers::Table<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(Conflict::VT_PARENT_ID, None)
   }
   #[inline]
-  pub fn local_timestamp(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(BufferOperation::VT_LOCAL_TIMESTAMP, None)
+  pub fn name(&self) -> Option<&'a str> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<&str>>(Conflict::VT_NAME, None)
   }
   #[inline]
-  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(BufferOperation::VT_LAMPORT_TIMESTAMP, None)
+  pub fn cycle(&self) -> bool {
+    self._tab.get::<bool>(Conflict::VT_CYCLE, Some(false)).unwrap()
   }
   #[inline]
   #[allow(non_snake_case)]
@@ -1809,76 +4430,134 @@ impl<'a> BufferOperation<'a> {
     }
   }
 
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn winner_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.winner_id_type() == FileId::BaseFileId {
+      self.winner_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn winner_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.winner_id_type() == FileId::NewFileId {
+      self.winner_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
+    if self.parent_id_type() == FileId::BaseFileId {
+      self.parent_id().map(|u| BaseFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn parent_id_as_new_file_id(&'a self) -> Option<NewFileId> {
+    if self.parent_id_type() == FileId::NewFileId {
+      self.parent_id().map(|u| NewFileId::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
 }
 
-pub struct BufferOperationArgs<'a> {
+pub struct ConflictArgs<'a> {
     pub file_id_type: FileId,
     pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
-    pub operations: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<super::buffer::Operation<'a >>>>>,
-    pub local_timestamp: Option<&'a  super::Timestamp>,
-    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+    pub winner_id_type: FileId,
+    pub winner_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub parent_id_type: FileId,
+    pub parent_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
+    pub name: Option<flatbuffers::WIPOffset<&'a  str>>,
+    pub cycle: bool,
 }
-impl<'a> Default for BufferOperationArgs<'a> {
+impl<'a> Default for ConflictArgs<'a> {
     #[inline]
     fn default() -> Self {
-        BufferOperationArgs {
+        ConflictArgs {
             file_id_type: FileId::NONE,
             file_id: None,
-            operations: None,
-            local_timestamp: None,
-            lamport_timestamp: None,
+            winner_id_type: FileId::NONE,
+            winner_id: None,
+            parent_id_type: FileId::NONE,
+            parent_id: None,
+            name: None,
+            cycle: false,
         }
     }
 }
-pub struct BufferOperationBuilder<'a: 'b, 'b> {
+pub struct ConflictBuilder<'a: 'b, 'b> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b> BufferOperationBuilder<'a, 'b> {
+impl<'a: 'b, 'b> ConflictBuilder<'a, 'b> {
   #[inline]
   pub fn add_file_id_type(&mut self, file_id_type: FileId) {
-    self.fbb_.push_slot::<FileId>(BufferOperation::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+    self.fbb_.push_slot::<FileId>(Conflict::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
   }
   #[inline]
   pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(BufferOperation::VT_FILE_ID, file_id);
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Conflict::VT_FILE_ID, file_id);
   }
   #[inline]
-  pub fn add_operations(&mut self, operations: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<super::buffer::Operation<'b >>>>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(BufferOperation::VT_OPERATIONS, operations);
+  pub fn add_winner_id_type(&mut self, winner_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(Conflict::VT_WINNER_ID_TYPE, winner_id_type, FileId::NONE);
   }
   #[inline]
-  pub fn add_local_timestamp(&mut self, local_timestamp: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(BufferOperation::VT_LOCAL_TIMESTAMP, local_timestamp);
+  pub fn add_winner_id(&mut self, winner_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Conflict::VT_WINNER_ID, winner_id);
   }
   #[inline]
-  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(BufferOperation::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  pub fn add_parent_id_type(&mut self, parent_id_type: FileId) {
+    self.fbb_.push_slot::<FileId>(Conflict::VT_PARENT_ID_TYPE, parent_id_type, FileId::NONE);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> BufferOperationBuilder<'a, 'b> {
+  pub fn add_parent_id(&mut self, parent_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Conflict::VT_PARENT_ID, parent_id);
+  }
+  #[inline]
+  pub fn add_name(&mut self, name: flatbuffers::WIPOffset<&'b  str>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Conflict::VT_NAME, name);
+  }
+  #[inline]
+  pub fn add_cycle(&mut self, cycle: bool) {
+    self.fbb_.push_slot::<bool>(Conflict::VT_CYCLE, cycle, false);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> ConflictBuilder<'a, 'b> {
     let start = _fbb.start_table();
-    BufferOperationBuilder {
+    ConflictBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<BufferOperation<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<Conflict<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
 
-pub enum UpdateActiveLocationOffset {}
+pub enum SnapshotOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
-pub struct UpdateActiveLocation<'a> {
+pub struct Snapshot<'a> {
   pub _tab: flatbuffers::Table<'a>,
 }
 
-impl<'a> flatbuffers::Follow<'a> for UpdateActiveLocation<'a> {
-    type Inner = UpdateActiveLocation<'a>;
+impl<'a> flatbuffers::Follow<'a> for Snapshot<'a> {
+    type Inner = Snapshot<'a>;
     #[inline]
     fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
         Self {
@@ -1887,109 +4566,196 @@ impl<'a> flatbuffers::Follow<'a> for UpdateActiveLocation<'a> {
     }
 }
 
-impl<'a> UpdateActiveLocation<'a> {
+impl<'a> Snapshot<'a> {
     #[inline]
     pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
-        UpdateActiveLocation {
+        Snapshot {
             _tab: table,
         }
     }
     #[allow(unused_mut)]
     pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
         _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
-        args: &'args UpdateActiveLocationArgs<'args>) -> flatbuffers::WIPOffset<UpdateActiveLocation<'bldr>> {
-      let mut builder = UpdateActiveLocationBuilder::new(_fbb);
-      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
-      if let Some(x) = args.file_id { builder.add_file_id(x); }
-      builder.add_file_id_type(args.file_id_type);
+        args: &'args SnapshotArgs<'args>) -> flatbuffers::WIPOffset<Snapshot<'bldr>> {
+      let mut builder = SnapshotBuilder::new(_fbb);
+      builder.add_base_entries_next_id(args.base_entries_next_id);
+      if let Some(x) = args.conflicts { builder.add_conflicts(x); }
+      if let Some(x) = args.deferred_operations { builder.add_deferred_operations(x); }
+      if let Some(x) = args.text_files { builder.add_text_files(x); }
+      if let Some(x) = args.version { builder.add_version(x); }
+      if let Some(x) = args.replica_locations { builder.add_replica_locations(x); }
+      if let Some(x) = args.child_refs { builder.add_child_refs(x); }
+      if let Some(x) = args.parent_refs { builder.add_parent_refs(x); }
+      if let Some(x) = args.metadata { builder.add_metadata(x); }
+      if let Some(x) = args.base_entries_stack { builder.add_base_entries_stack(x); }
+      if let Some(x) = args.head { builder.add_head(x); }
+      if let Some(x) = args.id { builder.add_id(x); }
       builder.finish()
     }
 
-    pub const VT_FILE_ID_TYPE: flatbuffers::VOffsetT = 4;
-    pub const VT_FILE_ID: flatbuffers::VOffsetT = 6;
-    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 8;
+    pub const VT_ID: flatbuffers::VOffsetT = 4;
+    pub const VT_HEAD: flatbuffers::VOffsetT = 6;
+    pub const VT_BASE_ENTRIES_NEXT_ID: flatbuffers::VOffsetT = 8;
+    pub const VT_BASE_ENTRIES_STACK: flatbuffers::VOffsetT = 10;
+    pub const VT_METADATA: flatbuffers::VOffsetT = 12;
+    pub const VT_PARENT_REFS: flatbuffers::VOffsetT = 14;
+    pub const VT_CHILD_REFS: flatbuffers::VOffsetT = 16;
+    pub const VT_REPLICA_LOCATIONS: flatbuffers::VOffsetT = 18;
+    pub const VT_VERSION: flatbuffers::VOffsetT = 20;
+    pub const VT_TEXT_FILES: flatbuffers::VOffsetT = 22;
+    pub const VT_DEFERRED_OPERATIONS: flatbuffers::VOffsetT = 24;
+    pub const VT_CONFLICTS: flatbuffers::VOffsetT = 26;
 
   #[inline]
-  pub fn file_id_type(&self) -> FileId {
-    self._tab.get::<FileId>(UpdateActiveLocation::VT_FILE_ID_TYPE, Some(FileId::NONE)).unwrap()
+  pub fn id(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Snapshot::VT_ID, None)
   }
   #[inline]
-  pub fn file_id(&self) -> Option<flatbuffers::Table<'a>> {
-    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Table<'a>>>(UpdateActiveLocation::VT_FILE_ID, None)
+  pub fn head(&self) -> Option<&'a [u8]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(Snapshot::VT_HEAD, None).map(|v| v.safe_slice())
   }
   #[inline]
-  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
-    self._tab.get::<super::Timestamp>(UpdateActiveLocation::VT_LAMPORT_TIMESTAMP, None)
+  pub fn base_entries_next_id(&self) -> u64 {
+    self._tab.get::<u64>(Snapshot::VT_BASE_ENTRIES_NEXT_ID, Some(0)).unwrap()
   }
   #[inline]
-  #[allow(non_snake_case)]
-  pub fn file_id_as_base_file_id(&'a self) -> Option<BaseFileId> {
-    if self.file_id_type() == FileId::BaseFileId {
-      self.file_id().map(|u| BaseFileId::init_from_table(u))
-    } else {
-      None
-    }
+  pub fn base_entries_stack(&self) -> Option<flatbuffers::Vector<'a, u64>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u64>>>(Snapshot::VT_BASE_ENTRIES_STACK, None)
   }
-
   #[inline]
-  #[allow(non_snake_case)]
-  pub fn file_id_as_new_file_id(&'a self) -> Option<NewFileId> {
-    if self.file_id_type() == FileId::NewFileId {
-      self.file_id().map(|u| NewFileId::init_from_table(u))
-    } else {
-      None
-    }
+  pub fn metadata(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Metadata<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Metadata<'a>>>>>(Snapshot::VT_METADATA, None)
+  }
+  #[inline]
+  pub fn parent_refs(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<ParentRef<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<ParentRef<'a>>>>>(Snapshot::VT_PARENT_REFS, None)
+  }
+  #[inline]
+  pub fn child_refs(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<ChildRef<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<ChildRef<'a>>>>>(Snapshot::VT_CHILD_REFS, None)
+  }
+  #[inline]
+  pub fn replica_locations(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<ReplicaLocation<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<ReplicaLocation<'a>>>>>(Snapshot::VT_REPLICA_LOCATIONS, None)
+  }
+  #[inline]
+  pub fn version(&self) -> Option<super::GlobalTimestamp<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<super::GlobalTimestamp<'a>>>(Snapshot::VT_VERSION, None)
+  }
+  #[inline]
+  pub fn text_files(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<TextFile<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<TextFile<'a>>>>>(Snapshot::VT_TEXT_FILES, None)
+  }
+  #[inline]
+  pub fn deferred_operations(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<DeferredOperation<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<DeferredOperation<'a>>>>>(Snapshot::VT_DEFERRED_OPERATIONS, None)
+  }
+  #[inline]
+  pub fn conflicts(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Conflict<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Conflict<'a>>>>>(Snapshot::VT_CONFLICTS, None)
   }
-
 }
 
-pub struct UpdateActiveLocationArgs<'a> {
-    pub file_id_type: FileId,
-    pub file_id: Option<flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>>,
-    pub lamport_timestamp: Option<&'a  super::Timestamp>,
-}
-impl<'a> Default for UpdateActiveLocationArgs<'a> {
+pub struct SnapshotArgs<'a> {
+    pub id: Option<&'a  super::Timestamp>,
+    pub head: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u8>>>,
+    pub base_entries_next_id: u64,
+    pub base_entries_stack: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u64>>>,
+    pub metadata: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<Metadata<'a >>>>>,
+    pub parent_refs: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<ParentRef<'a >>>>>,
+    pub child_refs: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<ChildRef<'a >>>>>,
+    pub replica_locations: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<ReplicaLocation<'a >>>>>,
+    pub version: Option<flatbuffers::WIPOffset<super::GlobalTimestamp<'a >>>,
+    pub text_files: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<TextFile<'a >>>>>,
+    pub deferred_operations: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<DeferredOperation<'a >>>>>,
+    pub conflicts: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<Conflict<'a >>>>>,
+}
+impl<'a> Default for SnapshotArgs<'a> {
     #[inline]
     fn default() -> Self {
-        UpdateActiveLocationArgs {
-            file_id_type: FileId::NONE,
-            file_id: None,
-            lamport_timestamp: None,
+        SnapshotArgs {
+            id: None,
+            head: None,
+            base_entries_next_id: 0,
+            base_entries_stack: None,
+            metadata: None,
+            parent_refs: None,
+            child_refs: None,
+            replica_locations: None,
+            version: None,
+            text_files: None,
+            deferred_operations: None,
+            conflicts: None,
         }
     }
 }
-pub struct UpdateActiveLocationBuilder<'a: 'b, 'b> {
+pub struct SnapshotBuilder<'a: 'b, 'b> {
   fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
   start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
 }
-impl<'a: 'b, 'b> UpdateActiveLocationBuilder<'a, 'b> {
+impl<'a: 'b, 'b> SnapshotBuilder<'a, 'b> {
   #[inline]
-  pub fn add_file_id_type(&mut self, file_id_type: FileId) {
-    self.fbb_.push_slot::<FileId>(UpdateActiveLocation::VT_FILE_ID_TYPE, file_id_type, FileId::NONE);
+  pub fn add_id(&mut self, id: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Snapshot::VT_ID, id);
   }
   #[inline]
-  pub fn add_file_id(&mut self, file_id: flatbuffers::WIPOffset<flatbuffers::UnionWIPOffset>) {
-    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateActiveLocation::VT_FILE_ID, file_id);
+  pub fn add_head(&mut self, head: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u8>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_HEAD, head);
   }
   #[inline]
-  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
-    self.fbb_.push_slot_always::<&super::Timestamp>(UpdateActiveLocation::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  pub fn add_base_entries_next_id(&mut self, base_entries_next_id: u64) {
+    self.fbb_.push_slot::<u64>(Snapshot::VT_BASE_ENTRIES_NEXT_ID, base_entries_next_id, 0);
   }
   #[inline]
-  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UpdateActiveLocationBuilder<'a, 'b> {
+  pub fn add_base_entries_stack(&mut self, base_entries_stack: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u64>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_BASE_ENTRIES_STACK, base_entries_stack);
+  }
+  #[inline]
+  pub fn add_metadata(&mut self, metadata: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<Metadata<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_METADATA, metadata);
+  }
+  #[inline]
+  pub fn add_parent_refs(&mut self, parent_refs: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<ParentRef<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_PARENT_REFS, parent_refs);
+  }
+  #[inline]
+  pub fn add_child_refs(&mut self, child_refs: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<ChildRef<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_CHILD_REFS, child_refs);
+  }
+  #[inline]
+  pub fn add_replica_locations(&mut self, replica_locations: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<ReplicaLocation<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_REPLICA_LOCATIONS, replica_locations);
+  }
+  #[inline]
+  pub fn add_version(&mut self, version: flatbuffers::WIPOffset<super::GlobalTimestamp<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<super::GlobalTimestamp>>(Snapshot::VT_VERSION, version);
+  }
+  #[inline]
+  pub fn add_text_files(&mut self, text_files: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<TextFile<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_TEXT_FILES, text_files);
+  }
+  #[inline]
+  pub fn add_deferred_operations(&mut self, deferred_operations: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<DeferredOperation<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_DEFERRED_OPERATIONS, deferred_operations);
+  }
+  #[inline]
+  pub fn add_conflicts(&mut self, conflicts: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<Conflict<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_CONFLICTS, conflicts);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> SnapshotBuilder<'a, 'b> {
     let start = _fbb.start_table();
-    UpdateActiveLocationBuilder {
+    SnapshotBuilder {
       fbb_: _fbb,
       start_: start,
     }
   }
   #[inline]
-  pub fn finish(self) -> flatbuffers::WIPOffset<UpdateActiveLocation<'a>> {
+  pub fn finish(self) -> flatbuffers::WIPOffset<Snapshot<'a>> {
     let o = self.fbb_.end_table(self.start_);
     flatbuffers::WIPOffset::new(o.value())
   }
 }
-
 }  // pub mod epoch
 
 pub mod worktree {
@@ -2245,6 +5011,16 @@ impl<'a> EpochOperation<'a> {
     }
   }
 
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn operation_as_update_attributes(&'a self) -> Option<super::epoch::UpdateAttributes> {
+    if self.operation_type() == super::epoch::Operation::UpdateAttributes {
+      self.operation().map(|u| super::epoch::UpdateAttributes::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
 }
 
 pub struct EpochOperationArgs<'a> {
@@ -2402,6 +5178,106 @@ impl<'a: 'b, 'b> OperationBuilder<'a, 'b> {
   }
 }
 
+pub enum SnapshotOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct Snapshot<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for Snapshot<'a> {
+    type Inner = Snapshot<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> Snapshot<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        Snapshot {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args SnapshotArgs<'args>) -> flatbuffers::WIPOffset<Snapshot<'bldr>> {
+      let mut builder = SnapshotBuilder::new(_fbb);
+      if let Some(x) = args.lamport_timestamp { builder.add_lamport_timestamp(x); }
+      if let Some(x) = args.deferred_operations { builder.add_deferred_operations(x); }
+      if let Some(x) = args.epoch { builder.add_epoch(x); }
+      builder.finish()
+    }
+
+    pub const VT_EPOCH: flatbuffers::VOffsetT = 4;
+    pub const VT_DEFERRED_OPERATIONS: flatbuffers::VOffsetT = 6;
+    pub const VT_LAMPORT_TIMESTAMP: flatbuffers::VOffsetT = 8;
+
+  #[inline]
+  pub fn epoch(&self) -> Option<super::epoch::Snapshot<'a>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<super::epoch::Snapshot<'a>>>(Snapshot::VT_EPOCH, None)
+  }
+  #[inline]
+  pub fn deferred_operations(&self) -> Option<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Operation<'a>>>> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<flatbuffers::ForwardsUOffset<Operation<'a>>>>>(Snapshot::VT_DEFERRED_OPERATIONS, None)
+  }
+  #[inline]
+  pub fn lamport_timestamp(&self) -> Option<&'a super::Timestamp> {
+    self._tab.get::<super::Timestamp>(Snapshot::VT_LAMPORT_TIMESTAMP, None)
+  }
+}
+
+pub struct SnapshotArgs<'a> {
+    pub epoch: Option<flatbuffers::WIPOffset<super::epoch::Snapshot<'a >>>,
+    pub deferred_operations: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , flatbuffers::ForwardsUOffset<Operation<'a >>>>>,
+    pub lamport_timestamp: Option<&'a  super::Timestamp>,
+}
+impl<'a> Default for SnapshotArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        SnapshotArgs {
+            epoch: None,
+            deferred_operations: None,
+            lamport_timestamp: None,
+        }
+    }
+}
+pub struct SnapshotBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> SnapshotBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_epoch(&mut self, epoch: flatbuffers::WIPOffset<super::epoch::Snapshot<'b >>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<super::epoch::Snapshot>>(Snapshot::VT_EPOCH, epoch);
+  }
+  #[inline]
+  pub fn add_deferred_operations(&mut self, deferred_operations: flatbuffers::WIPOffset<flatbuffers::Vector<'b , flatbuffers::ForwardsUOffset<Operation<'b >>>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(Snapshot::VT_DEFERRED_OPERATIONS, deferred_operations);
+  }
+  #[inline]
+  pub fn add_lamport_timestamp(&mut self, lamport_timestamp: &'b  super::Timestamp) {
+    self.fbb_.push_slot_always::<&super::Timestamp>(Snapshot::VT_LAMPORT_TIMESTAMP, lamport_timestamp);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> SnapshotBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    SnapshotBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<Snapshot<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
 #[inline]
 pub fn get_root_as_operation<'a>(buf: &'a [u8]) -> Operation<'a> {
   flatbuffers::get_root::<Operation<'a>>(buf)
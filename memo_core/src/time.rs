@@ -129,6 +129,21 @@ impl Global {
         self.get(timestamp.replica_id) >= timestamp.value
     }
 
+    /// Returns the timestamps that were observed by both `self` and `other`.
+    pub fn meet(&self, other: &Self) -> Self {
+        let mut meet = Global::new();
+        for (replica_id, value) in self.0.as_ref() {
+            let value = cmp::min(*value, other.get(*replica_id));
+            if value > 0 {
+                meet.observe(Local {
+                    replica_id: *replica_id,
+                    value,
+                });
+            }
+        }
+        meet
+    }
+
     pub fn changed_since(&self, other: &Self) -> bool {
         self.0
             .iter()
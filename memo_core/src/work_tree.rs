@@ -1,42 +1,78 @@
-use crate::buffer::{self, Change, Point, Text};
-use crate::epoch::{self, Cursor, DirEntry, Epoch, FileId, FileType};
+use crate::buffer::{
+    self, Anchor, AnchorBias, AuthorshipSpan, BufferSnapshot, Change, LineDiffHunk, OffsetUnit,
+    Point, SearchDirection, SearchQuery, Text,
+};
+use crate::epoch::{
+    self, ConflictEntry, Cursor, DirEntry, Epoch, FileAttributes, FileId, FileStatus, FileType,
+    Presence, StatusEntry, TreeChange, UserInfo,
+};
+use crate::git::{Repository, TreeBuilder};
 use crate::serialization;
 use crate::{time, Error, Oid, ReplicaId};
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
 use futures::{future, stream, Async, Future, Poll, Stream};
 use serde_derive::{Deserialize, Serialize};
-use std::cell::{Ref, RefCell, RefMut};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::io;
 use std::mem;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::rc::Rc;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, SystemTime};
 
-pub trait GitProvider {
-    fn base_entries(&self, oid: Oid) -> Box<Stream<Item = DirEntry, Error = io::Error>>;
-    fn base_text(&self, oid: Oid, path: &Path) -> Box<Future<Item = String, Error = io::Error>>;
+pub trait GitProvider: Send + Sync {
+    fn base_entries(&self, oid: Oid) -> Box<Stream<Item = DirEntry, Error = io::Error> + Send>;
+    fn base_text(
+        &self,
+        oid: Oid,
+        path: &Path,
+    ) -> Box<Future<Item = String, Error = io::Error> + Send>;
+
+    /// Returns the contents of a blob, which is how the contents of binary files are read.
+    /// Providers that only serve text can rely on the default implementation.
+    fn blob(&self, _oid: Oid) -> Box<Future<Item = Vec<u8>, Error = io::Error> + Send> {
+        Box::new(future::err(io::Error::new(
+            io::ErrorKind::Other,
+            "git provider does not serve blobs",
+        )))
+    }
 }
 
-pub trait ChangeObserver {
+pub trait ChangeObserver: Send + Sync {
     fn changed(&self, buffer_id: BufferId, changes: Vec<Change>, selections: BufferSelectionRanges);
+
+    /// Called when remote operations create, move or remove files in the current epoch, and when
+    /// switching to a new epoch.
+    fn tree_changed(&self, _changes: Vec<TreeChange>) {}
+
+    /// Called when the replica passed to `WorkTree::follow` moves, scrolls or changes its cursor.
+    fn followed_replica_changed(&self, _replica_id: ReplicaId, _presence: ReplicaPresence) {}
 }
 
+/// A replica of a work tree. Every method takes `&self`, so a tree can be shared between threads.
+///
+/// State is split across several locks, some of which are also held by the futures and streams
+/// the tree returns. To avoid deadlocks, code that holds more than one of them at a time must
+/// acquire them in the order the fields are declared below, with an epoch that is being switched
+/// to locked right after the current one.
 pub struct WorkTree {
-    epoch: Option<Rc<RefCell<Epoch>>>,
-    buffers: Rc<RefCell<HashMap<BufferId, FileId>>>,
-    next_buffer_id: Rc<RefCell<BufferId>>,
+    epoch: Arc<RwLock<Epoch>>,
+    buffers: Arc<RwLock<HashMap<BufferId, FileId>>>,
+    next_buffer_id: Arc<RwLock<BufferId>>,
     local_selection_sets:
-        Rc<RefCell<HashMap<BufferId, HashMap<LocalSelectionSetId, buffer::SelectionSetId>>>>,
-    next_local_selection_set_id: Rc<RefCell<LocalSelectionSetId>>,
-    deferred_ops: Rc<RefCell<HashMap<epoch::Id, Vec<epoch::Operation>>>>,
-    lamport_clock: Rc<RefCell<time::Lamport>>,
-    git: Rc<GitProvider>,
-    observer: Option<Rc<ChangeObserver>>,
+        Arc<RwLock<HashMap<BufferId, HashMap<LocalSelectionSetId, buffer::SelectionSetId>>>>,
+    next_local_selection_set_id: Arc<RwLock<LocalSelectionSetId>>,
+    deferred_ops: Arc<RwLock<HashMap<epoch::Id, Vec<epoch::Operation>>>>,
+    lamport_clock: Arc<RwLock<time::Lamport>>,
+    git: Arc<GitProvider>,
+    observer: Option<Arc<ChangeObserver>>,
+    rebase_conflicts: Arc<RwLock<Vec<RebaseConflict>>>,
+    followed_replica: Arc<RwLock<Option<ReplicaId>>>,
+    fork_point: Option<Version>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Version {
     epoch_id: epoch::Id,
     epoch_version: time::Global,
@@ -59,9 +95,37 @@ pub enum Operation {
     },
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RebaseConflict {
+    /// A file was created locally at a path that is also taken in the new base.
+    BothAdded { path: PathBuf },
+    /// A file was renamed locally, but it no longer exists in the new base.
+    RenamedButRemoved { base_path: PathBuf, path: PathBuf },
+    /// A file was renamed locally, but its new path is already taken in the new base.
+    RenameTargetExists { base_path: PathBuf, path: PathBuf },
+    /// A file was removed locally, but it was modified in the new base, so it was kept.
+    RemovedButModified { path: PathBuf },
+    /// A file was modified locally, but it no longer exists in the new base. It is recreated with
+    /// the local text if its path is still available.
+    ModifiedButRemoved { path: PathBuf },
+    /// Local edits that overlapped with edits in the new base and were left out.
+    ConflictingEdits {
+        path: PathBuf,
+        hunks: Vec<LineDiffHunk>,
+    },
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct BufferId(u32);
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReplicaPresence {
+    pub user: UserInfo,
+    pub path: Option<PathBuf>,
+    pub cursor: Option<Point>,
+    pub scroll_position: Option<Point>,
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct LocalSelectionSetId(u32);
 
@@ -76,23 +140,30 @@ enum MaybeDone<F: Future> {
     Done(Result<F::Item, F::Error>),
 }
 
+struct RebasedFile {
+    entry: StatusEntry,
+    attributes: FileAttributes,
+    base_text: Option<String>,
+    text: Option<String>,
+}
+
 struct BaseTextRequest {
-    future: MaybeDone<Box<Future<Item = String, Error = io::Error>>>,
+    future: MaybeDone<Box<Future<Item = String, Error = io::Error> + Send>>,
     path: PathBuf,
 }
 
 struct SwitchEpoch {
-    to_assign: Rc<RefCell<Epoch>>,
-    cur_epoch: Rc<RefCell<Epoch>>,
+    to_assign: Arc<RwLock<Epoch>>,
+    cur_epoch: Arc<RwLock<Epoch>>,
     last_seen: epoch::Id,
     base_text_requests: HashMap<BufferId, Option<BaseTextRequest>>,
-    buffers: Rc<RefCell<HashMap<BufferId, FileId>>>,
+    buffers: Arc<RwLock<HashMap<BufferId, FileId>>>,
     local_selection_sets:
-        Rc<RefCell<HashMap<BufferId, HashMap<LocalSelectionSetId, buffer::SelectionSetId>>>>,
-    deferred_ops: Rc<RefCell<HashMap<epoch::Id, Vec<epoch::Operation>>>>,
-    lamport_clock: Rc<RefCell<time::Lamport>>,
-    git: Rc<GitProvider>,
-    observer: Option<Rc<ChangeObserver>>,
+        Arc<RwLock<HashMap<BufferId, HashMap<LocalSelectionSetId, buffer::SelectionSetId>>>>,
+    deferred_ops: Arc<RwLock<HashMap<epoch::Id, Vec<epoch::Operation>>>>,
+    lamport_clock: Arc<RwLock<time::Lamport>>,
+    git: Arc<GitProvider>,
+    observer: Option<Arc<ChangeObserver>>,
 }
 
 impl WorkTree {
@@ -100,12 +171,12 @@ impl WorkTree {
         replica_id: ReplicaId,
         base: Option<Oid>,
         ops: I,
-        git: Rc<GitProvider>,
-        observer: Option<Rc<ChangeObserver>>,
+        git: Arc<GitProvider>,
+        observer: Option<Arc<ChangeObserver>>,
     ) -> Result<
         (
             WorkTree,
-            Box<Stream<Item = OperationEnvelope, Error = Error>>,
+            Box<Stream<Item = OperationEnvelope, Error = Error> + Send>,
         ),
         Error,
     >
@@ -113,40 +184,174 @@ impl WorkTree {
         I: 'static + IntoIterator<Item = Operation>,
     {
         let mut ops = ops.into_iter().peekable();
-        let mut tree = WorkTree {
-            epoch: None,
-            buffers: Rc::new(RefCell::new(HashMap::new())),
-            next_buffer_id: Rc::new(RefCell::new(BufferId(0))),
-            local_selection_sets: Rc::new(RefCell::new(HashMap::new())),
-            next_local_selection_set_id: Rc::new(RefCell::new(LocalSelectionSetId(0))),
-            deferred_ops: Rc::new(RefCell::new(HashMap::new())),
-            lamport_clock: Rc::new(RefCell::new(time::Lamport::new(replica_id))),
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        let mut early_ops = Vec::new();
+        let is_new = ops.peek().is_none();
+        let (epoch_id, head) = if is_new {
+            (lamport_clock.tick(), base)
+        } else {
+            let mut start = None;
+            while let Some(op) = ops.next() {
+                match op {
+                    Operation::StartEpoch { epoch_id, head } => {
+                        lamport_clock.observe(epoch_id);
+                        start = Some((epoch_id, head));
+                        break;
+                    }
+                    Operation::EpochOperation {
+                        epoch_id,
+                        operation,
+                    } => early_ops.push((epoch_id, operation)),
+                }
+            }
+            start.ok_or(Error::InvalidOperations)?
+        };
+
+        let tree = WorkTree {
+            epoch: Arc::new(RwLock::new(Epoch::new(replica_id, epoch_id, head))),
+            buffers: Arc::new(RwLock::new(HashMap::new())),
+            next_buffer_id: Arc::new(RwLock::new(BufferId(0))),
+            local_selection_sets: Arc::new(RwLock::new(HashMap::new())),
+            next_local_selection_set_id: Arc::new(RwLock::new(LocalSelectionSetId(0))),
+            deferred_ops: Arc::new(RwLock::new(HashMap::new())),
+            lamport_clock: Arc::new(RwLock::new(lamport_clock)),
             git,
             observer,
+            rebase_conflicts: Arc::new(RwLock::new(Vec::new())),
+            followed_replica: Arc::new(RwLock::new(None)),
+            fork_point: None,
         };
+        for (epoch_id, operation) in early_ops {
+            tree.defer_epoch_op(epoch_id, operation);
+        }
 
-        let ops = if ops.peek().is_none() {
-            Box::new(tree.reset(base)) as Box<Stream<Item = OperationEnvelope, Error = Error>>
+        // Unlike later epochs, changes to the first one are reported while it is loading.
+        let load_base_entries = tree.load_base_entries(tree.epoch.clone(), tree.observer.clone());
+        let ops = if is_new {
+            Box::new(
+                stream::once(Ok(OperationEnvelope {
+                    epoch_head: head,
+                    operation: Operation::StartEpoch { epoch_id, head },
+                }))
+                .chain(load_base_entries),
+            ) as Box<Stream<Item = OperationEnvelope, Error = Error> + Send>
         } else {
-            Box::new(tree.apply_ops(ops)?) as Box<Stream<Item = OperationEnvelope, Error = Error>>
+            Box::new(load_base_entries.chain(tree.apply_ops(ops)?))
+                as Box<Stream<Item = OperationEnvelope, Error = Error> + Send>
+        };
+
+        Ok((tree, ops))
+    }
+
+    /// Restores a tree from the output of `snapshot` and applies the operations that were
+    /// received after the snapshot was taken. If the snapshot was taken while the base entries of
+    /// the epoch were still loading, loading resumes where it left off.
+    pub fn from_snapshot<I>(
+        replica_id: ReplicaId,
+        snapshot: &[u8],
+        ops: I,
+        git: Arc<GitProvider>,
+        observer: Option<Arc<ChangeObserver>>,
+    ) -> Result<
+        (
+            WorkTree,
+            Box<Stream<Item = OperationEnvelope, Error = Error> + Send>,
+        ),
+        Error,
+    >
+    where
+        I: 'static + IntoIterator<Item = Operation>,
+    {
+        let message = flatbuffers::get_root::<serialization::worktree::Snapshot>(snapshot);
+        let epoch =
+            Epoch::from_flatbuf(replica_id, message.epoch().ok_or(Error::DeserializeError)?)?;
+
+        let mut lamport_clock = time::Lamport::new(replica_id);
+        lamport_clock.observe(time::Lamport::from_flatbuf(
+            message.lamport_timestamp().ok_or(Error::DeserializeError)?,
+        ));
+        lamport_clock.observe(epoch.id);
+
+        let tree = WorkTree {
+            epoch: Arc::new(RwLock::new(epoch)),
+            buffers: Arc::new(RwLock::new(HashMap::new())),
+            next_buffer_id: Arc::new(RwLock::new(BufferId(0))),
+            local_selection_sets: Arc::new(RwLock::new(HashMap::new())),
+            next_local_selection_set_id: Arc::new(RwLock::new(LocalSelectionSetId(0))),
+            deferred_ops: Arc::new(RwLock::new(HashMap::new())),
+            lamport_clock: Arc::new(RwLock::new(lamport_clock)),
+            git,
+            observer,
+            rebase_conflicts: Arc::new(RwLock::new(Vec::new())),
+            followed_replica: Arc::new(RwLock::new(None)),
+            fork_point: None,
         };
 
+        let op_messages = message
+            .deferred_operations()
+            .ok_or(Error::DeserializeError)?;
+        for i in 0..op_messages.len() {
+            if let Some(Operation::EpochOperation {
+                epoch_id,
+                operation,
+            }) = Operation::from_flatbuf(op_messages.get(i))?
+            {
+                tree.defer_epoch_op(epoch_id, operation);
+            }
+        }
+
+        let load_base_entries = tree.load_base_entries(tree.epoch.clone(), tree.observer.clone());
+        let ops = Box::new(load_base_entries.chain(tree.apply_ops(ops)?));
         Ok((tree, ops))
     }
 
+    /// Serializes the state of the current epoch so that it can be restored with `from_snapshot`
+    /// without replaying its operations. Open buffers and local selection sets aren't included.
+    pub fn snapshot(&self) -> Vec<u8> {
+        use crate::serialization::worktree::{Snapshot, SnapshotArgs};
+
+        let mut builder = FlatBufferBuilder::new();
+        let epoch = Some(self.cur_epoch().to_flatbuf(&mut builder));
+        let deferred_op_flatbufs = &self
+            .deferred_ops
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|(epoch_id, operations)| {
+                operations
+                    .iter()
+                    .map(move |operation| Operation::EpochOperation {
+                        epoch_id: *epoch_id,
+                        operation: operation.clone(),
+                    })
+            })
+            .map(|operation| operation.to_flatbuf(&mut builder))
+            .collect::<Vec<_>>();
+        let deferred_operations = Some(builder.create_vector(deferred_op_flatbufs));
+        let root = Snapshot::create(
+            &mut builder,
+            &SnapshotArgs {
+                epoch,
+                deferred_operations,
+                lamport_timestamp: Some(&self.lamport_clock.read().unwrap().to_flatbuf()),
+            },
+        );
+        builder.finish(root, None);
+        let (mut bytes, first_valid_byte_index) = builder.collapse();
+        bytes.drain(0..first_valid_byte_index);
+        bytes
+    }
+
     pub fn head(&self) -> Option<Oid> {
-        self.epoch.as_ref().and_then(|e| e.borrow().head)
+        self.cur_epoch().head
     }
 
     pub fn epoch_id(&self) -> epoch::Id {
         self.cur_epoch().id
     }
 
-    pub fn reset(
-        &mut self,
-        head: Option<Oid>,
-    ) -> impl Stream<Item = OperationEnvelope, Error = Error> {
-        let epoch_id = self.lamport_clock.borrow_mut().tick();
+    pub fn reset(&self, head: Option<Oid>) -> impl Stream<Item = OperationEnvelope, Error = Error> {
+        let epoch_id = self.lamport_clock.write().unwrap().tick();
         stream::once(Ok(OperationEnvelope {
             epoch_head: head,
             operation: Operation::StartEpoch { epoch_id, head },
@@ -154,8 +359,94 @@ impl WorkTree {
         .chain(self.start_epoch(epoch_id, head))
     }
 
+    /// Resets the tree to `head` like `reset`, then replays the local changes of the current
+    /// epoch on top of it once the new epoch is loaded. Changes that can't be replayed cleanly are
+    /// reported by `rebase_conflicts`.
+    pub fn rebase(
+        &self,
+        head: Option<Oid>,
+    ) -> Box<Stream<Item = OperationEnvelope, Error = Error> + Send> {
+        self.rebase_conflicts.write().unwrap().clear();
+        let old_epoch = Arc::new(RwLock::new(self.cur_epoch().clone()));
+
+        let replay = self
+            .replay_changes(old_epoch)
+            .map(|ops| stream::iter_ok(ops))
+            .flatten_stream();
+        Box::new(self.reset(head).chain(replay))
+    }
+
+    pub fn rebase_conflicts(&self) -> Vec<RebaseConflict> {
+        self.rebase_conflicts.read().unwrap().clone()
+    }
+
+    /// Creates an independent replica of this tree at its current version. The fork can diverge
+    /// freely without exchanging operations with this tree's replicas, and can be brought back
+    /// with `merge`. Its replica id must not be used by any other replica of this tree.
+    pub fn fork(
+        &self,
+        replica_id: ReplicaId,
+        observer: Option<Arc<ChangeObserver>>,
+    ) -> Result<WorkTree, Error> {
+        let (mut fork, _) = WorkTree::from_snapshot(
+            replica_id,
+            &self.snapshot(),
+            Vec::new(),
+            self.git.clone(),
+            observer,
+        )?;
+        fork.fork_point = Some(fork.version());
+        Ok(fork)
+    }
+
+    /// Imports the changes of a tree created with `fork`.
+    ///
+    /// If the fork is still on this tree's epoch or has started a newer one, its operations are
+    /// applied as they are and converge with ours. Otherwise this tree has moved on to a newer
+    /// epoch since forking, and the fork's changes are replayed on top of it like in `rebase`,
+    /// reporting the ones that can't be replayed cleanly in `rebase_conflicts`. The returned
+    /// stream contains the operations to send to the other replicas of this tree.
+    ///
+    /// Fails with `OperationsUnavailable` if the fork has discarded some of the operations it
+    /// performed since it was created.
+    pub fn merge(
+        &self,
+        fork: &WorkTree,
+    ) -> Result<Box<Stream<Item = OperationEnvelope, Error = Error> + Send>, Error> {
+        let fork_point = fork.fork_point.clone().ok_or(Error::InvalidOperation)?;
+        if fork.epoch_id() >= self.epoch_id() {
+            // Send everything the fork did since it was created rather than what this tree is
+            // missing, since this tree may have moved past the fork's history or not have caught
+            // up with it yet. If the fork has started an epoch of its own since then, that's
+            // everything in its current epoch.
+            let fork_version = Version {
+                epoch_id: self.epoch_id(),
+                epoch_version: if fork.epoch_id() == fork_point.epoch_id {
+                    fork_point.epoch_version
+                } else {
+                    time::Global::new()
+                },
+            };
+            let envelopes = fork.operations_since(fork_version)?;
+            let ops = envelopes
+                .iter()
+                .map(|envelope| envelope.operation.clone())
+                .collect::<Vec<_>>();
+            let fixup_ops = self.apply_ops(ops)?;
+            Ok(Box::new(stream::iter_ok(envelopes).chain(fixup_ops)))
+        } else {
+            self.rebase_conflicts.write().unwrap().clear();
+            let fork_epoch = Arc::new(RwLock::new(fork.cur_epoch().clone()));
+            Ok(Box::new(
+                self.replay_changes(fork_epoch)
+                    .map(|ops| stream::iter_ok(ops))
+                    .flatten_stream(),
+            ))
+        }
+    }
+
     pub fn apply_ops<I>(
-        &mut self,
+        &self,
         ops: I,
     ) -> Result<impl Stream<Item = OperationEnvelope, Error = Error>, Error>
     where
@@ -167,136 +458,193 @@ impl WorkTree {
         for op in ops {
             match op {
                 Operation::StartEpoch { epoch_id, head } => {
-                    self.lamport_clock.borrow_mut().observe(epoch_id);
+                    self.lamport_clock.write().unwrap().observe(epoch_id);
                     epoch_streams.push(self.start_epoch(epoch_id, head));
                 }
                 Operation::EpochOperation {
                     epoch_id,
                     operation,
-                } => {
-                    if let Some(epoch) = self.epoch.clone() {
-                        match epoch_id.cmp(&epoch.borrow().id) {
-                            Ordering::Less => {}
-                            Ordering::Equal => cur_epoch_ops.push(operation),
-                            Ordering::Greater => self.defer_epoch_op(epoch_id, operation),
-                        }
-                    } else {
-                        self.defer_epoch_op(epoch_id, operation);
-                    }
-                }
+                } => match epoch_id.cmp(&self.epoch_id()) {
+                    Ordering::Less => {}
+                    Ordering::Equal => cur_epoch_ops.push(operation),
+                    Ordering::Greater => self.defer_epoch_op(epoch_id, operation),
+                },
             }
         }
 
-        if let Some(epoch_ref) = self.epoch.clone() {
-            let mut epoch = epoch_ref.borrow_mut();
+        let mut epoch = self.epoch.write().unwrap();
 
-            let mut prev_versions = HashMap::new();
-            for file_id in self.buffers.borrow().values() {
-                let edit_version = epoch.buffer_version(*file_id).unwrap();
-                let selections_last_update = epoch.buffer_selections_last_update(*file_id).unwrap();
-                prev_versions.insert(*file_id, (edit_version, selections_last_update));
-            }
+        let followed_replica = *self.followed_replica.read().unwrap();
+        let buffers = self.buffers.read().unwrap();
+        let prev_followed_presence =
+            followed_replica.and_then(|replica_id| Self::replica_presence(&epoch, replica_id));
 
-            let fixup_ops = epoch.apply_ops(cur_epoch_ops, &mut self.lamport_clock.borrow_mut())?;
+        let mut prev_versions = HashMap::new();
+        for file_id in buffers.values() {
+            let edit_version = epoch.buffer_version(*file_id).unwrap();
+            let selections_last_update = epoch.buffer_selections_last_update(*file_id).unwrap();
+            prev_versions.insert(*file_id, (edit_version, selections_last_update));
+        }
 
-            if let Some(observer) = self.observer.as_ref() {
-                for (buffer_id, file_id) in self.buffers.borrow().iter() {
-                    let (edit_version, selections_last_update) =
-                        prev_versions.remove(file_id).unwrap();
-                    let changes: Vec<_> = epoch.changes_since(*file_id, &edit_version)?.collect();
-                    if !changes.is_empty()
-                        || epoch.selections_changed_since(*file_id, selections_last_update)?
-                    {
-                        observer.changed(
-                            *buffer_id,
-                            changes,
-                            Self::selection_ranges_internal(
-                                &self.local_selection_sets.borrow(),
-                                &self.buffers.borrow(),
-                                &epoch,
-                                *buffer_id,
-                            )?,
-                        );
+        let (fixup_ops, tree_changes) = epoch
+            .apply_ops_with_changes(cur_epoch_ops, &mut self.lamport_clock.write().unwrap())?;
+
+        if let Some(observer) = self.observer.as_ref() {
+            if !tree_changes.is_empty() {
+                observer.tree_changed(tree_changes);
+            }
+
+            if let Some(replica_id) = followed_replica {
+                let followed_presence = Self::replica_presence(&epoch, replica_id);
+                if followed_presence != prev_followed_presence {
+                    if let Some(presence) = followed_presence {
+                        observer.followed_replica_changed(replica_id, presence);
                     }
                 }
             }
 
-            let fixup_ops_stream = Box::new(stream::iter_ok(OperationEnvelope::wrap_many(
-                epoch.id, epoch.head, fixup_ops,
-            )));
-            Ok(epoch_streams.into_iter().fold(
-                fixup_ops_stream as Box<Stream<Item = OperationEnvelope, Error = Error>>,
-                |acc, stream| Box::new(acc.chain(stream)),
-            ))
-        } else {
-            Err(Error::InvalidOperations)
+            for (buffer_id, file_id) in buffers.iter() {
+                let (edit_version, selections_last_update) = prev_versions.remove(file_id).unwrap();
+                let changes: Vec<_> = epoch.changes_since(*file_id, &edit_version)?.collect();
+                if !changes.is_empty()
+                    || epoch.selections_changed_since(*file_id, selections_last_update)?
+                {
+                    observer.changed(
+                        *buffer_id,
+                        changes,
+                        Self::selection_ranges_internal(
+                            &epoch,
+                            &buffers,
+                            &self.local_selection_sets.read().unwrap(),
+                            *buffer_id,
+                        )?,
+                    );
+                }
+            }
         }
+
+        let fixup_ops_stream = Box::new(stream::iter_ok(OperationEnvelope::wrap_many(
+            epoch.id, epoch.head, fixup_ops,
+        )));
+        Ok(epoch_streams.into_iter().fold(
+            fixup_ops_stream as Box<Stream<Item = OperationEnvelope, Error = Error> + Send>,
+            |acc, stream| Box::new(acc.chain(stream)),
+        ))
     }
 
     fn start_epoch(
-        &mut self,
+        &self,
         new_epoch_id: epoch::Id,
         new_head: Option<Oid>,
-    ) -> Box<Stream<Item = OperationEnvelope, Error = Error>> {
-        if self
-            .epoch
-            .as_ref()
-            .map_or(true, |e| new_epoch_id > e.borrow().id)
-        {
-            let new_epoch = Rc::new(RefCell::new(Epoch::new(
-                self.replica_id(),
-                new_epoch_id,
-                new_head,
-            )));
+    ) -> Box<Stream<Item = OperationEnvelope, Error = Error> + Send> {
+        if new_epoch_id > self.epoch_id() {
+            let mut new_epoch = Epoch::new(self.replica_id(), new_epoch_id, new_head);
+            new_epoch.set_partial_replication(self.cur_epoch().partial_replication());
+            let new_epoch = Arc::new(RwLock::new(new_epoch));
+
+            // Changes to an epoch that is still loading are reported when switching to it.
+            let load_base_entries = self.load_base_entries(new_epoch.clone(), None);
+            let switch_epoch = SwitchEpoch::new(
+                new_epoch,
+                self.epoch.clone(),
+                self.buffers.clone(),
+                self.local_selection_sets.clone(),
+                self.deferred_ops.clone(),
+                self.lamport_clock.clone(),
+                self.git.clone(),
+                self.observer.clone(),
+            )
+            .then(|fixup_ops| Ok(stream::iter_ok(fixup_ops?)))
+            .flatten_stream();
+            Box::new(load_base_entries.chain(switch_epoch))
+        } else {
+            Box::new(stream::empty())
+        }
+    }
+
+    fn load_base_entries(
+        &self,
+        epoch: Arc<RwLock<Epoch>>,
+        observer: Option<Arc<ChangeObserver>>,
+    ) -> Box<Stream<Item = OperationEnvelope, Error = Error> + Send> {
+        let (epoch_id, head, loaded_entries) = {
+            let epoch = epoch.read().unwrap();
+            (epoch.id, epoch.head, epoch.base_entries_len())
+        };
 
+        if let Some(head) = head {
             let lamport_clock = self.lamport_clock.clone();
-            let new_epoch_clone = new_epoch.clone();
-            let load_base_entries = if let Some(new_head) = new_head {
-                Box::new(
-                    self.git
-                        .base_entries(new_head)
-                        .map_err(|err| Error::IoError(err))
-                        .chunks(500)
-                        .and_then(move |base_entries| {
-                            let fixup_ops = new_epoch_clone.borrow_mut().append_base_entries(
+            Box::new(
+                self.git
+                    .base_entries(head)
+                    .skip(loaded_entries)
+                    .map_err(|err| Error::IoError(err))
+                    .chunks(500)
+                    .and_then(move |base_entries| {
+                        let (fixup_ops, tree_changes) =
+                            epoch.write().unwrap().append_base_entries_with_changes(
                                 base_entries,
-                                &mut lamport_clock.borrow_mut(),
+                                &mut lamport_clock.write().unwrap(),
                             )?;
-                            Ok(stream::iter_ok(OperationEnvelope::wrap_many(
-                                new_epoch_id,
-                                Some(new_head),
-                                fixup_ops,
-                            )))
-                        })
-                        .flatten(),
-                ) as Box<Stream<Item = OperationEnvelope, Error = Error>>
-            } else {
-                Box::new(stream::empty())
-            };
-
-            if let Some(cur_epoch) = self.epoch.clone() {
-                let switch_epoch = SwitchEpoch::new(
-                    new_epoch,
-                    cur_epoch,
-                    self.buffers.clone(),
-                    self.local_selection_sets.clone(),
-                    self.deferred_ops.clone(),
-                    self.lamport_clock.clone(),
-                    self.git.clone(),
-                    self.observer.clone(),
-                )
-                .then(|fixup_ops| Ok(stream::iter_ok(fixup_ops?)))
-                .flatten_stream();
-                Box::new(load_base_entries.chain(switch_epoch))
-            } else {
-                self.epoch = Some(new_epoch.clone());
-                load_base_entries
-            }
+                        if let Some(observer) = observer.as_ref() {
+                            if !tree_changes.is_empty() {
+                                observer.tree_changed(tree_changes);
+                            }
+                        }
+                        Ok(stream::iter_ok(OperationEnvelope::wrap_many(
+                            epoch_id,
+                            Some(head),
+                            fixup_ops,
+                        )))
+                    })
+                    .flatten(),
+            )
         } else {
             Box::new(stream::empty())
         }
     }
 
+    /// Enables partial replication, under which operations for text files that haven't been opened
+    /// or subscribed to are only applied once they are. Replicas that didn't receive them can catch
+    /// up by applying the `buffer_operations` of another replica after opening the file.
+    pub fn set_partial_replication(&self, enabled: bool) {
+        self.cur_epoch_mut().set_partial_replication(enabled);
+    }
+
+    /// Applies the operations for the text file at the given path as they are received, even
+    /// under partial replication and before the file is opened.
+    pub fn subscribe<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut epoch = self.cur_epoch_mut();
+        let file_id = epoch.file_id(path)?;
+        epoch.subscribe(file_id)
+    }
+
+    pub fn unsubscribe<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut epoch = self.cur_epoch_mut();
+        let file_id = epoch.file_id(path)?;
+        epoch.unsubscribe(file_id);
+        Ok(())
+    }
+
+    pub fn buffer_operations<P>(&self, path: P) -> Result<Vec<OperationEnvelope>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let epoch = self.cur_epoch();
+        let file_id = epoch.file_id(path)?;
+        Ok(OperationEnvelope::wrap_many(
+            epoch.id,
+            epoch.head,
+            epoch.buffer_operations(file_id)?,
+        ))
+    }
+
     pub fn observed(&self, other: Version) -> bool {
         let version = self.version();
         match version.epoch_id.cmp(&other.epoch_id) {
@@ -314,6 +662,126 @@ impl WorkTree {
         }
     }
 
+    /// Returns the operations that a replica at `version` is missing. Replicas on a previous epoch
+    /// receive the start of the current epoch followed by all of its operations.
+    pub fn operations_since(&self, version: Version) -> Result<Vec<OperationEnvelope>, Error> {
+        let epoch = self.cur_epoch();
+        let mut envelopes = Vec::new();
+        let epoch_version = match version.epoch_id.cmp(&epoch.id) {
+            Ordering::Less => {
+                envelopes.push(OperationEnvelope {
+                    epoch_head: epoch.head,
+                    operation: Operation::StartEpoch {
+                        epoch_id: epoch.id,
+                        head: epoch.head,
+                    },
+                });
+                time::Global::new()
+            }
+            Ordering::Equal => version.epoch_version,
+            Ordering::Greater => return Ok(envelopes),
+        };
+        envelopes.extend(OperationEnvelope::wrap_many(
+            epoch.id,
+            epoch.head,
+            epoch.operations_since(&epoch_version)?,
+        ));
+        Ok(envelopes)
+    }
+
+    pub fn status(&self) -> Vec<StatusEntry> {
+        self.cur_epoch().status()
+    }
+
+    pub fn diff<P>(&self, path: P) -> Box<Future<Item = Vec<LineDiffHunk>, Error = Error> + Send>
+    where
+        P: AsRef<Path>,
+    {
+        let epoch = self.epoch.clone();
+        Box::new(
+            Self::base_text(path.as_ref(), epoch.as_ref(), self.git.as_ref()).and_then(
+                move |(file_id, base_text)| {
+                    let text = epoch
+                        .read()
+                        .unwrap()
+                        .text_with_base(file_id, base_text.as_str())?;
+                    Ok(buffer::line_diff(&base_text, &text))
+                },
+            ),
+        )
+    }
+
+    /// Writes the current state of the tree to `repo` as loose blob and tree objects, returning
+    /// the oid of the root tree. The text of files that haven't been opened is reconstructed from
+    /// the base commit and the operations received for them.
+    pub fn write_tree(&self, repo: &Repository) -> Box<Future<Item = Oid, Error = Error> + Send> {
+        let epoch = self.cur_epoch();
+        let mut dir_paths = Vec::new();
+        let mut files = Vec::new();
+        let mut texts = Vec::new();
+        if let Some(mut cursor) = epoch.cursor() {
+            loop {
+                let entry = cursor.entry().unwrap();
+                if entry.visible {
+                    let path = cursor.path().unwrap().to_path_buf();
+                    let attributes = epoch.attributes(entry.file_id).unwrap();
+                    match entry.file_type {
+                        FileType::Directory => dir_paths.push(path),
+                        FileType::Symlink | FileType::Binary => files.push((path, attributes)),
+                        FileType::Text => {
+                            let file_id = entry.file_id;
+                            let text: Box<Future<Item = String, Error = Error> + Send> =
+                                if let Ok(text) = epoch.text(file_id) {
+                                    Box::new(future::ok(text.into_string()))
+                                } else if let (Some(head), Some(base_path)) =
+                                    (epoch.head, epoch.base_path(file_id))
+                                {
+                                    let epoch = self.epoch.clone();
+                                    Box::new(
+                                        self.git
+                                            .base_text(head, &base_path)
+                                            .map_err(|err| Error::IoError(err))
+                                            .and_then(move |base_text| {
+                                                epoch
+                                                    .read()
+                                                    .unwrap()
+                                                    .text_with_base(file_id, base_text)
+                                            }),
+                                    )
+                                } else {
+                                    Box::new(future::result(epoch.text_with_base(file_id, "")))
+                                };
+                            texts.push(text.map(move |text| (path, attributes, text)));
+                        }
+                    }
+                }
+
+                if !cursor.next(entry.visible) {
+                    break;
+                }
+            }
+        }
+
+        let repo = repo.clone();
+        Box::new(future::join_all(texts).and_then(move |texts| {
+            let mut tree = TreeBuilder::new();
+            for path in dir_paths {
+                tree.insert_dir(&path);
+            }
+            for (path, attributes) in files {
+                if let Some(target) = attributes.symlink_target {
+                    tree.insert_symlink(&path, &target);
+                } else if let Some(oid) = attributes.oid {
+                    tree.insert_object(&path, oid, attributes.executable);
+                }
+            }
+            for (path, attributes, text) in texts {
+                tree.insert_text(&path, text, attributes.executable);
+            }
+            Ok(tree.write(&repo)?)
+        }))
+    }
+
     pub fn with_cursor<F>(&self, mut f: F)
     where
         F: FnMut(&mut Cursor),
@@ -324,6 +792,18 @@ impl WorkTree {
     }
 
     pub fn create_file<P>(&self, path: P, file_type: FileType) -> Result<OperationEnvelope, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.create_file_with_attributes(path, file_type, FileAttributes::default())
+    }
+
+    pub fn create_file_with_attributes<P>(
+        &self,
+        path: P,
+        file_type: FileType,
+        attributes: FileAttributes,
+    ) -> Result<OperationEnvelope, Error>
     where
         P: AsRef<Path>,
     {
@@ -337,11 +817,12 @@ impl WorkTree {
         } else {
             epoch::ROOT_FILE_ID
         };
-        let operation = cur_epoch.create_file(
+        let operation = cur_epoch.create_file_with_attributes(
             parent_id,
             name,
             file_type,
-            &mut self.lamport_clock.borrow_mut(),
+            attributes,
+            &mut self.lamport_clock.write().unwrap(),
         )?;
 
         Ok(OperationEnvelope::wrap(
@@ -374,7 +855,7 @@ impl WorkTree {
             file_id,
             new_parent_id,
             new_name,
-            &mut self.lamport_clock.borrow_mut(),
+            &mut self.lamport_clock.write().unwrap(),
         )?;
 
         Ok(OperationEnvelope::wrap(
@@ -384,6 +865,47 @@ impl WorkTree {
         ))
     }
 
+    pub fn conflicts(&self) -> Vec<ConflictEntry> {
+        self.cur_epoch().conflicts()
+    }
+
+    /// Resolves the conflict involving the file at `path` in favor of the file at `winner_path`,
+    /// which must be either `path` itself or the path of the file it conflicted with.
+    pub fn resolve_conflict<P1, P2>(
+        &self,
+        path: P1,
+        winner_path: P2,
+    ) -> Result<Vec<OperationEnvelope>, Error>
+    where
+        P1: AsRef<Path>,
+        P2: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let winner_path = winner_path.as_ref();
+
+        let mut cur_epoch = self.cur_epoch_mut();
+        let conflict = cur_epoch
+            .conflicts()
+            .into_iter()
+            .find(|entry| {
+                entry.path == path && (entry.winner_path == winner_path || path == winner_path)
+            })
+            .ok_or(Error::InvalidPath("no conflict at the given paths".into()))?
+            .conflict;
+        let winner_id = cur_epoch.file_id(winner_path)?;
+        let operations = cur_epoch.resolve_conflict(
+            &conflict,
+            winner_id,
+            &mut self.lamport_clock.write().unwrap(),
+        )?;
+
+        Ok(OperationEnvelope::wrap_many(
+            cur_epoch.id,
+            cur_epoch.head,
+            operations,
+        ))
+    }
+
     pub fn set_active_location(
         &self,
         buffer_id: Option<BufferId>,
@@ -395,8 +917,52 @@ impl WorkTree {
             None
         };
         let operation =
-            cur_epoch.set_active_location(file_id, &mut self.lamport_clock.borrow_mut())?;
+            cur_epoch.set_active_location(file_id, &mut self.lamport_clock.write().unwrap())?;
+
+        Ok(OperationEnvelope::wrap(
+            cur_epoch.id,
+            cur_epoch.head,
+            operation,
+        ))
+    }
+
+    pub fn set_scroll_position(
+        &self,
+        buffer_id: BufferId,
+        scroll_position: Point,
+    ) -> Result<OperationEnvelope, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.update_presence(|presence| {
+            presence.file_id = Some(file_id);
+            presence.scroll_position = Some(scroll_position);
+        })
+    }
+
+    pub fn set_user(&self, user: UserInfo) -> Result<OperationEnvelope, Error> {
+        self.update_presence(|presence| presence.user = user)
+    }
 
+    fn update_presence<F>(&self, f: F) -> Result<OperationEnvelope, Error>
+    where
+        F: FnOnce(&mut Presence),
+    {
+        let mut cur_epoch = self.cur_epoch_mut();
+        let mut lamport_clock = self.lamport_clock.write().unwrap();
+        let mut presence = cur_epoch
+            .presence(lamport_clock.replica_id)
+            .unwrap_or(Presence {
+                user: UserInfo::default(),
+                file_id: None,
+                cursor: None,
+                scroll_position: None,
+            });
+        f(&mut presence);
+        let operation = cur_epoch.set_presence(
+            presence.file_id,
+            presence.scroll_position,
+            presence.user,
+            &mut lamport_clock,
+        )?;
         Ok(OperationEnvelope::wrap(
             cur_epoch.id,
             cur_epoch.head,
@@ -404,6 +970,32 @@ impl WorkTree {
         ))
     }
 
+    pub fn presence(&self) -> HashMap<ReplicaId, ReplicaPresence> {
+        let epoch = self.cur_epoch();
+        epoch
+            .replica_ids()
+            .filter_map(|replica_id| {
+                Self::replica_presence(&epoch, replica_id).map(|presence| (replica_id, presence))
+            })
+            .collect()
+    }
+
+    /// Starts reporting the given replica's presence to the observer whenever it changes, and
+    /// returns where it is right now. Passing `None` stops following.
+    pub fn follow(&self, replica_id: Option<ReplicaId>) -> Option<ReplicaPresence> {
+        *self.followed_replica.write().unwrap() = replica_id;
+        replica_id.and_then(|replica_id| Self::replica_presence(&self.cur_epoch(), replica_id))
+    }
+
+    fn replica_presence(epoch: &Epoch, replica_id: ReplicaId) -> Option<ReplicaPresence> {
+        epoch.presence(replica_id).map(|presence| ReplicaPresence {
+            user: presence.user,
+            path: presence.file_id.and_then(|file_id| epoch.path(file_id)),
+            cursor: presence.cursor,
+            scroll_position: presence.scroll_position,
+        })
+    }
+
     pub fn replica_locations(&self) -> HashMap<ReplicaId, PathBuf> {
         let epoch = self.cur_epoch();
         let mut locations = HashMap::new();
@@ -421,7 +1013,7 @@ impl WorkTree {
     {
         let mut cur_epoch = self.cur_epoch_mut();
         let file_id = cur_epoch.file_id(path.as_ref())?;
-        let operation = cur_epoch.remove(file_id, &mut self.lamport_clock.borrow_mut())?;
+        let operation = cur_epoch.remove(file_id, &mut self.lamport_clock.write().unwrap())?;
 
         Ok(OperationEnvelope::wrap(
             cur_epoch.id,
@@ -430,20 +1022,79 @@ impl WorkTree {
         ))
     }
 
-    pub fn exists<P>(&self, path: P) -> bool
+    pub fn update_attributes<P>(
+        &self,
+        path: P,
+        attributes: FileAttributes,
+    ) -> Result<OperationEnvelope, Error>
     where
         P: AsRef<Path>,
     {
-        self.cur_epoch().file_id(path).is_ok()
+        let mut cur_epoch = self.cur_epoch_mut();
+        let file_id = cur_epoch.file_id(path.as_ref())?;
+        let operation = cur_epoch.update_attributes(
+            file_id,
+            attributes,
+            &mut self.lamport_clock.write().unwrap(),
+        )?;
+
+        Ok(OperationEnvelope::wrap(
+            cur_epoch.id,
+            cur_epoch.head,
+            operation,
+        ))
     }
 
-    pub fn open_text_file<P>(&self, path: P) -> Box<Future<Item = BufferId, Error = Error>>
+    pub fn exists<P>(&self, path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        self.cur_epoch().file_id(path).is_ok()
+    }
+
+    pub fn file_type<P>(&self, path: P) -> Result<FileType, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let epoch = self.cur_epoch();
+        epoch
+            .file_id(path)
+            .and_then(|file_id| epoch.file_type(file_id))
+    }
+
+    pub fn attributes<P>(&self, path: P) -> Result<FileAttributes, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let epoch = self.cur_epoch();
+        epoch
+            .file_id(path)
+            .and_then(|file_id| epoch.attributes(file_id))
+    }
+
+    /// Returns the text of the file at `path` in the base commit, without reflecting any edits.
+    pub fn read_base_text<P>(&self, path: P) -> Box<Future<Item = String, Error = Error> + Send>
+    where
+        P: AsRef<Path>,
+    {
+        Box::new(
+            Self::base_text(path.as_ref(), &self.epoch, self.git.as_ref())
+                .map(|(_, base_text)| base_text),
+        )
+    }
+
+    /// Returns the contents of a binary file, given the oid in its attributes.
+    pub fn read_blob(&self, oid: Oid) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+        Box::new(self.git.blob(oid).map_err(|error| error.into()))
+    }
+
+    pub fn open_text_file<P>(&self, path: P) -> Box<Future<Item = BufferId, Error = Error> + Send>
     where
         P: Into<PathBuf>,
     {
         Self::open_text_file_internal(
             path.into(),
-            self.epoch.clone().unwrap(),
+            self.epoch.clone(),
             self.git.clone(),
             self.buffers.clone(),
             self.next_buffer_id.clone(),
@@ -453,36 +1104,47 @@ impl WorkTree {
 
     fn open_text_file_internal(
         path: PathBuf,
-        epoch: Rc<RefCell<Epoch>>,
-        git: Rc<GitProvider>,
-        buffers: Rc<RefCell<HashMap<BufferId, FileId>>>,
-        next_buffer_id: Rc<RefCell<BufferId>>,
-        lamport_clock: Rc<RefCell<time::Lamport>>,
-    ) -> Box<Future<Item = BufferId, Error = Error>> {
+        epoch: Arc<RwLock<Epoch>>,
+        git: Arc<GitProvider>,
+        buffers: Arc<RwLock<HashMap<BufferId, FileId>>>,
+        next_buffer_id: Arc<RwLock<BufferId>>,
+        lamport_clock: Arc<RwLock<time::Lamport>>,
+    ) -> Box<Future<Item = BufferId, Error = Error> + Send> {
         if let Some(buffer_id) = Self::existing_buffer(&epoch, &buffers, &path) {
             Box::new(future::ok(buffer_id))
         } else {
-            let epoch_id = epoch.borrow().id;
+            let epoch_id = epoch.read().unwrap().id;
             Box::new(
                 Self::base_text(&path, epoch.as_ref(), git.as_ref()).and_then(
                     move |(file_id, base_text)| {
-                        if let Some(buffer_id) = Self::existing_buffer(&epoch, &buffers, &path) {
-                            Box::new(future::ok(buffer_id))
-                        } else if epoch.borrow().id == epoch_id {
-                            match epoch.borrow_mut().open_text_file(
-                                file_id,
-                                base_text,
-                                &mut lamport_clock.borrow_mut(),
-                            ) {
-                                Ok(()) => {
-                                    let buffer_id = *next_buffer_id.borrow();
-                                    next_buffer_id.borrow_mut().0 += 1;
-                                    buffers.borrow_mut().insert(buffer_id, file_id);
-                                    Box::new(future::ok(buffer_id))
+                        // Check for a buffer opened by a concurrent call and register ours under
+                        // the same locks, so a file is never assigned two buffer ids.
+                        let mut cur_epoch = epoch.write().unwrap();
+                        if cur_epoch.id == epoch_id {
+                            let mut open_buffers = buffers.write().unwrap();
+                            if let Some(buffer_id) =
+                                Self::find_buffer(&cur_epoch, &open_buffers, &path)
+                            {
+                                Box::new(future::ok(buffer_id))
+                            } else {
+                                let mut next_buffer_id = next_buffer_id.write().unwrap();
+                                let result = cur_epoch.open_text_file(
+                                    file_id,
+                                    base_text,
+                                    &mut lamport_clock.write().unwrap(),
+                                );
+                                match result {
+                                    Ok(()) => {
+                                        let buffer_id = *next_buffer_id;
+                                        next_buffer_id.0 += 1;
+                                        open_buffers.insert(buffer_id, file_id);
+                                        Box::new(future::ok(buffer_id))
+                                    }
+                                    Err(error) => Box::new(future::err(error)),
                                 }
-                                Err(error) => Box::new(future::err(error)),
                             }
                         } else {
+                            drop(cur_epoch);
                             Self::open_text_file_internal(
                                 path,
                                 epoch,
@@ -499,12 +1161,19 @@ impl WorkTree {
     }
 
     fn existing_buffer(
-        epoch: &Rc<RefCell<Epoch>>,
-        buffers: &Rc<RefCell<HashMap<BufferId, FileId>>>,
+        epoch: &Arc<RwLock<Epoch>>,
+        buffers: &Arc<RwLock<HashMap<BufferId, FileId>>>,
+        path: &Path,
+    ) -> Option<BufferId> {
+        Self::find_buffer(&epoch.read().unwrap(), &buffers.read().unwrap(), path)
+    }
+
+    fn find_buffer(
+        epoch: &Epoch,
+        buffers: &HashMap<BufferId, FileId>,
         path: &Path,
     ) -> Option<BufferId> {
-        let epoch = epoch.borrow();
-        for (buffer_id, file_id) in buffers.borrow().iter() {
+        for (buffer_id, file_id) in buffers {
             if let Some(existing_path) = epoch.path(*file_id) {
                 if path == existing_path {
                     return Some(*buffer_id);
@@ -514,12 +1183,46 @@ impl WorkTree {
         None
     }
 
+    /// Returns the buffer that is currently open for the file at `path`, if any.
+    pub fn buffer_id<P>(&self, path: P) -> Option<BufferId>
+    where
+        P: AsRef<Path>,
+    {
+        Self::existing_buffer(&self.epoch, &self.buffers, path.as_ref())
+    }
+
+    /// Releases a buffer opened with `open_text_file`. Its local selection sets are removed and
+    /// the observer stops hearing about it, but the file's text remains part of the tree.
+    pub fn close_buffer(&self, buffer_id: BufferId) -> Result<Vec<OperationEnvelope>, Error> {
+        let mut cur_epoch = self.cur_epoch_mut();
+        let mut buffers = self.buffers.write().unwrap();
+        let mut local_selection_sets = self.local_selection_sets.write().unwrap();
+        let mut lamport_clock = self.lamport_clock.write().unwrap();
+        let file_id = buffers
+            .get(&buffer_id)
+            .cloned()
+            .ok_or(Error::InvalidBufferId)?;
+        cur_epoch.end_all_transactions(file_id)?;
+        let mut ops = Vec::new();
+        if let Some(buffer_sets) = local_selection_sets.remove(&buffer_id) {
+            for set_id in buffer_sets.values() {
+                ops.push(cur_epoch.remove_selection_set(file_id, *set_id, &mut lamport_clock)?);
+            }
+        }
+        buffers.remove(&buffer_id);
+        Ok(OperationEnvelope::wrap_many(
+            cur_epoch.id,
+            cur_epoch.head,
+            ops,
+        ))
+    }
+
     fn base_text(
         path: &Path,
-        epoch: &RefCell<Epoch>,
+        epoch: &RwLock<Epoch>,
         git: &GitProvider,
-    ) -> Box<Future<Item = (FileId, String), Error = Error>> {
-        let epoch = epoch.borrow();
+    ) -> Box<Future<Item = (FileId, String), Error = Error> + Send> {
+        let epoch = epoch.read().unwrap();
         match epoch.file_id(&path) {
             Ok(file_id) => {
                 if let (Some(head), Some(base_path)) = (epoch.head, epoch.base_path(file_id)) {
@@ -553,7 +1256,7 @@ impl WorkTree {
                 file_id,
                 old_ranges,
                 new_text,
-                &mut self.lamport_clock.borrow_mut(),
+                &mut self.lamport_clock.write().unwrap(),
             )
             .unwrap();
 
@@ -581,7 +1284,7 @@ impl WorkTree {
                 file_id,
                 old_ranges,
                 new_text,
-                &mut self.lamport_clock.borrow_mut(),
+                &mut self.lamport_clock.write().unwrap(),
             )
             .unwrap();
 
@@ -592,6 +1295,122 @@ impl WorkTree {
         ))
     }
 
+    pub fn start_transaction(&self, buffer_id: BufferId) -> Result<(), Error> {
+        self.start_transaction_at(buffer_id, SystemTime::now())
+    }
+
+    pub fn start_transaction_at(&self, buffer_id: BufferId, now: SystemTime) -> Result<(), Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch_mut().start_transaction(file_id, now)
+    }
+
+    pub fn end_transaction(
+        &self,
+        buffer_id: BufferId,
+        group_interval: Option<Duration>,
+    ) -> Result<(), Error> {
+        self.end_transaction_at(buffer_id, SystemTime::now(), group_interval)
+    }
+
+    pub fn end_transaction_at(
+        &self,
+        buffer_id: BufferId,
+        now: SystemTime,
+        group_interval: Option<Duration>,
+    ) -> Result<(), Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch_mut()
+            .end_transaction(file_id, now, group_interval)
+    }
+
+    pub fn undo(&self, buffer_id: BufferId) -> Result<OperationEnvelope, Error> {
+        self.undo_or_redo(buffer_id, true)
+    }
+
+    pub fn redo(&self, buffer_id: BufferId) -> Result<OperationEnvelope, Error> {
+        self.undo_or_redo(buffer_id, false)
+    }
+
+    fn undo_or_redo(&self, buffer_id: BufferId, undo: bool) -> Result<OperationEnvelope, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        let mut cur_epoch = self.cur_epoch_mut();
+        let prev_version = cur_epoch.buffer_version(file_id)?;
+        let operation = if undo {
+            cur_epoch.undo(file_id, &mut self.lamport_clock.write().unwrap())?
+        } else {
+            cur_epoch.redo(file_id, &mut self.lamport_clock.write().unwrap())?
+        };
+
+        // Unlike edits, the host can't know which text was affected by undoing or redoing, so we
+        // report the changes to the observer.
+        if let Some(observer) = self.observer.as_ref() {
+            let changes: Vec<_> = cur_epoch.changes_since(file_id, &prev_version)?.collect();
+            if !changes.is_empty() {
+                observer.changed(
+                    buffer_id,
+                    changes,
+                    Self::selection_ranges_internal(
+                        &cur_epoch,
+                        &self.buffers.read().unwrap(),
+                        &self.local_selection_sets.read().unwrap(),
+                        buffer_id,
+                    )?,
+                );
+            }
+        }
+
+        Ok(OperationEnvelope::wrap(
+            cur_epoch.id,
+            cur_epoch.head,
+            operation,
+        ))
+    }
+
+    /// Discards the undo history for the operations observed by this replica and every version in
+    /// `observed_versions`, which must include the latest version reported by each of the other
+    /// replicas. The returned version must be delivered to every other replica after the operations
+    /// generated so far, to be passed to their `collect_garbage`.
+    pub fn acknowledge_versions<I>(&self, observed_versions: I) -> Version
+    where
+        I: IntoIterator<Item = Version>,
+    {
+        let mut cur_epoch = self.cur_epoch_mut();
+        let stable_version = Self::stable_version(&cur_epoch, observed_versions);
+        cur_epoch.discard_history(&stable_version);
+        Version {
+            epoch_id: cur_epoch.id,
+            epoch_version: stable_version,
+        }
+    }
+
+    /// Compacts the history and the tombstones of the operations that every other replica has
+    /// acknowledged. `acknowledged_versions` must include the latest version returned by
+    /// `acknowledge_versions` on each of the other replicas, which guarantees that none of them
+    /// will undo those operations or anchor new ones to the discarded tombstones.
+    pub fn collect_garbage<I>(&self, acknowledged_versions: I)
+    where
+        I: IntoIterator<Item = Version>,
+    {
+        let mut cur_epoch = self.cur_epoch_mut();
+        let stable_version = Self::stable_version(&cur_epoch, acknowledged_versions);
+        cur_epoch.collect_garbage(&stable_version);
+    }
+
+    fn stable_version<I>(epoch: &Epoch, versions: I) -> time::Global
+    where
+        I: IntoIterator<Item = Version>,
+    {
+        let mut stable_version = epoch.version();
+        for version in versions {
+            if version.epoch_id == epoch.id {
+                stable_version = stable_version.meet(&version.epoch_version);
+            } else {
+                return time::Global::new();
+            }
+        }
+        stable_version
+    }
+
     pub fn add_selection_set<I>(
         &self,
         buffer_id: BufferId,
@@ -602,11 +1421,14 @@ impl WorkTree {
     {
         let file_id = self.buffer_file_id(buffer_id)?;
         let mut cur_epoch = self.cur_epoch_mut();
-        let (remote_set_id, operation) =
-            cur_epoch.add_selection_set(file_id, ranges, &mut self.lamport_clock.borrow_mut())?;
+        let (remote_set_id, operation) = cur_epoch.add_selection_set(
+            file_id,
+            ranges,
+            &mut self.lamport_clock.write().unwrap(),
+        )?;
 
         let local_set_id = self.gen_local_set_id();
-        let mut local_selection_sets = self.local_selection_sets.borrow_mut();
+        let mut local_selection_sets = self.local_selection_sets.write().unwrap();
         let buffer_sets = local_selection_sets
             .entry(buffer_id)
             .or_insert(HashMap::new());
@@ -634,7 +1456,7 @@ impl WorkTree {
             file_id,
             set_id,
             ranges,
-            &mut self.lamport_clock.borrow_mut(),
+            &mut self.lamport_clock.write().unwrap(),
         )?;
         Ok(OperationEnvelope::wrap(
             cur_epoch.id,
@@ -654,10 +1476,11 @@ impl WorkTree {
         let operation = cur_epoch.remove_selection_set(
             file_id,
             set_id,
-            &mut self.lamport_clock.borrow_mut(),
+            &mut self.lamport_clock.write().unwrap(),
         )?;
         self.local_selection_sets
-            .borrow_mut()
+            .write()
+            .unwrap()
             .get_mut(&buffer_id)
             .unwrap()
             .remove(&local_set_id);
@@ -670,7 +1493,8 @@ impl WorkTree {
 
     pub fn path(&self, buffer_id: BufferId) -> Option<PathBuf> {
         self.buffers
-            .borrow()
+            .read()
+            .unwrap()
             .get(&buffer_id)
             .and_then(|file_id| self.cur_epoch().path(*file_id))
     }
@@ -680,22 +1504,27 @@ impl WorkTree {
         self.cur_epoch().text(file_id)
     }
 
+    pub fn buffer_snapshot(&self, buffer_id: BufferId) -> Result<BufferSnapshot, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().buffer_snapshot(file_id)
+    }
+
     pub fn selection_ranges(&self, buffer_id: BufferId) -> Result<BufferSelectionRanges, Error> {
         Self::selection_ranges_internal(
-            &self.local_selection_sets.borrow(),
-            &self.buffers.borrow(),
             &self.cur_epoch(),
+            &self.buffers.read().unwrap(),
+            &self.local_selection_sets.read().unwrap(),
             buffer_id,
         )
     }
 
     fn selection_ranges_internal(
+        epoch: &Epoch,
+        buffers: &HashMap<BufferId, FileId>,
         local_selection_sets: &HashMap<
             BufferId,
             HashMap<LocalSelectionSetId, buffer::SelectionSetId>,
         >,
-        buffers: &HashMap<BufferId, FileId>,
-        epoch: &Epoch,
         buffer_id: BufferId,
     ) -> Result<BufferSelectionRanges, Error> {
         let file_id = buffers
@@ -738,142 +1567,599 @@ impl WorkTree {
         self.cur_epoch().changes_since(file_id, version)
     }
 
-    pub fn buffer_deferred_ops_len(&self, buffer_id: BufferId) -> Result<usize, Error> {
+    pub(crate) fn buffer_version(&self, buffer_id: BufferId) -> Result<time::Global, Error> {
         let file_id = self.buffer_file_id(buffer_id)?;
-        self.cur_epoch().buffer_deferred_ops_len(file_id)
-    }
-
-    fn cur_epoch(&self) -> Ref<Epoch> {
-        self.epoch.as_ref().unwrap().borrow()
+        self.cur_epoch().buffer_version(file_id)
     }
 
-    fn cur_epoch_mut(&self) -> RefMut<Epoch> {
-        self.epoch.as_ref().unwrap().borrow_mut()
+    /// Reports the changes made to a buffer since `version` to the observer. The host only knows
+    /// about its own edits, so this is needed whenever the tree edits a buffer on its behalf.
+    pub(crate) fn report_changes_since(
+        &self,
+        buffer_id: BufferId,
+        version: &time::Global,
+    ) -> Result<(), Error> {
+        if let Some(observer) = self.observer.as_ref() {
+            let changes = self.changes_since(buffer_id, version)?.collect::<Vec<_>>();
+            if !changes.is_empty() {
+                observer.changed(buffer_id, changes, self.selection_ranges(buffer_id)?);
+            }
+        }
+        Ok(())
     }
 
-    fn defer_epoch_op(&self, epoch_id: epoch::Id, operation: epoch::Operation) {
-        self.deferred_ops
-            .borrow_mut()
-            .entry(epoch_id)
-            .or_insert(Vec::new())
-            .push(operation);
+    /// Returns the buffer's text as of `version`, which must belong to the current epoch.
+    pub fn text_at(&self, buffer_id: BufferId, version: &Version) -> Result<Vec<u16>, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        let epoch = self.cur_epoch();
+        if version.epoch_id != epoch.id {
+            return Err(Error::VersionUnavailable);
+        }
+        epoch.text_at(file_id, &version.epoch_version)
     }
 
-    fn replica_id(&self) -> ReplicaId {
-        self.lamport_clock.borrow().replica_id
+    /// Returns the changes that turn the buffer's text as of `from` into its text as of `to`.
+    pub fn changes_between(
+        &self,
+        buffer_id: BufferId,
+        from: &Version,
+        to: &Version,
+    ) -> Result<Vec<buffer::Change>, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        let epoch = self.cur_epoch();
+        if from.epoch_id != epoch.id || to.epoch_id != epoch.id {
+            return Err(Error::VersionUnavailable);
+        }
+        epoch.changes_between(file_id, &from.epoch_version, &to.epoch_version)
     }
 
-    fn buffer_file_id(&self, buffer_id: BufferId) -> Result<FileId, Error> {
-        self.buffers
-            .borrow()
-            .get(&buffer_id)
-            .cloned()
-            .ok_or(Error::InvalidBufferId)
+    /// Returns who inserted each span of the buffer's text.
+    pub fn authorship(&self, buffer_id: BufferId) -> Result<Vec<AuthorshipSpan>, Error> {
+        self.authorship_in_rows(buffer_id, 0..u32::max_value())
     }
 
-    fn gen_local_set_id(&self) -> LocalSelectionSetId {
-        let local_set_id = *self.next_local_selection_set_id.borrow();
-        self.next_local_selection_set_id.borrow_mut().0 += 1;
-        local_set_id
+    pub fn authorship_in_rows(
+        &self,
+        buffer_id: BufferId,
+        rows: Range<u32>,
+    ) -> Result<Vec<AuthorshipSpan>, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().authorship_in_rows(file_id, rows)
     }
 
-    fn selection_set_id(
+    pub fn search(
         &self,
         buffer_id: BufferId,
-        set_id: LocalSelectionSetId,
-    ) -> Result<buffer::SelectionSetId, Error> {
-        self.local_selection_sets
-            .borrow()
-            .get(&buffer_id)
-            .ok_or(Error::InvalidLocalSelectionSet(set_id))?
-            .get(&set_id)
-            .cloned()
-            .ok_or(Error::InvalidLocalSelectionSet(set_id))
+        query: &SearchQuery,
+    ) -> Result<Vec<Range<Anchor>>, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().search(file_id, query)
     }
-}
 
-impl OperationEnvelope {
-    fn wrap(epoch_id: epoch::Id, epoch_head: Option<Oid>, operation: epoch::Operation) -> Self {
-        OperationEnvelope {
-            epoch_head,
-            operation: Operation::EpochOperation {
-                epoch_id,
-                operation,
-            },
-        }
+    pub fn find(
+        &self,
+        buffer_id: BufferId,
+        query: &SearchQuery,
+        offset: usize,
+        direction: SearchDirection,
+    ) -> Result<Option<Range<Anchor>>, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().find(file_id, query, offset, direction)
     }
 
-    fn wrap_many<T>(epoch_id: epoch::Id, epoch_head: Option<Oid>, operations: T) -> Vec<Self>
+    pub fn replace_all<T>(
+        &self,
+        buffer_id: BufferId,
+        query: &SearchQuery,
+        replacement: T,
+    ) -> Result<Option<OperationEnvelope>, Error>
     where
-        T: IntoIterator<Item = epoch::Operation>,
+        T: Into<Text>,
     {
-        operations
-            .into_iter()
-            .map(move |operation| OperationEnvelope {
-                epoch_head,
-                operation: Operation::EpochOperation {
-                    epoch_id,
-                    operation,
-                },
-            })
-            .collect()
+        let file_id = self.buffer_file_id(buffer_id)?;
+        let mut cur_epoch = self.cur_epoch_mut();
+        let operation = cur_epoch.replace_all(
+            file_id,
+            query,
+            replacement,
+            &mut self.lamport_clock.write().unwrap(),
+        )?;
+
+        Ok(operation
+            .map(|operation| OperationEnvelope::wrap(cur_epoch.id, cur_epoch.head, operation)))
     }
-}
 
-impl Operation {
-    pub fn epoch_id(&self) -> epoch::Id {
-        match self {
-            Operation::StartEpoch { epoch_id, .. } => *epoch_id,
-            Operation::EpochOperation { epoch_id, .. } => *epoch_id,
-        }
+    pub fn clip_point(
+        &self,
+        buffer_id: BufferId,
+        point: Point,
+        bias: AnchorBias,
+    ) -> Result<Point, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().clip_point(file_id, point, bias)
     }
 
-    pub fn is_selection_update(&self) -> bool {
-        match self {
-            Operation::EpochOperation { operation, .. } => match operation {
-                epoch::Operation::BufferOperation { operations, .. } => {
-                    operations.iter().all(|buffer_op| match buffer_op {
-                        buffer::Operation::UpdateSelections { .. } => true,
-                        _ => false,
-                    })
-                }
-                _ => false,
-            },
-            _ => false,
-        }
+    pub fn clip_offset(
+        &self,
+        buffer_id: BufferId,
+        offset: usize,
+        bias: AnchorBias,
+    ) -> Result<usize, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().clip_offset(file_id, offset, bias)
     }
 
-    pub fn serialize(&self) -> Vec<u8> {
-        let mut builder = FlatBufferBuilder::new();
-        let root = self.to_flatbuf(&mut builder);
-        builder.finish(root, None);
-        let (mut bytes, first_valid_byte_index) = builder.collapse();
-        bytes.drain(0..first_valid_byte_index);
-        bytes
+    pub fn is_valid_point(&self, buffer_id: BufferId, point: Point) -> Result<bool, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().is_valid_point(file_id, point)
     }
 
-    pub fn deserialize<'a>(buffer: &'a [u8]) -> Result<Option<Self>, Error> {
-        use crate::serialization::worktree::Operation;
-        let root = flatbuffers::get_root::<Operation<'a>>(buffer);
-        Self::from_flatbuf(root)
+    pub fn is_valid_offset(&self, buffer_id: BufferId, offset: usize) -> Result<bool, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().is_valid_offset(file_id, offset)
     }
 
-    pub fn to_flatbuf<'fbb>(
+    pub fn point_for_offset(&self, buffer_id: BufferId, offset: usize) -> Result<Point, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().point_for_offset(file_id, offset)
+    }
+
+    pub fn offset_for_point(&self, buffer_id: BufferId, point: Point) -> Result<usize, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().offset_for_point(file_id, point)
+    }
+
+    pub fn convert_offset(
         &self,
-        builder: &mut FlatBufferBuilder<'fbb>,
-    ) -> WIPOffset<serialization::worktree::Operation<'fbb>> {
-        use crate::serialization::worktree::{
-            EpochOperation, EpochOperationArgs, Operation as OperationFlatbuf, OperationArgs,
-            OperationVariant, StartEpoch, StartEpochArgs,
-        };
+        buffer_id: BufferId,
+        offset: usize,
+        from: OffsetUnit,
+        to: OffsetUnit,
+    ) -> Result<usize, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().convert_offset(file_id, offset, from, to)
+    }
 
-        let variant_type;
-        let variant;
+    pub fn buffer_deferred_ops_len(&self, buffer_id: BufferId) -> Result<usize, Error> {
+        let file_id = self.buffer_file_id(buffer_id)?;
+        self.cur_epoch().buffer_deferred_ops_len(file_id)
+    }
 
-        match self {
-            Operation::StartEpoch { epoch_id, head } => {
-                variant_type = OperationVariant::StartEpoch;
-                let head = head.map(|head| builder.create_vector(&head));
+    /// Returns another handle to the same tree, for use in futures that outlive `&self`.
+    fn shared(&self) -> WorkTree {
+        WorkTree {
+            epoch: self.epoch.clone(),
+            buffers: self.buffers.clone(),
+            next_buffer_id: self.next_buffer_id.clone(),
+            local_selection_sets: self.local_selection_sets.clone(),
+            next_local_selection_set_id: self.next_local_selection_set_id.clone(),
+            deferred_ops: self.deferred_ops.clone(),
+            lamport_clock: self.lamport_clock.clone(),
+            git: self.git.clone(),
+            observer: self.observer.clone(),
+            rebase_conflicts: self.rebase_conflicts.clone(),
+            followed_replica: self.followed_replica.clone(),
+            fork_point: self.fork_point.clone(),
+        }
+    }
+
+    fn rebased_file(
+        entry: StatusEntry,
+        old_epoch: Arc<RwLock<Epoch>>,
+        old_head: Option<Oid>,
+        git: Arc<GitProvider>,
+    ) -> Box<Future<Item = RebasedFile, Error = Error> + Send> {
+        let attributes = old_epoch
+            .read()
+            .unwrap()
+            .attributes(entry.file_id)
+            .unwrap_or(FileAttributes::default());
+        if entry.file_type != FileType::Text || entry.status == FileStatus::Renamed {
+            return Box::new(future::ok(RebasedFile {
+                entry,
+                attributes,
+                base_text: None,
+                text: None,
+            }));
+        }
+
+        let base_text = match (old_head, entry.base_path.as_ref()) {
+            (Some(old_head), Some(base_path)) => Box::new(
+                git.base_text(old_head, base_path)
+                    .map(|base_text| Some(base_text))
+                    .map_err(|err| Error::IoError(err)),
+            )
+                as Box<Future<Item = Option<String>, Error = Error> + Send>,
+            _ => Box::new(future::ok(None)),
+        };
+        Box::new(base_text.and_then(move |base_text| {
+            let text = if entry.status == FileStatus::Removed {
+                None
+            } else {
+                // The old epoch is a private copy, so it's fine to open its files and discard the
+                // resulting operations.
+                let mut old_epoch = old_epoch.write().unwrap();
+                old_epoch.open_text_file(
+                    entry.file_id,
+                    base_text.clone().unwrap_or(String::new()),
+                    &mut time::Lamport::default(),
+                )?;
+                Some(old_epoch.text(entry.file_id)?.into_string())
+            };
+            Ok(RebasedFile {
+                entry,
+                attributes,
+                base_text,
+                text,
+            })
+        }))
+    }
+
+    /// Replays the changes that `epoch` contains relative to its head on top of the current epoch.
+    fn replay_changes(
+        &self,
+        epoch: Arc<RwLock<Epoch>>,
+    ) -> Box<Future<Item = Vec<OperationEnvelope>, Error = Error> + Send> {
+        let head = epoch.read().unwrap().head;
+        let rebased_files = epoch
+            .read()
+            .unwrap()
+            .status()
+            .into_iter()
+            .map(|entry| Self::rebased_file(entry, epoch.clone(), head, self.git.clone()))
+            .collect::<Vec<_>>();
+
+        let tree = self.shared();
+        Box::new(
+            future::join_all(rebased_files).and_then(move |files| tree.replay_rebased_files(files)),
+        )
+    }
+
+    fn replay_rebased_files(
+        &self,
+        files: Vec<RebasedFile>,
+    ) -> Box<Future<Item = Vec<OperationEnvelope>, Error = Error> + Send> {
+        // Texts in the new base are needed to detect conflicts. They are requested before
+        // replaying anything, since replayed renames may move files away from their base path.
+        let new_base_texts = files
+            .iter()
+            .map(|file| {
+                let path = match file.entry.status {
+                    FileStatus::New => Some(&file.entry.path),
+                    _ => file.entry.base_path.as_ref(),
+                };
+                match path {
+                    Some(path) if file.entry.file_type == FileType::Text => Box::new(
+                        self.read_base_text(path)
+                            .then(|result| Ok::<_, Error>(result.ok())),
+                    )
+                        as Box<Future<Item = Option<String>, Error = Error> + Send>,
+                    _ => Box::new(future::ok(None)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let tree = self.shared();
+        Box::new(
+            future::join_all(new_base_texts)
+                .and_then(move |new_base_texts| tree.apply_rebased_files(files, new_base_texts)),
+        )
+    }
+
+    fn apply_rebased_files(
+        &self,
+        files: Vec<RebasedFile>,
+        new_base_texts: Vec<Option<String>>,
+    ) -> Result<Vec<OperationEnvelope>, Error> {
+        let mut ops = Vec::new();
+        let mut conflicts = Vec::new();
+
+        let mut buffer_versions = HashMap::new();
+        {
+            let cur_epoch = self.cur_epoch();
+            for (buffer_id, file_id) in self.buffers.read().unwrap().iter() {
+                buffer_versions.insert(*buffer_id, cur_epoch.buffer_version(*file_id)?);
+            }
+        }
+
+        let base_file_ids = files
+            .iter()
+            .map(|file| {
+                file.entry
+                    .base_path
+                    .as_ref()
+                    .and_then(|base_path| self.cur_epoch().file_id(base_path).ok())
+            })
+            .collect::<Vec<_>>();
+
+        for (file, file_id) in files.iter().zip(&base_file_ids) {
+            let path = &file.entry.path;
+            let base_path = file.entry.base_path.as_ref();
+            match (file.entry.status, file_id) {
+                (FileStatus::Renamed, None) => conflicts.push(RebaseConflict::RenamedButRemoved {
+                    base_path: base_path.unwrap().clone(),
+                    path: path.clone(),
+                }),
+                (FileStatus::Renamed, Some(file_id))
+                | (FileStatus::RenamedAndModified, Some(file_id)) => {
+                    let old_path = self.cur_epoch().path(*file_id);
+                    if let Some(old_path) = old_path {
+                        if self.exists(path) {
+                            conflicts.push(RebaseConflict::RenameTargetExists {
+                                base_path: base_path.unwrap().clone(),
+                                path: path.clone(),
+                            });
+                        } else {
+                            ops.extend(self.create_parent_dirs(path)?);
+                            ops.push(self.rename(old_path, path)?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for ((file, file_id), new_base_text) in
+            files.iter().zip(&base_file_ids).zip(&new_base_texts)
+        {
+            if let (FileStatus::Removed, Some(file_id)) = (file.entry.status, file_id) {
+                let path = self.cur_epoch().path(*file_id);
+                if let Some(path) = path {
+                    if file.entry.file_type == FileType::Text && *new_base_text != file.base_text {
+                        conflicts.push(RebaseConflict::RemovedButModified { path });
+                    } else {
+                        ops.push(self.remove(path)?);
+                    }
+                }
+            }
+        }
+
+        for (file, new_base_text) in files.iter().zip(&new_base_texts) {
+            if file.entry.status == FileStatus::New {
+                let path = &file.entry.path;
+                match self.file_type(path) {
+                    Ok(FileType::Directory) if file.entry.file_type == FileType::Directory => {}
+                    Ok(FileType::Text) if *new_base_text == file.text => {}
+                    Ok(_) => conflicts.push(RebaseConflict::BothAdded { path: path.clone() }),
+                    Err(_) => {
+                        ops.extend(self.create_parent_dirs(path)?);
+                        ops.push(self.create_file_with_attributes(
+                            path,
+                            file.entry.file_type,
+                            file.attributes.clone(),
+                        )?);
+                        if let Some(text) = file.text.as_ref() {
+                            let file_id = self.cur_epoch().file_id(path)?;
+                            ops.extend(self.replace_text(file_id, "", text)?);
+                        }
+                    }
+                }
+            }
+        }
+
+        for ((file, file_id), new_base_text) in
+            files.iter().zip(&base_file_ids).zip(&new_base_texts)
+        {
+            match file.entry.status {
+                FileStatus::Modified | FileStatus::RenamedAndModified => {}
+                _ => continue,
+            }
+
+            let text = file.text.as_ref().unwrap();
+            let path = file_id.and_then(|file_id| self.cur_epoch().path(file_id));
+            match (*file_id, path, new_base_text.as_ref()) {
+                (Some(file_id), Some(path), Some(new_base_text)) => {
+                    // Merge into the file's current text, which differs from the new base when
+                    // replaying onto an epoch that was already edited, like in `merge`.
+                    let cur_text = self
+                        .cur_epoch()
+                        .text_with_base(file_id, new_base_text.as_str())?;
+                    let base_text = file.base_text.as_ref().map_or("", |text| text.as_str());
+                    let (merged_text, hunks) = buffer::line_merge(base_text, text, &cur_text);
+                    ops.extend(self.replace_text(file_id, new_base_text, &merged_text)?);
+                    if !hunks.is_empty() {
+                        conflicts.push(RebaseConflict::ConflictingEdits { path, hunks });
+                    }
+                }
+                _ => {
+                    let path = &file.entry.path;
+                    if !self.exists(path) {
+                        ops.extend(self.create_parent_dirs(path)?);
+                        ops.push(self.create_file(path, FileType::Text)?);
+                        let file_id = self.cur_epoch().file_id(path)?;
+                        ops.extend(self.replace_text(file_id, "", text)?);
+                    }
+                    conflicts.push(RebaseConflict::ModifiedButRemoved { path: path.clone() });
+                }
+            }
+        }
+
+        for (buffer_id, version) in buffer_versions {
+            self.report_changes_since(buffer_id, &version)?;
+        }
+
+        self.rebase_conflicts.write().unwrap().extend(conflicts);
+        Ok(ops)
+    }
+
+    fn create_parent_dirs(&self, path: &Path) -> Result<Vec<OperationEnvelope>, Error> {
+        let mut ops = Vec::new();
+        if let Some(parent) = path.parent() {
+            let mut dir_path = PathBuf::new();
+            for component in parent.components() {
+                dir_path.push(component);
+                if !self.exists(&dir_path) {
+                    ops.push(self.create_file(&dir_path, FileType::Directory)?);
+                }
+            }
+        }
+        Ok(ops)
+    }
+
+    /// Edits the given file so that its text matches `new_text`, opening it with `base_text` if
+    /// it isn't open already.
+    fn replace_text(
+        &self,
+        file_id: FileId,
+        base_text: &str,
+        new_text: &str,
+    ) -> Result<Vec<OperationEnvelope>, Error> {
+        let mut epoch = self.cur_epoch_mut();
+        let mut lamport_clock = self.lamport_clock.write().unwrap();
+        epoch.open_text_file(file_id, base_text, &mut lamport_clock)?;
+        let changes = buffer::diff(
+            &epoch.text(file_id)?.collect::<Vec<_>>(),
+            &new_text.encode_utf16().collect::<Vec<_>>(),
+        );
+
+        let mut ops = Vec::new();
+        for change in changes {
+            let operation = epoch.edit_2d(
+                file_id,
+                Some(change.range),
+                change.code_units,
+                &mut lamport_clock,
+            )?;
+            ops.push(OperationEnvelope::wrap(epoch.id, epoch.head, operation));
+        }
+        Ok(ops)
+    }
+
+    fn cur_epoch(&self) -> RwLockReadGuard<Epoch> {
+        self.epoch.read().unwrap()
+    }
+
+    fn cur_epoch_mut(&self) -> RwLockWriteGuard<Epoch> {
+        self.epoch.write().unwrap()
+    }
+
+    fn defer_epoch_op(&self, epoch_id: epoch::Id, operation: epoch::Operation) {
+        self.deferred_ops
+            .write()
+            .unwrap()
+            .entry(epoch_id)
+            .or_insert(Vec::new())
+            .push(operation);
+    }
+
+    fn replica_id(&self) -> ReplicaId {
+        self.lamport_clock.read().unwrap().replica_id
+    }
+
+    fn buffer_file_id(&self, buffer_id: BufferId) -> Result<FileId, Error> {
+        self.buffers
+            .read()
+            .unwrap()
+            .get(&buffer_id)
+            .cloned()
+            .ok_or(Error::InvalidBufferId)
+    }
+
+    fn gen_local_set_id(&self) -> LocalSelectionSetId {
+        let mut next_local_set_id = self.next_local_selection_set_id.write().unwrap();
+        let local_set_id = *next_local_set_id;
+        next_local_set_id.0 += 1;
+        local_set_id
+    }
+
+    fn selection_set_id(
+        &self,
+        buffer_id: BufferId,
+        set_id: LocalSelectionSetId,
+    ) -> Result<buffer::SelectionSetId, Error> {
+        self.local_selection_sets
+            .read()
+            .unwrap()
+            .get(&buffer_id)
+            .ok_or(Error::InvalidLocalSelectionSet(set_id))?
+            .get(&set_id)
+            .cloned()
+            .ok_or(Error::InvalidLocalSelectionSet(set_id))
+    }
+}
+
+impl OperationEnvelope {
+    fn wrap(epoch_id: epoch::Id, epoch_head: Option<Oid>, operation: epoch::Operation) -> Self {
+        OperationEnvelope {
+            epoch_head,
+            operation: Operation::EpochOperation {
+                epoch_id,
+                operation,
+            },
+        }
+    }
+
+    fn wrap_many<T>(epoch_id: epoch::Id, epoch_head: Option<Oid>, operations: T) -> Vec<Self>
+    where
+        T: IntoIterator<Item = epoch::Operation>,
+    {
+        operations
+            .into_iter()
+            .map(move |operation| OperationEnvelope {
+                epoch_head,
+                operation: Operation::EpochOperation {
+                    epoch_id,
+                    operation,
+                },
+            })
+            .collect()
+    }
+}
+
+impl Operation {
+    pub fn epoch_id(&self) -> epoch::Id {
+        match self {
+            Operation::StartEpoch { epoch_id, .. } => *epoch_id,
+            Operation::EpochOperation { epoch_id, .. } => *epoch_id,
+        }
+    }
+
+    pub fn is_selection_update(&self) -> bool {
+        match self {
+            Operation::EpochOperation { operation, .. } => match operation {
+                epoch::Operation::BufferOperation { operations, .. } => {
+                    operations.iter().all(|buffer_op| match buffer_op {
+                        buffer::Operation::UpdateSelections { .. } => true,
+                        _ => false,
+                    })
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+        let root = self.to_flatbuf(&mut builder);
+        builder.finish(root, None);
+        let (mut bytes, first_valid_byte_index) = builder.collapse();
+        bytes.drain(0..first_valid_byte_index);
+        bytes
+    }
+
+    pub fn deserialize<'a>(buffer: &'a [u8]) -> Result<Option<Self>, Error> {
+        use crate::serialization::worktree::Operation;
+        let root = flatbuffers::get_root::<Operation<'a>>(buffer);
+        Self::from_flatbuf(root)
+    }
+
+    pub fn to_flatbuf<'fbb>(
+        &self,
+        builder: &mut FlatBufferBuilder<'fbb>,
+    ) -> WIPOffset<serialization::worktree::Operation<'fbb>> {
+        use crate::serialization::worktree::{
+            EpochOperation, EpochOperationArgs, Operation as OperationFlatbuf, OperationArgs,
+            OperationVariant, StartEpoch, StartEpochArgs,
+        };
+
+        let variant_type;
+        let variant;
+
+        match self {
+            Operation::StartEpoch { epoch_id, head } => {
+                variant_type = OperationVariant::StartEpoch;
+                let head = head.map(|head| builder.create_vector(&head));
                 variant = StartEpoch::create(
                     builder,
                     &StartEpochArgs {
@@ -951,18 +2237,18 @@ impl Operation {
 
 impl SwitchEpoch {
     fn new(
-        to_assign: Rc<RefCell<Epoch>>,
-        cur_epoch: Rc<RefCell<Epoch>>,
-        buffers: Rc<RefCell<HashMap<BufferId, FileId>>>,
-        local_selection_sets: Rc<
-            RefCell<HashMap<BufferId, HashMap<LocalSelectionSetId, buffer::SelectionSetId>>>,
+        to_assign: Arc<RwLock<Epoch>>,
+        cur_epoch: Arc<RwLock<Epoch>>,
+        buffers: Arc<RwLock<HashMap<BufferId, FileId>>>,
+        local_selection_sets: Arc<
+            RwLock<HashMap<BufferId, HashMap<LocalSelectionSetId, buffer::SelectionSetId>>>,
         >,
-        deferred_ops: Rc<RefCell<HashMap<epoch::Id, Vec<epoch::Operation>>>>,
-        lamport_clock: Rc<RefCell<time::Lamport>>,
-        git: Rc<GitProvider>,
-        observer: Option<Rc<ChangeObserver>>,
+        deferred_ops: Arc<RwLock<HashMap<epoch::Id, Vec<epoch::Operation>>>>,
+        lamport_clock: Arc<RwLock<time::Lamport>>,
+        git: Arc<GitProvider>,
+        observer: Option<Arc<ChangeObserver>>,
     ) -> Self {
-        let last_seen = cur_epoch.borrow().id;
+        let last_seen = cur_epoch.read().unwrap().id;
         Self {
             to_assign,
             cur_epoch,
@@ -983,12 +2269,12 @@ impl Future for SwitchEpoch {
     type Error = Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        let mut buffers = self.buffers.borrow_mut();
-        let mut cur_epoch = self.cur_epoch.borrow_mut();
-        let mut to_assign = self.to_assign.borrow_mut();
-        let mut deferred_ops = self.deferred_ops.borrow_mut();
-        let mut lamport_clock = self.lamport_clock.borrow_mut();
-        let mut local_selection_sets = self.local_selection_sets.borrow_mut();
+        let mut cur_epoch = self.cur_epoch.write().unwrap();
+        let mut to_assign = self.to_assign.write().unwrap();
+        let mut buffers = self.buffers.write().unwrap();
+        let mut local_selection_sets = self.local_selection_sets.write().unwrap();
+        let mut deferred_ops = self.deferred_ops.write().unwrap();
+        let mut lamport_clock = self.lamport_clock.write().unwrap();
 
         if to_assign.id > cur_epoch.id {
             if self.last_seen != cur_epoch.id {
@@ -1031,6 +2317,8 @@ impl Future for SwitchEpoch {
                     }
                 }
             }
+            self.base_text_requests
+                .retain(|buffer_id, _| buffers.contains_key(buffer_id));
 
             let mut is_done = true;
             for request in self.base_text_requests.values_mut() {
@@ -1079,6 +2367,13 @@ impl Future for SwitchEpoch {
                     }
                 }
 
+                for path in cur_epoch.subscribed_paths() {
+                    if let Ok(file_id) = to_assign.file_id(path) {
+                        to_assign.subscribe(file_id).ok();
+                    }
+                }
+                to_assign.restore_conflicts(cur_epoch.conflicts());
+
                 if let Some(ops) = deferred_ops.remove(&to_assign.id) {
                     fixup_ops.extend(OperationEnvelope::wrap_many(
                         to_assign.id,
@@ -1088,7 +2383,8 @@ impl Future for SwitchEpoch {
                 }
                 deferred_ops.retain(|id, _| *id > to_assign.id);
 
-                let old_active_location = cur_epoch.replica_location(lamport_clock.replica_id);
+                let old_presence = cur_epoch.presence(lamport_clock.replica_id);
+                let mut presence_moved = false;
                 let mut buffer_changes = Vec::new();
                 for (buffer_id, new_file_id) in buffer_mappings {
                     let old_file_id = buffers[&buffer_id];
@@ -1126,28 +2422,65 @@ impl Future for SwitchEpoch {
                         }
                     }
 
-                    if old_active_location.map_or(false, |location| location == old_file_id) {
-                        let op = to_assign
-                            .set_active_location(Some(new_file_id), &mut lamport_clock)
-                            .unwrap();
-                        fixup_ops.push(OperationEnvelope::wrap(to_assign.id, to_assign.head, op));
+                    let presence = cur_epoch.presence(lamport_clock.replica_id);
+                    if let Some(presence) = presence {
+                        if presence.file_id == Some(old_file_id) {
+                            let op = to_assign
+                                .set_presence(
+                                    Some(new_file_id),
+                                    presence.scroll_position,
+                                    presence.user,
+                                    &mut lamport_clock,
+                                )
+                                .unwrap();
+                            fixup_ops.push(OperationEnvelope::wrap(
+                                to_assign.id,
+                                to_assign.head,
+                                op,
+                            ));
+                            presence_moved = true;
+                        }
                     }
 
                     buffer_changes.push((buffer_id, changes));
                     buffers.insert(buffer_id, new_file_id);
                 }
 
+                if let Some(old_presence) = old_presence {
+                    if !presence_moved && old_presence.user != UserInfo::default() {
+                        let op = to_assign
+                            .set_presence(None, None, old_presence.user, &mut lamport_clock)
+                            .unwrap();
+                        fixup_ops.push(OperationEnvelope::wrap(to_assign.id, to_assign.head, op));
+                    }
+                }
+
                 mem::swap(&mut *cur_epoch, &mut *to_assign);
 
                 if let Some(observer) = self.observer.as_ref() {
+                    let tree_changes = to_assign
+                        .root_entries()
+                        .into_iter()
+                        .map(|(file_id, path)| TreeChange::Removed { file_id, path })
+                        .chain(
+                            cur_epoch
+                                .root_entries()
+                                .into_iter()
+                                .map(|(file_id, path)| TreeChange::Created { file_id, path }),
+                        )
+                        .collect::<Vec<_>>();
+                    if !tree_changes.is_empty() {
+                        observer.tree_changed(tree_changes);
+                    }
+
                     for (buffer_id, changes) in buffer_changes {
                         observer.changed(
                             buffer_id,
                             changes,
                             WorkTree::selection_ranges_internal(
-                                &local_selection_sets,
-                                &buffers,
                                 &cur_epoch,
+                                &buffers,
+                                &local_selection_sets,
                                 buffer_id,
                             )?,
                         );
@@ -1194,10 +2527,11 @@ impl<F: Future> MaybeDone<F> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
-    use crate::epoch::CursorEntry;
+    use crate::epoch::{CursorEntry, MAX_HISTORY_LEN};
     use rand::{Rng, SeedableRng, StdRng};
+    use std::sync::Mutex;
     use uuid::Uuid;
 
     #[test]
@@ -1209,7 +2543,7 @@ mod tests {
         for seed in 0..100 {
             println!("SEED: {:?}", seed);
             let mut rng = StdRng::from_seed(&[seed]);
-            let git = Rc::new(TestGitProvider::new());
+            let git = Arc::new(TestGitProvider::new());
 
             let mut commits = vec![None];
             let base_tree = WorkTree::empty();
@@ -1225,7 +2559,7 @@ mod tests {
             let mut trees = Vec::new();
             let mut network = Network::new();
             for i in 0..PEERS {
-                let observer = Rc::new(TestChangeObserver::new());
+                let observer = Arc::new(TestChangeObserver::new());
                 let commit = if rng.gen_weighted_bool(4) {
                     *rng.choose(&commits).unwrap()
                 } else {
@@ -1320,7 +2654,7 @@ mod tests {
 
     #[test]
     fn test_reset() {
-        let git = Rc::new(TestGitProvider::new());
+        let git = Arc::new(TestGitProvider::new());
         let base_tree = WorkTree::empty();
         base_tree.create_file("a", FileType::Text).unwrap();
         let a_base = base_tree.open_text_file("a").wait().unwrap();
@@ -1335,9 +2669,9 @@ mod tests {
         base_tree.create_file("b/c", FileType::Text).unwrap();
         let commit_2 = git.commit(&base_tree);
 
-        let observer_1 = Rc::new(TestChangeObserver::new());
-        let observer_2 = Rc::new(TestChangeObserver::new());
-        let (mut tree_1, ops_1) = WorkTree::new(
+        let observer_1 = Arc::new(TestChangeObserver::new());
+        let observer_2 = Arc::new(TestChangeObserver::new());
+        let (tree_1, ops_1) = WorkTree::new(
             Uuid::from_u128(1),
             Some(commit_0),
             vec![],
@@ -1346,7 +2680,7 @@ mod tests {
         )
         .unwrap();
 
-        let (mut tree_2, ops_2) = WorkTree::new(
+        let (tree_2, ops_2) = WorkTree::new(
             Uuid::from_u128(2),
             Some(commit_0),
             open_envelopes(ops_1.collect().wait().unwrap()),
@@ -1399,7 +2733,7 @@ mod tests {
         assert_eq!(observer_2.text(a_2), tree_2.text_str(a_2));
 
         // Reload tree using only ops for the newest epoch.
-        let (mut tree_1, ops_1) = WorkTree::new(
+        let (tree_1, ops_1) = WorkTree::new(
             Uuid::from_u128(1),
             Some(commit_0),
             ops_2,
@@ -1419,7 +2753,7 @@ mod tests {
 
     #[test]
     fn test_selections_across_resets() {
-        let git = Rc::new(TestGitProvider::new());
+        let git = Arc::new(TestGitProvider::new());
         let base_tree = WorkTree::empty();
         base_tree.create_file("a", FileType::Text).unwrap();
         let a_base = base_tree.open_text_file("a").wait().unwrap();
@@ -1430,7 +2764,7 @@ mod tests {
         base_tree.edit(a_base, Some(8..8), "ghi\n").unwrap();
         let commit_1 = git.commit(&base_tree);
 
-        let (mut tree_1, ops_1) = WorkTree::new(
+        let (tree_1, ops_1) = WorkTree::new(
             Uuid::from_u128(1),
             Some(commit_0),
             vec![],
@@ -1438,7 +2772,7 @@ mod tests {
             None,
         )
         .unwrap();
-        let (mut tree_2, ops_2) = WorkTree::new(
+        let (tree_2, ops_2) = WorkTree::new(
             Uuid::from_u128(2),
             Some(commit_0),
             open_envelopes(ops_1.collect().wait().unwrap()),
@@ -1499,73 +2833,1086 @@ mod tests {
         let fixup_ops_1 = tree_1.reset(Some(commit_1)).collect().wait().unwrap();
         let tree_1_selections = tree_1.selection_ranges(a_1).unwrap();
         assert_eq!(
-            tree_1_selections.local.into_iter().collect::<Vec<_>>(),
-            vec![(a_1_set, vec![Point::new(3, 1)..Point::new(3, 1)])]
+            tree_1_selections.local.into_iter().collect::<Vec<_>>(),
+            vec![(a_1_set, vec![Point::new(3, 1)..Point::new(3, 1)])]
+        );
+        assert_eq!(
+            tree_1_selections.remote.into_iter().collect::<Vec<_>>(),
+            vec![]
+        );
+
+        let fixup_ops_2 = tree_2
+            .apply_ops(open_envelopes(fixup_ops_1))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let tree_2_selections = tree_2.selection_ranges(a_2).unwrap();
+        assert_eq!(
+            tree_2_selections.local.into_iter().collect::<Vec<_>>(),
+            vec![(a_2_set, vec![Point::new(0, 0)..Point::new(0, 0)])]
+        );
+        assert_eq!(
+            tree_2_selections.remote.into_iter().collect::<Vec<_>>(),
+            vec![(
+                tree_1.replica_id(),
+                vec![vec![Point::new(3, 1)..Point::new(3, 1)]]
+            )]
+        );
+
+        tree_1
+            .apply_ops(open_envelopes(fixup_ops_2))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let tree_1_selections = tree_1.selection_ranges(a_1).unwrap();
+        assert_eq!(
+            tree_1_selections.local.into_iter().collect::<Vec<_>>(),
+            vec![(a_1_set, vec![Point::new(3, 1)..Point::new(3, 1)])]
+        );
+        assert_eq!(
+            tree_1_selections.remote.into_iter().collect::<Vec<_>>(),
+            vec![(
+                tree_2.replica_id(),
+                vec![vec![Point::new(0, 0)..Point::new(0, 0)]]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_active_location_across_resets() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Text).unwrap();
+        base_tree.create_file("b", FileType::Text).unwrap();
+        base_tree.create_file("c", FileType::Text).unwrap();
+        let commit_0 = git.commit(&base_tree);
+
+        base_tree.create_file("d", FileType::Text).unwrap();
+        base_tree.create_file("e", FileType::Text).unwrap();
+        let commit_1 = git.commit(&base_tree);
+
+        let replica_1_id = Uuid::from_u128(1);
+        let (tree_1, ops_1) =
+            WorkTree::new(replica_1_id, Some(commit_0), vec![], git.clone(), None).unwrap();
+
+        let replica_2_id = Uuid::from_u128(2);
+        let (tree_2, ops_2) = WorkTree::new(
+            replica_2_id,
+            Some(commit_0),
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        assert!(ops_2.wait().next().is_none());
+
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        let tree_1_location_op = tree_1.set_active_location(Some(a_1)).unwrap().operation;
+        tree_2
+            .apply_ops(Some(tree_1_location_op))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+
+        let b_2 = tree_2.open_text_file("b").wait().unwrap();
+        let tree_2_location_op = tree_2.set_active_location(Some(b_2)).unwrap().operation;
+        tree_1
+            .apply_ops(Some(tree_2_location_op))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+
+        assert_eq!(tree_1.replica_location(replica_1_id).unwrap(), "a");
+        assert_eq!(tree_1.replica_location(replica_2_id).unwrap(), "b");
+        assert_eq!(tree_2.replica_location(replica_1_id).unwrap(), "a");
+        assert_eq!(tree_2.replica_location(replica_2_id).unwrap(), "b");
+
+        let fixup_ops_1 = tree_1.reset(Some(commit_1)).collect().wait().unwrap();
+        assert_eq!(tree_1.replica_location(replica_1_id).unwrap(), "a");
+        let fixup_ops_2 = tree_2
+            .apply_ops(open_envelopes(fixup_ops_1))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        tree_1
+            .apply_ops(open_envelopes(fixup_ops_2))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+
+        assert_eq!(tree_1.replica_location(replica_1_id).unwrap(), "a");
+        assert_eq!(tree_1.replica_location(replica_2_id).unwrap(), "b");
+        assert_eq!(tree_2.replica_location(replica_1_id).unwrap(), "a");
+        assert_eq!(tree_2.replica_location(replica_2_id).unwrap(), "b");
+    }
+
+    #[test]
+    fn test_presence() {
+        let observer_2 = Arc::new(TestChangeObserver::new());
+        let git = Arc::new(TestGitProvider::new());
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), None, vec![], git.clone(), None).unwrap();
+        let (tree_2, _) = WorkTree::new(
+            Uuid::from_u128(2),
+            None,
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            Some(observer_2.clone()),
+        )
+        .unwrap();
+        let replica_1 = tree_1.replica_id();
+
+        let mut ops = Vec::new();
+        ops.push(tree_1.create_file("a", FileType::Text).unwrap().operation);
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        ops.push(
+            tree_1
+                .edit(a_1, Some(0..0), "abc\ndef\nghi")
+                .unwrap()
+                .operation,
+        );
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        tree_2.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_2.follow(Some(replica_1)), None);
+
+        let user = UserInfo {
+            name: "Ada".into(),
+            color: "#ff0000".into(),
+        };
+        let ops = vec![
+            tree_1.set_user(user.clone()).unwrap(),
+            tree_1
+                .add_selection_set(a_1, vec![Point::new(1, 1)..Point::new(1, 2)])
+                .unwrap()
+                .1,
+            tree_1.set_scroll_position(a_1, Point::new(1, 0)).unwrap(),
+        ];
+        tree_2
+            .apply_ops(deserialize_ops(serialize_ops(open_envelopes(ops))))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let presence = ReplicaPresence {
+            user: user.clone(),
+            path: Some(PathBuf::from("a")),
+            cursor: Some(Point::new(1, 2)),
+            scroll_position: Some(Point::new(1, 0)),
+        };
+        assert_eq!(tree_2.presence()[&replica_1], presence);
+        assert_eq!(
+            mem::replace(&mut *observer_2.followed.lock().unwrap(), Vec::new()),
+            vec![(replica_1, presence)]
+        );
+
+        // Remote edits shift the cursor and scroll position along with the text.
+        let ops = tree_1.edit(a_1, Some(0..0), "xyz\n").unwrap();
+        tree_2
+            .apply_ops(open_envelopes(Some(ops)))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let presence = ReplicaPresence {
+            user,
+            path: Some(PathBuf::from("a")),
+            cursor: Some(Point::new(2, 2)),
+            scroll_position: Some(Point::new(2, 0)),
+        };
+        assert_eq!(tree_1.presence()[&replica_1], presence);
+        assert_eq!(tree_2.follow(Some(replica_1)), Some(presence.clone()));
+        assert_eq!(
+            mem::replace(&mut *observer_2.followed.lock().unwrap(), Vec::new()),
+            vec![(replica_1, presence)]
+        );
+    }
+
+    #[test]
+    fn test_exists() {
+        let git = Arc::new(TestGitProvider::new());
+        let commit = git.commit(&WorkTree::empty());
+        let (tree, ops) =
+            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+        ops.collect().wait().unwrap();
+
+        tree.create_file("a", FileType::Directory).unwrap();
+        tree.create_file("a/b", FileType::Directory).unwrap();
+        tree.create_file("a/b/c", FileType::Text).unwrap();
+        tree.create_file("a/b/d", FileType::Text).unwrap();
+        tree.remove("a/b/d").unwrap();
+        assert!(tree.exists("a"));
+        assert!(tree.exists("a/b"));
+        assert!(tree.exists("a/b/c"));
+        assert!(!tree.exists("a/b/d"));
+        assert!(!tree.exists("non-existent-path"));
+        assert!(!tree.exists("invalid-path-;.'"));
+    }
+
+    #[test]
+    fn test_resolve_conflict() {
+        let git = Arc::new(TestGitProvider::new());
+        let commit = git.commit(&WorkTree::empty());
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+        let (tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
+            Some(commit),
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        assert!(ops_2.wait().next().is_none());
+
+        let op = tree_1.create_file("a", FileType::Text).unwrap().operation;
+        tree_2.create_file("a", FileType::Directory).unwrap();
+        tree_2
+            .apply_ops(Some(op))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(tree_2.file_type("a").unwrap(), FileType::Directory);
+        assert_eq!(tree_2.file_type("a~").unwrap(), FileType::Text);
+
+        let conflicts = tree_2.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, PathBuf::from("a~"));
+        assert_eq!(conflicts[0].winner_path, PathBuf::from("a"));
+        assert_eq!(conflicts[0].requested_path, PathBuf::from("a"));
+
+        assert!(tree_2.resolve_conflict("a", "a~").is_err());
+        tree_2.resolve_conflict("a~", "a~").unwrap();
+        assert_eq!(tree_2.file_type("a").unwrap(), FileType::Text);
+        assert_eq!(tree_2.file_type("a~").unwrap(), FileType::Directory);
+        assert!(tree_2.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let observer_1 = Arc::new(TestChangeObserver::new());
+        let git = Arc::new(TestGitProvider::new());
+        let (tree_1, ops_1) = WorkTree::new(
+            Uuid::from_u128(1),
+            None,
+            vec![],
+            git.clone(),
+            Some(observer_1.clone()),
+        )
+        .unwrap();
+        let (tree_2, _) = WorkTree::new(
+            Uuid::from_u128(2),
+            None,
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+
+        let mut ops = Vec::new();
+        ops.push(tree_1.create_file("a", FileType::Text).unwrap().operation);
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        ops.push(tree_1.edit(a_1, Some(0..0), "abc").unwrap().operation);
+        ops.push(tree_1.edit(a_1, Some(1..2), "def").unwrap().operation);
+        assert_eq!(tree_1.text_str(a_1), "adefc");
+        observer_1.opened_buffer(a_1, &tree_1);
+
+        ops.push(tree_1.undo(a_1).unwrap().operation);
+        assert_eq!(tree_1.text_str(a_1), "abc");
+        assert_eq!(observer_1.text(a_1), "abc");
+        ops.push(tree_1.undo(a_1).unwrap().operation);
+        assert_eq!(tree_1.text_str(a_1), "");
+        assert_eq!(observer_1.text(a_1), "");
+        ops.push(tree_1.redo(a_1).unwrap().operation);
+        assert_eq!(tree_1.text_str(a_1), "abc");
+        assert_eq!(observer_1.text(a_1), "abc");
+
+        tree_2
+            .apply_ops(deserialize_ops(serialize_ops(ops)))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_2.text_str(a_2), "abc");
+    }
+
+    #[test]
+    fn test_close_buffer() {
+        let observer_1 = Arc::new(TestChangeObserver::new());
+        let git = Arc::new(TestGitProvider::new());
+        let (tree_1, ops_1) = WorkTree::new(
+            Uuid::from_u128(1),
+            None,
+            vec![],
+            git.clone(),
+            Some(observer_1.clone()),
+        )
+        .unwrap();
+        let (tree_2, _) = WorkTree::new(
+            Uuid::from_u128(2),
+            None,
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+
+        let mut ops = Vec::new();
+        ops.push(tree_1.create_file("a", FileType::Text).unwrap().operation);
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        ops.push(tree_1.edit(a_1, Some(0..0), "abc").unwrap().operation);
+        let (_, set_op) = tree_1
+            .add_selection_set(a_1, vec![Point::new(0, 1)..Point::new(0, 1)])
+            .unwrap();
+        ops.push(set_op.operation);
+        observer_1.opened_buffer(a_1, &tree_1);
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_2.selection_ranges(a_2).unwrap().remote.len(), 1);
+
+        let close_ops = tree_1.close_buffer(a_1).unwrap();
+        assert_eq!(close_ops.len(), 1);
+        assert_eq!(tree_1.path(a_1), None);
+        assert!(tree_1.text(a_1).is_err());
+        assert!(tree_1.close_buffer(a_1).is_err());
+        tree_2
+            .apply_ops(open_envelopes(close_ops))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert!(tree_2.selection_ranges(a_2).unwrap().remote.is_empty());
+
+        // Remote edits to a closed buffer are still applied, but aren't reported.
+        let edit_ops = tree_2.edit(a_2, Some(3..3), "def").unwrap();
+        tree_1
+            .apply_ops(open_envelopes(Some(edit_ops)))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(observer_1.change_count(a_1), 0);
+
+        let a_1_reopened = tree_1.open_text_file("a").wait().unwrap();
+        assert_ne!(a_1_reopened, a_1);
+        assert_eq!(tree_1.text_str(a_1_reopened), "abcdef");
+
+        // Closing a buffer ends its open transactions, so their edits can be undone once the
+        // buffer is reopened.
+        tree_1.start_transaction(a_1_reopened).unwrap();
+        tree_1.start_transaction(a_1_reopened).unwrap();
+        tree_1.edit(a_1_reopened, Some(0..0), "123").unwrap();
+        tree_1.close_buffer(a_1_reopened).unwrap();
+        let a_1_reopened = tree_1.open_text_file("a").wait().unwrap();
+        tree_1.undo(a_1_reopened).unwrap();
+        assert_eq!(tree_1.text_str(a_1_reopened), "abcdef");
+    }
+
+    #[test]
+    fn test_tree_changed() {
+        let observer_2 = Arc::new(TestChangeObserver::new());
+        let git = Arc::new(TestGitProvider::new());
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), None, vec![], git.clone(), None).unwrap();
+        let (tree_2, _) = WorkTree::new(
+            Uuid::from_u128(2),
+            None,
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            Some(observer_2.clone()),
+        )
+        .unwrap();
+
+        let ops = vec![
+            tree_1.create_file("a", FileType::Directory).unwrap(),
+            tree_1.create_file("b", FileType::Text).unwrap(),
+        ];
+        tree_2
+            .apply_ops(open_envelopes(ops))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let a = tree_2.cur_epoch().file_id("a").unwrap();
+        let b = tree_2.cur_epoch().file_id("b").unwrap();
+        assert_eq!(
+            mem::replace(&mut *observer_2.tree_changes.lock().unwrap(), Vec::new()),
+            vec![
+                TreeChange::Created {
+                    file_id: a,
+                    path: PathBuf::from("a"),
+                },
+                TreeChange::Created {
+                    file_id: b,
+                    path: PathBuf::from("b"),
+                },
+            ]
+        );
+
+        let ops = vec![
+            tree_1.rename("b", "a/b").unwrap(),
+            tree_1.remove("a").unwrap(),
+        ];
+        tree_2
+            .apply_ops(open_envelopes(ops))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(
+            mem::replace(&mut *observer_2.tree_changes.lock().unwrap(), Vec::new()),
+            vec![
+                TreeChange::Removed {
+                    file_id: a,
+                    path: PathBuf::from("a"),
+                },
+                TreeChange::Removed {
+                    file_id: b,
+                    path: PathBuf::from("b"),
+                },
+            ]
+        );
+
+        let ops = vec![tree_1.create_file("c", FileType::Text).unwrap()];
+        tree_2
+            .apply_ops(open_envelopes(ops))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let old_c = tree_2.cur_epoch().file_id("c").unwrap();
+        mem::replace(&mut *observer_2.tree_changes.lock().unwrap(), Vec::new());
+
+        let commit = git.commit(&tree_1);
+        tree_2
+            .apply_ops(open_envelopes(
+                tree_1.reset(Some(commit)).collect().wait().unwrap(),
+            ))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        let new_c = tree_2.cur_epoch().file_id("c").unwrap();
+        assert_eq!(
+            mem::replace(&mut *observer_2.tree_changes.lock().unwrap(), Vec::new()),
+            vec![
+                TreeChange::Removed {
+                    file_id: old_c,
+                    path: PathBuf::from("c"),
+                },
+                TreeChange::Created {
+                    file_id: new_c,
+                    path: PathBuf::from("c"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transactions() {
+        let git = Arc::new(TestGitProvider::new());
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), None, vec![], git.clone(), None).unwrap();
+        let observer_2 = Arc::new(TestChangeObserver::new());
+        let (tree_2, _) = WorkTree::new(
+            Uuid::from_u128(2),
+            None,
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            Some(observer_2.clone()),
+        )
+        .unwrap();
+
+        let ops = open_envelopes(Some(tree_1.create_file("a", FileType::Text).unwrap()));
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+        observer_2.opened_buffer(a_2, &tree_2);
+
+        // Edits are sent as they happen, so applying them together reports a single change.
+        tree_1.start_transaction(a_1).unwrap();
+        let mut ops = Vec::new();
+        ops.push(tree_1.edit(a_1, Some(0..0), "abc").unwrap().operation);
+        ops.push(tree_1.edit(a_1, Some(3..3), "def").unwrap().operation);
+        tree_1.end_transaction(a_1, None).unwrap();
+        assert_eq!(tree_1.text_str(a_1), "abcdef");
+        assert!(tree_1.end_transaction(a_1, None).is_err());
+
+        tree_2
+            .apply_ops(deserialize_ops(serialize_ops(ops)))
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(tree_2.text_str(a_2), "abcdef");
+        assert_eq!(observer_2.text(a_2), "abcdef");
+        assert_eq!(observer_2.change_count(a_2), 1);
+
+        // Transactions are undone and redone as a single step.
+        tree_1.edit(a_1, Some(0..0), "123").unwrap();
+        tree_1.undo(a_1).unwrap();
+        assert_eq!(tree_1.text_str(a_1), "abcdef");
+        tree_1.undo(a_1).unwrap();
+        assert_eq!(tree_1.text_str(a_1), "");
+        tree_1.redo(a_1).unwrap();
+        assert_eq!(tree_1.text_str(a_1), "abcdef");
+
+        // Transactions that start within the group interval are grouped with the previous one.
+        // Start well after the transactions above so that they aren't grouped with these.
+        let now = SystemTime::now() + Duration::from_secs(60);
+        let group_interval = Some(Duration::from_millis(300));
+        tree_1.start_transaction_at(a_1, now).unwrap();
+        tree_1.edit(a_1, Some(0..0), "1").unwrap();
+        tree_1.end_transaction_at(a_1, now, group_interval).unwrap();
+        tree_1
+            .start_transaction_at(a_1, now + Duration::from_millis(200))
+            .unwrap();
+        tree_1.edit(a_1, Some(1..1), "2").unwrap();
+        tree_1
+            .end_transaction_at(a_1, now + Duration::from_millis(200), group_interval)
+            .unwrap();
+        assert_eq!(tree_1.text_str(a_1), "12abcdef");
+        tree_1.undo(a_1).unwrap();
+        assert_eq!(tree_1.text_str(a_1), "abcdef");
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let git = Arc::new(TestGitProvider::new());
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), None, vec![], git.clone(), None).unwrap();
+        let mut ops = open_envelopes(ops_1.collect().wait().unwrap());
+        ops.push(
+            tree_1
+                .create_file("a", FileType::Directory)
+                .unwrap()
+                .operation,
+        );
+        ops.push(tree_1.create_file("a/b", FileType::Text).unwrap().operation);
+        let b_1 = tree_1.open_text_file("a/b").wait().unwrap();
+        ops.push(tree_1.edit(b_1, Some(0..0), "abc").unwrap().operation);
+        ops.push(tree_1.edit(b_1, Some(1..2), "def").unwrap().operation);
+        ops.push(tree_1.undo(b_1).unwrap().operation);
+        let snapshot = tree_1.snapshot();
+
+        let mut tail_ops = Vec::new();
+        tail_ops.push(tree_1.edit(b_1, Some(3..3), "ghi").unwrap().operation);
+        tail_ops.push(tree_1.rename("a/b", "c").unwrap().operation);
+        ops.extend(tail_ops.iter().cloned());
+
+        let (tree_2, _) = WorkTree::from_snapshot(
+            Uuid::from_u128(2),
+            &snapshot,
+            tail_ops.clone(),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        let (tree_3, _) = WorkTree::new(Uuid::from_u128(3), None, ops, git.clone(), None).unwrap();
+        assert!(tree_2.observed(tree_1.version()));
+        assert!(tree_1.observed(tree_2.version()));
+        assert_eq!(tree_2.entries(), tree_1.entries());
+        assert_eq!(tree_3.entries(), tree_1.entries());
+        let c_2 = tree_2.open_text_file("c").wait().unwrap();
+        let c_3 = tree_3.open_text_file("c").wait().unwrap();
+        assert_eq!(tree_2.text_str(c_2), "abcghi");
+        assert_eq!(tree_3.text_str(c_3), "abcghi");
+
+        // Restoring a snapshot on the replica that created it doesn't reuse its timestamps.
+        let (tree_4, _) =
+            WorkTree::from_snapshot(Uuid::from_u128(1), &snapshot, tail_ops, git.clone(), None)
+                .unwrap();
+        let c_4 = tree_4.open_text_file("c").wait().unwrap();
+        let edit_ops = vec![tree_4.edit(c_4, Some(0..1), "x").unwrap().operation];
+        tree_3
+            .apply_ops(edit_ops)
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(tree_3.text_str(c_3), "xbcghi");
+        assert_eq!(tree_4.text_str(c_4), "xbcghi");
+
+        // Undoing an edit that was made before the snapshot replicates to the restored tree.
+        let undo_ops = vec![
+            tree_1.undo(b_1).unwrap().operation,
+            tree_1.undo(b_1).unwrap().operation,
+        ];
+        assert_eq!(tree_1.text_str(b_1), "");
+        tree_2
+            .apply_ops(undo_ops)
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(tree_2.text_str(c_2), "");
+
+        // Base entries that were still loading when the snapshot was taken are loaded on restore.
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("d", FileType::Directory).unwrap();
+        base_tree.create_file("d/e", FileType::Text).unwrap();
+        base_tree.create_file("f", FileType::Text).unwrap();
+        let commit = git.commit(&base_tree);
+        let (tree_5, ops_5) =
+            WorkTree::new(Uuid::from_u128(5), Some(commit), vec![], git.clone(), None).unwrap();
+        let snapshot = tree_5.snapshot();
+        let (tree_6, ops_6) =
+            WorkTree::from_snapshot(Uuid::from_u128(6), &snapshot, vec![], git.clone(), None)
+                .unwrap();
+        assert_eq!(tree_6.entries(), vec![]);
+        ops_6.collect().wait().unwrap();
+        assert_eq!(tree_6.dir_entries(), base_tree.dir_entries());
+
+        ops_5.collect().wait().unwrap();
+        let snapshot = tree_5.snapshot();
+        let (tree_7, ops_7) =
+            WorkTree::from_snapshot(Uuid::from_u128(7), &snapshot, vec![], git.clone(), None)
+                .unwrap();
+        ops_7.collect().wait().unwrap();
+        assert_eq!(tree_7.dir_entries(), base_tree.dir_entries());
+    }
+
+    #[test]
+    fn test_version() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Text).unwrap();
+        let a_base = base_tree.open_text_file("a").wait().unwrap();
+        base_tree.edit(a_base, Some(0..0), "abc").unwrap();
+        let commit_0 = git.commit(&base_tree);
+
+        base_tree.edit(a_base, Some(1..2), "def").unwrap();
+        base_tree.create_file("b", FileType::Directory).unwrap();
+        let commit_1 = git.commit(&base_tree);
+
+        base_tree.edit(a_base, Some(2..3), "ghi").unwrap();
+        base_tree.create_file("b/c", FileType::Text).unwrap();
+        let commit_2 = git.commit(&base_tree);
+
+        let (tree_1, ops_1) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(commit_0),
+            vec![],
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        let (tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
+            Some(commit_0),
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        assert!(ops_2.wait().next().is_none());
+
+        let ops_1 = open_envelopes(tree_1.create_file("x.txt", FileType::Text));
+        let ops_2 = open_envelopes(tree_2.create_file("y.txt", FileType::Text));
+        assert!(!tree_1.observed(tree_2.version()));
+        assert!(!tree_2.observed(tree_1.version()));
+
+        tree_1.apply_ops(ops_2).unwrap().collect().wait().unwrap();
+        assert!(tree_1.observed(tree_2.version()));
+        tree_2.apply_ops(ops_1).unwrap().collect().wait().unwrap();
+        assert!(tree_2.observed(tree_1.version()));
+
+        let ops_1 = open_envelopes(tree_1.reset(Some(commit_1)).collect().wait().unwrap());
+        let ops_2 = open_envelopes(tree_2.reset(Some(commit_2)).collect().wait().unwrap());
+        // Even though the two sites haven't exchanged operations yet, it's as if tree_2 has
+        // already observed tree_1's state, since it won't ever go back to an epoch whose Lamport
+        // timestamp is smaller.
+        assert!(!tree_1.observed(tree_2.version()));
+        assert!(tree_2.observed(tree_1.version()));
+
+        tree_1.apply_ops(ops_2).unwrap().collect().wait().unwrap();
+        assert!(tree_1.observed(tree_2.version()));
+        tree_2.apply_ops(ops_1).unwrap().collect().wait().unwrap();
+        assert!(tree_2.observed(tree_1.version()));
+    }
+
+    #[test]
+    fn test_operations_since() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Text).unwrap();
+        let a_base = base_tree.open_text_file("a").wait().unwrap();
+        base_tree.edit(a_base, Some(0..0), "abc").unwrap();
+        let commit_0 = git.commit(&base_tree);
+        base_tree.create_file("b", FileType::Directory).unwrap();
+        let commit_1 = git.commit(&base_tree);
+
+        let (tree_1, ops_1) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(commit_0),
+            vec![],
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        let (tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
+            Some(commit_0),
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        assert!(ops_2.wait().next().is_none());
+
+        // Operations are dropped on the floor, but tree_2 can catch up by asking for the ones it
+        // hasn't observed.
+        tree_1.create_file("c", FileType::Text).unwrap();
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        tree_1.edit(a_1, Some(1..2), "def").unwrap();
+        tree_1.rename("c", "d").unwrap();
+        tree_1.set_active_location(Some(a_1)).unwrap();
+        let ops = open_envelopes(tree_1.operations_since(tree_2.version()).unwrap());
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        assert!(tree_2.observed(tree_1.version()));
+        assert_eq!(tree_2.entries(), tree_1.entries());
+        assert_eq!(tree_2.replica_locations(), tree_1.replica_locations());
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_2.text_str(a_2), "adefc");
+
+        // Only active locations are resent once replicas are in sync, and operations that were
+        // performed by the requesting replica aren't included.
+        tree_2.edit(a_2, Some(0..0), "x").unwrap();
+        for envelope in tree_1.operations_since(tree_2.version()).unwrap() {
+            match envelope.operation {
+                Operation::EpochOperation {
+                    operation: epoch::Operation::UpdateActiveLocation { .. },
+                    ..
+                } => {}
+                _ => panic!("unexpected operation {:?}", envelope.operation),
+            }
+        }
+
+        // Replicas on a previous epoch receive the new epoch in its entirety.
+        tree_1.reset(Some(commit_1)).collect().wait().unwrap();
+        tree_1.create_file("e", FileType::Text).unwrap();
+        let ops = open_envelopes(tree_1.operations_since(tree_2.version()).unwrap());
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        assert!(tree_2.observed(tree_1.version()));
+        assert_eq!(tree_2.entries(), tree_1.entries());
+
+        // Operations can't be computed anymore for versions preceding a garbage collection.
+        let version_2 = tree_2.version();
+        let ops = open_envelopes(Some(tree_1.create_file("f", FileType::Text).unwrap()));
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        tree_1.collect_garbage(Some(tree_2.acknowledge_versions(Some(tree_1.version()))));
+        assert_eq!(
+            tree_1.operations_since(version_2).err(),
+            Some(Error::OperationsUnavailable)
+        );
+        assert!(tree_1.operations_since(tree_1.version()).is_ok());
+    }
+
+    #[test]
+    fn test_text_at() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Text).unwrap();
+        let a_base = base_tree.open_text_file("a").wait().unwrap();
+        base_tree.edit(a_base, Some(0..0), "abc").unwrap();
+        let commit_0 = git.commit(&base_tree);
+        base_tree.create_file("b", FileType::Directory).unwrap();
+        let commit_1 = git.commit(&base_tree);
+
+        let (tree_1, ops_1) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(commit_0),
+            vec![],
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        let (tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
+            Some(commit_0),
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        assert!(ops_2.wait().next().is_none());
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+
+        let version_0 = tree_2.version();
+        let mut ops = vec![tree_1.edit(a_1, Some(1..2), "def").unwrap().operation];
+        ops.push(tree_1.edit(a_1, Some(0..0), "x").unwrap().operation);
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        let version_1 = tree_2.version();
+        tree_2.edit(a_2, Some(4..5), "").unwrap();
+        assert_eq!(tree_2.text_str(a_2), "xadec");
+
+        let text_at = |version| String::from_utf16(&tree_2.text_at(a_2, version).unwrap()).unwrap();
+        assert_eq!(text_at(&version_0), "abc");
+        assert_eq!(text_at(&version_1), "xadefc");
+        assert_eq!(text_at(&tree_2.version()), "xadec");
+
+        // Changes between two versions describe what a collaborator did in the meantime.
+        let changes = tree_2
+            .changes_between(a_2, &version_0, &version_1)
+            .unwrap()
+            .into_iter()
+            .map(|change| {
+                (
+                    change.range,
+                    String::from_utf16(&change.code_units).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (Point::new(0, 0)..Point::new(0, 0), "x".to_string()),
+                (Point::new(0, 2)..Point::new(0, 3), "def".to_string())
+            ]
+        );
+
+        // Versions from other epochs and versions preceding a garbage collection are unavailable.
+        tree_2.collect_garbage(Some(version_1.clone()));
+        assert_eq!(
+            tree_2.text_at(a_2, &version_0).err(),
+            Some(Error::VersionUnavailable)
+        );
+        let version_2 = tree_2.version();
+        tree_2.reset(Some(commit_1)).collect().wait().unwrap();
+        assert_eq!(
+            tree_2.text_at(a_2, &version_2).err(),
+            Some(Error::VersionUnavailable)
+        );
+    }
+
+    #[test]
+    fn test_authorship() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Text).unwrap();
+        let a_base = base_tree.open_text_file("a").wait().unwrap();
+        base_tree.edit(a_base, Some(0..0), "abc\ndef").unwrap();
+        let commit = git.commit(&base_tree);
+
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+        let (tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
+            Some(commit),
+            open_envelopes(ops_1.collect().wait().unwrap()),
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        assert!(ops_2.wait().next().is_none());
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+
+        let ops = vec![tree_1.edit(a_1, Some(5..5), "xyz\n").unwrap().operation];
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        assert_eq!(tree_2.text_str(a_2), "abc\ndxyz\nef");
+        assert_eq!(tree_2.authorship(a_2), tree_1.authorship(a_1));
+
+        let spans = tree_2.authorship_in_rows(a_2, 1..2).unwrap();
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| span.range.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Point::new(1, 0)..Point::new(1, 1),
+                Point::new(1, 1)..Point::new(2, 0)
+            ]
         );
+        assert_eq!(spans[0].inserted_by, None);
         assert_eq!(
-            tree_1_selections.remote.into_iter().collect::<Vec<_>>(),
-            vec![]
+            spans[1].inserted_by.map(|timestamp| timestamp.replica_id),
+            Some(Uuid::from_u128(1))
         );
+    }
 
-        let fixup_ops_2 = tree_2
-            .apply_ops(open_envelopes(fixup_ops_1))
-            .unwrap()
-            .collect()
-            .wait()
+    #[test]
+    fn test_status() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Directory).unwrap();
+        base_tree.create_file("a/b", FileType::Text).unwrap();
+        let a_b_base = base_tree.open_text_file("a/b").wait().unwrap();
+        base_tree
+            .edit(a_b_base, Some(0..0), "one\ntwo\nthree\n")
             .unwrap();
-        let tree_2_selections = tree_2.selection_ranges(a_2).unwrap();
+        base_tree.create_file("c", FileType::Text).unwrap();
+        base_tree.create_file("d", FileType::Text).unwrap();
+        let commit = git.commit(&base_tree);
+
+        let (tree, ops) =
+            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+        let ops = open_envelopes(ops.collect().wait().unwrap());
+        assert_eq!(tree.status(), vec![]);
+
+        let a_b = tree.open_text_file("a/b").wait().unwrap();
+        let mut edit_ops = vec![tree.edit(a_b, Some(4..7), "TWO").unwrap().operation];
+        edit_ops.push(tree.edit(a_b, Some(14..14), "four\n").unwrap().operation);
+        tree.rename("c", "e").unwrap();
+        tree.remove("d").unwrap();
+        tree.create_file("f", FileType::Text).unwrap();
+        tree.create_file("g", FileType::Text).unwrap();
+        tree.remove("g").unwrap();
+
+        let status = tree
+            .status()
+            .into_iter()
+            .map(|entry| (entry.path, entry.base_path, entry.status))
+            .collect::<Vec<_>>();
         assert_eq!(
-            tree_2_selections.local.into_iter().collect::<Vec<_>>(),
-            vec![(a_2_set, vec![Point::new(0, 0)..Point::new(0, 0)])]
+            status,
+            vec![
+                (
+                    PathBuf::from("a/b"),
+                    Some(PathBuf::from("a/b")),
+                    FileStatus::Modified
+                ),
+                (
+                    PathBuf::from("d"),
+                    Some(PathBuf::from("d")),
+                    FileStatus::Removed
+                ),
+                (
+                    PathBuf::from("e"),
+                    Some(PathBuf::from("c")),
+                    FileStatus::Renamed
+                ),
+                (PathBuf::from("f"), None, FileStatus::New),
+            ]
         );
+
         assert_eq!(
-            tree_2_selections.remote.into_iter().collect::<Vec<_>>(),
-            vec![(
-                tree_1.replica_id(),
-                vec![vec![Point::new(3, 1)..Point::new(3, 1)]]
-            )]
+            tree.diff("a/b").wait().unwrap(),
+            vec![
+                LineDiffHunk {
+                    old_rows: 1..2,
+                    new_rows: 1..2,
+                    old_text: "two\n".into(),
+                    new_text: "TWO\n".into(),
+                },
+                LineDiffHunk {
+                    old_rows: 3..3,
+                    new_rows: 3..4,
+                    old_text: "".into(),
+                    new_text: "four\n".into(),
+                },
+            ]
         );
+        assert_eq!(tree.diff("e").wait().unwrap(), vec![]);
 
-        tree_1
-            .apply_ops(open_envelopes(fixup_ops_2))
+        // Edits received for files that haven't been opened are included in the diff.
+        let (tree_2, ops_2) =
+            WorkTree::new(Uuid::from_u128(2), Some(commit), ops, git.clone(), None).unwrap();
+        assert!(ops_2.wait().next().is_none());
+        tree_2
+            .apply_ops(edit_ops)
             .unwrap()
             .collect()
             .wait()
             .unwrap();
-        let tree_1_selections = tree_1.selection_ranges(a_1).unwrap();
-        assert_eq!(
-            tree_1_selections.local.into_iter().collect::<Vec<_>>(),
-            vec![(a_1_set, vec![Point::new(3, 1)..Point::new(3, 1)])]
-        );
         assert_eq!(
-            tree_1_selections.remote.into_iter().collect::<Vec<_>>(),
-            vec![(
-                tree_2.replica_id(),
-                vec![vec![Point::new(0, 0)..Point::new(0, 0)]]
-            )]
+            tree_2.diff("a/b").wait().unwrap(),
+            tree.diff("a/b").wait().unwrap()
         );
     }
 
     #[test]
-    fn test_active_location_across_resets() {
-        let git = Rc::new(TestGitProvider::new());
+    fn test_rebase() {
+        let git = Arc::new(TestGitProvider::new());
         let base_tree = WorkTree::empty();
         base_tree.create_file("a", FileType::Text).unwrap();
+        let a_base = base_tree.open_text_file("a").wait().unwrap();
+        base_tree
+            .edit(a_base, Some(0..0), "one\ntwo\nthree\n")
+            .unwrap();
         base_tree.create_file("b", FileType::Text).unwrap();
         base_tree.create_file("c", FileType::Text).unwrap();
+        base_tree.create_file("d", FileType::Directory).unwrap();
         let commit_0 = git.commit(&base_tree);
 
-        base_tree.create_file("d", FileType::Text).unwrap();
+        base_tree.edit(a_base, Some(0..0), "zero\n").unwrap();
+        base_tree.remove("c").unwrap();
         base_tree.create_file("e", FileType::Text).unwrap();
         let commit_1 = git.commit(&base_tree);
 
-        let replica_1_id = Uuid::from_u128(1);
-        let (mut tree_1, ops_1) =
-            WorkTree::new(replica_1_id, Some(commit_0), vec![], git.clone(), None).unwrap();
+        let (tree, ops) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(commit_0),
+            vec![],
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        ops.collect().wait().unwrap();
+        let a = tree.open_text_file("a").wait().unwrap();
+        tree.edit(a, Some(14..14), "four\n").unwrap();
+        tree.rename("b", "d/b").unwrap();
+        let c = tree.open_text_file("c").wait().unwrap();
+        tree.edit(c, Some(0..0), "local").unwrap();
+        tree.create_file("e", FileType::Text).unwrap();
+        let e = tree.open_text_file("e").wait().unwrap();
+        tree.edit(e, Some(0..0), "local").unwrap();
+        tree.create_file("f", FileType::Directory).unwrap();
+
+        tree.rebase(Some(commit_1)).collect().wait().unwrap();
+        assert_eq!(
+            tree.text(a).unwrap().into_string(),
+            "zero\none\ntwo\nthree\nfour\n"
+        );
+        assert!(!tree.exists("b"));
+        assert!(tree.exists("d/b"));
+        assert!(tree.exists("f"));
+        let c = tree.open_text_file("c").wait().unwrap();
+        assert_eq!(tree.text(c).unwrap().into_string(), "local");
+        assert_eq!(
+            tree.rebase_conflicts(),
+            vec![
+                RebaseConflict::BothAdded {
+                    path: PathBuf::from("e")
+                },
+                RebaseConflict::ModifiedButRemoved {
+                    path: PathBuf::from("c")
+                },
+            ]
+        );
+    }
 
-        let replica_2_id = Uuid::from_u128(2);
-        let (mut tree_2, ops_2) = WorkTree::new(
-            replica_2_id,
+    #[test]
+    fn test_fork_and_merge() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Text).unwrap();
+        let a_base = base_tree.open_text_file("a").wait().unwrap();
+        base_tree
+            .edit(a_base, Some(0..0), "one\ntwo\nthree\n")
+            .unwrap();
+        let commit_0 = git.commit(&base_tree);
+        base_tree.create_file("b", FileType::Text).unwrap();
+        let commit_1 = git.commit(&base_tree);
+
+        let (tree_1, ops_1) = WorkTree::new(
+            Uuid::from_u128(1),
+            Some(commit_0),
+            vec![],
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        let (tree_2, ops_2) = WorkTree::new(
+            Uuid::from_u128(2),
             Some(commit_0),
             open_envelopes(ops_1.collect().wait().unwrap()),
             git.clone(),
@@ -1573,129 +3920,290 @@ mod tests {
         )
         .unwrap();
         assert!(ops_2.wait().next().is_none());
-
         let a_1 = tree_1.open_text_file("a").wait().unwrap();
-        let tree_1_location_op = tree_1.set_active_location(Some(a_1)).unwrap().operation;
-        tree_2
-            .apply_ops(Some(tree_1_location_op))
-            .unwrap()
-            .collect()
-            .wait()
-            .unwrap();
-
-        let b_2 = tree_2.open_text_file("b").wait().unwrap();
-        let tree_2_location_op = tree_2.set_active_location(Some(b_2)).unwrap().operation;
-        tree_1
-            .apply_ops(Some(tree_2_location_op))
-            .unwrap()
-            .collect()
-            .wait()
-            .unwrap();
-
-        assert_eq!(tree_1.replica_location(replica_1_id).unwrap(), "a");
-        assert_eq!(tree_1.replica_location(replica_2_id).unwrap(), "b");
-        assert_eq!(tree_2.replica_location(replica_1_id).unwrap(), "a");
-        assert_eq!(tree_2.replica_location(replica_2_id).unwrap(), "b");
-
-        let fixup_ops_1 = tree_1.reset(Some(commit_1)).collect().wait().unwrap();
-        assert_eq!(tree_1.replica_location(replica_1_id).unwrap(), "a");
-        let fixup_ops_2 = tree_2
-            .apply_ops(open_envelopes(fixup_ops_1))
-            .unwrap()
-            .collect()
-            .wait()
-            .unwrap();
+        let ops = vec![tree_1.edit(a_1, Some(0..0), "zero\n").unwrap().operation];
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+
+        // Forks diverge without exchanging operations, and converge once merged back.
+        let fork = tree_1.fork(Uuid::from_u128(3), None).unwrap();
+        let a_fork = fork.open_text_file("a").wait().unwrap();
+        assert_eq!(fork.text_str(a_fork), "zero\none\ntwo\nthree\n");
+        fork.edit(a_fork, Some(19..19), "four\n").unwrap();
+        fork.create_file("c", FileType::Text).unwrap();
+        let ops = vec![tree_1.edit(a_1, Some(5..8), "ONE").unwrap().operation];
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        assert!(!tree_1.exists("c"));
+
+        let ops = open_envelopes(tree_1.merge(&fork).unwrap().collect().wait().unwrap());
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_1.text_str(a_1), "zero\nONE\ntwo\nthree\nfour\n");
+        assert_eq!(tree_2.text_str(a_2), tree_1.text_str(a_1));
+        assert!(tree_1.exists("c"));
+        assert_eq!(tree_2.entries(), tree_1.entries());
+
+        // The fork's own operations are merged, even into replicas that haven't seen everything
+        // the fork was created from yet.
+        let ops = vec![tree_1.edit(a_1, Some(0..0), "-").unwrap().operation];
+        let fork = tree_1.fork(Uuid::from_u128(6), None).unwrap();
+        let a_fork = fork.open_text_file("a").wait().unwrap();
+        fork.edit(a_fork, Some(1..1), "+").unwrap();
+        fork.create_file("e", FileType::Text).unwrap();
+        let merge_ops = open_envelopes(tree_2.merge(&fork).unwrap().collect().wait().unwrap());
+        assert!(tree_2.exists("e"));
+        tree_2.apply_ops(ops).unwrap().collect().wait().unwrap();
         tree_1
-            .apply_ops(open_envelopes(fixup_ops_2))
+            .apply_ops(merge_ops)
             .unwrap()
             .collect()
             .wait()
             .unwrap();
-
-        assert_eq!(tree_1.replica_location(replica_1_id).unwrap(), "a");
-        assert_eq!(tree_1.replica_location(replica_2_id).unwrap(), "b");
-        assert_eq!(tree_2.replica_location(replica_1_id).unwrap(), "a");
-        assert_eq!(tree_2.replica_location(replica_2_id).unwrap(), "b");
+        assert_eq!(tree_1.text_str(a_1), "-+zero\nONE\ntwo\nthree\nfour\n");
+        assert_eq!(tree_2.text_str(a_2), tree_1.text_str(a_1));
+        assert_eq!(tree_2.entries(), tree_1.entries());
+
+        // If the tree moved on to a newer epoch since forking, the fork's changes are replayed on
+        // top of it.
+        let (tree_3, ops_3) = WorkTree::new(
+            Uuid::from_u128(4),
+            Some(commit_0),
+            vec![],
+            git.clone(),
+            None,
+        )
+        .unwrap();
+        ops_3.collect().wait().unwrap();
+        let fork = tree_3.fork(Uuid::from_u128(5), None).unwrap();
+        let a_fork = fork.open_text_file("a").wait().unwrap();
+        fork.edit(a_fork, Some(4..7), "TWO").unwrap();
+        fork.create_file("d", FileType::Text).unwrap();
+
+        let a_3 = tree_3.open_text_file("a").wait().unwrap();
+        tree_3.edit(a_3, Some(8..13), "THREE").unwrap();
+        tree_3.rebase(Some(commit_1)).collect().wait().unwrap();
+        tree_3.merge(&fork).unwrap().collect().wait().unwrap();
+        assert_eq!(tree_3.text_str(a_3), "one\nTWO\nTHREE\n");
+        assert!(tree_3.exists("b"));
+        assert!(tree_3.exists("d"));
+        assert_eq!(tree_3.rebase_conflicts(), vec![]);
     }
 
     #[test]
-    fn test_exists() {
-        let git = Rc::new(TestGitProvider::new());
-        let commit = git.commit(&WorkTree::empty());
+    fn test_merge_trimmed_fork() {
+        let git = Arc::new(TestGitProvider::new());
         let (tree, ops) =
-            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+            WorkTree::new(Uuid::from_u128(1), None, vec![], git.clone(), None).unwrap();
         ops.collect().wait().unwrap();
+        tree.create_file("a", FileType::Text).unwrap();
 
-        tree.create_file("a", FileType::Directory).unwrap();
-        tree.create_file("a/b", FileType::Directory).unwrap();
-        tree.create_file("a/b/c", FileType::Text).unwrap();
-        tree.create_file("a/b/d", FileType::Text).unwrap();
-        tree.remove("a/b/d").unwrap();
-        assert!(tree.exists("a"));
-        assert!(tree.exists("a/b"));
-        assert!(tree.exists("a/b/c"));
-        assert!(!tree.exists("a/b/d"));
-        assert!(!tree.exists("non-existent-path"));
-        assert!(!tree.exists("invalid-path-;.'"));
+        // A fork that has discarded some of its own operations can't be merged.
+        let fork = tree.fork(Uuid::from_u128(2), None).unwrap();
+        let a_fork = fork.open_text_file("a").wait().unwrap();
+        for _ in 0..=MAX_HISTORY_LEN {
+            fork.edit(a_fork, Some(0..0), "x").unwrap();
+        }
+        assert_eq!(tree.merge(&fork).err(), Some(Error::OperationsUnavailable));
     }
 
     #[test]
-    fn test_version() {
-        let git = Rc::new(TestGitProvider::new());
+    fn test_partial_replication() {
+        let git = Arc::new(TestGitProvider::new());
         let base_tree = WorkTree::empty();
         base_tree.create_file("a", FileType::Text).unwrap();
+        base_tree.create_file("b", FileType::Text).unwrap();
         let a_base = base_tree.open_text_file("a").wait().unwrap();
         base_tree.edit(a_base, Some(0..0), "abc").unwrap();
-        let commit_0 = git.commit(&base_tree);
-
-        base_tree.edit(a_base, Some(1..2), "def").unwrap();
-        base_tree.create_file("b", FileType::Directory).unwrap();
-        let commit_1 = git.commit(&base_tree);
-
-        base_tree.edit(a_base, Some(2..3), "ghi").unwrap();
-        base_tree.create_file("b/c", FileType::Text).unwrap();
-        let commit_2 = git.commit(&base_tree);
+        let commit = git.commit(&base_tree);
 
-        let (mut tree_1, ops_1) = WorkTree::new(
-            Uuid::from_u128(1),
-            Some(commit_0),
-            vec![],
-            git.clone(),
-            None,
-        )
-        .unwrap();
-        let (mut tree_2, ops_2) = WorkTree::new(
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+        let ops_1 = open_envelopes(ops_1.collect().wait().unwrap());
+        let (tree_2, ops_2) = WorkTree::new(
             Uuid::from_u128(2),
-            Some(commit_0),
-            open_envelopes(ops_1.collect().wait().unwrap()),
+            Some(commit),
+            ops_1.clone(),
             git.clone(),
             None,
         )
         .unwrap();
         assert!(ops_2.wait().next().is_none());
+        let (tree_3, ops_3) =
+            WorkTree::new(Uuid::from_u128(3), Some(commit), ops_1, git.clone(), None).unwrap();
+        assert!(ops_3.wait().next().is_none());
+        tree_2.set_partial_replication(true);
+        tree_3.set_partial_replication(true);
 
-        let ops_1 = open_envelopes(tree_1.create_file("x.txt", FileType::Text));
-        let ops_2 = open_envelopes(tree_2.create_file("y.txt", FileType::Text));
-        assert!(!tree_1.observed(tree_2.version()));
-        assert!(!tree_2.observed(tree_1.version()));
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        let b_1 = tree_1.open_text_file("b").wait().unwrap();
+        let a_ops = vec![tree_1.edit(a_1, Some(1..2), "xyz").unwrap().operation];
+        let b_ops = vec![tree_1.edit(b_1, Some(0..0), "def").unwrap().operation];
+
+        // Operations for files that haven't been opened are applied once they are, even if garbage
+        // was collected in the meantime. Operations for subscribed files are applied as they're
+        // received, so they aren't kept in the history once garbage is collected.
+        tree_2.subscribe("a").unwrap();
+        tree_2
+            .apply_ops(a_ops.clone())
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        tree_2.apply_ops(b_ops).unwrap().collect().wait().unwrap();
+        assert_eq!(tree_2.status(), tree_1.status());
+        tree_2.collect_garbage(Some(tree_1.acknowledge_versions(Some(tree_2.version()))));
+        assert_eq!(
+            tree_2.buffer_operations("a").err(),
+            Some(Error::OperationsUnavailable)
+        );
+        assert_eq!(
+            open_envelopes(tree_2.buffer_operations("b").unwrap()).len(),
+            1
+        );
+        let b_2 = tree_2.open_text_file("b").wait().unwrap();
+        assert_eq!(tree_2.text_str(b_2), "def");
+        assert_eq!(
+            tree_2.buffer_operations("b").err(),
+            Some(Error::OperationsUnavailable)
+        );
 
-        tree_1.apply_ops(ops_2).unwrap().collect().wait().unwrap();
-        assert!(tree_1.observed(tree_2.version()));
-        tree_2.apply_ops(ops_1).unwrap().collect().wait().unwrap();
-        assert!(tree_2.observed(tree_1.version()));
+        let (tree_4, _) =
+            WorkTree::from_snapshot(Uuid::from_u128(4), &tree_2.snapshot(), vec![], git, None)
+                .unwrap();
+        let a_4 = tree_4.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_4.text_str(a_4), "axyzc");
+
+        // Replicas that didn't receive the operations for a file can request them from a peer.
+        tree_3.apply_ops(a_ops).unwrap().collect().wait().unwrap();
+        let b_3 = tree_3.open_text_file("b").wait().unwrap();
+        assert_eq!(tree_3.text_str(b_3), "");
+        let ops = open_envelopes(tree_1.buffer_operations("b").unwrap());
+        tree_3.apply_ops(ops).unwrap().collect().wait().unwrap();
+        assert_eq!(tree_3.text_str(b_3), "def");
+        // Operations that were held back are applied even if partial replication was disabled
+        // since.
+        tree_3.set_partial_replication(false);
+        let a_3 = tree_3.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_3.text_str(a_3), "axyzc");
+    }
 
-        let ops_1 = open_envelopes(tree_1.reset(Some(commit_1)).collect().wait().unwrap());
-        let ops_2 = open_envelopes(tree_2.reset(Some(commit_2)).collect().wait().unwrap());
-        // Even though the two sites haven't exchanged operations yet, it's as if tree_2 has
-        // already observed tree_1's state, since it won't ever go back to an epoch whose Lamport
-        // timestamp is smaller.
-        assert!(!tree_1.observed(tree_2.version()));
-        assert!(tree_2.observed(tree_1.version()));
+    #[test]
+    fn test_write_tree() {
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Directory).unwrap();
+        base_tree.create_file("a/b", FileType::Text).unwrap();
+        let a_b_base = base_tree.open_text_file("a/b").wait().unwrap();
+        base_tree.edit(a_b_base, Some(0..0), "abc").unwrap();
+        base_tree.create_file("e", FileType::Text).unwrap();
+        let commit = git.commit(&base_tree);
 
-        tree_1.apply_ops(ops_2).unwrap().collect().wait().unwrap();
-        assert!(tree_1.observed(tree_2.version()));
-        tree_2.apply_ops(ops_1).unwrap().collect().wait().unwrap();
-        assert!(tree_2.observed(tree_1.version()));
+        let (tree, ops) =
+            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+        ops.collect().wait().unwrap();
+        tree.create_file("a.txt", FileType::Text).unwrap();
+        let a_txt = tree.open_text_file("a.txt").wait().unwrap();
+        tree.edit(a_txt, Some(0..0), "abc").unwrap();
+        tree.create_file("c", FileType::Text).unwrap();
+        tree.create_file("d", FileType::Directory).unwrap();
+        tree.remove("e").unwrap();
+
+        let repo = Repository::new(crate::tests::temp_dir("test_write_tree"));
+        let oid = tree.write_tree(&repo).wait().unwrap();
+        assert_eq!(
+            crate::git::oid_to_hex(oid),
+            "59218bccaf562000914d9b264fbf3a606f3426a7"
+        );
+
+        // Edits received for files that haven't been opened are included in the written tree.
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(2), Some(commit), vec![], git.clone(), None).unwrap();
+        let ops_1 = open_envelopes(ops_1.collect().wait().unwrap());
+        let a_b_1 = tree_1.open_text_file("a/b").wait().unwrap();
+        let edit_ops = vec![tree_1.edit(a_b_1, Some(1..2), "xyz").unwrap().operation];
+        let (tree_2, ops_2) =
+            WorkTree::new(Uuid::from_u128(3), Some(commit), ops_1, git.clone(), None).unwrap();
+        assert!(ops_2.wait().next().is_none());
+        tree_2
+            .apply_ops(edit_ops)
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(
+            tree_2.write_tree(&repo).wait().unwrap(),
+            tree_1.write_tree(&repo).wait().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_send_and_sync() {
+        use std::thread;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<WorkTree>();
+
+        let git = Arc::new(TestGitProvider::new());
+        let base_tree = WorkTree::empty();
+        base_tree.create_file("a", FileType::Text).unwrap();
+        let a_base = base_tree.open_text_file("a").wait().unwrap();
+        base_tree.edit(a_base, Some(0..0), "abc").unwrap();
+        let commit = git.commit(&base_tree);
+
+        let (tree_1, ops_1) =
+            WorkTree::new(Uuid::from_u128(1), Some(commit), vec![], git.clone(), None).unwrap();
+        let ops_1 = open_envelopes(ops_1.collect().wait().unwrap());
+        let tree_1 = Arc::new(tree_1);
+        let a_1 = tree_1.open_text_file("a").wait().unwrap();
+        let edit_ops = vec![tree_1.edit(a_1, Some(1..2), "xyz").unwrap().operation];
+
+        let tree_2 = {
+            let tree_1 = tree_1.clone();
+            let git = git.clone();
+            thread::spawn(move || {
+                let (tree_2, ops_2) =
+                    WorkTree::new(Uuid::from_u128(2), Some(commit), ops_1, git, None).unwrap();
+                assert!(ops_2.wait().next().is_none());
+                tree_2
+                    .apply_ops(edit_ops)
+                    .unwrap()
+                    .collect()
+                    .wait()
+                    .unwrap();
+                assert_eq!(tree_2.dir_entries(), tree_1.dir_entries());
+                tree_2
+            })
+            .join()
+            .unwrap()
+        };
+        let a_2 = tree_2.open_text_file("a").wait().unwrap();
+        assert_eq!(tree_2.text_str(a_2), "axyzc");
+        assert_eq!(tree_1.text_str(a_1), "axyzc");
+
+        // Concurrently opening the same file yields a single buffer.
+        tree_1.create_file("b", FileType::Text).unwrap();
+        let handles = (0..4)
+            .map(|_| {
+                let tree_1 = tree_1.clone();
+                thread::spawn(move || tree_1.open_text_file("b").wait().unwrap())
+            })
+            .collect::<Vec<_>>();
+        let b_1 = tree_1.open_text_file("b").wait().unwrap();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), b_1);
+        }
+        assert_eq!(tree_1.buffers.read().unwrap().len(), 2);
+
+        // The futures and streams returned by the tree can be driven on other threads.
+        let (tree_3, ops_3) =
+            WorkTree::new(Uuid::from_u128(3), Some(commit), vec![], git, None).unwrap();
+        thread::spawn(move || ops_3.collect().wait().unwrap())
+            .join()
+            .unwrap();
+        let open_a = tree_3.open_text_file("a");
+        let a_3 = thread::spawn(move || open_a.wait().unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(tree_3.text_str(a_3), "abc");
     }
 
     fn open_envelopes<I: IntoIterator<Item = OperationEnvelope>>(envelopes: I) -> Vec<Operation> {
@@ -1724,7 +4232,7 @@ mod tests {
                 Uuid::from_u128(999 as u128),
                 None,
                 Vec::new(),
-                Rc::new(TestGitProvider::new()),
+                Arc::new(TestGitProvider::new()),
                 None,
             )
             .unwrap();
@@ -1740,7 +4248,7 @@ mod tests {
         }
 
         fn open_buffers(&self) -> Vec<BufferId> {
-            self.buffers.borrow().keys().cloned().collect()
+            self.buffers.read().unwrap().keys().cloned().collect()
         }
 
         fn text_str(&self, buffer_id: BufferId) -> String {
@@ -1750,15 +4258,17 @@ mod tests {
         fn randomly_mutate<T: Rng>(&self, rng: &mut T, count: usize) -> Vec<OperationEnvelope> {
             // Store version for all open buffers so that we can keep the observer up to date.
             let mut buffer_versions = Vec::new();
-            let buffers = self.buffers.borrow();
-            for (buffer_id, file_id) in buffers.iter() {
-                let version = self.cur_epoch().buffer_version(*file_id).unwrap();
-                buffer_versions.push((*buffer_id, *file_id, version));
+            {
+                let epoch = self.cur_epoch();
+                for (buffer_id, file_id) in self.buffers.read().unwrap().iter() {
+                    let version = epoch.buffer_version(*file_id).unwrap();
+                    buffer_versions.push((*buffer_id, *file_id, version));
+                }
             }
 
             let operations = self.cur_epoch_mut().randomly_mutate(
                 rng,
-                &mut self.lamport_clock.borrow_mut(),
+                &mut self.lamport_clock.write().unwrap(),
                 count,
             );
             self.update_local_selection_sets();
@@ -1829,16 +4339,18 @@ mod tests {
         fn update_local_selection_sets(&self) {
             use std::collections::HashSet;
 
-            let mut local_selection_sets = self.local_selection_sets.borrow_mut();
+            let epoch = self.cur_epoch();
+            let buffers = self.buffers.read().unwrap();
+            let mut local_selection_sets = self.local_selection_sets.write().unwrap();
 
-            for (buffer_id, file_id) in self.buffers.borrow().iter() {
+            for (buffer_id, file_id) in buffers.iter() {
                 let buffer_sets = local_selection_sets
                     .entry(*buffer_id)
                     .or_insert(HashMap::new());
 
                 for local_set_id in buffer_sets.keys().cloned().collect::<Vec<_>>() {
                     let set_id = buffer_sets[&local_set_id];
-                    match self.cur_epoch().selection_ranges(*file_id, set_id) {
+                    match epoch.selection_ranges(*file_id, set_id) {
                         Ok(_) => {}
                         Err(Error::InvalidSelectionSet(_)) => {
                             buffer_sets.remove(&local_set_id);
@@ -1848,7 +4360,7 @@ mod tests {
                 }
 
                 let buffer_set_ids = buffer_sets.values().cloned().collect::<HashSet<_>>();
-                for (set_id, _) in self.cur_epoch().all_selections(*file_id).unwrap() {
+                for (set_id, _) in epoch.all_selections(*file_id).unwrap() {
                     if set_id.replica_id == self.replica_id() && !buffer_set_ids.contains(&set_id) {
                         buffer_sets.insert(self.gen_local_set_id(), set_id);
                     }
@@ -1864,44 +4376,47 @@ mod tests {
     }
 
     struct TestGitProvider {
-        commits: RefCell<HashMap<Oid, WorkTree>>,
-        next_oid: RefCell<u64>,
+        commits: Mutex<HashMap<Oid, Arc<WorkTree>>>,
+        next_oid: Mutex<u64>,
     }
 
-    struct TestChangeObserver {
-        buffers: RefCell<HashMap<BufferId, buffer::Buffer>>,
-        local_clock: RefCell<time::Local>,
-        lamport_clock: RefCell<time::Lamport>,
-        selections: RefCell<HashMap<BufferId, BufferSelectionRanges>>,
+    pub(crate) struct TestChangeObserver {
+        buffers: Mutex<HashMap<BufferId, buffer::Buffer>>,
+        local_clock: Mutex<time::Local>,
+        lamport_clock: Mutex<time::Lamport>,
+        selections: Mutex<HashMap<BufferId, BufferSelectionRanges>>,
+        change_counts: Mutex<HashMap<BufferId, usize>>,
+        tree_changes: Mutex<Vec<TreeChange>>,
+        followed: Mutex<Vec<(ReplicaId, ReplicaPresence)>>,
     }
 
     impl TestGitProvider {
         fn new() -> Self {
             TestGitProvider {
-                commits: RefCell::new(HashMap::new()),
-                next_oid: RefCell::new(0),
+                commits: Mutex::new(HashMap::new()),
+                next_oid: Mutex::new(0),
             }
         }
 
         fn commit(&self, tree: &WorkTree) -> Oid {
             let mut tree_clone = WorkTree::empty();
-            tree_clone.epoch = tree
-                .epoch
-                .as_ref()
-                .map(|e| Rc::new(RefCell::new(e.borrow().clone())));
-            tree_clone.buffers = Rc::new(RefCell::new(tree.buffers.borrow().clone()));
+            tree_clone.epoch = Arc::new(RwLock::new(tree.cur_epoch().clone()));
+            tree_clone.buffers = Arc::new(RwLock::new(tree.buffers.read().unwrap().clone()));
 
             let oid = self.gen_oid();
-            self.commits.borrow_mut().insert(oid, tree_clone);
+            self.commits
+                .lock()
+                .unwrap()
+                .insert(oid, Arc::new(tree_clone));
             oid
         }
 
-        fn tree(&self, oid: Oid) -> Ref<WorkTree> {
-            Ref::map(self.commits.borrow(), |commits| commits.get(&oid).unwrap())
+        fn tree(&self, oid: Oid) -> Arc<WorkTree> {
+            self.commits.lock().unwrap().get(&oid).unwrap().clone()
         }
 
         fn gen_oid(&self) -> Oid {
-            let mut next_oid = self.next_oid.borrow_mut();
+            let mut next_oid = self.next_oid.lock().unwrap();
             let mut oid = [0; 20];
             oid[0] = (*next_oid >> 0) as u8;
             oid[1] = (*next_oid >> 8) as u8;
@@ -1917,8 +4432,8 @@ mod tests {
     }
 
     impl GitProvider for TestGitProvider {
-        fn base_entries(&self, oid: Oid) -> Box<Stream<Item = DirEntry, Error = io::Error>> {
-            match self.commits.borrow().get(&oid) {
+        fn base_entries(&self, oid: Oid) -> Box<Stream<Item = DirEntry, Error = io::Error> + Send> {
+            match self.commits.lock().unwrap().get(&oid) {
                 Some(tree) => Box::new(stream::iter_ok(tree.dir_entries().into_iter())),
                 None => Box::new(stream::once(Err(io::Error::new(
                     io::ErrorKind::Other,
@@ -1931,56 +4446,76 @@ mod tests {
             &self,
             oid: Oid,
             path: &Path,
-        ) -> Box<Future<Item = String, Error = io::Error>> {
+        ) -> Box<Future<Item = String, Error = io::Error> + Send> {
             use futures::IntoFuture;
 
+            let tree = self.commits.lock().unwrap().get(&oid).cloned();
             Box::new(
-                self.commits
-                    .borrow_mut()
-                    .get_mut(&oid)
-                    .ok_or(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Commit does not exist",
-                    ))
-                    .and_then(|tree| {
-                        tree.open_text_file(path)
-                            .wait()
-                            .map_err(|_| {
-                                io::Error::new(io::ErrorKind::Other, "Path does not exist")
-                            })
-                            .map(|buffer_id| tree.text(buffer_id).unwrap().into_string())
-                    })
-                    .into_future(),
+                tree.ok_or(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Commit does not exist",
+                ))
+                .and_then(|tree| {
+                    tree.open_text_file(path)
+                        .wait()
+                        .map_err(|_| io::Error::new(io::ErrorKind::Other, "Path does not exist"))
+                        .map(|buffer_id| tree.text(buffer_id).unwrap().into_string())
+                })
+                .into_future(),
             )
         }
     }
 
     impl TestChangeObserver {
-        fn new() -> Self {
+        pub(crate) fn new() -> Self {
             Self {
-                buffers: RefCell::new(HashMap::new()),
-                local_clock: RefCell::new(time::Local::default()),
-                lamport_clock: RefCell::new(time::Lamport::default()),
-                selections: RefCell::new(HashMap::new()),
+                buffers: Mutex::new(HashMap::new()),
+                local_clock: Mutex::new(time::Local::new(Uuid::nil())),
+                lamport_clock: Mutex::new(time::Lamport::default()),
+                selections: Mutex::new(HashMap::new()),
+                change_counts: Mutex::new(HashMap::new()),
+                tree_changes: Mutex::new(Vec::new()),
+                followed: Mutex::new(Vec::new()),
             }
         }
 
-        fn opened_buffer(&self, buffer_id: BufferId, tree: &WorkTree) {
+        pub(crate) fn opened_buffer(&self, buffer_id: BufferId, tree: &WorkTree) {
             let text = tree.text(buffer_id).unwrap().collect::<Vec<u16>>();
             self.buffers
-                .borrow_mut()
+                .lock()
+                .unwrap()
                 .insert(buffer_id, buffer::Buffer::new(text));
             self.selections
-                .borrow_mut()
+                .lock()
+                .unwrap()
                 .insert(buffer_id, tree.selection_ranges(buffer_id).unwrap());
         }
 
-        fn text(&self, buffer_id: BufferId) -> String {
-            self.buffers.borrow().get(&buffer_id).unwrap().to_string()
+        pub(crate) fn text(&self, buffer_id: BufferId) -> String {
+            self.buffers
+                .lock()
+                .unwrap()
+                .get(&buffer_id)
+                .unwrap()
+                .to_string()
         }
 
         fn selection_ranges(&self, buffer_id: BufferId) -> BufferSelectionRanges {
-            self.selections.borrow().get(&buffer_id).unwrap().clone()
+            self.selections
+                .lock()
+                .unwrap()
+                .get(&buffer_id)
+                .unwrap()
+                .clone()
+        }
+
+        fn change_count(&self, buffer_id: BufferId) -> usize {
+            self.change_counts
+                .lock()
+                .unwrap()
+                .get(&buffer_id)
+                .cloned()
+                .unwrap_or(0)
         }
     }
 
@@ -1991,18 +4526,35 @@ mod tests {
             changes: Vec<Change>,
             selections: BufferSelectionRanges,
         ) {
-            if let Some(buffer) = self.buffers.borrow_mut().get_mut(&buffer_id) {
+            if let Some(buffer) = self.buffers.lock().unwrap().get_mut(&buffer_id) {
                 for change in changes {
                     buffer.edit_2d(
                         Some(change.range),
                         change.code_units,
-                        &mut self.local_clock.borrow_mut(),
-                        &mut self.lamport_clock.borrow_mut(),
+                        &mut self.local_clock.lock().unwrap(),
+                        &mut self.lamport_clock.lock().unwrap(),
                     );
                 }
             }
 
-            self.selections.borrow_mut().insert(buffer_id, selections);
+            self.selections
+                .lock()
+                .unwrap()
+                .insert(buffer_id, selections);
+            *self
+                .change_counts
+                .lock()
+                .unwrap()
+                .entry(buffer_id)
+                .or_insert(0) += 1;
+        }
+
+        fn tree_changed(&self, changes: Vec<TreeChange>) {
+            self.tree_changes.lock().unwrap().extend(changes);
+        }
+
+        fn followed_replica_changed(&self, replica_id: ReplicaId, presence: ReplicaPresence) {
+            self.followed.lock().unwrap().push((replica_id, presence));
         }
     }
 }
@@ -2,6 +2,7 @@ export {
   BaseEntry,
   Change,
   GitProvider,
+  FileId,
   FileType,
   Oid,
   Path,
@@ -9,10 +10,14 @@ export {
   Range,
   ReplicaId,
   SelectionRanges,
-  SelectionSetId
+  SelectionSetId,
+  ReplicaPresence,
+  TreeChange,
+  UserInfo
 } from "./support";
 import {
   BufferId,
+  Change,
   ChangeObserver,
   ChangeObserverCallback,
   Disposable,
@@ -26,6 +31,11 @@ import {
   SelectionRanges,
   SelectionSetId,
   Tagged,
+  FollowCallback,
+  Point,
+  ReplicaPresence,
+  TreeChangeCallback,
+  UserInfo,
   fromMemoSelectionRanges
 } from "./support";
 
@@ -42,6 +52,7 @@ async function init() {
 
 export type Version = Tagged<Uint8Array, "Version">;
 export type Operation = Tagged<Uint8Array, "Operation">;
+export type Snapshot = Tagged<Uint8Array, "Snapshot">;
 export type EpochId = Tagged<Uint8Array, "EpochId">;
 export interface OperationEnvelope {
   epochId(): EpochId;
@@ -61,6 +72,66 @@ export enum FileStatus {
   Unchanged = "Unchanged"
 }
 
+export interface StatusEntry {
+  readonly type: FileType;
+  readonly path: Path;
+  readonly basePath: Path | null;
+  readonly status: FileStatus;
+}
+
+export interface DiffHunk {
+  readonly oldStartRow: number;
+  readonly oldEndRow: number;
+  readonly newStartRow: number;
+  readonly newEndRow: number;
+  readonly oldText: string;
+  readonly newText: string;
+}
+
+export interface AuthorshipSpan {
+  readonly start: Point;
+  readonly end: Point;
+  readonly replicaId: ReplicaId | null;
+  readonly timestamp: number | null;
+}
+
+export enum Bias {
+  Left = "Left",
+  Right = "Right"
+}
+
+export enum ConflictType {
+  Name = "Name",
+  Cycle = "Cycle"
+}
+
+export interface ConflictEntry {
+  readonly type: ConflictType;
+  readonly path: Path;
+  readonly winnerPath: Path;
+  readonly requestedPath: Path;
+}
+
+export type RebaseConflict =
+  | { readonly type: "BothAdded"; readonly path: Path }
+  | {
+      readonly type: "RenamedButRemoved";
+      readonly basePath: Path;
+      readonly path: Path;
+    }
+  | {
+      readonly type: "RenameTargetExists";
+      readonly basePath: Path;
+      readonly path: Path;
+    }
+  | { readonly type: "RemovedButModified"; readonly path: Path }
+  | { readonly type: "ModifiedButRemoved"; readonly path: Path }
+  | {
+      readonly type: "ConflictingEdits";
+      readonly path: Path;
+      readonly hunks: DiffHunk[];
+    };
+
 export interface Entry {
   readonly depth: number;
   readonly type: FileType;
@@ -95,6 +166,25 @@ export class WorkTree {
     return [new WorkTree(result.tree(), observer), result.operations()];
   }
 
+  static async fromSnapshot(
+    replicaId: string,
+    snapshot: Snapshot,
+    ops: ReadonlyArray<Operation>,
+    git: GitProvider
+  ): Promise<[WorkTree, AsyncIterable<OperationEnvelope>]> {
+    await init();
+
+    const observer = new ChangeObserver();
+    const result = memo.WorkTree.from_snapshot(
+      new GitProviderWrapper(git),
+      observer,
+      replicaId,
+      snapshot,
+      ops
+    );
+    return [new WorkTree(result.tree(), observer), result.operations()];
+  }
+
   private constructor(tree: any, observer: ChangeObserver) {
     this.tree = tree;
     this.observer = observer;
@@ -104,10 +194,42 @@ export class WorkTree {
     return this.tree.version();
   }
 
+  snapshot(): Snapshot {
+    return this.tree.snapshot();
+  }
+
   hasObserved(version: Version): boolean {
     return this.tree.observed(version);
   }
 
+  operationsSince(version: Version): OperationEnvelope[] {
+    return this.tree.operations_since(version);
+  }
+
+  acknowledgeVersions(observedVersions: Version[]): Version {
+    return this.tree.acknowledge_versions(observedVersions);
+  }
+
+  collectGarbage(acknowledgedVersions: Version[]) {
+    this.tree.collect_garbage(acknowledgedVersions);
+  }
+
+  setPartialReplication(enabled: boolean) {
+    this.tree.set_partial_replication(enabled);
+  }
+
+  subscribe(path: Path) {
+    this.tree.subscribe(path);
+  }
+
+  unsubscribe(path: Path) {
+    this.tree.unsubscribe(path);
+  }
+
+  getBufferOperations(path: Path): OperationEnvelope[] {
+    return this.tree.buffer_operations(path);
+  }
+
   head(): null | Oid {
     return this.tree.head();
   }
@@ -120,6 +242,27 @@ export class WorkTree {
     return this.tree.reset(base);
   }
 
+  rebase(base: Oid | null): AsyncIterable<OperationEnvelope> {
+    return this.tree.rebase(base);
+  }
+
+  rebaseConflicts(): RebaseConflict[] {
+    return this.tree.rebase_conflicts();
+  }
+
+  onTreeChange(callback: TreeChangeCallback): Disposable {
+    return this.observer.onTreeChange(callback);
+  }
+
+  fork(replicaId: string): WorkTree {
+    const observer = new ChangeObserver();
+    return new WorkTree(this.tree.fork(observer, replicaId), observer);
+  }
+
+  merge(fork: WorkTree): AsyncIterable<OperationEnvelope> {
+    return this.tree.merge(fork.tree);
+  }
+
   applyOps(ops: Operation[]): AsyncIterable<OperationEnvelope> {
     return this.tree.apply_ops(ops);
   }
@@ -140,6 +283,14 @@ export class WorkTree {
     return this.tree.exists(path);
   }
 
+  conflicts(): ConflictEntry[] {
+    return this.tree.conflicts();
+  }
+
+  resolveConflict(path: Path, winnerPath: Path): OperationEnvelope[] {
+    return this.tree.resolve_conflict(path, winnerPath);
+  }
+
   entries(options?: { descendInto?: Path[]; showDeleted?: boolean }): Entry[] {
     let descendInto = null;
     let showDeleted = false;
@@ -150,6 +301,14 @@ export class WorkTree {
     return this.tree.entries(descendInto, showDeleted);
   }
 
+  status(): StatusEntry[] {
+    return this.tree.status();
+  }
+
+  diff(path: Path): Promise<DiffHunk[]> {
+    return this.tree.diff(path);
+  }
+
   async openTextFile(path: Path): Promise<Buffer> {
     const bufferId = await this.tree.open_text_file(path);
     let buffer = this.buffers.get(bufferId);
@@ -164,6 +323,27 @@ export class WorkTree {
     return this.tree.set_active_location(buffer ? buffer.id : null);
   }
 
+  setUser(user: UserInfo): OperationEnvelope {
+    return this.tree.set_user(user.name, user.color);
+  }
+
+  getPresence(): Map<ReplicaId, ReplicaPresence> {
+    const presence = this.tree.presence();
+    const map = new Map();
+    for (const replicaId in presence) {
+      map.set(replicaId, presence[replicaId]);
+    }
+    return map;
+  }
+
+  follow(replicaId: ReplicaId | null): ReplicaPresence | null {
+    return this.tree.follow(replicaId);
+  }
+
+  onFollowedReplicaChange(callback: FollowCallback): Disposable {
+    return this.observer.onFollowedReplicaChange(callback);
+  }
+
   getReplicaLocations(): Map<ReplicaId, Path> {
     const locations = this.tree.replica_locations();
 
@@ -190,6 +370,22 @@ export class Buffer {
     return this.tree.edit(this.id, oldRanges, newText);
   }
 
+  startTransaction() {
+    this.tree.start_transaction(this.id);
+  }
+
+  endTransaction(groupInterval?: number) {
+    this.tree.end_transaction(this.id, groupInterval);
+  }
+
+  undo(): OperationEnvelope {
+    return this.tree.undo(this.id);
+  }
+
+  redo(): OperationEnvelope {
+    return this.tree.redo(this.id);
+  }
+
   addSelectionSet(ranges: Range[]): [SelectionSetId, OperationEnvelope] {
     const result = this.tree.add_selection_set(this.id, ranges);
     return [result.set_id(), result.operation()];
@@ -203,6 +399,16 @@ export class Buffer {
     return this.tree.remove_selection_set(this.id, id);
   }
 
+  close(): OperationEnvelope[] {
+    const envelopes = this.tree.close_buffer(this.id);
+    this.observer.release(this.id);
+    return envelopes;
+  }
+
+  setScrollPosition(position: Point): OperationEnvelope {
+    return this.tree.set_scroll_position(this.id, position);
+  }
+
   getPath(): string | null {
     return this.tree.path(this.id);
   }
@@ -211,6 +417,46 @@ export class Buffer {
     return this.tree.text(this.id);
   }
 
+  getTextAt(version: Version): string {
+    return this.tree.text_at(this.id, version);
+  }
+
+  getChangesBetween(from: Version, to: Version): Change[] {
+    return this.tree.changes_between(this.id, from, to);
+  }
+
+  getAuthorship(rows?: { start: number; end: number }): AuthorshipSpan[] {
+    if (rows) {
+      return this.tree.authorship_in_rows(this.id, rows.start, rows.end);
+    } else {
+      return this.tree.authorship(this.id);
+    }
+  }
+
+  clipPoint(point: Point, bias: Bias = Bias.Left): Point {
+    return this.tree.clip_point(this.id, point, bias);
+  }
+
+  clipOffset(offset: number, bias: Bias = Bias.Left): number {
+    return this.tree.clip_offset(this.id, offset, bias);
+  }
+
+  isValidPoint(point: Point): boolean {
+    return this.tree.is_valid_point(this.id, point);
+  }
+
+  isValidOffset(offset: number): boolean {
+    return this.tree.is_valid_offset(this.id, offset);
+  }
+
+  pointForOffset(offset: number): Point {
+    return this.tree.point_for_offset(this.id, offset);
+  }
+
+  offsetForPoint(point: Point): number {
+    return this.tree.offset_for_point(this.id, point);
+  }
+
   getSelectionRanges(): SelectionRanges {
     const selections = this.tree.selection_ranges(this.id);
     return fromMemoSelectionRanges(selections);
@@ -9,14 +9,19 @@ use std::cell::Cell;
 use std::collections::{HashMap, HashSet};
 use std::io;
 use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use wasm_bindgen::{prelude::*, JsCast};
 use wasm_bindgen_futures::{future_to_promise, JsFuture};
 
 trait JsValueExt {
     fn into_operation(self) -> Result<Option<memo::Operation>, JsValue>;
+    fn into_version(self) -> Result<memo::Version, JsValue>;
     fn into_ranges_vec(self) -> Result<Vec<Range<memo::Point>>, JsValue>;
     fn into_error_message(self) -> Result<String, String>;
 }
@@ -65,6 +70,15 @@ struct Change {
     text: String,
 }
 
+#[derive(Serialize)]
+struct AuthorshipSpan {
+    start: memo::Point,
+    end: memo::Point,
+    #[serde(rename = "replicaId")]
+    replica_id: Option<memo::ReplicaId>,
+    timestamp: Option<u64>,
+}
+
 #[derive(Serialize)]
 struct Entry {
     #[serde(rename = "type")]
@@ -78,6 +92,115 @@ struct Entry {
     visible: bool,
 }
 
+#[derive(Serialize)]
+struct StatusEntry {
+    #[serde(rename = "type")]
+    file_type: memo::FileType,
+    path: String,
+    #[serde(rename = "basePath")]
+    base_path: Option<String>,
+    status: memo::FileStatus,
+}
+
+#[derive(Serialize)]
+struct DiffHunk {
+    #[serde(rename = "oldStartRow")]
+    old_start_row: u32,
+    #[serde(rename = "oldEndRow")]
+    old_end_row: u32,
+    #[serde(rename = "newStartRow")]
+    new_start_row: u32,
+    #[serde(rename = "newEndRow")]
+    new_end_row: u32,
+    #[serde(rename = "oldText")]
+    old_text: String,
+    #[serde(rename = "newText")]
+    new_text: String,
+}
+
+#[derive(Serialize)]
+struct ConflictEntry {
+    #[serde(rename = "type")]
+    conflict_type: ConflictType,
+    path: String,
+    #[serde(rename = "winnerPath")]
+    winner_path: String,
+    #[serde(rename = "requestedPath")]
+    requested_path: String,
+}
+
+#[derive(Serialize)]
+enum ConflictType {
+    Name,
+    Cycle,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum RebaseConflict {
+    BothAdded {
+        path: String,
+    },
+    RenamedButRemoved {
+        #[serde(rename = "basePath")]
+        base_path: String,
+        path: String,
+    },
+    RenameTargetExists {
+        #[serde(rename = "basePath")]
+        base_path: String,
+        path: String,
+    },
+    RemovedButModified {
+        path: String,
+    },
+    ModifiedButRemoved {
+        path: String,
+    },
+    ConflictingEdits {
+        path: String,
+        hunks: Vec<DiffHunk>,
+    },
+}
+
+#[derive(Serialize)]
+struct ReplicaPresence {
+    user: memo::UserInfo,
+    path: Option<String>,
+    cursor: Option<memo::Point>,
+    #[serde(rename = "scrollPosition")]
+    scroll_position: Option<memo::Point>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum TreeChange {
+    Created {
+        #[serde(rename = "fileId")]
+        file_id: JsFileId,
+        path: String,
+    },
+    Renamed {
+        #[serde(rename = "fileId")]
+        file_id: JsFileId,
+        #[serde(rename = "oldPath")]
+        old_path: String,
+        #[serde(rename = "newPath")]
+        new_path: String,
+    },
+    Removed {
+        #[serde(rename = "fileId")]
+        file_id: JsFileId,
+        path: String,
+    },
+}
+
+#[derive(Deserialize)]
+enum Bias {
+    Left,
+    Right,
+}
+
 #[derive(Deserialize, Serialize)]
 struct JsRange {
     start: memo::Point,
@@ -92,6 +215,17 @@ struct JsSelections {
 
 pub struct HexOid(memo::Oid);
 
+struct JsFileId(memo::FileId);
+
+/// Lets a JS value be passed to `memo::WorkTree`, which requires its git provider and observer to be
+/// `Send + Sync`. JS values can only be used on the thread that created them, so every access
+/// checks the current thread and panics on any other one, and dropping on another thread leaks
+/// the value instead of touching it.
+struct ThreadBound<T> {
+    value: ManuallyDrop<T>,
+    thread_id: ThreadId,
+}
+
 #[wasm_bindgen(module = "./support")]
 extern "C" {
     pub type AsyncIteratorWrapper;
@@ -116,6 +250,12 @@ extern "C" {
         changes: JsValue,
         selection_ranges: JsValue,
     );
+
+    #[wasm_bindgen(method, js_name = treeChanged)]
+    fn tree_changed(this: &ChangeObserver, changes: JsValue);
+
+    #[wasm_bindgen(method, js_name = followedReplicaChanged)]
+    fn followed_replica_changed(this: &ChangeObserver, replica_id: JsValue, presence: JsValue);
 }
 
 #[wasm_bindgen]
@@ -149,8 +289,8 @@ impl WorkTree {
             replica_id,
             base,
             start_ops,
-            Rc::new(git),
-            Some(Rc::new(observer)),
+            Arc::new(ThreadBound::new(git)),
+            Some(Arc::new(ThreadBound::new(observer))),
         )
         .map_err(|e| e.into_js_err())?;
         Ok(WorkTreeNewResult {
@@ -163,6 +303,48 @@ impl WorkTree {
         })
     }
 
+    pub fn from_snapshot(
+        git: GitProviderWrapper,
+        observer: ChangeObserver,
+        replica_id: JsValue,
+        snapshot: &[u8],
+        js_ops: js_sys::Array,
+    ) -> Result<WorkTreeNewResult, JsValue> {
+        console_error_panic_hook::set_once();
+
+        let replica_id = replica_id.into_serde().map_err(|e| {
+            format!("ReplicaId {:?} must be a valid UUID: {}", replica_id, e).into_js_err()
+        })?;
+
+        let mut ops = Vec::new();
+        for js_op in js_ops.values() {
+            if let Some(op) = js_op?.into_operation()? {
+                ops.push(op);
+            }
+        }
+
+        let (tree, operations) = memo::WorkTree::from_snapshot(
+            replica_id,
+            snapshot,
+            ops,
+            Arc::new(ThreadBound::new(git)),
+            Some(Arc::new(ThreadBound::new(observer))),
+        )
+        .map_err(|e| e.into_js_err())?;
+        Ok(WorkTreeNewResult {
+            tree: Some(WorkTree(tree)),
+            operations: Some(StreamToAsyncIterator::new(
+                operations
+                    .map(|op| JsValue::from(OperationEnvelope::new(op)))
+                    .map_err(|e| e.into_js_err()),
+            )),
+        })
+    }
+
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.0.snapshot()
+    }
+
     pub fn version(&self) -> Vec<u8> {
         bincode::serialize(&self.0.version()).unwrap()
     }
@@ -172,6 +354,60 @@ impl WorkTree {
         Ok(self.0.observed(version))
     }
 
+    pub fn operations_since(&self, version_bytes: &[u8]) -> Result<js_sys::Array, JsValue> {
+        let version = bincode::deserialize(&version_bytes).map_err(|e| e.into_js_err())?;
+        let envelopes = js_sys::Array::new();
+        for envelope in self
+            .0
+            .operations_since(version)
+            .map_err(|e| e.into_js_err())?
+        {
+            envelopes.push(&JsValue::from(OperationEnvelope::new(envelope)));
+        }
+        Ok(envelopes)
+    }
+
+    pub fn acknowledge_versions(&self, js_versions: js_sys::Array) -> Result<Vec<u8>, JsValue> {
+        let mut versions = Vec::new();
+        for js_version in js_versions.values() {
+            versions.push(js_version?.into_version()?);
+        }
+        Ok(bincode::serialize(&self.0.acknowledge_versions(versions)).unwrap())
+    }
+
+    pub fn collect_garbage(&self, js_versions: js_sys::Array) -> Result<(), JsValue> {
+        let mut versions = Vec::new();
+        for js_version in js_versions.values() {
+            versions.push(js_version?.into_version()?);
+        }
+        self.0.collect_garbage(versions);
+        Ok(())
+    }
+
+    pub fn set_partial_replication(&self, enabled: bool) {
+        self.0.set_partial_replication(enabled);
+    }
+
+    pub fn subscribe(&self, path: String) -> Result<(), JsValue> {
+        self.0.subscribe(&path).map_err(|e| e.into_js_err())
+    }
+
+    pub fn unsubscribe(&self, path: String) -> Result<(), JsValue> {
+        self.0.unsubscribe(&path).map_err(|e| e.into_js_err())
+    }
+
+    pub fn buffer_operations(&self, path: String) -> Result<js_sys::Array, JsValue> {
+        let envelopes = js_sys::Array::new();
+        for envelope in self
+            .0
+            .buffer_operations(&path)
+            .map_err(|e| e.into_js_err())?
+        {
+            envelopes.push(&JsValue::from(OperationEnvelope::new(envelope)));
+        }
+        Ok(envelopes)
+    }
+
     pub fn head(&self) -> JsValue {
         JsValue::from_serde(&self.0.head().map(|head| HexOid(head))).unwrap()
     }
@@ -193,6 +429,51 @@ impl WorkTree {
         ))
     }
 
+    pub fn rebase(&mut self, base: JsValue) -> Result<StreamToAsyncIterator, JsValue> {
+        let base = base
+            .into_serde::<Option<HexOid>>()
+            .map_err(|e| e.into_js_err())?
+            .map(|b| b.0);
+        Ok(StreamToAsyncIterator::new(
+            self.0
+                .rebase(base)
+                .map(|op| JsValue::from(OperationEnvelope::new(op)))
+                .map_err(|e| e.into_js_err()),
+        ))
+    }
+
+    pub fn rebase_conflicts(&self) -> Result<JsValue, JsValue> {
+        let conflicts = self
+            .0
+            .rebase_conflicts()
+            .into_iter()
+            .map(RebaseConflict::from)
+            .collect::<Vec<_>>();
+        JsValue::from_serde(&conflicts).map_err(|e| e.into_js_err())
+    }
+
+    pub fn fork(&self, observer: ChangeObserver, replica_id: JsValue) -> Result<WorkTree, JsValue> {
+        let replica_id = replica_id.into_serde().map_err(|e| {
+            format!("ReplicaId {:?} must be a valid UUID: {}", replica_id, e).into_js_err()
+        })?;
+        self.0
+            .fork(replica_id, Some(Arc::new(ThreadBound::new(observer))))
+            .map(WorkTree)
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn merge(&mut self, fork: &WorkTree) -> Result<StreamToAsyncIterator, JsValue> {
+        self.0
+            .merge(&fork.0)
+            .map(|ops| {
+                StreamToAsyncIterator::new(
+                    ops.map(|op| JsValue::from(OperationEnvelope::new(op)))
+                        .map_err(|e| e.into_js_err()),
+                )
+            })
+            .map_err(|e| e.into_js_err())
+    }
+
     pub fn apply_ops(&mut self, js_ops: js_sys::Array) -> Result<StreamToAsyncIterator, JsValue> {
         let mut ops = Vec::new();
         for js_op in js_ops.values() {
@@ -243,6 +524,40 @@ impl WorkTree {
         self.0.exists(&path)
     }
 
+    pub fn conflicts(&self) -> Result<JsValue, JsValue> {
+        let entries = self
+            .0
+            .conflicts()
+            .into_iter()
+            .map(|entry| ConflictEntry {
+                conflict_type: match entry.conflict {
+                    memo::Conflict::Name { .. } => ConflictType::Name,
+                    memo::Conflict::Cycle { .. } => ConflictType::Cycle,
+                },
+                path: entry.path.to_string_lossy().into_owned(),
+                winner_path: entry.winner_path.to_string_lossy().into_owned(),
+                requested_path: entry.requested_path.to_string_lossy().into_owned(),
+            })
+            .collect::<Vec<_>>();
+        JsValue::from_serde(&entries).map_err(|e| e.into_js_err())
+    }
+
+    pub fn resolve_conflict(
+        &self,
+        path: String,
+        winner_path: String,
+    ) -> Result<js_sys::Array, JsValue> {
+        let envelopes = js_sys::Array::new();
+        for envelope in self
+            .0
+            .resolve_conflict(path, winner_path)
+            .map_err(|e| e.into_js_err())?
+        {
+            envelopes.push(&JsValue::from(OperationEnvelope::new(envelope)));
+        }
+        Ok(envelopes)
+    }
+
     pub fn set_active_location(&self, buffer_id: JsValue) -> Result<OperationEnvelope, JsValue> {
         let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
         self.0
@@ -255,6 +570,42 @@ impl WorkTree {
         JsValue::from_serde(&self.0.replica_locations()).unwrap()
     }
 
+    pub fn set_user(&self, name: String, color: String) -> Result<OperationEnvelope, JsValue> {
+        self.0
+            .set_user(memo::UserInfo { name, color })
+            .map(|operation| OperationEnvelope::new(operation))
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn set_scroll_position(
+        &self,
+        buffer_id: JsValue,
+        scroll_position: JsValue,
+    ) -> Result<OperationEnvelope, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let scroll_position = scroll_position.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .set_scroll_position(buffer_id, scroll_position)
+            .map(|operation| OperationEnvelope::new(operation))
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn presence(&self) -> JsValue {
+        let presence = self
+            .0
+            .presence()
+            .into_iter()
+            .map(|(replica_id, presence)| (replica_id, ReplicaPresence::from(presence)))
+            .collect::<HashMap<_, _>>();
+        JsValue::from_serde(&presence).unwrap()
+    }
+
+    pub fn follow(&self, replica_id: JsValue) -> Result<JsValue, JsValue> {
+        let replica_id = replica_id.into_serde().map_err(|e| e.into_js_err())?;
+        let presence = self.0.follow(replica_id).map(ReplicaPresence::from);
+        JsValue::from_serde(&presence).map_err(|e| e.into_js_err())
+    }
+
     pub fn open_text_file(&mut self, path: String) -> js_sys::Promise {
         future_to_promise(
             self.0
@@ -264,6 +615,35 @@ impl WorkTree {
         )
     }
 
+    pub fn status(&self) -> Result<JsValue, JsValue> {
+        let entries = self
+            .0
+            .status()
+            .into_iter()
+            .map(|entry| StatusEntry {
+                file_type: entry.file_type,
+                path: entry.path.to_string_lossy().into_owned(),
+                base_path: entry
+                    .base_path
+                    .map(|path| path.to_string_lossy().into_owned()),
+                status: entry.status,
+            })
+            .collect::<Vec<_>>();
+        JsValue::from_serde(&entries).map_err(|e| e.into_js_err())
+    }
+
+    pub fn diff(&self, path: String) -> js_sys::Promise {
+        future_to_promise(
+            self.0
+                .diff(path)
+                .map(|hunks| {
+                    let hunks = hunks.into_iter().map(DiffHunk::from).collect::<Vec<_>>();
+                    JsValue::from_serde(&hunks).unwrap()
+                })
+                .map_err(|e| e.into_js_err()),
+        )
+    }
+
     pub fn path(&self, buffer_id: JsValue) -> Result<Option<String>, JsValue> {
         let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
         Ok(self
@@ -280,6 +660,67 @@ impl WorkTree {
             .map_err(|e| e.into_js_err())
     }
 
+    pub fn text_at(&self, buffer_id: JsValue, version_bytes: &[u8]) -> Result<JsValue, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let version = bincode::deserialize(&version_bytes).map_err(|e| e.into_js_err())?;
+        self.0
+            .text_at(buffer_id, &version)
+            .map(|text| JsValue::from_str(&String::from_utf16_lossy(&text)))
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn changes_between(
+        &self,
+        buffer_id: JsValue,
+        from_bytes: &[u8],
+        to_bytes: &[u8],
+    ) -> Result<JsValue, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let from = bincode::deserialize(&from_bytes).map_err(|e| e.into_js_err())?;
+        let to = bincode::deserialize(&to_bytes).map_err(|e| e.into_js_err())?;
+        let changes = self
+            .0
+            .changes_between(buffer_id, &from, &to)
+            .map_err(|e| e.into_js_err())?
+            .into_iter()
+            .map(|change| Change {
+                start: change.range.start,
+                end: change.range.end,
+                text: String::from_utf16_lossy(&change.code_units),
+            })
+            .collect::<Vec<_>>();
+        Ok(JsValue::from_serde(&changes).unwrap())
+    }
+
+    pub fn authorship(&self, buffer_id: JsValue) -> Result<JsValue, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let spans = self
+            .0
+            .authorship(buffer_id)
+            .map_err(|e| e.into_js_err())?
+            .into_iter()
+            .map(AuthorshipSpan::from)
+            .collect::<Vec<_>>();
+        Ok(JsValue::from_serde(&spans).unwrap())
+    }
+
+    pub fn authorship_in_rows(
+        &self,
+        buffer_id: JsValue,
+        start_row: u32,
+        end_row: u32,
+    ) -> Result<JsValue, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let spans = self
+            .0
+            .authorship_in_rows(buffer_id, start_row..end_row)
+            .map_err(|e| e.into_js_err())?
+            .into_iter()
+            .map(AuthorshipSpan::from)
+            .collect::<Vec<_>>();
+        Ok(JsValue::from_serde(&spans).unwrap())
+    }
+
     pub fn buffer_deferred_ops_len(&self, buffer_id: JsValue) -> Result<u32, JsValue> {
         let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
         self.0
@@ -288,6 +729,69 @@ impl WorkTree {
             .map_err(|e| e.into_js_err())
     }
 
+    pub fn clip_point(
+        &self,
+        buffer_id: JsValue,
+        point: JsValue,
+        bias: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let point = point.into_serde().map_err(|e| e.into_js_err())?;
+        let bias: Bias = bias.into_serde().map_err(|e| e.into_js_err())?;
+        let point = self
+            .0
+            .clip_point(buffer_id, point, bias.into())
+            .map_err(|e| e.into_js_err())?;
+        Ok(JsValue::from_serde(&point).unwrap())
+    }
+
+    pub fn clip_offset(
+        &self,
+        buffer_id: JsValue,
+        offset: u32,
+        bias: JsValue,
+    ) -> Result<u32, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let bias: Bias = bias.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .clip_offset(buffer_id, offset as usize, bias.into())
+            .map(|offset| offset as u32)
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn is_valid_point(&self, buffer_id: JsValue, point: JsValue) -> Result<bool, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let point = point.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .is_valid_point(buffer_id, point)
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn is_valid_offset(&self, buffer_id: JsValue, offset: u32) -> Result<bool, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .is_valid_offset(buffer_id, offset as usize)
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn point_for_offset(&self, buffer_id: JsValue, offset: u32) -> Result<JsValue, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let point = self
+            .0
+            .point_for_offset(buffer_id, offset as usize)
+            .map_err(|e| e.into_js_err())?;
+        Ok(JsValue::from_serde(&point).unwrap())
+    }
+
+    pub fn offset_for_point(&self, buffer_id: JsValue, point: JsValue) -> Result<u32, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let point = point.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .offset_for_point(buffer_id, point)
+            .map(|offset| offset as u32)
+            .map_err(|e| e.into_js_err())
+    }
+
     pub fn edit(
         &self,
         buffer_id: JsValue,
@@ -302,6 +806,41 @@ impl WorkTree {
             .map_err(|e| e.into_js_err())
     }
 
+    pub fn start_transaction(&self, buffer_id: JsValue) -> Result<(), JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .start_transaction_at(buffer_id, now())
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn end_transaction(
+        &self,
+        buffer_id: JsValue,
+        group_interval: Option<f64>,
+    ) -> Result<(), JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let group_interval = group_interval.map(|millis| Duration::from_millis(millis as u64));
+        self.0
+            .end_transaction_at(buffer_id, now(), group_interval)
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn undo(&self, buffer_id: JsValue) -> Result<OperationEnvelope, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .undo(buffer_id)
+            .map(|op| OperationEnvelope::new(op))
+            .map_err(|e| e.into_js_err())
+    }
+
+    pub fn redo(&self, buffer_id: JsValue) -> Result<OperationEnvelope, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        self.0
+            .redo(buffer_id)
+            .map(|op| OperationEnvelope::new(op))
+            .map_err(|e| e.into_js_err())
+    }
+
     pub fn add_selection_set(
         &self,
         buffer_id: JsValue,
@@ -335,6 +874,19 @@ impl WorkTree {
         Ok(OperationEnvelope::new(op))
     }
 
+    pub fn close_buffer(&self, buffer_id: JsValue) -> Result<js_sys::Array, JsValue> {
+        let buffer_id = buffer_id.into_serde().map_err(|e| e.into_js_err())?;
+        let envelopes = js_sys::Array::new();
+        for envelope in self
+            .0
+            .close_buffer(buffer_id)
+            .map_err(|e| e.into_js_err())?
+        {
+            envelopes.push(&JsValue::from(OperationEnvelope::new(envelope)));
+        }
+        Ok(envelopes)
+    }
+
     pub fn remove_selection_set(
         &self,
         buffer_id: JsValue,
@@ -533,26 +1085,84 @@ impl StreamToAsyncIterator {
     }
 }
 
-impl memo::GitProvider for GitProviderWrapper {
+impl<T> ThreadBound<T> {
+    fn new(value: T) -> Self {
+        ThreadBound {
+            value: ManuallyDrop::new(value),
+            thread_id: thread::current().id(),
+        }
+    }
+
+    fn get(&self) -> &T {
+        assert_eq!(
+            thread::current().id(),
+            self.thread_id,
+            "JS value accessed from a thread other than the one that created it"
+        );
+        &self.value
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        assert_eq!(
+            thread::current().id(),
+            self.thread_id,
+            "JS value accessed from a thread other than the one that created it"
+        );
+        &mut self.value
+    }
+}
+
+// Streams and futures backed by JS values must be polled on the thread that created them.
+impl<S: Stream> Stream for ThreadBound<S> {
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.get_mut().poll()
+    }
+}
+
+impl<F: Future> Future for ThreadBound<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.get_mut().poll()
+    }
+}
+
+impl<T> Drop for ThreadBound<T> {
+    fn drop(&mut self) {
+        if thread::current().id() == self.thread_id {
+            unsafe { ManuallyDrop::drop(&mut self.value) }
+        }
+    }
+}
+
+// Sound because the wrapped value is only ever accessed or dropped on the thread that created it.
+unsafe impl<T> Send for ThreadBound<T> {}
+unsafe impl<T> Sync for ThreadBound<T> {}
+
+impl memo::GitProvider for ThreadBound<GitProviderWrapper> {
     fn base_entries(
         &self,
         oid: memo::Oid,
-    ) -> Box<Stream<Item = memo::DirEntry, Error = io::Error>> {
-        let iterator = GitProviderWrapper::base_entries(self, &hex::encode(oid));
-        Box::new(
+    ) -> Box<Stream<Item = memo::DirEntry, Error = io::Error> + Send> {
+        let iterator = GitProviderWrapper::base_entries(self.get(), &hex::encode(oid));
+        Box::new(ThreadBound::new(
             AsyncIteratorToStream::new(iterator)
                 .map_err(|error: String| io::Error::new(io::ErrorKind::Other, error)),
-        )
+        ))
     }
 
     fn base_text(
         &self,
         oid: memo::Oid,
         path: &Path,
-    ) -> Box<Future<Item = String, Error = io::Error>> {
-        Box::new(
+    ) -> Box<Future<Item = String, Error = io::Error> + Send> {
+        Box::new(ThreadBound::new(
             JsFuture::from(GitProviderWrapper::base_text(
-                self,
+                self.get(),
                 &hex::encode(oid),
                 path.to_string_lossy().as_ref(),
             ))
@@ -563,11 +1173,11 @@ impl memo::GitProvider for GitProviderWrapper {
                 Err(error) => Err(error.into_error_message()?),
             })
             .map_err(|error| io::Error::new(io::ErrorKind::Other, error)),
-        )
+        ))
     }
 }
 
-impl memo::ChangeObserver for ChangeObserver {
+impl memo::ChangeObserver for ThreadBound<ChangeObserver> {
     fn changed(
         &self,
         buffer_id: memo::BufferId,
@@ -583,12 +1193,63 @@ impl memo::ChangeObserver for ChangeObserver {
             })
             .collect::<Vec<_>>();
         ChangeObserver::changed(
-            self,
+            self.get(),
             JsValue::from_serde(&buffer_id).unwrap(),
             JsValue::from_serde(&changes).unwrap(),
             JsValue::from_serde(&JsSelections::from(selection_ranges)).unwrap(),
         );
     }
+
+    fn tree_changed(&self, changes: Vec<memo::TreeChange>) {
+        let changes = changes
+            .into_iter()
+            .map(|change| match change {
+                memo::TreeChange::Created { file_id, path } => TreeChange::Created {
+                    file_id: JsFileId(file_id),
+                    path: path.to_string_lossy().into_owned(),
+                },
+                memo::TreeChange::Renamed {
+                    file_id,
+                    old_path,
+                    new_path,
+                } => TreeChange::Renamed {
+                    file_id: JsFileId(file_id),
+                    old_path: old_path.to_string_lossy().into_owned(),
+                    new_path: new_path.to_string_lossy().into_owned(),
+                },
+                memo::TreeChange::Removed { file_id, path } => TreeChange::Removed {
+                    file_id: JsFileId(file_id),
+                    path: path.to_string_lossy().into_owned(),
+                },
+            })
+            .collect::<Vec<_>>();
+        ChangeObserver::tree_changed(self.get(), JsValue::from_serde(&changes).unwrap());
+    }
+
+    fn followed_replica_changed(
+        &self,
+        replica_id: memo::ReplicaId,
+        presence: memo::ReplicaPresence,
+    ) {
+        ChangeObserver::followed_replica_changed(
+            self.get(),
+            JsValue::from_serde(&replica_id).unwrap(),
+            JsValue::from_serde(&ReplicaPresence::from(presence)).unwrap(),
+        );
+    }
+}
+
+impl From<memo::ReplicaPresence> for ReplicaPresence {
+    fn from(presence: memo::ReplicaPresence) -> Self {
+        ReplicaPresence {
+            user: presence.user,
+            path: presence
+                .path
+                .map(|path| path.to_string_lossy().into_owned()),
+            cursor: presence.cursor,
+            scroll_position: presence.scroll_position,
+        }
+    }
 }
 
 impl From<memo::BufferSelectionRanges> for JsSelections {
@@ -617,6 +1278,15 @@ impl From<memo::BufferSelectionRanges> for JsSelections {
     }
 }
 
+impl From<Bias> for memo::AnchorBias {
+    fn from(bias: Bias) -> Self {
+        match bias {
+            Bias::Left => memo::AnchorBias::Left,
+            Bias::Right => memo::AnchorBias::Right,
+        }
+    }
+}
+
 impl From<Range<memo::Point>> for JsRange {
     fn from(range: Range<memo::Point>) -> Self {
         JsRange {
@@ -626,6 +1296,72 @@ impl From<Range<memo::Point>> for JsRange {
     }
 }
 
+impl From<memo::AuthorshipSpan> for AuthorshipSpan {
+    fn from(span: memo::AuthorshipSpan) -> Self {
+        AuthorshipSpan {
+            start: span.range.start,
+            end: span.range.end,
+            replica_id: span.inserted_by.map(|timestamp| timestamp.replica_id),
+            timestamp: span.inserted_by.map(|timestamp| timestamp.value),
+        }
+    }
+}
+
+impl From<memo::LineDiffHunk> for DiffHunk {
+    fn from(hunk: memo::LineDiffHunk) -> Self {
+        DiffHunk {
+            old_start_row: hunk.old_rows.start,
+            old_end_row: hunk.old_rows.end,
+            new_start_row: hunk.new_rows.start,
+            new_end_row: hunk.new_rows.end,
+            old_text: hunk.old_text,
+            new_text: hunk.new_text,
+        }
+    }
+}
+
+impl From<memo::RebaseConflict> for RebaseConflict {
+    fn from(conflict: memo::RebaseConflict) -> Self {
+        fn path_string(path: PathBuf) -> String {
+            path.to_string_lossy().into_owned()
+        }
+
+        match conflict {
+            memo::RebaseConflict::BothAdded { path } => RebaseConflict::BothAdded {
+                path: path_string(path),
+            },
+            memo::RebaseConflict::RenamedButRemoved { base_path, path } => {
+                RebaseConflict::RenamedButRemoved {
+                    base_path: path_string(base_path),
+                    path: path_string(path),
+                }
+            }
+            memo::RebaseConflict::RenameTargetExists { base_path, path } => {
+                RebaseConflict::RenameTargetExists {
+                    base_path: path_string(base_path),
+                    path: path_string(path),
+                }
+            }
+            memo::RebaseConflict::RemovedButModified { path } => {
+                RebaseConflict::RemovedButModified {
+                    path: path_string(path),
+                }
+            }
+            memo::RebaseConflict::ModifiedButRemoved { path } => {
+                RebaseConflict::ModifiedButRemoved {
+                    path: path_string(path),
+                }
+            }
+            memo::RebaseConflict::ConflictingEdits { path, hunks } => {
+                RebaseConflict::ConflictingEdits {
+                    path: path_string(path),
+                    hunks: hunks.into_iter().map(DiffHunk::from).collect(),
+                }
+            }
+        }
+    }
+}
+
 impl Serialize for HexOid {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -656,6 +1392,21 @@ impl<'de> Deserialize<'de> for HexOid {
     }
 }
 
+// File ids are only meaningful within an epoch, so JavaScript only needs to compare them. Encoding
+// them as strings lets them be used as keys of a `Map`.
+impl Serialize for JsFileId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            memo::FileId::Base(id) => format!("base-{}", id),
+            memo::FileId::New(timestamp) => format!("{}-{}", timestamp.replica_id, timestamp.value),
+        }
+        .serialize(serializer)
+    }
+}
+
 impl<T: ToString> IntoJsError for T {
     fn into_js_err(self) -> JsValue {
         js_sys::Error::new(&self.to_string()).into()
@@ -672,6 +1423,15 @@ impl JsValueExt for JsValue {
         memo::Operation::deserialize(&bytes).map_err(|e| e.into_js_err())
     }
 
+    fn into_version(self) -> Result<memo::Version, JsValue> {
+        let js_bytes = self
+            .dyn_into::<js_sys::Uint8Array>()
+            .map_err(|_| "Version must be Uint8Array".into_js_err())?;
+        let mut bytes = Vec::with_capacity(js_bytes.byte_length() as usize);
+        js_bytes.for_each(&mut |byte, _, _| bytes.push(byte));
+        bincode::deserialize(&bytes).map_err(|e| e.into_js_err())
+    }
+
     fn into_ranges_vec(self) -> Result<Vec<Range<memo::Point>>, JsValue> {
         Ok(self
             .into_serde::<Vec<JsRange>>()
@@ -690,3 +1450,7 @@ impl JsValueExt for JsValue {
         }
     }
 }
+
+fn now() -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(js_sys::Date::now() as u64)
+}
@@ -3,6 +3,7 @@ export type Oid = string;
 export type Path = string;
 export type ReplicaId = Tagged<string, "ReplicaId">;
 export type BufferId = Tagged<number, "BufferId">;
+export type FileId = Tagged<string, "FileId">;
 export type SelectionSetId = Tagged<number, "SelectionSetId">;
 export type Point = { row: number; column: number };
 export type Range = { start: Point; end: Point };
@@ -12,11 +13,20 @@ export interface BaseEntry {
   readonly depth: number;
   readonly name: string;
   readonly type: FileType;
+  readonly attributes?: FileAttributes;
+}
+
+export interface FileAttributes {
+  readonly executable?: boolean;
+  readonly symlinkTarget?: Path | null;
+  readonly oid?: Oid | null;
 }
 
 export enum FileType {
   Directory = "Directory",
-  Text = "Text"
+  Text = "Text",
+  Symlink = "Symlink",
+  Binary = "Binary"
 }
 
 export interface GitProvider {
@@ -71,6 +81,35 @@ export type ChangeObserverCallback = (
   }
 ) => void;
 
+export type TreeChange =
+  | { readonly type: "Created"; readonly fileId: FileId; readonly path: Path }
+  | {
+      readonly type: "Renamed";
+      readonly fileId: FileId;
+      readonly oldPath: Path;
+      readonly newPath: Path;
+    }
+  | { readonly type: "Removed"; readonly fileId: FileId; readonly path: Path };
+
+export type TreeChangeCallback = (changes: ReadonlyArray<TreeChange>) => void;
+
+export interface UserInfo {
+  readonly name: string;
+  readonly color: string;
+}
+
+export interface ReplicaPresence {
+  readonly user: UserInfo;
+  readonly path: Path | null;
+  readonly cursor: Point | null;
+  readonly scrollPosition: Point | null;
+}
+
+export type FollowCallback = (
+  replicaId: ReplicaId,
+  presence: ReplicaPresence
+) => void;
+
 export class ChangeObserver {
   emitter: Emitter;
 
@@ -82,6 +121,28 @@ export class ChangeObserver {
     return this.emitter.on(`buffer-${bufferId}-change`, callback);
   }
 
+  onTreeChange(callback: TreeChangeCallback): Disposable {
+    return this.emitter.on("tree-change", callback);
+  }
+
+  treeChanged(changes: TreeChange[]) {
+    this.emitter.emit("tree-change", changes);
+  }
+
+  onFollowedReplicaChange(callback: FollowCallback): Disposable {
+    return this.emitter.on("followed-replica-change", ({ replicaId, presence }) =>
+      callback(replicaId, presence)
+    );
+  }
+
+  followedReplicaChanged(replicaId: ReplicaId, presence: ReplicaPresence) {
+    this.emitter.emit("followed-replica-change", { replicaId, presence });
+  }
+
+  release(bufferId: BufferId) {
+    this.emitter.clear(`buffer-${bufferId}-change`);
+  }
+
   changed(
     bufferId: BufferId,
     textChanges: Change[],
@@ -154,6 +215,10 @@ export class Emitter {
     }
   }
 
+  clear(eventName: string) {
+    this.callbacks.delete(eventName);
+  }
+
   on(eventName: string, callback: EmitterCallback): Disposable {
     let callbacks = this.callbacks.get(eventName);
     if (!callbacks) {
@@ -569,6 +569,70 @@ suite("WorkTree", () => {
     assert.strictEqual(buffer1ChangeCount, 1);
   });
 
+  test("undo and redo", async () => {
+    const git = new TestGitProvider();
+    const [tree1, initOps1] = await WorkTree.create(uuid(), null, [], git);
+    const [tree2] = await WorkTree.create(
+      uuid(),
+      null,
+      await collectOps(initOps1),
+      git
+    );
+
+    const ops = [tree1.createFile("a", FileType.Text).operation()];
+    const buffer1 = await tree1.openTextFile("a");
+    const changes: Change[] = [];
+    buffer1.onChange(c => changes.push(...c.textChanges));
+
+    ops.push(
+      buffer1.edit([{ start: point(0, 0), end: point(0, 0) }], "abc").operation()
+    );
+    ops.push(buffer1.undo().operation());
+    assert.strictEqual(buffer1.getText(), "");
+    assert.deepEqual(changes, [
+      { start: point(0, 0), end: point(0, 3), text: "" }
+    ]);
+    ops.push(buffer1.redo().operation());
+    assert.strictEqual(buffer1.getText(), "abc");
+
+    tree2.applyOps(ops);
+    const buffer2 = await tree2.openTextFile("a");
+    assert.strictEqual(buffer2.getText(), "abc");
+  });
+
+  test("transactions", async () => {
+    const git = new TestGitProvider();
+    const [tree1, initOps1] = await WorkTree.create(uuid(), null, [], git);
+    const [tree2] = await WorkTree.create(
+      uuid(),
+      null,
+      await collectOps(initOps1),
+      git
+    );
+    tree2.applyOps([tree1.createFile("a", FileType.Text).operation()]);
+    const buffer1 = await tree1.openTextFile("a");
+    const buffer2 = await tree2.openTextFile("a");
+    let buffer2ChangeCount = 0;
+    buffer2.onChange(_ => buffer2ChangeCount++);
+
+    buffer1.startTransaction();
+    const ops = [
+      buffer1
+        .edit([{ start: point(0, 0), end: point(0, 0) }], "abc")
+        .operation(),
+      buffer1
+        .edit([{ start: point(0, 3), end: point(0, 3) }], "def")
+        .operation()
+    ];
+    buffer1.endTransaction();
+    tree2.applyOps(ops);
+    assert.strictEqual(buffer2.getText(), "abcdef");
+    assert.strictEqual(buffer2ChangeCount, 1);
+
+    buffer1.undo();
+    assert.strictEqual(buffer1.getText(), "");
+  });
+
   test("throwing error when retrieving base entries", async () => {
     const git = {
       async *baseEntries(): AsyncIterable<BaseEntry> {